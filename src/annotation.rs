@@ -0,0 +1,80 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::assertion::Failure;
+
+/// True when running as a step of a GitHub Actions job.
+pub fn enabled() -> bool {
+    env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// Escapes the message of a workflow command.
+fn data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes a property (file, title) of a workflow command.
+fn property(text: &str) -> String {
+    data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// The path GitHub expects: relative to the checked-out repository when FILE is in it.
+fn workspace_path(file: &Path) -> String {
+    let relative = env::var_os("GITHUB_WORKSPACE").and_then(|workspace| file.strip_prefix(workspace).ok());
+    relative.unwrap_or(file).display().to_string()
+}
+
+/// Line (1-based) on which the assertion SOURCE is written: as a JSON string, or as plain
+/// text in a YAML file.
+fn line_of(content: &str, source: &str) -> Option<usize> {
+    let quoted = serde_json::to_string(source).unwrap_or_default();
+    let find = |needle: &str| content.lines().position(|line| line.contains(needle));
+    find(&quoted).or_else(|| find(source)).map(|index| index + 1)
+}
+
+/// Prints FAILURES of the assertions of REQUEST as `::error` commands (`::warning` for
+/// `warn:` assertions) when running in GitHub Actions, so they show inline in pull requests.
+/// Each points at the line of the first of FILES that contains the assertion.
+pub fn report(request: &str, files: &[PathBuf], failures: &[Failure]) {
+    if failures.is_empty() || !enabled() {
+        return;
+    }
+    let contents: Vec<(&PathBuf, String)> =
+        files.iter().filter_map(|file| fs::read_to_string(file).ok().map(|content| (file, content))).collect();
+    for failure in failures {
+        let location = contents
+            .iter()
+            .find_map(|(file, content)| line_of(content, &failure.source).map(|line| (file, Some(line))))
+            .or_else(|| contents.first().map(|(file, _)| (file, None)));
+        let mut properties = Vec::new();
+        if let Some((file, line)) = location {
+            properties.push(format!("file={}", property(&workspace_path(file))));
+            properties.extend(line.map(|line| format!("line={}", line)));
+        }
+        properties.push(format!("title={}", property(&format!("Assertion failed: {}", request))));
+        let level = if failure.warn { "warning" } else { "error" };
+        // ワークフローコマンドは標準エラーでも解釈されるので、標準出力の結果を汚さない
+        eprintln!("::{} {}::{}", level, properties.join(","), data(&failure.message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assertions_are_found_in_json_and_yaml_files() {
+        let json = concat!(
+            "{\n  \"url\": \"http://x\",\n  \"assertions\": [\n",
+            "    \"status == 200\",\n    \"header x-id matches \\\"^a\\\"\"\n  ]\n}"
+        );
+        assert_eq!(line_of(json, "status == 200"), Some(4));
+        assert_eq!(line_of(json, "header x-id matches \"^a\""), Some(5));
+        let yaml = "url: http://x\nassertions:\n  - .items | length > 0\n";
+        assert_eq!(line_of(yaml, ".items | length > 0"), Some(3));
+        assert_eq!(line_of(yaml, "status == 200"), None);
+        assert_eq!(property("GET a: b,c\n"), "GET a%3A b%2Cc%0A");
+    }
+}
@@ -0,0 +1,405 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::{cmp::Ordering, fmt};
+
+use crate::exit_code;
+use crate::filter::{self, Filter};
+use crate::request::Exchange;
+
+/// What an assertion looks at.
+#[derive(Debug, Clone)]
+enum Subject {
+    Status,
+    Header(String),
+    /// The raw response body as text.
+    Body,
+    /// A value inside the JSON body, selected with the --filter syntax.
+    Path(Filter),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Matches,
+    Exists,
+    Missing,
+}
+
+const OPERATORS: &[(&str, Op)] = &[
+    ("==", Op::Eq),
+    ("!=", Op::Ne),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+    ("contains", Op::Contains),
+    ("matches", Op::Matches),
+    ("exists", Op::Exists),
+    ("!exists", Op::Missing),
+];
+
+/// A check on a response, written as `SUBJECT OP [VALUE]`, e.g. `status == 201`,
+/// `header content-type matches "json"`, `.count >= 10`, `.items | length > 0`
+/// or `.user.email exists`. A `warn:` prefix makes it a soft check.
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    source: String,
+    /// Failures are reported as warnings and do not fail the run.
+    warn: bool,
+    subject: Subject,
+    /// Compare the length of the subject (array items, string characters or object keys).
+    length: bool,
+    op: Op,
+    expected: Value,
+    regex: Option<Regex>,
+}
+
+/// Splits on whitespace outside double-quoted strings, keeping byte offsets.
+fn tokens(expr: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in expr.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if let Some(s) = start.take() {
+                    tokens.push((s, &expr[s..i]));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        tokens.push((s, &expr[s..]));
+    }
+    tokens
+}
+
+/// Parses the expected value: JSON when possible (numbers, "strings", true, null, [..]),
+/// otherwise the bare text.
+fn literal(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| json!(text))
+}
+
+impl Assertion {
+    pub fn parse(expr: &str) -> Result<Assertion> {
+        let source = expr.trim();
+        let (warn, expr) = match source.strip_prefix("warn:") {
+            Some(rest) => (true, rest.trim()),
+            None => (false, source),
+        };
+        let tokens = tokens(expr);
+        let (position, op) = tokens
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find_map(|(i, (_, token))| OPERATORS.iter().find(|(name, _)| name == token).map(|(_, op)| (i, *op)))
+            .with_context(|| {
+                format!(
+                    "No operator in assertion '{}' (use ==, !=, <, <=, >, >=, contains, matches, exists or !exists)",
+                    expr
+                )
+            })?;
+        let subject_end = tokens[position].0;
+        let mut subject = expr[..subject_end].trim();
+        let rest = tokens.get(position + 1).map(|(offset, _)| expr[*offset..].trim()).unwrap_or_default();
+
+        let length = match subject.strip_suffix("length").map(str::trim_end) {
+            Some(prefix) if prefix.ends_with('|') => {
+                subject = prefix.trim_end_matches('|').trim_end();
+                true
+            }
+            _ => false,
+        };
+        let subject = match subject.split_once(char::is_whitespace) {
+            _ if subject == "status" => Subject::Status,
+            _ if subject == "body" => Subject::Body,
+            Some(("header", name)) => Subject::Header(name.trim().to_lowercase()),
+            _ if subject.starts_with(['.', '$']) => Subject::Path(
+                Filter::parse(subject).with_context(|| format!("Invalid path in assertion '{}'", expr))?,
+            ),
+            _ => bail!("Unknown subject '{}' in assertion '{}' (use status, header NAME, body or a .path)", subject, expr),
+        };
+
+        let needs_value = !matches!(op, Op::Exists | Op::Missing);
+        if needs_value && rest.is_empty() {
+            bail!("Missing value in assertion '{}'", expr);
+        }
+        if !needs_value && !rest.is_empty() {
+            bail!("Unexpected '{}' after '{}' in assertion '{}'", rest, if op == Op::Exists { "exists" } else { "!exists" }, expr);
+        }
+        let expected = if needs_value { literal(rest) } else { Value::Null };
+        let regex = match (op, &expected) {
+            (Op::Matches, Value::String(pattern)) => {
+                Some(Regex::new(pattern).with_context(|| format!("Invalid regex in assertion '{}'", expr))?)
+            }
+            (Op::Matches, _) => bail!("matches needs a regex string in assertion '{}'", expr),
+            _ => None,
+        };
+        Ok(Assertion {
+            source: source.to_string(),
+            warn,
+            subject,
+            length,
+            op,
+            expected,
+            regex,
+        })
+    }
+
+    /// Looks up the subject; None when a header or JSON value is absent.
+    fn actual(&self, exchange: &Exchange, body: Option<&Value>) -> Result<Option<Value>, String> {
+        let value = match &self.subject {
+            Subject::Status => Some(json!(exchange.status.as_u16())),
+            Subject::Header(name) => exchange
+                .headers
+                .get_all(name.as_str())
+                .iter()
+                .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+                .reduce(|a, b| format!("{}, {}", a, b))
+                .map(Value::String),
+            Subject::Body => Some(json!(exchange.text())),
+            Subject::Path(filter) => {
+                let body = body.ok_or("the response body is not JSON")?;
+                Some(filter.apply(body)).filter(|value| !value.is_null())
+            }
+        };
+        if !self.length {
+            return Ok(value);
+        }
+        match value {
+            Some(Value::Array(items)) => Ok(Some(json!(items.len()))),
+            Some(Value::Object(map)) => Ok(Some(json!(map.len()))),
+            Some(Value::String(text)) => Ok(Some(json!(text.chars().count()))),
+            Some(other) => Err(format!("length of {} is undefined", other)),
+            None => Err("value is missing".to_string()),
+        }
+    }
+
+    /// Checks the assertion; returns a description of the mismatch on failure.
+    /// BODY is the response parsed as JSON, if it is JSON.
+    pub fn check(&self, exchange: &Exchange, body: Option<&Value>) -> Result<(), String> {
+        let actual = self.actual(exchange, body)?;
+        let Some(actual) = actual else {
+            return match self.op {
+                Op::Missing => Ok(()),
+                _ => Err("value is missing".to_string()),
+            };
+        };
+        let ok = match self.op {
+            Op::Exists => true,
+            Op::Missing => false,
+            Op::Eq => equal(&actual, &self.expected),
+            Op::Ne => !equal(&actual, &self.expected),
+            Op::Lt | Op::Le | Op::Gt | Op::Ge => {
+                let comparable = matches!(
+                    (&actual, &self.expected),
+                    (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_))
+                );
+                if !comparable {
+                    return Err(format!("cannot compare {} with {}", actual, self.expected));
+                }
+                let ordering = filter::compare_values(&actual, &self.expected);
+                match self.op {
+                    Op::Lt => ordering == Ordering::Less,
+                    Op::Le => ordering != Ordering::Greater,
+                    Op::Gt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                }
+            }
+            Op::Contains => match (&actual, &self.expected) {
+                (Value::String(text), Value::String(part)) => text.contains(part.as_str()),
+                (Value::Array(items), expected) => items.iter().any(|item| equal(item, expected)),
+                (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                (actual, expected) => actual.to_string().contains(&expected.to_string()),
+            },
+            Op::Matches => {
+                let text = match &actual {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                self.regex.as_ref().is_some_and(|regex| regex.is_match(&text))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("got {}", abbreviate(&actual)))
+        }
+    }
+}
+
+/// Equality where numbers compare by value (10 == 10.0).
+fn equal(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(_), Value::Number(_)) => filter::compare_values(actual, expected) == Ordering::Equal,
+        _ => actual == expected,
+    }
+}
+
+fn abbreviate(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > 120 {
+        format!("{}…", text.chars().take(120).collect::<String>())
+    } else {
+        text
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Parses assertion expressions, reporting the first invalid one.
+pub fn parse_all(exprs: &[String]) -> Result<Vec<Assertion>> {
+    exprs.iter().map(|expr| Assertion::parse(expr).map_err(exit_code::config)).collect()
+}
+
+/// An assertion that did not hold, formatted as "ASSERTION: reason".
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub message: String,
+    /// The assertion as written, e.g. `status == 200`.
+    pub source: String,
+    /// True for `warn:` assertions, which must not fail the run.
+    pub warn: bool,
+}
+
+/// Runs every assertion against the response and returns the ones that failed.
+pub fn check_all(assertions: &[Assertion], exchange: &Exchange) -> Vec<Failure> {
+    let body = serde_json::from_slice::<Value>(&exchange.body).ok();
+    assertions
+        .iter()
+        .filter_map(|assertion| {
+            assertion.check(exchange, body.as_ref()).err().map(|reason| Failure {
+                message: format!("{}: {}", assertion, reason),
+                source: assertion.source.clone(),
+                warn: assertion.warn,
+            })
+        })
+        .collect()
+}
+
+/// Splits failures into errors and warnings, as message lists.
+pub fn partition(failures: Vec<Failure>) -> (Vec<String>, Vec<String>) {
+    let (warnings, errors): (Vec<Failure>, Vec<Failure>) = failures.into_iter().partition(|f| f.warn);
+    (
+        errors.into_iter().map(|f| f.message).collect(),
+        warnings.into_iter().map(|f| f.message).collect(),
+    )
+}
+
+/// True if any strict assertion checks the status code, which then replaces the
+/// default "status below 400" success rule.
+pub fn checks_status(assertions: &[Assertion]) -> bool {
+    assertions
+        .iter()
+        .any(|assertion| !assertion.warn && matches!(assertion.subject, Subject::Status))
+}
+
+/// Returned when assertions on the response failed.
+#[derive(Debug)]
+pub struct Failed {
+    pub failures: usize,
+}
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} assertion(s) failed", self.failures)
+    }
+}
+
+impl std::error::Error for Failed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{header::HeaderMap, StatusCode};
+    use std::time::Duration;
+
+    fn exchange(body: Value) -> Exchange {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json; charset=utf-8".parse().unwrap());
+        Exchange {
+            status: StatusCode::CREATED,
+            headers,
+            body: body.to_string().into_bytes(),
+            elapsed: Duration::from_millis(5),
+            request: None,
+        }
+    }
+
+    fn failures(exprs: &[&str], body: Value) -> Vec<String> {
+        let exprs: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+        check_all(&parse_all(&exprs).unwrap(), &exchange(body))
+            .into_iter()
+            .map(|failure| failure.message)
+            .collect()
+    }
+
+    #[test]
+    fn passing_assertions_report_nothing() {
+        let body = json!({"count": 12, "items": [1, 2, 3], "user": {"name": "Alice", "email": "a@example.com"}});
+        let checks = [
+            "status == 201",
+            "status < 400",
+            "header content-type matches \"^application/json\"",
+            "header Content-Type contains json",
+            ".count >= 10",
+            ".count == 12.0",
+            ".items | length == 3",
+            ".items contains 2",
+            ".user.name == \"Alice\"",
+            ".user.email exists",
+            ".user.phone !exists",
+            "body matches \"\\\"count\\\":\\\\s*12\"",
+        ];
+        assert_eq!(failures(&checks, body), Vec::<String>::new());
+    }
+
+    #[test]
+    fn failures_name_the_assertion_and_actual_value() {
+        let body = json!({"count": 3, "items": []});
+        assert_eq!(
+            failures(&[".count >= 10", ".items | length > 0", ".missing exists", ".count < \"x\""], body),
+            vec![
+                ".count >= 10: got 3",
+                ".items | length > 0: got 0",
+                ".missing exists: value is missing",
+                ".count < \"x\": cannot compare 3 with \"x\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_assertions_fail_softly() {
+        let exprs = vec!["warn: .count >= 10".to_string(), "warn:status == 500".to_string(), ".count > 0".to_string()];
+        let assertions = parse_all(&exprs).unwrap();
+        assert!(!checks_status(&assertions));
+        let (errors, warnings) = partition(check_all(&assertions, &exchange(json!({"count": 3}))));
+        assert!(errors.is_empty());
+        assert_eq!(warnings, vec!["warn: .count >= 10: got 3", "warn:status == 500: got 201"]);
+    }
+
+    #[test]
+    fn invalid_assertions_are_rejected() {
+        for expr in [".count 10", "status ==", "cookie == 1", ".name matches \"(\"", ".id exists 1", "warn:"] {
+            assert!(Assertion::parse(expr).is_err(), "{} should be rejected", expr);
+        }
+    }
+}
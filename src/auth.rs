@@ -0,0 +1,428 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use clap::Subcommand;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::cancel;
+use crate::credential;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::request::RequestConfig;
+use crate::store;
+use crate::vars::{self, Vars};
+
+/// File (under the base directory) caching OAuth2 access tokens until they expire.
+pub const TOKEN_CACHE_FILE: &str = "token_cache.json";
+
+/// Tokens are fetched again this long before they expire.
+const EXPIRY_MARGIN_SECS: u64 = 30;
+
+/// Lifetime assumed when the token response has no expires_in.
+const DEFAULT_LIFETIME_SECS: u64 = 300;
+
+/// Credentials of a saved configuration (`auth` in the config file), turned into a
+/// header when the request is sent. Values may contain `{{name}}` placeholders.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Auth {
+    /// `Authorization: Bearer TOKEN`.
+    Bearer { token: String },
+    /// `Authorization: Basic base64(USERNAME:PASSWORD)`.
+    Basic { username: String, password: String },
+    /// A key sent in its own header, e.g. `X-Api-Key: abc`.
+    ApiKey { header: String, value: String },
+    /// A bearer token fetched with the OAuth2 client credentials grant and cached in
+    /// token_cache.json until it expires.
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        audience: Option<String>,
+        /// Send the client credentials as form fields instead of HTTP Basic authentication.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        credentials_in_body: Option<bool>,
+    },
+}
+
+impl Auth {
+    /// Parses `--basic USER:PASSWORD`.
+    pub fn basic(option: &str) -> Result<Auth> {
+        let (username, password) = option
+            .split_once(':')
+            .with_context(|| format!("Invalid --basic value (expected USER:PASSWORD): {}", option))?;
+        Ok(Auth::Basic { username: username.to_string(), password: password.to_string() })
+    }
+
+    /// Parses `--api-key NAME=VALUE`.
+    pub fn api_key(option: &str) -> Result<Auth> {
+        let (header, value) = option
+            .split_once('=')
+            .filter(|(header, _)| !header.trim().is_empty())
+            .with_context(|| format!("Invalid --api-key value (expected NAME=VALUE, e.g. X-Api-Key=abc): {}", option))?;
+        Ok(Auth::ApiKey { header: header.trim().to_string(), value: value.to_string() })
+    }
+
+    /// The values that may contain placeholders.
+    pub fn values_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Auth::Bearer { token } => vec![token],
+            Auth::Basic { username, password } => vec![username, password],
+            Auth::ApiKey { header: _, value } => vec![value],
+            Auth::OAuth2 { token_url, client_id, client_secret, scope, audience, credentials_in_body: _ } => {
+                let mut values = vec![token_url, client_id, client_secret];
+                values.extend(scope.iter_mut().chain(audience.iter_mut()));
+                values
+            }
+        }
+    }
+
+    /// The header carrying the credentials; fetches an OAuth2 token when none is cached.
+    pub async fn header(&self, insecure: bool) -> Result<(String, String)> {
+        Ok(match self {
+            Auth::Bearer { token } => ("Authorization".to_string(), format!("Bearer {}", token)),
+            Auth::Basic { username, password } => {
+                ("Authorization".to_string(), format!("Basic {}", basic_credentials(username, password)))
+            }
+            Auth::ApiKey { header, value } => (header.clone(), value.clone()),
+            Auth::OAuth2 { .. } => ("Authorization".to_string(), format!("Bearer {}", self.token(insecure).await?)),
+        })
+    }
+
+    /// Returns a cached access token that is still valid, or fetches a new one.
+    async fn token(&self, insecure: bool) -> Result<String> {
+        let Auth::OAuth2 { token_url, client_id, client_secret, scope, audience, credentials_in_body } = self else {
+            bail!("Not an OAuth2 configuration");
+        };
+        let path = get_default_dir()?.join(TOKEN_CACHE_FILE);
+        let key = format!("{} {} {}", token_url, client_id, scope.as_deref().unwrap_or_default());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        {
+            let _lock = store::lock(&path)?;
+            if let Some(cached) = read_token_cache(&path)?.get(&key) {
+                if cached.expires_at > now + EXPIRY_MARGIN_SECS {
+                    return Ok(cached.access_token.clone());
+                }
+            }
+        }
+
+        let mut form = vec![("grant_type", "client_credentials")];
+        form.extend(scope.as_deref().map(|scope| ("scope", scope)));
+        form.extend(audience.as_deref().map(|audience| ("audience", audience)));
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .danger_accept_invalid_certs(insecure)
+            .build()?;
+        let mut request = client.post(token_url.as_str());
+        if *credentials_in_body == Some(true) {
+            form.extend([("client_id", client_id.as_str()), ("client_secret", client_secret.as_str())]);
+        } else {
+            request = request.header("Authorization", format!("Basic {}", basic_credentials(client_id, client_secret)));
+        }
+        let response = request
+            .form(&form)
+            .send()
+            .await
+            .with_context(|| format!("Token request to {} failed", token_url))?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            bail!("Token request to {} failed with {}: {}", token_url, status, body.trim());
+        }
+        let body: Value = serde_json::from_str(&body)
+            .with_context(|| format!("Token response from {} is not JSON", token_url))?;
+        let access_token = body
+            .get("access_token")
+            .and_then(Value::as_str)
+            .with_context(|| format!("Token response from {} has no access_token", token_url))?
+            .to_string();
+        let lifetime = body.get("expires_in").and_then(Value::as_u64).unwrap_or(DEFAULT_LIFETIME_SECS);
+
+        let _lock = store::lock(&path)?;
+        let mut cache = read_token_cache(&path)?;
+        cache.retain(|_, cached| cached.expires_at > now);
+        cache.insert(key, CachedToken { access_token: access_token.clone(), expires_at: now + lifetime });
+        store::write_private(&path, serde_json::to_string_pretty(&cache)?)?;
+        Ok(access_token)
+    }
+}
+
+fn basic_credentials(username: &str, password: &str) -> String {
+    base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password))
+}
+
+/// An access token in token_cache.json.
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedToken {
+    access_token: String,
+    /// Seconds since the Unix epoch.
+    expires_at: u64,
+}
+
+fn read_token_cache(path: &Path) -> Result<BTreeMap<String, CachedToken>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    // 壊れたキャッシュはトークンを取り直せば済むので無視する
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+/// Moves the `auth` of CONFIG into its headers, replacing a header of the same name.
+/// Called once placeholders are resolved, since Basic credentials are encoded.
+pub async fn apply(mut config: RequestConfig) -> Result<RequestConfig> {
+    if let Some(auth) = config.auth.take() {
+        let (name, value) = auth.header(config.insecure == Some(true)).await?;
+        let headers = config.headers.get_or_insert_with(Default::default);
+        headers.retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+        headers.insert(name, value);
+    }
+    Ok(config)
+}
+
+/// Subcommands of `auth`.
+#[derive(Subcommand, Debug)]
+pub enum AuthCommand {
+    /// ブラウザで SSO (SAML/OIDC) ログインを行い、コールバックで受け取ったトークンや Cookie を名前空間の変数に保存します。
+    BrowserLogin {
+        /// ログインページの URL
+        url: String,
+
+        /// 保存先の名前空間（例: "SystemA"）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: String,
+
+        /// ログイン後のリダイレクト先として IdP に登録したローカル URL
+        #[arg(long = "callback", default_value = "http://127.0.0.1:8400/callback")]
+        callback: String,
+
+        /// コールバックを待つ最大時間（例: 5m）
+        #[arg(long = "timeout", default_value = "5m", value_parser = humantime::parse_duration)]
+        timeout: Duration,
+
+        /// 保存する変数名の接頭辞（例: sso_ → sso_access_token）
+        #[arg(long = "prefix", default_value = "")]
+        prefix: String,
+
+        /// ブラウザを開かず、URL を表示するだけにします。
+        #[arg(long = "no-browser")]
+        no_browser: bool,
+
+        /// 受け取った値を vars.json ではなく資格情報ストアに保存します（{{secret:NAME}} で参照）。
+        #[arg(long = "store-secrets")]
+        store_secrets: bool,
+    },
+}
+
+/// Page served at the callback. Tokens in the URL fragment never reach the server,
+/// so the script sends them back as a query string. Without any parameters the page
+/// offers a form for pasting the session cookie copied from the browser.
+const CALLBACK_PAGE: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>ferrapi_tester login</title></head>
+<body style="font-family: sans-serif">
+<script>
+if (location.hash.length > 1) {
+  location.replace(location.pathname + "?" + location.hash.substring(1));
+}
+</script>
+<p>No token was found in the callback URL.</p>
+<form method="get">
+<p>Paste the session cookie (the Cookie header from the browser's developer tools):</p>
+<textarea name="cookie" rows="4" cols="80"></textarea>
+<p><button type="submit">Save</button></p>
+</form>
+</body></html>"#;
+
+const DONE_PAGE: &str = r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>ferrapi_tester login</title></head>
+<body style="font-family: sans-serif"><p>Login captured. You can close this tab and return to the terminal.</p></body></html>"#;
+
+/// Opens the URL in the system browser.
+fn open_browser(url: &str) -> Result<()> {
+    let status = if cfg!(target_os = "macos") {
+        Command::new("open").arg(url).status()
+    } else if cfg!(windows) {
+        Command::new("cmd").args(["/C", "start", "", url]).status()
+    } else {
+        Command::new("xdg-open").arg(url).status()
+    }
+    .context("Failed to start the browser")?;
+    if !status.success() {
+        bail!("The browser command failed ({})", status);
+    }
+    Ok(())
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Handles one connection; returns the captured values once the callback carries any.
+async fn handle(mut stream: TcpStream, path: &str) -> Result<Option<Vars>> {
+    let mut buffer = vec![0u8; 16 * 1024];
+    let mut read = 0;
+    // リクエストラインとヘッダーの終わりまで読む（ボディは使わない）
+    while !buffer[..read].windows(4).any(|w| w == b"\r\n\r\n") && read < buffer.len() {
+        let n = stream.read(&mut buffer[read..]).await?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let url = Url::parse(&format!("http://localhost{}", target)).context("Invalid callback request")?;
+    if url.path() != path {
+        respond(&mut stream, "404 Not Found", "Not found").await?;
+        return Ok(None);
+    }
+    let captured: Vars = url
+        .query_pairs()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| (key.into_owned(), value.trim().to_string()))
+        .collect();
+    if captured.is_empty() {
+        respond(&mut stream, "200 OK", CALLBACK_PAGE).await?;
+        return Ok(None);
+    }
+    respond(&mut stream, "200 OK", DONE_PAGE).await?;
+    Ok(Some(captured))
+}
+
+/// Waits for the browser to reach the callback and returns the parameters it carried.
+async fn wait_for_callback(listener: TcpListener, path: &str) -> Result<Vars> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        if let Some(captured) = handle(stream, path).await? {
+            return Ok(captured);
+        }
+    }
+}
+
+/// Names the query parameters of the login callback with PREFIX. Any page can redirect the
+/// browser to the callback, so values containing `{{` are skipped: saved as variables they
+/// would be resolved as placeholders in later requests.
+fn login_values(captured: Vars, prefix: &str) -> Vars {
+    let mut values = Vars::new();
+    for (key, value) in captured {
+        let name = format!("{}{}", prefix, key);
+        if vars::has_placeholder(&value) {
+            eprintln!("{}", i18n::tf("Callback parameter {} contains '{{' and is not saved.", &[&key]));
+        } else {
+            values.insert(name, value);
+        }
+    }
+    values
+}
+
+async fn browser_login(
+    url: &str,
+    namespace: &str,
+    callback: &str,
+    timeout: Duration,
+    prefix: &str,
+    no_browser: bool,
+    store_secrets: bool,
+) -> Result<()> {
+    let callback = Url::parse(callback).with_context(|| format!("Invalid callback URL {}", callback))?;
+    let host = callback.host_str().context("Callback URL has no host")?;
+    if !matches!(host, "127.0.0.1" | "localhost" | "[::1]") {
+        bail!("The callback must point to this machine (127.0.0.1 or localhost): {}", callback);
+    }
+    let port = callback.port_or_known_default().context("Callback URL has no port")?;
+    let listener = TcpListener::bind((if host == "[::1]" { "::1" } else { "127.0.0.1" }, port))
+        .await
+        .with_context(|| format!("Failed to listen on port {} for the callback", port))?;
+
+    println!("{}", i18n::tf("Waiting for the login to redirect to {}", &[&callback]));
+    if no_browser {
+        println!("{}", i18n::tf("Open this URL in your browser:\n  {}", &[&url]));
+    } else if let Err(err) = open_browser(url) {
+        println!(
+            "{}",
+            i18n::tf("Could not open a browser ({}). Open this URL manually:\n  {}", &[&format!("{:#}", err), &url])
+        );
+    }
+
+    let captured = cancel::guard(async {
+        tokio::time::timeout(timeout, wait_for_callback(listener, callback.path()))
+            .await
+            .map_err(|_| {
+                anyhow!("Timed out after {} waiting for the login callback", humantime::format_duration(timeout))
+            })?
+    })
+    .await?;
+    let values = login_values(captured, prefix);
+    if values.is_empty() {
+        bail!("The login callback carried no values that can be saved");
+    }
+    let names: Vec<String> = values.keys().cloned().collect();
+    if store_secrets {
+        // 資格情報ストアの名前は名前空間をまたいで共有されるため、名前空間を接頭辞にする
+        let store = credential::open()?;
+        let secrets: Vec<String> = names.iter().map(|name| format!("{}/{}", namespace, name)).collect();
+        for (secret, value) in secrets.iter().zip(values.values()) {
+            store.set(secret, value)?;
+        }
+        println!("{}", i18n::tf("Stored {} in {}", &[&secrets.join(", "), &store.describe()]));
+        println!("{}", i18n::tf("Use them as header placeholders, e.g. {{secret:{}}}", &[&secrets[0]]));
+        return Ok(());
+    }
+    let path = vars::set_all(Some(namespace), values)?;
+    println!("{}", i18n::tf("Saved {} to {}", &[&names.join(", "), &format!("{:?}", path)]));
+    println!("{}", i18n::tf("Use them as placeholders, e.g. {{{}}}", &[&names[0]]));
+    Ok(())
+}
+
+pub async fn run(command: AuthCommand) -> Result<()> {
+    match command {
+        AuthCommand::BrowserLogin {
+            url,
+            namespace,
+            callback,
+            timeout,
+            prefix,
+            no_browser,
+            store_secrets,
+        } => browser_login(&url, &namespace, &callback, timeout, &prefix, no_browser, store_secrets).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callback_values_with_placeholders_are_not_saved() {
+        let captured = Vars::from([
+            ("token".to_string(), "abc".to_string()),
+            ("state".to_string(), "{{cmd:touch pwned}}".to_string()),
+        ]);
+        let values = login_values(captured, "login_");
+        assert_eq!(values, Vars::from([("login_token".to_string(), "abc".to_string())]));
+    }
+}
@@ -0,0 +1,100 @@
+use dialoguer::Completion;
+use std::{
+    collections::HashMap,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::request::RequestConfig;
+use crate::{history, mount};
+
+/// Age (in days) at which a use from history counts half as much as one from today.
+const HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Past requests (from history and saved configurations) used to suggest input.
+pub struct Sources {
+    /// Each request with the weight of that use: recent history weighs more,
+    /// saved configurations count as one use.
+    requests: Vec<(RequestConfig, f64)>,
+}
+
+/// Candidates ranked by frequency and recency, best first.
+fn ranked(scores: HashMap<String, f64>) -> Vec<String> {
+    let mut scored: Vec<(String, f64)> = scores.into_iter().collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+    scored.into_iter().map(|(value, _)| value).collect()
+}
+
+impl Sources {
+    /// Loads history and saved configurations; unreadable sources are skipped.
+    pub fn load() -> Sources {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut requests: Vec<(RequestConfig, f64)> = history::load()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                let age_days = now_ms.saturating_sub(entry.timestamp_ms) as f64 / 86_400_000.0;
+                (entry.request, 0.5f64.powf(age_days / HALF_LIFE_DAYS))
+            })
+            .collect();
+        for saved in mount::list_saved().unwrap_or_default() {
+            if let Some(config) = fs::read_to_string(&saved.path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<RequestConfig>(&content).ok())
+            {
+                requests.push((config, 1.0));
+            }
+        }
+        Sources { requests }
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for (config, weight) in &self.requests {
+            if let Some(ref url) = config.url {
+                *scores.entry(url.clone()).or_default() += weight;
+            }
+        }
+        ranked(scores)
+    }
+
+    pub fn header_names(&self) -> Vec<String> {
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for (config, weight) in &self.requests {
+            for name in config.headers.iter().flat_map(|h| h.keys()) {
+                *scores.entry(name.clone()).or_default() += weight;
+            }
+        }
+        ranked(scores)
+    }
+
+    /// Values used for the header NAME (matched case-insensitively).
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for (config, weight) in &self.requests {
+            for (key, value) in config.headers.iter().flatten() {
+                if key.eq_ignore_ascii_case(name) {
+                    *scores.entry(value.clone()).or_default() += weight;
+                }
+            }
+        }
+        ranked(scores)
+    }
+}
+
+/// Tab completion for dialoguer inputs: completes to the best-ranked candidate
+/// that starts with what was typed (case-insensitive).
+pub struct Suggestions(pub Vec<String>);
+
+impl Completion for Suggestions {
+    fn get(&self, input: &str) -> Option<String> {
+        let typed = input.to_lowercase();
+        self.0
+            .iter()
+            .find(|candidate| candidate.to_lowercase().starts_with(&typed) && candidate.len() > input.len())
+            .cloned()
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use dialoguer::console::Style;
+use serde::{Deserialize, Serialize};
+use std::{sync::Mutex, time::Duration};
+
+use crate::i18n;
+use crate::request::{Exchange, RequestConfig};
+use crate::settings::{self, Settings};
+
+/// Response size and time thresholds (`budget` in settings.json), e.g.
+/// `{"max_body_size": "5MB", "max_latency": "2s"}`. Responses over a threshold are
+/// still shown; a warning is printed once the command has finished.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BudgetSettings {
+    /// Largest expected response body, e.g. "512KB" or "5MB".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<String>,
+    /// Longest expected response time, e.g. "800ms" or "2s".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency: Option<String>,
+}
+
+/// Warnings collected during this command, printed by `report` at the end.
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1}MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1}KB", b as f64 / 1024.0),
+        b => format!("{}B", b),
+    }
+}
+
+/// Returns what EXCHANGE exceeded of the budget for TARGET, e.g.
+/// "took 2.4s (budget 2s)"; empty when no budget is set or everything is within it.
+pub fn exceeded(exchange: &Exchange, target: Option<&str>) -> Result<Vec<String>> {
+    let Some(budget) = Settings::for_target(target)?.budget else {
+        return Ok(Vec::new());
+    };
+    let mut exceeded = Vec::new();
+    if let Some(ref max) = budget.max_body_size {
+        let max_bytes = settings::parse_size(max).context("Invalid max_body_size in the budget settings")?;
+        let size = exchange.body.len() as u64;
+        if size > max_bytes {
+            exceeded.push(format!("response body is {} (budget {})", format_size(size), max));
+        }
+    }
+    if let Some(ref max) = budget.max_latency {
+        let max_latency: Duration = humantime::parse_duration(max)
+            .with_context(|| format!("Invalid max_latency '{}' in the budget settings", max))?;
+        if exchange.elapsed > max_latency {
+            exceeded.push(format!("took {} (budget {})", format_elapsed(exchange.elapsed), max));
+        }
+    }
+    Ok(exceeded)
+}
+
+/// The `max_latency` budget of TARGET, if one is set and valid.
+pub fn max_latency(target: Option<&str>) -> Option<Duration> {
+    let budget = Settings::for_target(target).ok()?.budget?;
+    humantime::parse_duration(budget.max_latency.as_deref()?).ok()
+}
+
+/// Checks a finished request against its budget and keeps a warning for `report`.
+pub fn observe(config: &RequestConfig, target: Option<&str>, exchange: &Exchange) {
+    let warning = match exceeded(exchange, target) {
+        Ok(exceeded) if exceeded.is_empty() => return,
+        Ok(exceeded) => format!(
+            "{} {}: {}",
+            config.method.as_deref().unwrap_or("GET"),
+            target.unwrap_or_else(|| config.url.as_deref().unwrap_or("")),
+            exceeded.join(", ")
+        ),
+        Err(err) => format!("{:#}; skipping the budget check", err),
+    };
+    let mut warnings = WARNINGS.lock().unwrap_or_else(|e| e.into_inner());
+    // 同じ設定エラーを何度も表示しない
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+}
+
+/// Prints the highlighted budget warnings of this command to stderr.
+pub fn report() {
+    let warnings = std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(|e| e.into_inner()));
+    if warnings.is_empty() {
+        return;
+    }
+    let style = Style::new().yellow().bold().for_stderr();
+    eprintln!();
+    for warning in warnings {
+        eprintln!("{}", style.apply_to(i18n::tf("Budget warning: {}", &[&warning])));
+    }
+}
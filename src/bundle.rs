@@ -0,0 +1,355 @@
+use anyhow::{bail, Context, Result};
+use dialoguer::{console::Term, Select};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::cookies;
+use crate::diff;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::namespace;
+use crate::store;
+
+const BUNDLE_FORMAT: &str = "ferrapi_tester-bundle";
+const BUNDLE_VERSION: u32 = 1;
+
+/// Directory (under the base directory) keeping the last imported version of every
+/// file, the common ancestor for merging the next import with local changes.
+pub const IMPORT_BASE_DIR: &str = ".import_base";
+
+/// A file inside a bundle. JSON files are embedded as JSON so the bundle stays readable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleFile {
+    /// Path relative to the namespace directory, always with '/' separators.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A single self-contained document holding a whole namespace tree
+/// (saved configs, vars.json, settings.json, transaction files, ...).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Bundle {
+    pub format: String,
+    pub version: u32,
+    pub namespace: String,
+    pub files: Vec<BundleFile>,
+}
+
+fn collect(root: &Path, dir: &Path, files: &mut Vec<BundleFile>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect(root, &path, files)?;
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        // ルートの表示名はインポート先の名前空間名で決まるので含めない
+        if relative == namespace::METADATA_FILE {
+            continue;
+        }
+        // クッキージャーはログイン中のセッションなので共有しない
+        if path.file_name().is_some_and(|name| name == cookies::COOKIES_FILE) {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?} (only text files can be exported)", path))?;
+        let file = match serde_json::from_str::<Value>(&content) {
+            Ok(json) if path.extension().is_some_and(|ext| ext == "json") => BundleFile {
+                path: relative,
+                json: Some(json),
+                text: None,
+            },
+            _ => BundleFile {
+                path: relative,
+                json: None,
+                text: Some(content),
+            },
+        };
+        files.push(file);
+    }
+    Ok(())
+}
+
+/// Builds a bundle from the namespace directory.
+pub fn export(namespace: &str) -> Result<Bundle> {
+    let dir = namespace::dir(&get_default_dir()?, namespace)?;
+    if !dir.is_dir() {
+        bail!("No namespace directory found at {:?}", dir);
+    }
+    let mut files = Vec::new();
+    collect(&dir, &dir, &mut files)?;
+    Ok(Bundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        namespace: namespace.to_string(),
+        files,
+    })
+}
+
+impl BundleFile {
+    /// The file content as it is written to disk.
+    pub fn content(&self) -> Result<String> {
+        match (&self.json, &self.text) {
+            (Some(json), _) => Ok(serde_json::to_string_pretty(json)?),
+            (None, Some(text)) => Ok(text.clone()),
+            (None, None) => bail!("Bundle entry {} has no content", self.path),
+        }
+    }
+
+    /// Compares with existing file content; JSON is compared structurally so
+    /// formatting and key order do not count as a difference.
+    pub fn same_as(&self, existing: &str) -> bool {
+        match (&self.json, &self.text) {
+            (Some(json), _) => serde_json::from_str::<Value>(existing).is_ok_and(|v| &v == json),
+            (None, Some(text)) => text == existing,
+            (None, None) => false,
+        }
+    }
+}
+
+/// Returns the on-disk path of a bundle entry, rejecting paths that escape the namespace.
+fn entry_path(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        bail!("Refusing to import unsafe path {:?}", relative);
+    }
+    Ok(dir.join(relative))
+}
+
+/// Reads and validates a bundle file.
+pub fn read(file: &Path) -> Result<Bundle> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read bundle {:?}", file))?;
+    let bundle: Bundle = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse bundle {:?}", file))?;
+    if bundle.format != BUNDLE_FORMAT {
+        bail!("{:?} is not a ferrapi_tester bundle", file);
+    }
+    if bundle.version > BUNDLE_VERSION {
+        bail!("Bundle version {} is newer than this tool supports ({})", bundle.version, BUNDLE_VERSION);
+    }
+    Ok(bundle)
+}
+
+/// How a file that differs locally and in the bundle is resolved.
+enum Resolution {
+    KeepLocal,
+    TakeBundle,
+    Merged(String),
+}
+
+/// Compares file contents, structurally when both are JSON.
+fn same_content(a: &str, b: &str) -> bool {
+    match (serde_json::from_str::<Value>(a), serde_json::from_str::<Value>(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Where the last imported version of the file at PATH is kept.
+fn base_path(path: &Path) -> Result<PathBuf> {
+    let base_dir = get_default_dir()?;
+    let relative = path.strip_prefix(&base_dir).with_context(|| format!("{:?} is outside {:?}", path, base_dir))?;
+    Ok(base_dir.join(IMPORT_BASE_DIR).join(relative))
+}
+
+/// Merges the JSON documents LOCAL and BUNDLE field by field against their common
+/// ancestor BASE: a field changed on one side only takes that change, and CHOOSE
+/// picks (true for the bundle) between fields changed differently on both sides.
+/// `None` stands for a missing field.
+pub fn merge<F>(
+    path: &str,
+    base: Option<&Value>,
+    local: Option<&Value>,
+    bundle: Option<&Value>,
+    choose: &mut F,
+) -> Result<Option<Value>>
+where
+    F: FnMut(&str, Option<&Value>, Option<&Value>) -> Result<bool>,
+{
+    if local == bundle || base == bundle {
+        return Ok(local.cloned());
+    }
+    if base == local {
+        return Ok(bundle.cloned());
+    }
+    if let (Some(Value::Object(l)), Some(Value::Object(b))) = (local, bundle) {
+        let base = base.and_then(Value::as_object);
+        let mut merged = Map::new();
+        let keys = l.keys().chain(b.keys().filter(|key| !l.contains_key(*key)));
+        for key in keys {
+            let child = if path == "." { format!(".{}", key) } else { format!("{}.{}", path, key) };
+            let value = merge(&child, base.and_then(|base| base.get(key)), l.get(key), b.get(key), choose)?;
+            if let Some(value) = value {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Ok(Some(Value::Object(merged)));
+    }
+    Ok(if choose(path, local, bundle)? { bundle.cloned() } else { local.cloned() })
+}
+
+fn show(value: Option<&Value>) -> String {
+    value.map_or_else(|| "(none)".to_string(), Value::to_string)
+}
+
+/// Asks how to resolve a file changed both locally and in the bundle.
+fn resolve_interactively(file: &BundleFile, existing: &str, base: Option<&str>) -> Result<Resolution> {
+    let local = serde_json::from_str::<Value>(existing).ok();
+    let json = file.json.as_ref().zip(local.as_ref());
+    eprintln!("\n{}", i18n::tf("{} was changed both locally and in the bundle:", &[&file.path]));
+    if let Some((bundle, local)) = json {
+        for change in diff::diff(local, bundle) {
+            eprintln!("  {}", change.to_string().replace('\n', "\n  "));
+        }
+    }
+    let mut items = vec![i18n::t("Keep local"), i18n::t("Take the bundle's version")];
+    if json.is_some() {
+        items.push(i18n::t("Merge field by field"));
+    }
+    let selection = Select::new().with_prompt(file.path.as_str()).items(&items).default(0).interact()?;
+    let Some((bundle, local)) = json.filter(|_| selection == 2) else {
+        return Ok(if selection == 0 { Resolution::KeepLocal } else { Resolution::TakeBundle });
+    };
+    let base = base.and_then(|base| serde_json::from_str::<Value>(base).ok());
+    let mut choose = |path: &str, local: Option<&Value>, bundle: Option<&Value>| -> Result<bool> {
+        let items = [
+            i18n::tf("local:  {}", &[&show(local)]),
+            i18n::tf("bundle: {}", &[&show(bundle)]),
+        ];
+        Ok(Select::new().with_prompt(format!("{} {}", file.path, path)).items(&items).default(0).interact()? == 1)
+    };
+    let merged = merge(".", base.as_ref(), Some(local), Some(bundle), &mut choose)?.unwrap_or(Value::Null);
+    Ok(Resolution::Merged(serde_json::to_string_pretty(&merged)?))
+}
+
+/// Writes the bundle into `namespace` (or the namespace it was exported from).
+/// A file changed only in the bundle since the last import replaces the local one,
+/// and local changes to a file the bundle did not change are kept. Files changed on
+/// both sides are overwritten with `force`, resolved interactively on a terminal
+/// (keep local, take the bundle's version, or merge JSON field by field), and stop
+/// the import otherwise.
+pub fn import(bundle: &Bundle, namespace: Option<&str>, force: bool) -> Result<()> {
+    let namespace = namespace.unwrap_or(&bundle.namespace);
+    let dir = namespace::dir(&get_default_dir()?, namespace)?;
+
+    let mut planned = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut kept = 0;
+    for file in &bundle.files {
+        let path = entry_path(&dir, &file.path)?;
+        let content = file.content()?;
+        let base = fs::read_to_string(base_path(&path)?).ok();
+        match fs::read_to_string(&path) {
+            Ok(existing) if file.same_as(&existing) => continue,
+            // 前回の取り込みから変わっていない側は、もう一方の変更を採る
+            Ok(_) if base.as_deref().is_some_and(|base| file.same_as(base)) => {
+                kept += 1;
+                continue;
+            }
+            Ok(existing) if base.as_deref().is_some_and(|base| same_content(base, &existing)) || force => {}
+            Ok(existing) => {
+                conflicts.push((file, path, existing, base));
+                continue;
+            }
+            Err(_) => {}
+        }
+        planned.push((path, content));
+    }
+    if !conflicts.is_empty() {
+        if !(Term::stderr().is_term() && io::stdin().is_terminal()) {
+            let names: Vec<&str> = conflicts.iter().map(|(file, ..)| file.path.as_str()).collect();
+            bail!(
+                "{} file(s) in {:?} were changed both locally and in the bundle: {}. \
+                 Run the import in a terminal to merge them, or use --force to overwrite them.",
+                conflicts.len(),
+                dir,
+                names.join(", ")
+            );
+        }
+        for (file, path, existing, base) in conflicts {
+            match resolve_interactively(file, &existing, base.as_deref())? {
+                Resolution::KeepLocal => kept += 1,
+                Resolution::TakeBundle => planned.push((path, file.content()?)),
+                Resolution::Merged(content) => planned.push((path, content)),
+            }
+        }
+    }
+    namespace::create(&get_default_dir()?, namespace)?;
+    for (path, content) in &planned {
+        store::write_atomic(path, content)?;
+    }
+    // 次回の取り込みで三方向マージの基準にする
+    for file in &bundle.files {
+        store::write_atomic(&base_path(&entry_path(&dir, &file.path)?)?, file.content()?)?;
+    }
+    let unchanged = bundle.files.len() - planned.len() - kept;
+    println!(
+        "{}",
+        i18n::tf(
+            "Imported {} file(s) into {} ({} unchanged, {} kept local).",
+            &[&planned.len(), &format!("{:?}", dir), &unchanged, &kept]
+        )
+    );
+    // {{cmd:...}} はリクエスト時にシェルコマンドを実行するので、取り込んだ内容の確認を促す
+    let commands: Vec<&str> = bundle
+        .files
+        .iter()
+        .filter(|file| file.content().is_ok_and(|content| content.contains("{{cmd:")))
+        .map(|file| file.path.as_str())
+        .collect();
+    if !commands.is_empty() {
+        eprintln!(
+            "{}",
+            i18n::tf(
+                "Warning: {} run shell commands through {{cmd:...}} placeholders; review them before sending requests.",
+                &[&commands.join(", ")]
+            )
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_takes_one_sided_changes_and_asks_for_the_rest() {
+        let base = json!({ "url": "http://a", "method": "GET", "timeout": 30, "headers": { "X-A": "1" } });
+        let local =
+            json!({ "url": "http://local", "method": "GET", "timeout": 10, "headers": { "X-A": "1", "X-L": "2" } });
+        let bundle = json!({ "url": "http://bundle", "method": "POST", "timeout": 30, "headers": { "X-A": "9" } });
+        let mut asked = Vec::new();
+        let mut choose = |path: &str, _: Option<&Value>, _: Option<&Value>| {
+            asked.push(path.to_string());
+            Ok(true)
+        };
+        let merged = merge(".", Some(&base), Some(&local), Some(&bundle), &mut choose).unwrap();
+        let expected =
+            json!({ "url": "http://bundle", "method": "POST", "timeout": 10, "headers": { "X-A": "9", "X-L": "2" } });
+        assert_eq!(merged, Some(expected));
+        assert_eq!(asked, vec![".url"]);
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use dialoguer::console::Term;
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tokio::sync::Notify;
+
+use crate::i18n;
+
+/// Exit status used after an interrupt (128 + SIGINT), as shells expect.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Error returned by work that was stopped with Ctrl-C.
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Interrupted")
+    }
+}
+
+impl Error for Interrupted {}
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static NOTIFY: Notify = Notify::const_new();
+
+/// Installs the Ctrl-C handler. The first Ctrl-C asks running work to stop so
+/// partial results can be written; a second one quits immediately.
+pub fn install() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if CANCELLED.swap(true, Ordering::SeqCst) {
+                restore_terminal();
+                process::exit(EXIT_INTERRUPTED);
+            }
+            eprintln!("{}", i18n::t("\nInterrupted; finishing up (press Ctrl-C again to quit immediately)."));
+            NOTIFY.notify_waiters();
+        }
+    });
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Completes once Ctrl-C has been pressed.
+pub async fn cancelled() {
+    let notified = NOTIFY.notified();
+    if is_cancelled() {
+        return;
+    }
+    notified.await;
+}
+
+/// Runs `future` unless Ctrl-C is pressed first, in which case it is dropped
+/// and an "Interrupted" error is returned.
+pub async fn guard<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        _ = cancelled() => Err(Interrupted.into()),
+    }
+}
+
+/// Shows the cursor again in case an interactive prompt was aborted while it was hidden.
+pub fn restore_terminal() {
+    for term in [Term::stderr(), Term::stdout()] {
+        if term.is_term() {
+            let _ = term.show_cursor();
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Context, Result};
+use clap::{Subcommand, ValueHint};
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::cancel;
+use crate::exit_code;
+use crate::filter::Filter;
+use crate::i18n;
+use crate::output::{self, Table};
+use crate::retry::{RetryArgs, RetryPolicy};
+use crate::transaction::{self, Call};
+use crate::vars::{self, Vars};
+
+/// Subcommands of `chain`.
+#[derive(Subcommand, Debug)]
+pub enum ChainCommand {
+    /// チェーンファイル（YAML または JSON）のステップを順に実行し、レスポンスから取り出した値を後続のステップの変数として使います。
+    Run {
+        /// ステップを定義したチェーンファイル（例: flow.yaml）
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+    },
+}
+
+/// A file run with `chain run FILE`.
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ChainFile {
+    /// Initial variables, e.g. a base URL shared by the steps.
+    #[serde(default)]
+    vars: Vars,
+    steps: Vec<ChainStep>,
+}
+
+/// A step of a chain: a saved TARGET and/or inline request, like a transaction step.
+#[derive(Deserialize, Debug, JsonSchema)]
+struct ChainStep {
+    name: Option<String>,
+    #[serde(flatten)]
+    call: Call,
+    /// Variables to set from the response body: NAME: PATH (JSONPath or a jq-style path).
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+}
+
+/// Reads VARIABLES' paths from the response BODY; strings are taken without quotes and
+/// other values as JSON. A value containing a placeholder fails the step, so that a server
+/// cannot put `{{...}}` into the following requests.
+fn extract(body: &Value, variables: &BTreeMap<String, String>) -> Result<Vars> {
+    variables
+        .iter()
+        .map(|(name, path)| {
+            let value = match Filter::parse(path).map_err(exit_code::config)?.apply(body) {
+                Value::Null => {
+                    return Err(exit_code::assertion(anyhow!("{} not found in the response for {}", path, name)))
+                }
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            if vars::has_placeholder(&value) {
+                return Err(exit_code::assertion(anyhow!("{} contains '{{{{' and is not used for {}", path, name)));
+            }
+            Ok((name.clone(), value))
+        })
+        .collect()
+}
+
+/// Runs the steps of the chain FILE in order, stopping at the first failure. Values
+/// extracted by a step are `{{name}}` variables of the following steps.
+async fn run_file(file: &Path, retry: &RetryPolicy) -> Result<()> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read chain file {:?}", file))
+        .map_err(exit_code::config)?;
+    // JSON も YAML として読める
+    let chain: ChainFile = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse chain file {:?}", file))
+        .map_err(exit_code::config)?;
+    vars::set_runtime_vars(chain.vars);
+
+    let renderer = output::renderer()?;
+    let mut table = Table::new(&[
+        ("STEP", "step"),
+        ("REQUEST", "request"),
+        ("STATUS", "status"),
+        ("RESULT", "result"),
+        ("EXTRACTED", "extracted"),
+    ]);
+    let suite = file.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let total = chain.steps.len();
+    let mut failure = None;
+    for (index, step) in chain.steps.iter().enumerate() {
+        let label = format!("{}/{}", suite, step.name.clone().unwrap_or_else(|| format!("step {}", index + 1)));
+        let config = step.call.resolve().map_err(exit_code::config)?;
+        let result = async {
+            let (status, body) = cancel::guard(step.call.execute(&config, retry, file)).await?;
+            let extracted = extract(&body, &step.extract)?;
+            anyhow::Ok((status, extracted))
+        }
+        .await;
+        let request = transaction::describe(&config);
+        match result {
+            Ok((status, extracted)) => {
+                // 取り出した値はトークンのこともあるため、名前だけを表示する
+                let names = extracted.keys().cloned().collect::<Vec<_>>().join(", ");
+                let suffix = if names.is_empty() { String::new() } else { format!(" ({})", names) };
+                renderer.note(&format!("[{}/{}] {}: {} -> {}{}", index + 1, total, label, request, status, suffix));
+                let extracted_names = (!names.is_empty()).then_some(names.clone());
+                table.push(vec![json!(label), json!(request), json!(status), json!("ok"), json!(extracted_names)]);
+                vars::set_runtime_vars(extracted);
+            }
+            Err(err) => {
+                renderer.note(&i18n::tf(
+                    "[{}/{}] {}: {} -> failed: {}",
+                    &[&(index + 1), &total, &label, &request, &err],
+                ));
+                let result = json!(format!("FAIL {}", err));
+                table.push(vec![json!(label), json!(request), Value::Null, result, Value::Null]);
+                failure = Some((label, exit_code::of(&err)));
+                break;
+            }
+        }
+    }
+    // human 形式では進捗行がそのまま結果になるので、表はそれ以外の形式でだけ出力する
+    if !renderer.human() {
+        print!("{}", renderer.table(&table)?);
+    }
+    match failure {
+        Some((label, code)) => Err(exit_code::classify(code, anyhow!("Chain failed at '{}'", label))),
+        None => {
+            renderer.note(&i18n::tf("Chain completed: {} step(s) succeeded.", &[&total]));
+            Ok(())
+        }
+    }
+}
+
+/// JSON Schema of chain files.
+pub fn file_schema() -> RootSchema {
+    schemars::schema_for!(ChainFile)
+}
+
+pub async fn run(command: ChainCommand) -> Result<()> {
+    match command {
+        ChainCommand::Run { file, retry } => run_file(&file, &retry.policy()).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_extracted_with_json_and_jq_paths() {
+        let body = json!({"access_token": "abc", "user": {"id": 42, "roles": ["admin"]}});
+        let paths = BTreeMap::from([
+            ("token".to_string(), "$.access_token".to_string()),
+            ("user_id".to_string(), ".user.id".to_string()),
+            ("roles".to_string(), "$.user.roles".to_string()),
+        ]);
+        let extracted = extract(&body, &paths).unwrap();
+        assert_eq!(extracted["token"], "abc");
+        assert_eq!(extracted["user_id"], "42");
+        assert_eq!(extracted["roles"], "[\"admin\"]");
+        let missing = BTreeMap::from([("id".to_string(), "$.missing".to_string())]);
+        assert_eq!(exit_code::of(&extract(&body, &missing).unwrap_err()), exit_code::ASSERTION);
+    }
+
+    #[test]
+    fn values_with_placeholders_are_not_extracted() {
+        let body = json!({"token": "{{cmd:touch /tmp/pwned}}", "user": {"name": "{{env:HOME}}"}});
+        let paths = BTreeMap::from([("token".to_string(), "$.token".to_string())]);
+        let err = extract(&body, &paths).unwrap_err();
+        assert_eq!(exit_code::of(&err), exit_code::ASSERTION);
+        // JSON として取り出す値も対象にする
+        let paths = BTreeMap::from([("user".to_string(), ".user".to_string())]);
+        assert!(extract(&body, &paths).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use base64::Engine;
+use serde::{de::DeserializeOwned, de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::settings::Settings;
+
+/// Values whose JSON is shorter than this are always stored as is.
+const MIN_SIZE: usize = 1024;
+
+const LEVEL: i32 = 3;
+
+/// Whether large values are compressed (`compress_recordings` in settings.json, on by default).
+fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| Settings::load().map_or(true, |settings| settings.compress_recordings != Some(false)))
+}
+
+fn engine() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// Returns VALUE as `{"zstd": BASE64}` when that is smaller than its JSON, otherwise unchanged.
+pub fn pack(value: Value) -> Value {
+    if !enabled() {
+        return value;
+    }
+    let Ok(json) = serde_json::to_vec(&value) else {
+        return value;
+    };
+    if json.len() < MIN_SIZE {
+        return value;
+    }
+    match zstd::bulk::compress(&json, LEVEL) {
+        Ok(compressed) if compressed.len() * 4 / 3 < json.len() => {
+            serde_json::json!({ "zstd": engine().encode(compressed) })
+        }
+        _ => value,
+    }
+}
+
+/// Reverses `pack`; any other value is returned unchanged.
+pub fn unpack(value: Value) -> Result<Value, String> {
+    let packed = match value {
+        Value::Object(ref object) if object.len() == 1 => object.get("zstd").and_then(Value::as_str),
+        _ => None,
+    };
+    let Some(packed) = packed else {
+        return Ok(value);
+    };
+    let compressed = engine().decode(packed).map_err(|e| format!("invalid compressed value: {}", e))?;
+    let json = zstd::stream::decode_all(compressed.as_slice()).map_err(|e| format!("invalid compressed value: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("invalid compressed value: {}", e))
+}
+
+/// `#[serde(with = "compress")]`: stores large fields zstd-compressed and decompresses
+/// them transparently when read, so plain values written before keep working.
+pub fn serialize<T: Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    pack(serde_json::to_value(value).map_err(S::Error::custom)?).serialize(serializer)
+}
+
+pub fn deserialize<'de, T: DeserializeOwned, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    let value = unpack(Value::deserialize(deserializer)?).map_err(D::Error::custom)?;
+    serde_json::from_value(value).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pack_compresses_only_large_values() {
+        let small = json!({ "name": "x" });
+        assert_eq!(pack(small.clone()), small);
+
+        let large = json!({ "items": vec!["the same line of text"; 200] });
+        let packed = pack(large.clone());
+        assert!(packed["zstd"].is_string());
+        assert!(packed.to_string().len() < large.to_string().len() / 4);
+        assert_eq!(unpack(packed).unwrap(), large);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use cookie_store::{Cookie, CookieStore, RawCookie};
+use reqwest::header::HeaderValue;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use url::Url;
+
+use crate::namespace;
+use crate::request::RequestConfig;
+use crate::store;
+use crate::get_default_dir;
+
+/// File of a cookie jar.
+pub const COOKIES_FILE: &str = "cookies.json";
+
+/// The cookie jar of a namespace. Cookies received while sending are kept in memory for
+/// the following requests of the process and written back by `save`.
+pub struct Jar {
+    path: PathBuf,
+    store: Mutex<CookieStore>,
+    /// Set-Cookie headers received by this process, replayed on the jar file when saving so
+    /// that invocations running at the same time keep each other's cookies.
+    received: Mutex<Vec<(Url, Vec<String>)>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Jar file of TARGET: the nearest cookies.json of its namespace tree, or one in its first
+/// segment. Requests sent to a URL directly share the jar of the base directory.
+fn path(base_dir: &Path, target: Option<&str>) -> Result<PathBuf> {
+    let Some(target) = target.filter(|t| !t.starts_with("http")) else {
+        return Ok(base_dir.join(COOKIES_FILE));
+    };
+    let dirs = namespace::ancestry(base_dir, target)?;
+    let nearest = dirs.iter().rev().map(|dir| dir.join(COOKIES_FILE)).find(|file| file.exists());
+    Ok(nearest.unwrap_or_else(|| dirs[0].join(COOKIES_FILE)))
+}
+
+fn read(path: &Path) -> Result<CookieStore> {
+    if !path.exists() {
+        return Ok(CookieStore::default());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read cookie jar {:?}", path))?;
+    let cookies: Vec<Cookie<'static>> =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse cookie jar {:?}", path))?;
+    CookieStore::from_cookies(cookies.into_iter().map(anyhow::Ok), false)
+        .map_err(|e| anyhow::anyhow!("Failed to load cookie jar {:?}: {}", path, e))
+}
+
+fn store_received(store: &mut CookieStore, headers: &[String], url: &Url) {
+    let cookies = headers.iter().filter_map(|header| RawCookie::parse(header.clone()).ok());
+    store.store_response_cookies(cookies, url);
+}
+
+/// Opens the jar for TARGET when CONFIG enables cookies.
+pub fn open(config: &RequestConfig, target: Option<&str>) -> Result<Option<Arc<Jar>>> {
+    if config.cookies != Some(true) {
+        return Ok(None);
+    }
+    let path = path(&get_default_dir()?, target)?;
+    let store = read(&path)?;
+    Ok(Some(Arc::new(Jar { path, store: Mutex::new(store), received: Mutex::default() })))
+}
+
+impl Jar {
+    /// Writes the cookies received so far to the jar file, on top of its current contents.
+    /// Session cookies are kept too, so that a login is reused by later invocations.
+    pub fn save(&self) -> Result<()> {
+        let received = lock(&self.received);
+        if received.is_empty() {
+            return Ok(());
+        }
+        let _lock = store::lock(&self.path)?;
+        let mut store = read(&self.path)?;
+        for (url, headers) in received.iter() {
+            store_received(&mut store, headers, url);
+        }
+        let cookies: Vec<&Cookie> = store.iter_unexpired().collect();
+        store::write_private(&self.path, serde_json::to_string_pretty(&cookies)?)
+    }
+}
+
+impl reqwest::cookie::CookieStore for Jar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let headers: Vec<String> =
+            cookie_headers.filter_map(|value| value.to_str().ok()).map(str::to_string).collect();
+        store_received(&mut lock(&self.store), &headers, url);
+        lock(&self.received).push((url.clone(), headers));
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let pairs: Vec<String> =
+            lock(&self.store).get_request_values(url).map(|(name, value)| format!("{}={}", name, value)).collect();
+        if pairs.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&pairs.join("; ")).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::cookie::CookieStore as _;
+
+    #[test]
+    fn cookies_are_sent_back_to_the_host_that_set_them() {
+        let jar = Jar { path: PathBuf::new(), store: Mutex::default(), received: Mutex::default() };
+        let login = Url::parse("http://api.example.com/login").unwrap();
+        let headers = [
+            HeaderValue::from_static("session=abc; Path=/"),
+            HeaderValue::from_static("theme=dark; Path=/admin"),
+        ];
+        jar.set_cookies(&mut headers.iter(), &login);
+        let me = Url::parse("http://api.example.com/me").unwrap();
+        assert_eq!(jar.cookies(&me).unwrap(), "session=abc");
+        assert!(jar.cookies(&Url::parse("http://other.example.com/").unwrap()).is_none());
+        let logout = [HeaderValue::from_static("session=; Path=/; Max-Age=0")];
+        jar.set_cookies(&mut logout.iter(), &login);
+        assert!(jar.cookies(&me).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use clap::{Subcommand, ValueEnum};
+use dialoguer::{console::Term, Password};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, IsTerminal, Read},
+    num::NonZeroU32,
+    path::PathBuf,
+};
+
+use crate::get_default_dir;
+use crate::i18n;
+use crate::settings::Settings;
+use crate::store;
+
+/// Service name under which secrets are kept in the system keychain.
+const SERVICE: &str = "ferrapi_tester";
+
+/// File (under the base directory) of the encrypted-file backend.
+pub const CREDENTIALS_FILE: &str = "credentials.enc.json";
+
+/// Environment variable holding the passphrase of the encrypted-file backend.
+pub const PASSPHRASE_ENV: &str = "FERRAPI_CREDENTIAL_PASSPHRASE";
+
+/// Environment variable that overrides the `credential_store` setting.
+pub const BACKEND_ENV: &str = "FERRAPI_CREDENTIAL_STORE";
+
+/// Prefix of the environment variables read by the env backend.
+pub const ENV_PREFIX: &str = "FERRAPI_SECRET_";
+
+const PBKDF2_ITERATIONS: u32 = 200_000;
+
+/// Where `{{secret:NAME}}` values and `secret set` store their data.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// The system keychain: macOS Keychain, Windows Credential Manager or the Linux kernel keyring.
+    #[default]
+    Keyring,
+    /// A file in the base directory encrypted with a passphrase (AES-256-GCM).
+    File,
+    /// Read-only FERRAPI_SECRET_<NAME> environment variables, e.g. from a CI secret store.
+    Env,
+}
+
+/// Subcommands of `secret`.
+#[derive(Subcommand, Debug)]
+pub enum SecretCommand {
+    /// シークレットを資格情報ストアに保存します（値は標準入力または入力プロンプトから読み取ります）。
+    Set {
+        /// シークレット名（{{secret:NAME}} で参照します）
+        name: String,
+    },
+    /// シークレットの値を表示します。
+    Get {
+        /// シークレット名
+        name: String,
+    },
+    /// シークレットを資格情報ストアから削除します。
+    Delete {
+        /// シークレット名
+        name: String,
+    },
+}
+
+/// Storage for secrets, independent of where they are actually kept.
+pub trait CredentialStore {
+    /// Short description shown by `doctor` and in messages.
+    fn describe(&self) -> String;
+    /// Returns the secret NAME, or None if it is not stored.
+    fn get(&self, name: &str) -> Result<Option<String>>;
+    fn set(&self, name: &str, value: &str) -> Result<()>;
+    /// Removes the secret NAME; returns false if it did not exist.
+    fn delete(&self, name: &str) -> Result<bool>;
+}
+
+/// The native credential store of the operating system.
+pub struct Keyring;
+
+impl Keyring {
+    fn entry(name: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE, name).with_context(|| format!("Invalid secret name '{}'", name))
+    }
+}
+
+impl CredentialStore for Keyring {
+    fn describe(&self) -> String {
+        let native = if cfg!(target_os = "macos") {
+            "macOS Keychain"
+        } else if cfg!(windows) {
+            "Windows Credential Manager"
+        } else {
+            "Linux kernel keyring"
+        };
+        format!("keyring ({})", native)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        match Self::entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(anyhow!(err)).with_context(|| format!("Failed to read secret '{}' from the keyring", name)),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        Self::entry(name)?
+            .set_password(value)
+            .with_context(|| {
+                format!(
+                    "Failed to store secret '{}' in the keyring (without a keychain, set \"credential_store\" to \"file\" or \"env\")",
+                    name
+                )
+            })
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        match Self::entry(name)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(anyhow!(err)).with_context(|| format!("Failed to delete secret '{}' from the keyring", name)),
+        }
+    }
+}
+
+/// Contents of the encrypted file. Each entry is base64 of nonce + ciphertext + tag,
+/// sealed with the entry name as associated data so entries cannot be swapped.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct EncryptedFileContent {
+    salt: String,
+    entries: BTreeMap<String, String>,
+}
+
+/// Secrets in a passphrase-encrypted file, for machines without a keychain.
+pub struct EncryptedFile {
+    pub path: PathBuf,
+}
+
+impl EncryptedFile {
+    fn read(&self) -> Result<EncryptedFileContent> {
+        if !self.path.exists() {
+            return Ok(EncryptedFileContent::default());
+        }
+        let content = fs::read_to_string(&self.path).with_context(|| format!("Failed to read {:?}", self.path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", self.path))
+    }
+
+    /// Derives the key from the passphrase, asking for it on a terminal when the
+    /// environment variable is not set.
+    fn key(salt: &[u8]) -> Result<LessSafeKey> {
+        let passphrase = match env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) if Term::stderr().is_term() => Password::new().with_prompt("Credential file passphrase").interact()?,
+            Err(_) => bail!("Set {} to unlock the encrypted credential file", PASSPHRASE_ENV),
+        };
+        let mut key = [0u8; 32];
+        let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iteration count is not zero");
+        pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+        let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow!("Failed to create the encryption key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+fn engine() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// Decrypts one entry of the encrypted file.
+fn unseal(key: &LessSafeKey, name: &str, sealed: &str) -> Result<String> {
+    let mut sealed = engine().decode(sealed).with_context(|| format!("Corrupt entry '{}' in the credential file", name))?;
+    if sealed.len() < NONCE_LEN {
+        bail!("Corrupt entry '{}' in the credential file", name);
+    }
+    let mut ciphertext = sealed.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&sealed).map_err(|_| anyhow!("Invalid nonce for '{}'", name))?;
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(name.as_bytes()), &mut ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt secret '{}' (wrong passphrase?)", name))?;
+    String::from_utf8(plaintext.to_vec()).context("Decrypted secret is not UTF-8")
+}
+
+impl CredentialStore for EncryptedFile {
+    fn describe(&self) -> String {
+        format!("encrypted file {:?}", self.path)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let content = self.read()?;
+        let Some(sealed) = content.entries.get(name) else {
+            return Ok(None);
+        };
+        let key = Self::key(&engine().decode(&content.salt).context("Corrupt salt in the credential file")?)?;
+        unseal(&key, name, sealed).map(Some)
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        let _lock = store::lock(&self.path)?;
+        let mut content = self.read()?;
+        let random = SystemRandom::new();
+        if content.salt.is_empty() {
+            let mut salt = [0u8; 16];
+            random.fill(&mut salt).map_err(|_| anyhow!("Failed to generate a salt"))?;
+            content.salt = engine().encode(salt);
+        }
+        let key = Self::key(&engine().decode(&content.salt).context("Corrupt salt in the credential file")?)?;
+        // 同じパスフレーズであることを、既存のエントリが復号できるかで確認する
+        if let Some((existing, sealed)) = content.entries.iter().next() {
+            unseal(&key, existing, sealed)?;
+        }
+        let mut nonce = [0u8; NONCE_LEN];
+        random.fill(&mut nonce).map_err(|_| anyhow!("Failed to generate a nonce"))?;
+        let mut sealed = value.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut sealed)
+            .map_err(|_| anyhow!("Failed to encrypt secret '{}'", name))?;
+        content
+            .entries
+            .insert(name.to_string(), engine().encode([nonce.as_slice(), &sealed].concat()));
+        store::write_private(&self.path, serde_json::to_string_pretty(&content)?)
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        let _lock = store::lock(&self.path)?;
+        let mut content = self.read()?;
+        if content.entries.remove(name).is_none() {
+            return Ok(false);
+        }
+        store::write_private(&self.path, serde_json::to_string_pretty(&content)?)?;
+        Ok(true)
+    }
+}
+
+/// Read-only secrets from FERRAPI_SECRET_<NAME> environment variables.
+pub struct EnvVars;
+
+impl CredentialStore for EnvVars {
+    fn describe(&self) -> String {
+        format!("environment variables ({}<NAME>)", ENV_PREFIX)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(env::var(format!("{}{}", ENV_PREFIX, name)).ok())
+    }
+
+    fn set(&self, name: &str, _value: &str) -> Result<()> {
+        bail!(
+            "The env credential store is read-only; set {}{} in the environment instead",
+            ENV_PREFIX,
+            name
+        )
+    }
+
+    fn delete(&self, name: &str) -> Result<bool> {
+        bail!("The env credential store is read-only; unset {}{} instead", ENV_PREFIX, name)
+    }
+}
+
+/// Returns the backend chosen by FERRAPI_CREDENTIAL_STORE or settings.json (default: keyring).
+pub fn backend() -> Result<Backend> {
+    match env::var(BACKEND_ENV) {
+        Ok(value) => Backend::from_str(&value, true)
+            .map_err(|_| anyhow!("Unknown credential store '{}' in {} (use keyring, file or env)", value, BACKEND_ENV)),
+        Err(_) => Ok(Settings::load()?.credential_store.unwrap_or_default()),
+    }
+}
+
+/// Opens the configured credential store.
+pub fn open() -> Result<Box<dyn CredentialStore>> {
+    Ok(match backend()? {
+        Backend::Keyring => Box::new(Keyring),
+        Backend::File => Box::new(EncryptedFile {
+            path: get_default_dir()?.join(CREDENTIALS_FILE),
+        }),
+        Backend::Env => Box::new(EnvVars),
+    })
+}
+
+/// Returns the secret NAME for a `{{secret:NAME}}` placeholder.
+pub fn lookup(name: &str) -> Result<String> {
+    let store = open()?;
+    store
+        .get(name)?
+        .with_context(|| format!("Secret '{}' is not in the credential store ({})", name, store.describe()))
+}
+
+/// Reads the value to store: from a prompt on a terminal, otherwise from stdin.
+fn read_value(name: &str) -> Result<String> {
+    if Term::stderr().is_term() && io::stdin().is_terminal() {
+        return Ok(Password::new().with_prompt(format!("Value for {}", name)).interact()?);
+    }
+    let mut value = String::new();
+    io::stdin().read_to_string(&mut value).context("Failed to read the secret from stdin")?;
+    Ok(value.trim_end_matches(['\r', '\n']).to_string())
+}
+
+pub fn run(command: SecretCommand) -> Result<()> {
+    let store = open()?;
+    match command {
+        SecretCommand::Set { name } => {
+            let value = read_value(&name)?;
+            if value.is_empty() {
+                bail!("No value given for secret '{}'", name);
+            }
+            store.set(&name, &value)?;
+            println!(
+                "{}",
+                i18n::tf("Stored secret '{}' in {}. Use it as {{secret:{}}}", &[&name, &store.describe(), &name])
+            );
+        }
+        SecretCommand::Get { name } => match store.get(&name)? {
+            Some(value) => println!("{}", value),
+            None => bail!("Secret '{}' is not in the credential store ({})", name, store.describe()),
+        },
+        SecretCommand::Delete { name } => {
+            if !store.delete(&name)? {
+                bail!("Secret '{}' is not in the credential store ({})", name, store.describe());
+            }
+            println!("{}", i18n::tf("Deleted secret '{}' from {}", &[&name, &store.describe()]));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,466 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, IsTerminal, Read},
+};
+
+use crate::auth::Auth;
+use crate::form::{self, FormPart};
+use crate::get_config_path;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::mount;
+use crate::request::{self, RequestConfig};
+use crate::saved;
+use crate::vars;
+
+/// Formats of `export` other than the namespace bundle.
+#[derive(Subcommand, Debug)]
+pub enum ExportCommand {
+    /// 保存済み設定と同じリクエストを送る curl コマンドを表示します（変数は展開されます）。
+    Curl {
+        /// 対象の保存済み設定（例: SystemA/example）
+        target: String,
+
+        /// HTTP メソッド
+        #[arg(short = 'X', long = "method", default_value = "GET")]
+        method: String,
+    },
+}
+
+/// Options that only change how curl prints or follows the response; they are skipped.
+const IGNORED_FLAGS: &[&str] = &[
+    "-s", "--silent", "-S", "--show-error", "-v", "--verbose", "-i", "--include", "-L", "--location", "-f",
+    "--fail", "--fail-with-body", "-#", "--progress-bar", "--no-progress-meter", "--compressed", "-g", "--globoff",
+];
+
+/// Ignored options that take a value.
+const IGNORED_OPTIONS: &[&str] =
+    &["-o", "--output", "-w", "--write-out", "--retry", "--retry-delay", "--retry-max-time", "-D", "--dump-header"];
+
+/// Short options whose value may be attached, as in `-XPOST`.
+const SHORT_WITH_VALUE: &[char] = &['X', 'H', 'd', 'u', 'A', 'e', 'b', 'm', 'o', 'w', 'D', 'F'];
+
+/// Splits a shell command line into words, handling single and double quotes,
+/// backslash escapes and line continuations as a POSIX shell does.
+pub fn split(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => bail!("Unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // 二重引用符の中では \" \\ \$ \` と行継続だけが特別
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => bail!("Unterminated double quote"),
+                        },
+                        Some(c) => current.push(c),
+                        None => bail!("Unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // 行末の \ は次の行に続く
+                Some('\n') | Some('\r') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Percent-encodes TEXT like `--data-urlencode` does.
+fn url_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Reads `@FILE` data arguments; anything else is returned as is.
+fn read_data(value: &str) -> Result<String> {
+    match value.strip_prefix('@') {
+        Some("-") => bail!("Reading curl data from stdin (@-) is not supported; put the body in the command or a file"),
+        Some(path) => fs::read_to_string(path).with_context(|| format!("Failed to read data file {:?}", path)),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Encodes a `--data-urlencode` value: "content", "=content", "name=content",
+/// "@file" or "name@file".
+fn encode_data(value: &str) -> Result<String> {
+    if let Some((name, content)) = value.split_once('=') {
+        let content = url_encode(content);
+        return Ok(if name.is_empty() { content } else { format!("{}={}", name, content) });
+    }
+    match value.split_once('@') {
+        Some((name, path)) => {
+            let content = url_encode(&read_data(&format!("@{}", path))?);
+            Ok(if name.is_empty() { content } else { format!("{}={}", name, content) })
+        }
+        None => Ok(url_encode(value)),
+    }
+}
+
+fn seconds(option: &str, value: &str) -> Result<u64> {
+    let seconds: f64 = value.parse().with_context(|| format!("Invalid {} value: {}", option, value))?;
+    Ok(seconds.ceil().max(1.0) as u64)
+}
+
+/// Parses a curl command line (with or without the leading `curl`) into a request configuration.
+pub fn parse(command: &str) -> Result<RequestConfig> {
+    let mut words = split(command)?.into_iter().peekable();
+    if words.peek().is_some_and(|word| word == "curl" || word.ends_with("/curl")) {
+        words.next();
+    }
+    let mut url = None;
+    let mut method = None;
+    let mut headers = HashMap::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut json = false;
+    let mut get = false;
+    let mut config = RequestConfig { timeout: Some(30), ..RequestConfig::default() };
+    while let Some(word) = words.next() {
+        if !word.starts_with('-') || word == "-" {
+            if url.replace(word.clone()).is_some() {
+                bail!("The curl command has more than one URL; import one request at a time");
+            }
+            continue;
+        }
+        if IGNORED_FLAGS.contains(&word.as_str()) {
+            continue;
+        }
+        // -XPOST のように値が続いている短いオプションと、-sSL のようにまとめたフラグ
+        let (option, attached) = match word.strip_prefix('-').and_then(|rest| rest.chars().next()) {
+            Some(letter) if !word.starts_with("--") && word.len() > 2 => {
+                if SHORT_WITH_VALUE.contains(&letter) {
+                    (format!("-{}", letter), Some(word[2..].to_string()))
+                } else {
+                    let flags: Vec<String> = word[1..].chars().map(|c| format!("-{}", c)).collect();
+                    if let Some(unknown) = flags.iter().find(|flag| !IGNORED_FLAGS.contains(&flag.as_str())) {
+                        bail!("Unsupported curl option {} in {}", unknown, word);
+                    }
+                    continue;
+                }
+            }
+            _ => (word.clone(), None),
+        };
+        let mut value = || match attached.clone() {
+            Some(value) => Ok(value),
+            None => words.next().with_context(|| format!("curl option {} needs a value", option)),
+        };
+        match option.as_str() {
+            "--url" => url = Some(value()?),
+            "-X" | "--request" => method = Some(value()?.to_uppercase()),
+            "-I" | "--head" => method = Some("HEAD".to_string()),
+            "-G" | "--get" => get = true,
+            "-H" | "--header" => {
+                let header = value()?;
+                let Some((name, value)) = header.split_once(':') else {
+                    bail!("Invalid curl header (expected \"Name: value\"): {}", header);
+                };
+                headers.insert(name.trim().to_string(), value.trim().to_string());
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => data.push(read_data(&value()?)?),
+            "--data-raw" => data.push(value()?),
+            "--data-urlencode" => data.push(encode_data(&value()?)?),
+            "--json" => {
+                data.push(read_data(&value()?)?);
+                json = true;
+            }
+            "-u" | "--user" => config.auth = Some(Auth::basic(&value()?)?),
+            "-A" | "--user-agent" => {
+                headers.insert("User-Agent".to_string(), value()?);
+            }
+            "-e" | "--referer" => {
+                headers.insert("Referer".to_string(), value()?);
+            }
+            "-b" | "--cookie" => {
+                let cookie = value()?;
+                if !cookie.contains('=') {
+                    bail!("Cookie files (-b {}) are not supported; pass the cookies as NAME=VALUE", cookie);
+                }
+                headers.insert("Cookie".to_string(), cookie);
+            }
+            "-k" | "--insecure" => config.insecure = Some(true),
+            "--cacert" => config.cacert = Some(value()?),
+            "-E" | "--cert" => config.cert = Some(value()?),
+            "--key" => config.key = Some(value()?),
+            "-m" | "--max-time" => config.timeout = Some(seconds(&option, &value()?)?),
+            "--connect-timeout" => config.connect_timeout = Some(seconds(&option, &value()?)?),
+            "-F" | "--form" => {
+                let field = value()?;
+                let part = match field.split_once('=') {
+                    Some((_, value)) if value.starts_with('@') => FormPart::upload(&field)?,
+                    Some((name, value)) if value.starts_with('<') => {
+                        let path = value[1..].split(';').next().unwrap_or_default();
+                        let content =
+                            fs::read_to_string(path).with_context(|| format!("Failed to read {:?} for -F", path))?;
+                        FormPart::field(&format!("{}={}", name, content))?
+                    }
+                    _ => FormPart::field(&field)?,
+                };
+                config.form.get_or_insert_with(Vec::new).push(part);
+            }
+            "--form-string" => config.form.get_or_insert_with(Vec::new).push(FormPart::field(&value()?)?),
+            option if IGNORED_OPTIONS.contains(&option) => {
+                value()?;
+            }
+            option => bail!("Unsupported curl option {}", option),
+        }
+    }
+    let Some(mut url) = url else {
+        bail!("The curl command has no URL");
+    };
+    if !data.is_empty() && config.form.is_some() {
+        bail!("The curl command has both -d and -F; curl does not allow mixing them either");
+    }
+    if !data.is_empty() {
+        let body = data.join("&");
+        if get {
+            // -G はデータをクエリ文字列として付ける
+            url = format!("{}{}{}", url, if url.contains('?') { '&' } else { '?' }, body);
+        } else {
+            if json {
+                headers.entry("Content-Type".to_string()).or_insert_with(|| "application/json".to_string());
+                headers.entry("Accept".to_string()).or_insert_with(|| "application/json".to_string());
+            }
+            let content_type = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+            let urlencoded = content_type.is_none_or(|(_, value)| value.contains("x-www-form-urlencoded"));
+            match serde_json::from_str::<Value>(&body) {
+                Ok(value) => config.data = Some(value),
+                // curl は -d のボディを application/x-www-form-urlencoded として送る
+                Err(_) if urlencoded && !json && form::parse_urlencoded(&body).is_some() => {
+                    config.form_urlencoded = form::parse_urlencoded(&body);
+                }
+                Err(_) => {
+                    eprintln!(
+                        "{}",
+                        i18n::tf("Warning: the body is not JSON and is saved as a JSON string: {}", &[&body])
+                    );
+                    config.data = Some(Value::String(body));
+                }
+            }
+        }
+    }
+    config.method = Some(method.unwrap_or_else(|| {
+        let body = config.data.is_some() || config.form.is_some() || config.form_urlencoded.is_some();
+        if body { "POST" } else { "GET" }.to_string()
+    }));
+    config.url = Some(url);
+    config.headers = Some(headers);
+    Ok(config)
+}
+
+/// Parses COMMAND (read from stdin when omitted or "-") and prints the configuration,
+/// or saves it as TARGET.
+pub fn import(command: Option<&str>, save: Option<&str>, force: bool) -> Result<()> {
+    let command = match command {
+        Some(command) if command != "-" => command.to_string(),
+        _ => {
+            if io::stdin().is_terminal() {
+                eprintln!("{}", i18n::t("Paste the curl command, then press Ctrl-D:"));
+            }
+            let mut command = String::new();
+            io::stdin().read_to_string(&mut command).context("Failed to read the curl command from stdin")?;
+            command
+        }
+    };
+    let config = parse(&command)?;
+    let Some(target) = save else {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(());
+    };
+    let method = config.method.clone().unwrap_or_else(|| "GET".to_string());
+    let path = get_config_path(&get_default_dir()?, target, &method)?;
+    if path.exists() && !force {
+        bail!("{:?} already exists. Use --force to overwrite.", path);
+    }
+    saved::save(target, &method, &config)
+}
+
+/// Quotes TEXT for a POSIX shell.
+fn quote(text: &str) -> String {
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c)) {
+        return text.to_string();
+    }
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// A curl command sending the same request as CONFIG, one option per line.
+pub fn command(config: &RequestConfig) -> String {
+    let method = config.method.as_deref().unwrap_or("GET").to_uppercase();
+    let mut command = format!("curl -X {} {}", method, quote(config.url.as_deref().unwrap_or_default()));
+    let mut push = |option: &str, value: &str| command.push_str(&format!(" \\\n  {} {}", option, quote(value)));
+    let mut headers: BTreeMap<&String, &String> = config.headers.iter().flatten().collect();
+    // 送信時と同様、ボディがあり Content-Type がなければ JSON とする
+    let content_type = "Content-Type".to_string();
+    let json = "application/json".to_string();
+    if config.data.is_some() && !headers.keys().any(|name| name.eq_ignore_ascii_case(&content_type)) {
+        headers.insert(&content_type, &json);
+    }
+    match config.auth {
+        Some(Auth::Basic { ref username, ref password }) => push("-u", &format!("{}:{}", username, password)),
+        Some(Auth::Bearer { ref token }) => push("-H", &format!("Authorization: Bearer {}", token)),
+        Some(Auth::ApiKey { ref header, ref value }) => {
+            headers.retain(|name, _| !name.eq_ignore_ascii_case(header));
+            push("-H", &format!("{}: {}", header, value));
+        }
+        _ => {}
+    }
+    for (name, value) in headers {
+        push("-H", &format!("{}: {}", name, value));
+    }
+    if let Some(ref data) = config.data {
+        let data = match config.canonical_json {
+            Some(true) => request::canonical_json(data.clone()),
+            _ => data.clone(),
+        };
+        push("--data-raw", &data.to_string());
+    }
+    for field in config.form_urlencoded.iter().flatten() {
+        push("--data-urlencode", &format!("{}={}", field.name, field.value.as_deref().unwrap_or_default()));
+    }
+    for part in config.form.iter().flatten() {
+        match (&part.value, &part.file) {
+            (_, Some(file)) => match part.content_type {
+                Some(ref content_type) => push("-F", &format!("{}=@{};type={}", part.name, file, content_type)),
+                None => push("-F", &format!("{}=@{}", part.name, file)),
+            },
+            (value, None) => push("--form-string", &format!("{}={}", part.name, value.as_deref().unwrap_or_default())),
+        }
+    }
+    for (option, path) in [("--cacert", &config.cacert), ("--cert", &config.cert), ("--key", &config.key)] {
+        if let Some(path) = path {
+            push(option, path);
+        }
+    }
+    if config.insecure == Some(true) {
+        command.push_str(" \\\n  -k");
+    }
+    if let Some(seconds) = config.connect_timeout {
+        command.push_str(&format!(" \\\n  --connect-timeout {}", seconds));
+    }
+    if let Some(seconds) = config.timeout {
+        command.push_str(&format!(" \\\n  --max-time {}", seconds));
+    }
+    command
+}
+
+/// Prints the saved configuration of TARGET and METHOD as a curl command with its
+/// variables resolved. Secret references stay placeholders so the command can be shared.
+pub fn export(target: &str, method: &str) -> Result<()> {
+    let path = mount::config_path(target, method)?;
+    if !path.exists() {
+        bail!("No configuration saved for {} {} ({:?})", method.to_uppercase(), target, path);
+    }
+    let config = vars::substitute(&request::load_saved(target, method)?, Some(target))?.with_query()?;
+    if matches!(config.auth, Some(Auth::OAuth2 { .. })) {
+        eprintln!(
+            "{}",
+            i18n::t("Note: the OAuth2 access token is not included; add -H 'Authorization: Bearer TOKEN'.")
+        );
+    }
+    println!("{}", command(&config));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_reads_a_documented_curl_example() {
+        let command = r#"curl -sS -X POST 'https://api.example.com/v1/users?dry_run=1' \
+  -H 'Content-Type: application/json' \
+  -H "Authorization: Bearer $TOKEN" \
+  -d '{"name": "morpheus"}' -m 5"#;
+        let config = parse(command).unwrap();
+        assert_eq!(config.method.as_deref(), Some("POST"));
+        assert_eq!(config.url.as_deref(), Some("https://api.example.com/v1/users?dry_run=1"));
+        let headers = config.headers.unwrap();
+        assert_eq!(headers["Content-Type"], "application/json");
+        assert_eq!(headers["Authorization"], "Bearer $TOKEN");
+        assert_eq!(config.data, Some(json!({ "name": "morpheus" })));
+        assert_eq!(config.timeout, Some(5));
+
+        let config = parse("curl -G https://example.com/search --data-urlencode 'q=a b' -uuser:pw").unwrap();
+        assert_eq!(config.method.as_deref(), Some("GET"));
+        assert_eq!(config.url.as_deref(), Some("https://example.com/search?q=a%20b"));
+        assert!(matches!(config.auth, Some(Auth::Basic { ref username, .. }) if username == "user"));
+    }
+
+    #[test]
+    fn exported_command_parses_back_to_the_same_request() {
+        let config = parse(concat!(
+            r#"curl -X PATCH 'https://example.com/items?q=a b' -H "X-Note: it's" -d '{"n": 1}' -k"#,
+            " --cacert ca.pem -E client.pem --key 'my key.pem'"
+        ))
+        .unwrap();
+        let exported = command(&config);
+        assert!(exported.starts_with("curl -X PATCH 'https://example.com/items?q=a b'"));
+        let parsed = parse(&exported).unwrap();
+        assert_eq!(parsed.method.as_deref(), Some("PATCH"));
+        assert_eq!(parsed.url, config.url);
+        assert_eq!(parsed.data, Some(json!({ "n": 1 })));
+        assert_eq!(parsed.insecure, Some(true));
+        let tls = [parsed.cacert.as_deref(), parsed.cert.as_deref(), parsed.key.as_deref()];
+        assert_eq!(tls, [Some("ca.pem"), Some("client.pem"), Some("my key.pem")]);
+        let headers = parsed.headers.unwrap();
+        assert_eq!(headers["X-Note"], "it's");
+        assert_eq!(headers["Content-Type"], "application/json");
+    }
+
+    #[test]
+    fn form_parts_survive_an_export_and_import() {
+        let config = parse("curl https://example.com/upload -F 'avatar=@./me.png;type=image/png' -F note=@home");
+        let config = config.unwrap();
+        assert_eq!(config.method.as_deref(), Some("POST"));
+        let form = config.form.clone().unwrap();
+        assert_eq!(form[0].file.as_deref(), Some("./me.png"));
+        assert_eq!(form[0].content_type.as_deref(), Some("image/png"));
+        assert_eq!(form[1].file.as_deref(), Some("home"));
+        let mut literal = config.clone();
+        literal.form = Some(vec![FormPart::field("note=@home").unwrap()]);
+        assert_eq!(parse(&command(&config)).unwrap().form, config.form);
+        assert_eq!(parse(&command(&literal)).unwrap().form, literal.form);
+
+        let config = parse("curl https://example.com/token -d grant_type=password --data-urlencode 'user=a b&c'");
+        let config = config.unwrap();
+        let fields = config.form_urlencoded.clone().unwrap();
+        assert_eq!(fields[1].value.as_deref(), Some("a b&c"));
+        assert_eq!(parse(&command(&config)).unwrap().form_urlencoded, config.form_urlencoded);
+    }
+}
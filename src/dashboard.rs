@@ -0,0 +1,318 @@
+use anyhow::Result;
+use clap::{Subcommand, ValueHint};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::history::HistoryEntry;
+use crate::i18n;
+use crate::openapi::escape;
+use crate::stats;
+use crate::store;
+
+/// Subcommands of `report`.
+#[derive(Subcommand, Debug)]
+pub enum ReportCommand {
+    /// 履歴（テストスイートや定期実行の結果）から、成功率とエンドポイントごとのレイテンシの推移を示す静的 HTML ダッシュボードを生成します。
+    /// CI からステータスページとして公開できます
+    Html {
+        /// 集計対象の名前空間または URL の接頭辞（省略時はすべて）
+        target: Option<String>,
+
+        /// 出力先ディレクトリ（index.html と results.json を書き込みます）
+        #[arg(long = "out", value_name = "DIR", value_hint = ValueHint::DirPath)]
+        out: PathBuf,
+
+        /// 集計期間（例: 7d, 12h, 30m）
+        #[arg(long = "since", value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+
+        /// ページのタイトル
+        #[arg(long = "title", default_value = "API status")]
+        title: String,
+    },
+}
+
+/// Days shown in the daily pass-rate and latency charts.
+const CHART_DAYS: usize = 30;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Results of one endpoint on one day (UTC).
+#[derive(Serialize, Default)]
+struct Day {
+    date: String,
+    runs: usize,
+    passed: usize,
+    avg_ms: Option<u64>,
+    #[serde(skip)]
+    latencies: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct EndpointSummary {
+    endpoint: String,
+    runs: usize,
+    passed: usize,
+    pass_rate: f64,
+    avg_ms: Option<u64>,
+    p95_ms: Option<u64>,
+    /// Change of the mean latency of the newer half of the runs against the older half.
+    trend: Option<String>,
+    last_run: String,
+    /// "HTTP 200", or why the last run failed.
+    last_result: String,
+    last_passed: bool,
+    days: Vec<Day>,
+}
+
+/// The contents of results.json, from which index.html is rendered.
+#[derive(Serialize)]
+struct Summary {
+    title: String,
+    generated_at: String,
+    runs: usize,
+    passed: usize,
+    pass_rate: f64,
+    endpoints: Vec<EndpointSummary>,
+}
+
+fn time(timestamp_ms: u64) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_millis(timestamp_ms)).to_string()
+}
+
+fn rate(passed: usize, runs: usize) -> f64 {
+    if runs == 0 {
+        return 0.0;
+    }
+    (passed as f64 / runs as f64 * 1000.0).round() / 10.0
+}
+
+fn mean(values: &[u64]) -> Option<u64> {
+    (!values.is_empty()).then(|| values.iter().sum::<u64>() / values.len() as u64)
+}
+
+/// Summarizes ENTRIES (oldest first) per endpoint.
+fn summarize(title: &str, entries: &[HistoryEntry]) -> Summary {
+    let mut grouped: BTreeMap<String, Vec<&HistoryEntry>> = BTreeMap::new();
+    for entry in entries {
+        grouped.entry(entry.endpoint()).or_default().push(entry);
+    }
+    let endpoints: Vec<EndpointSummary> = grouped
+        .into_iter()
+        .map(|(endpoint, entries)| {
+            let latencies: Vec<u64> = entries.iter().filter_map(|entry| entry.elapsed_ms).collect();
+            let mut sorted = latencies.clone();
+            sorted.sort_unstable();
+            let mut days: BTreeMap<u64, Day> = BTreeMap::new();
+            for entry in &entries {
+                let day = days.entry(entry.timestamp_ms / DAY_MS).or_default();
+                day.runs += 1;
+                day.passed += usize::from(entry.succeeded());
+                day.latencies.extend(entry.elapsed_ms);
+            }
+            let first_day = days.keys().next_back().map_or(0, |last| last.saturating_sub(CHART_DAYS as u64 - 1));
+            let days = days
+                .split_off(&first_day)
+                .into_iter()
+                .map(|(day, mut summary)| {
+                    summary.date = time(day * DAY_MS)[..10].to_string();
+                    summary.avg_ms = mean(&summary.latencies);
+                    summary
+                })
+                .collect();
+            let last = entries[entries.len() - 1];
+            let passed = entries.iter().filter(|entry| entry.succeeded()).count();
+            EndpointSummary {
+                endpoint,
+                runs: entries.len(),
+                passed,
+                pass_rate: rate(passed, entries.len()),
+                avg_ms: mean(&latencies),
+                p95_ms: (!sorted.is_empty()).then(|| stats::percentile(&sorted, 0.95)),
+                trend: stats::trend(&latencies),
+                last_run: time(last.timestamp_ms),
+                last_result: stats::failure_reason(last)
+                    .unwrap_or_else(|| format!("HTTP {}", last.status.unwrap_or_default())),
+                last_passed: last.succeeded(),
+                days,
+            }
+        })
+        .collect();
+    let runs = endpoints.iter().map(|endpoint| endpoint.runs).sum();
+    let passed = endpoints.iter().map(|endpoint| endpoint.passed).sum();
+    Summary {
+        title: title.to_string(),
+        generated_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        runs,
+        passed,
+        pass_rate: rate(passed, runs),
+        endpoints,
+    }
+}
+
+/// Class of a pass rate: all passed, mostly passed or failing.
+fn health(pass_rate: f64) -> &'static str {
+    if pass_rate >= 100.0 {
+        "ok"
+    } else if pass_rate >= 90.0 {
+        "warn"
+    } else {
+        "bad"
+    }
+}
+
+/// One square per day, colored by the day's pass rate.
+fn pass_chart(days: &[Day]) -> String {
+    let squares: String = days
+        .iter()
+        .enumerate()
+        .map(|(i, day)| {
+            let rate = rate(day.passed, day.runs);
+            format!(
+                "<rect x=\"{}\" width=\"8\" height=\"16\" class=\"{}\"><title>{}: {}/{} passed</title></rect>",
+                i * 10,
+                health(rate),
+                day.date,
+                day.passed,
+                day.runs
+            )
+        })
+        .collect();
+    format!("<svg width=\"{}\" height=\"16\">{}</svg>", days.len() * 10, squares)
+}
+
+/// A line of the daily mean latencies, scaled to the slowest day.
+fn latency_chart(days: &[Day]) -> String {
+    let points: Vec<(usize, u64)> =
+        days.iter().enumerate().filter_map(|(i, day)| day.avg_ms.map(|ms| (i, ms))).collect();
+    if points.len() < 2 {
+        return "-".to_string();
+    }
+    let slowest = points.iter().map(|(_, ms)| *ms).max().unwrap_or(1).max(1);
+    let line: Vec<String> = points
+        .iter()
+        .map(|(i, ms)| format!("{},{}", i * 10 + 4, 22 - ms * 20 / slowest))
+        .collect();
+    format!(
+        "<svg width=\"{}\" height=\"24\"><title>{} ms at most</title><polyline points=\"{}\"/></svg>",
+        days.len() * 10,
+        slowest,
+        line.join(" ")
+    )
+}
+
+fn render_html(summary: &Summary) -> String {
+    let mut rows = String::new();
+    for endpoint in &summary.endpoints {
+        let ms = |value: Option<u64>| value.map_or("-".to_string(), |ms| ms.to_string());
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"{}\">{:.1}%</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>\n",
+            escape(&endpoint.endpoint),
+            endpoint.runs,
+            health(endpoint.pass_rate),
+            endpoint.pass_rate,
+            ms(endpoint.avg_ms),
+            ms(endpoint.p95_ms),
+            endpoint.trend.as_deref().unwrap_or("-"),
+            pass_chart(&endpoint.days),
+            latency_chart(&endpoint.days),
+            escape(&endpoint.last_run),
+            if endpoint.last_passed { "ok" } else { "bad" },
+            escape(&endpoint.last_result)
+        ));
+    }
+    format!(
+        r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: middle; }}
+.ok {{ color: #070; fill: #5b5; }}
+.warn {{ color: #a60; fill: #eb4; }}
+.bad {{ color: #b00; fill: #d55; }}
+polyline {{ fill: none; stroke: #36c; stroke-width: 1.5; }}
+</style></head>
+<body>
+<h1>{title}</h1>
+<p class="{health}">Pass rate: {pass_rate:.1}% ({passed}/{runs} runs)</p>
+<p>Generated at {generated_at} &middot; <a href="results.json">results.json</a></p>
+<table>
+<tr><th>Endpoint</th><th>Runs</th><th>Pass rate</th><th>Avg (ms)</th><th>P95 (ms)</th><th>Trend</th>
+<th>Daily results</th><th>Daily latency</th><th>Last run</th><th>Last result</th></tr>
+{rows}</table>
+</body></html>
+"#,
+        title = escape(&summary.title),
+        health = health(summary.pass_rate),
+        pass_rate = summary.pass_rate,
+        passed = summary.passed,
+        runs = summary.runs,
+        generated_at = escape(&summary.generated_at),
+        rows = rows
+    )
+}
+
+/// Writes index.html and results.json for the history of TARGET (everything without one)
+/// within SINCE into OUT.
+pub fn generate(target: Option<&str>, out: &Path, since: Option<Duration>, title: &str) -> Result<()> {
+    let entries = stats::select(target, since)?;
+    if entries.is_empty() {
+        println!("{}", i18n::t("No history entries match."));
+        return Ok(());
+    }
+    let summary = summarize(title, &entries);
+    store::write_atomic(&out.join("results.json"), serde_json::to_string_pretty(&summary)?)?;
+    store::write_atomic(&out.join("index.html"), render_html(&summary))?;
+    println!(
+        "{}",
+        i18n::tf(
+            "Dashboard of {} endpoint(s) and {} run(s) written to {}",
+            &[&summary.endpoints.len(), &summary.runs, &format!("{:?}", out)]
+        )
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestConfig;
+
+    fn entry(day: u64, status: Option<u16>, elapsed_ms: u64, error: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            timestamp_ms: day * DAY_MS + elapsed_ms,
+            target: Some("SystemA/users".to_string()),
+            request: RequestConfig { method: Some("GET".to_string()), ..RequestConfig::default() },
+            written: None,
+            status,
+            elapsed_ms: Some(elapsed_ms),
+            error: error.map(str::to_string),
+            response_headers: None,
+            response_body: None,
+        }
+    }
+
+    #[test]
+    fn summary_groups_runs_by_endpoint_and_day() {
+        let entries = [
+            entry(20000, Some(200), 100, None),
+            entry(20000, Some(500), 300, None),
+            entry(20001, Some(200), 50, None),
+            entry(20001, None, 10, Some("connection refused")),
+        ];
+        let summary = summarize("status", &entries);
+        assert_eq!((summary.runs, summary.passed, summary.pass_rate), (4, 2, 50.0));
+        let endpoint = &summary.endpoints[0];
+        assert_eq!(endpoint.endpoint, "GET SystemA/users");
+        assert_eq!((endpoint.last_result.as_str(), endpoint.last_passed), ("connection refused", false));
+        let days: Vec<(&str, usize, usize, Option<u64>)> =
+            endpoint.days.iter().map(|day| (day.date.as_str(), day.runs, day.passed, day.avg_ms)).collect();
+        assert_eq!(days, [("2024-10-04", 2, 1, Some(200)), ("2024-10-05", 2, 1, Some(30))]);
+    }
+}
@@ -0,0 +1,270 @@
+use serde_json::Value;
+use std::fmt;
+
+/// A single difference between an expected and an actual JSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { path: String, actual: Value },
+    Removed { path: String, expected: Value },
+    Changed { path: String, expected: Value, actual: Value },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, actual } => write!(f, "+ {}: {}", path, actual),
+            Change::Removed { path, expected } => write!(f, "- {}: {}", path, expected),
+            Change::Changed { path, expected, actual } => {
+                write!(f, "- {}: {}\n+ {}: {}", path, expected, path, actual)
+            }
+        }
+    }
+}
+
+/// Structurally compares two JSON documents. Objects are compared key by key and
+/// arrays index by index, so only the leaves that actually differ are reported.
+pub fn diff(expected: &Value, actual: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at(".", expected, actual, &mut changes);
+    changes
+}
+
+fn join(parent: &str, child: &str) -> String {
+    if parent == "." {
+        format!(".{}", child)
+    } else {
+        format!("{}.{}", parent, child)
+    }
+}
+
+fn index(parent: &str, i: usize) -> String {
+    if parent == "." {
+        format!(".[{}]", i)
+    } else {
+        format!("{}[{}]", parent, i)
+    }
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, changes: &mut Vec<Change>) {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            for (key, e_value) in e {
+                match a.get(key) {
+                    Some(a_value) => diff_at(&join(path, key), e_value, a_value, changes),
+                    None => changes.push(Change::Removed {
+                        path: join(path, key),
+                        expected: e_value.clone(),
+                    }),
+                }
+            }
+            for (key, a_value) in a {
+                if !e.contains_key(key) {
+                    changes.push(Change::Added {
+                        path: join(path, key),
+                        actual: a_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(e), Value::Array(a)) => {
+            for i in 0..e.len().max(a.len()) {
+                match (e.get(i), a.get(i)) {
+                    (Some(e_value), Some(a_value)) => diff_at(&index(path, i), e_value, a_value, changes),
+                    (Some(e_value), None) => changes.push(Change::Removed {
+                        path: index(path, i),
+                        expected: e_value.clone(),
+                    }),
+                    (None, Some(a_value)) => changes.push(Change::Added {
+                        path: index(path, i),
+                        actual: a_value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => changes.push(Change::Changed {
+            path: path.to_string(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Splits a diff path like `.items[0].id` into comparable segments.
+fn segments(path: &str) -> Vec<String> {
+    path.replace('[', ".[")
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns true if `path` is at or below the ignore pattern. `[]` in the pattern
+/// matches any array index, e.g. `.items[].id` ignores the id of every item.
+pub fn is_ignored(path: &str, pattern: &str) -> bool {
+    let path = segments(path);
+    let pattern = segments(pattern);
+    pattern.len() <= path.len()
+        && pattern
+            .iter()
+            .zip(path.iter())
+            .all(|(p, s)| p == s || (p == "[]" && s.starts_with('[')))
+}
+
+/// Error returned when the response body differs from the expected fixture.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub differences: usize,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Response body does not match the expected fixture ({} difference(s))",
+            self.differences
+        )
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// Removes changes whose path matches any of the ignore patterns.
+pub fn without_ignored(changes: Vec<Change>, ignore: &[String]) -> Vec<Change> {
+    changes
+        .into_iter()
+        .filter(|change| !ignore.iter().any(|pattern| is_ignored(change.path(), pattern)))
+        .collect()
+}
+
+/// Type name of a JSON value as reported by `type_diff`.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The structure of VALUE with every leaf replaced by its type name. The elements of an
+/// array are merged into one shape, so `[{"id": 1}, {"id": 2, "tag": "a"}]` becomes
+/// `[{"id": "number", "tag": "string"}]`; mixed types are joined as "number|string".
+pub fn shape(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            Value::Object(object.iter().map(|(key, value)| (key.clone(), shape(value))).collect())
+        }
+        Value::Array(items) => {
+            let merged = items.iter().map(shape).reduce(merge_shapes);
+            Value::Array(merged.into_iter().collect())
+        }
+        other => Value::String(type_name(other).to_string()),
+    }
+}
+
+fn merge_shapes(a: Value, b: Value) -> Value {
+    match (a, b) {
+        (Value::Object(mut a), Value::Object(b)) => {
+            for (key, b_value) in b {
+                let merged = match a.remove(&key) {
+                    Some(a_value) => merge_shapes(a_value, b_value),
+                    None => b_value,
+                };
+                a.insert(key, merged);
+            }
+            Value::Object(a)
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            Value::Array(a.into_iter().chain(b).reduce(merge_shapes).into_iter().collect())
+        }
+        (a, b) if a == b => a,
+        (a, b) => {
+            let names = [shape_name(&a), shape_name(&b)];
+            let mut names: Vec<&str> = names.iter().flat_map(|name| name.split('|')).collect();
+            names.sort();
+            names.dedup();
+            Value::String(names.join("|"))
+        }
+    }
+}
+
+/// Type name of a shape: the leaf type (possibly "a|b"), "array" or "object".
+fn shape_name(shape: &Value) -> String {
+    match shape {
+        Value::String(name) => name.clone(),
+        other => type_name(other).to_string(),
+    }
+}
+
+/// Compares only the structure and types of two JSON documents: fields that were added,
+/// removed or changed type. Values are ignored, and array elements are compared as one
+/// merged shape under `[]`, e.g. `.items[].id`. Empty arrays match any element type.
+pub fn type_diff(previous: &Value, current: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    type_diff_at(".", &shape(previous), &shape(current), &mut changes);
+    changes
+}
+
+fn type_diff_at(path: &str, previous: &Value, current: &Value, changes: &mut Vec<Change>) {
+    match (previous, current) {
+        (Value::Object(p), Value::Object(c)) => {
+            for (key, p_shape) in p {
+                match c.get(key) {
+                    Some(c_shape) => type_diff_at(&join(path, key), p_shape, c_shape, changes),
+                    None => changes.push(Change::Removed { path: join(path, key), expected: p_shape.clone() }),
+                }
+            }
+            for (key, c_shape) in c {
+                if !p.contains_key(key) {
+                    changes.push(Change::Added { path: join(path, key), actual: c_shape.clone() });
+                }
+            }
+        }
+        (Value::Array(p), Value::Array(c)) => {
+            if let (Some(p_shape), Some(c_shape)) = (p.first(), c.first()) {
+                let items = if path == "." { ".[]".to_string() } else { format!("{}[]", path) };
+                type_diff_at(&items, p_shape, c_shape, changes);
+            }
+        }
+        _ if shape_name(previous) != shape_name(current) => changes.push(Change::Changed {
+            path: path.to_string(),
+            expected: Value::String(shape_name(previous)),
+            actual: Value::String(shape_name(current)),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn type_diff_ignores_values_and_reports_shape_changes() {
+        let previous = json!({ "id": 1, "name": "a", "items": [{ "id": 1 }, { "id": 2, "tag": "x" }], "meta": null });
+        let current = json!({ "id": "7", "name": "b", "items": [{ "id": 3, "tag": null }], "next": "/page/2", "meta": null });
+        let changes: Vec<String> = type_diff(&previous, &current).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "- .id: \"number\"\n+ .id: \"string\"",
+                "- .items[].tag: \"string\"\n+ .items[].tag: \"null\"",
+                "+ .next: \"string\"",
+            ]
+        );
+        assert!(type_diff(&json!({ "items": [] }), &json!({ "items": [{ "id": 1 }] })).is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+use anyhow::{bail, Result};
+use clap::{Subcommand, ValueEnum, ValueHint};
+use reqwest::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use crate::auth::Auth;
+use crate::i18n;
+use crate::mount;
+use crate::namespace::{self, SavedConfig};
+use crate::openapi::escape;
+use crate::output;
+use crate::report::{is_sensitive, REDACTED};
+use crate::request::{self, Exchange, RequestConfig};
+use crate::saved;
+
+/// A response kept with a saved configuration (`examples`) to document the endpoint,
+/// recorded with `send --save-example`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseExample {
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// The body as JSON, or as a string when it is not JSON.
+    pub body: Value,
+}
+
+impl ResponseExample {
+    pub fn from_exchange(exchange: &Exchange, description: Option<&str>) -> ResponseExample {
+        let text = exchange.text();
+        ResponseExample {
+            status: exchange.status.as_u16(),
+            description: description.map(str::to_string),
+            content_type: exchange
+                .headers
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            body: serde_json::from_str(&text).unwrap_or(Value::String(text)),
+        }
+    }
+}
+
+/// Adds EXAMPLE to CONFIG, replacing an earlier example with the same status.
+pub fn add_example(config: &mut RequestConfig, example: ResponseExample) {
+    let examples = config.examples.get_or_insert_with(Vec::new);
+    examples.retain(|existing| existing.status != example.status);
+    examples.push(example);
+    examples.sort_by_key(|example| example.status);
+}
+
+/// Records the response of EXCHANGE as an example of the configuration saved for TARGET
+/// and METHOD (`send --save-example`).
+pub fn save_example(target: &str, method: &str, exchange: &Exchange, description: Option<&str>) -> Result<()> {
+    if !mount::config_path(target, method)?.exists() {
+        bail!("No configuration is saved for {} {}; save it first with --save", method, target);
+    }
+    let mut config = request::load_saved(target, method)?;
+    add_example(&mut config, ResponseExample::from_exchange(exchange, description));
+    saved::save(target, method, &config)
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DocsCommand {
+    /// 保存済み設定から API リファレンス（エンドポイント・パラメータ・ヘッダー・リクエストとレスポンスの例）を生成します。
+    Generate {
+        /// 対象の名前空間（省略時はすべて）
+        namespace: Option<String>,
+
+        /// 出力形式
+        #[arg(long = "format", value_enum, default_value = "markdown")]
+        format: DocsFormat,
+
+        /// 標準出力ではなく指定したファイルに書き込みます。
+        #[arg(short = 'o', long = "output", value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// What the reference shows for one saved configuration.
+struct Endpoint {
+    target: String,
+    method: String,
+    url: String,
+    tags: Vec<String>,
+    auth: Option<String>,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    /// Fields of a form body, with "@path" for files.
+    form: Vec<(String, String)>,
+    body: Option<String>,
+    assertions: Vec<String>,
+    examples: Vec<(String, String)>,
+}
+
+/// Hides the values of secret headers, keeping `{{placeholders}}` that name where they come from.
+fn header_value(name: &str, value: &str) -> String {
+    if !is_sensitive(name) || (value.starts_with("{{") && value.ends_with("}}")) {
+        return value.to_string();
+    }
+    match value.split_once(' ') {
+        Some((scheme, _)) if name.to_lowercase().contains("authorization") => format!("{} {}", scheme, REDACTED),
+        _ => REDACTED.to_string(),
+    }
+}
+
+/// Indents JSON, including bodies saved as a JSON string (`-d '{...}'`).
+fn pretty(value: &Value) -> String {
+    match value {
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(parsed) if parsed.is_object() || parsed.is_array() => pretty(&parsed),
+            _ => text.clone(),
+        },
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+impl Endpoint {
+    fn new(saved: &SavedConfig, config: RequestConfig) -> Endpoint {
+        let auth = config.auth.as_ref().map(|auth| match auth {
+            Auth::Bearer { .. } => "Bearer token".to_string(),
+            Auth::Basic { .. } => "HTTP Basic".to_string(),
+            Auth::ApiKey { header, .. } => format!("API key in the {} header", header),
+            Auth::OAuth2 { token_url, .. } => format!("OAuth2 client credentials ({})", token_url),
+        });
+        let headers: BTreeMap<String, String> = config.headers.unwrap_or_default().into_iter().collect();
+        let form = config.form.iter().chain(config.form_urlencoded.iter()).flatten().map(|part| {
+            let value = match part.file {
+                Some(ref file) => format!("@{}", file),
+                None => part.value.clone().unwrap_or_default(),
+            };
+            (part.name.clone(), value)
+        });
+        Endpoint {
+            target: saved.target.clone(),
+            method: saved.method.clone(),
+            url: config.url.unwrap_or_default(),
+            tags: config.tags.unwrap_or_default(),
+            auth,
+            query: config.query.unwrap_or_default().into_iter().collect(),
+            headers: headers.iter().map(|(name, value)| (name.clone(), header_value(name, value))).collect(),
+            form: form.collect(),
+            body: config.data.as_ref().filter(|data| !data.is_null()).map(pretty),
+            assertions: config.assertions.unwrap_or_default(),
+            examples: config
+                .examples
+                .unwrap_or_default()
+                .into_iter()
+                .map(|example| {
+                    let reason = StatusCode::from_u16(example.status).ok().and_then(|s| s.canonical_reason());
+                    let mut title = format!("{} {}", example.status, reason.unwrap_or_default()).trim_end().to_string();
+                    if let Some(description) = example.description {
+                        title = format!("{} ({})", title, description);
+                    }
+                    (title, pretty(&example.body))
+                })
+                .collect(),
+        }
+    }
+
+    /// The name/value tables of the section, with their headings.
+    fn tables(&self) -> [(&str, &[(String, String)]); 3] {
+        [("Query parameters", &self.query), ("Headers", &self.headers), ("Form fields", &self.form)]
+    }
+
+    /// Anchor of the endpoint's section.
+    fn anchor(&self) -> String {
+        let text = format!("{} {}", self.method, self.target).to_lowercase();
+        text.chars().map(|c| if c.is_alphanumeric() { c } else { '-' }).collect()
+    }
+}
+
+fn markdown_table(rows: &[(String, String)]) -> String {
+    let mut table = String::from("| Name | Value |\n| --- | --- |\n");
+    for (name, value) in rows {
+        table.push_str(&format!("| `{}` | `{}` |\n", name.replace('|', "\\|"), value.replace('|', "\\|")));
+    }
+    table
+}
+
+/// A fenced code block, with a fence longer than any backtick run in TEXT.
+fn markdown_code(text: &str) -> String {
+    let language = if serde_json::from_str::<Value>(text).is_ok() { "json" } else { "" };
+    let mut fence = "```".to_string();
+    while text.contains(&fence) {
+        fence.push('`');
+    }
+    format!("{}{}\n{}\n{}\n", fence, language, text, fence)
+}
+
+fn render_markdown(title: &str, endpoints: &[Endpoint]) -> String {
+    let mut text = format!("# {}\n\n", title);
+    for endpoint in endpoints {
+        text.push_str(&format!("- [{} {}](#{})\n", endpoint.method, endpoint.target, endpoint.anchor()));
+    }
+    for endpoint in endpoints {
+        let (anchor, method, target) = (endpoint.anchor(), &endpoint.method, &endpoint.target);
+        text.push_str(&format!("\n<a id=\"{}\"></a>\n## {} {}\n\n", anchor, method, target));
+        text.push_str(&format!("`{} {}`\n\n", endpoint.method, endpoint.url));
+        if !endpoint.tags.is_empty() {
+            text.push_str(&format!("Tags: {}\n\n", endpoint.tags.join(", ")));
+        }
+        if let Some(ref auth) = endpoint.auth {
+            text.push_str(&format!("Authentication: {}\n\n", auth));
+        }
+        for (heading, rows) in endpoint.tables() {
+            if !rows.is_empty() {
+                text.push_str(&format!("### {}\n\n{}\n", heading, markdown_table(rows)));
+            }
+        }
+        if let Some(ref body) = endpoint.body {
+            text.push_str(&format!("### Example request\n\n{}\n", markdown_code(body)));
+        }
+        for (title, body) in &endpoint.examples {
+            text.push_str(&format!("### Example response: {}\n\n{}\n", title, markdown_code(body)));
+        }
+        if !endpoint.assertions.is_empty() {
+            text.push_str("### Checks\n\n");
+            for assertion in &endpoint.assertions {
+                text.push_str(&format!("- `{}`\n", assertion));
+            }
+        }
+    }
+    text
+}
+
+fn html_table(rows: &[(String, String)]) -> String {
+    let rows: String = rows
+        .iter()
+        .map(|(name, value)| {
+            format!("<tr><td><code>{}</code></td><td><code>{}</code></td></tr>\n", escape(name), escape(value))
+        })
+        .collect();
+    format!("<table>\n<tr><th>Name</th><th>Value</th></tr>\n{}</table>\n", rows)
+}
+
+fn render_html(title: &str, endpoints: &[Endpoint]) -> String {
+    let mut toc = String::new();
+    let mut sections = String::new();
+    for endpoint in endpoints {
+        let name = escape(&format!("{} {}", endpoint.method, endpoint.target));
+        toc.push_str(&format!("<li><a href=\"#{}\">{}</a></li>\n", endpoint.anchor(), name));
+        sections.push_str(&format!("<section id=\"{}\">\n<h2>{}</h2>\n", endpoint.anchor(), name));
+        sections.push_str(&format!(
+            "<p><span class=\"method\">{}</span> <code>{}</code></p>\n",
+            escape(&endpoint.method),
+            escape(&endpoint.url)
+        ));
+        if !endpoint.tags.is_empty() {
+            sections.push_str(&format!("<p>Tags: {}</p>\n", escape(&endpoint.tags.join(", "))));
+        }
+        if let Some(ref auth) = endpoint.auth {
+            sections.push_str(&format!("<p>Authentication: {}</p>\n", escape(auth)));
+        }
+        for (heading, rows) in endpoint.tables() {
+            if !rows.is_empty() {
+                sections.push_str(&format!("<h3>{}</h3>\n{}", heading, html_table(rows)));
+            }
+        }
+        if let Some(ref body) = endpoint.body {
+            sections.push_str(&format!("<h3>Example request</h3>\n<pre>{}</pre>\n", escape(body)));
+        }
+        for (title, body) in &endpoint.examples {
+            sections.push_str(&format!("<h3>Example response: {}</h3>\n<pre>{}</pre>\n", escape(title), escape(body)));
+        }
+        if !endpoint.assertions.is_empty() {
+            let items: String = endpoint
+                .assertions
+                .iter()
+                .map(|assertion| format!("<li><code>{}</code></li>\n", escape(assertion)))
+                .collect();
+            sections.push_str(&format!("<h3>Checks</h3>\n<ul>\n{}</ul>\n", items));
+        }
+        sections.push_str("</section>\n");
+    }
+    format!(
+        r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; max-width: 60em; }}
+nav ul {{ columns: 2; }}
+section {{ border-top: 1px solid #ccc; margin-top: 2em; }}
+table {{ border-collapse: collapse; }}
+td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}
+pre {{ background: #f6f6f6; padding: 8px; overflow-x: auto; }}
+.method {{ font-weight: bold; background: #def; padding: 1px 6px; border-radius: 3px; }}
+</style></head>
+<body>
+<h1>{title}</h1>
+<nav><ul>
+{toc}</ul></nav>
+{sections}</body></html>
+"#,
+        title = escape(title),
+        toc = toc,
+        sections = sections
+    )
+}
+
+/// Generates the reference of the saved configurations under NAMESPACE (all without one).
+pub fn generate(namespace: Option<&str>, format: DocsFormat, output: Option<&std::path::Path>) -> Result<()> {
+    let prefix = namespace.map(namespace::normalize).transpose()?;
+    let mut endpoints = Vec::new();
+    for saved in mount::list_saved()? {
+        if prefix.as_deref().is_some_and(|p| !saved.is_under(p)) {
+            continue;
+        }
+        // 壊れた設定があっても残りの文書は生成する
+        let config = match fs::read_to_string(&saved.path).map_err(anyhow::Error::from).and_then(|content| {
+            serde_json::from_str::<RequestConfig>(&content).map_err(anyhow::Error::from)
+        }) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    i18n::tf("Warning: skipping {}: {}", &[&format!("{:?}", saved.path), &format!("{:#}", err)])
+                );
+                continue;
+            }
+        };
+        endpoints.push(Endpoint::new(&saved, config));
+    }
+    if endpoints.is_empty() {
+        bail!("No saved configurations found under {}", prefix.as_deref().unwrap_or("the configuration directory"));
+    }
+    let title = match prefix {
+        Some(ref prefix) => format!("{} API reference", prefix),
+        None => "API reference".to_string(),
+    };
+    let rendered = match format {
+        DocsFormat::Markdown => render_markdown(&title, &endpoints),
+        DocsFormat::Html => render_html(&title, &endpoints),
+    };
+    output::emit(&rendered, output)
+}
+
+pub fn run(action: DocsCommand) -> Result<()> {
+    match action {
+        DocsCommand::Generate { namespace, format, output } => {
+            generate(namespace.as_deref(), format, output.as_deref())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn examples_are_replaced_by_status_and_secret_headers_are_hidden() {
+        let example = |status, body| ResponseExample { status, description: None, content_type: None, body };
+        let mut config = RequestConfig::default();
+        add_example(&mut config, example(404, json!({ "error": "missing" })));
+        add_example(&mut config, example(200, json!({ "id": 1 })));
+        add_example(&mut config, example(200, json!({ "id": 2 })));
+        let statuses: Vec<(u16, Value)> = config.examples.unwrap().into_iter().map(|e| (e.status, e.body)).collect();
+        assert_eq!(statuses, [(200, json!({ "id": 2 })), (404, json!({ "error": "missing" }))]);
+
+        assert_eq!(header_value("Authorization", "Bearer abc"), "Bearer [REDACTED]");
+        assert_eq!(header_value("X-Api-Key", "k1"), "[REDACTED]");
+        assert_eq!(header_value("X-Api-Key", "{{api_key}}"), "{{api_key}}");
+        assert_eq!(header_value("Accept", "application/json"), "application/json");
+    }
+}
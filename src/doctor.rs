@@ -0,0 +1,342 @@
+use anyhow::{bail, Result};
+use reqwest::Url;
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::net::TcpStream;
+
+use crate::credential;
+use crate::history::Retention;
+use crate::i18n;
+use crate::request::RequestConfig;
+use crate::settings::Settings;
+use crate::theme::Theme;
+use crate::vars::{self, VARS_FILE};
+use crate::{get_default_dir, namespace};
+
+/// Environment variables that change how requests are routed.
+pub const PROXY_VARS: &[&str] = &[
+    "HTTP_PROXY", "http_proxy", "HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy", "NO_PROXY", "no_proxy",
+];
+
+/// CA bundle locations used by OpenSSL on common Linux distributions.
+const CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Info,
+    Warn,
+    Fail,
+}
+
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    fix: Option<String>,
+}
+
+#[derive(Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn add(&mut self, status: Status, name: impl Into<String>, detail: impl Into<String>, fix: Option<&str>) {
+        self.checks.push(Check {
+            status,
+            name: name.into(),
+            detail: detail.into(),
+            fix: fix.map(str::to_string),
+        });
+    }
+
+    fn print(&self) {
+        for check in &self.checks {
+            let label = match check.status {
+                Status::Ok => "[ok]  ",
+                Status::Info => "[info]",
+                Status::Warn => "[warn]",
+                Status::Fail => "[FAIL]",
+            };
+            println!("{} {}: {}", label, check.name, check.detail);
+            if let Some(ref fix) = check.fix {
+                println!("       {}", i18n::tf("fix: {}", &[fix]));
+            }
+        }
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+fn check_base_dir(report: &mut Report, base_dir: &Path) {
+    if !base_dir.exists() {
+        report.add(
+            Status::Warn,
+            "Base directory",
+            format!("{:?} does not exist", base_dir),
+            Some("Run `ferrapi_tester init` to create it."),
+        );
+        return;
+    }
+    let probe = base_dir.join(format!(".doctor-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            report.add(Status::Ok, "Base directory", format!("{:?} is writable", base_dir), None);
+        }
+        Err(err) => report.add(
+            Status::Fail,
+            "Base directory",
+            format!("{:?} is not writable: {}", base_dir, err),
+            Some("Fix the ownership or permissions of the directory (e.g. `chmod u+rwx`)."),
+        ),
+    }
+}
+
+fn check_settings(report: &mut Report) {
+    let settings = match Settings::load() {
+        Ok(settings) => settings,
+        Err(err) => {
+            report.add(
+                Status::Fail,
+                "Settings",
+                format!("{:#}", err),
+                Some("Fix the JSON in settings.json or delete it to use the defaults."),
+            );
+            return;
+        }
+    };
+    if let Err(err) = Retention::from_settings(&settings.history) {
+        report.add(
+            Status::Fail,
+            "Settings",
+            format!("{:#}", err),
+            Some("Use durations like \"30d\" and sizes like \"50MB\" in the history section."),
+        );
+        return;
+    }
+    if let Some(Err(err)) = settings.theme.as_ref().map(Theme::from_setting) {
+        report.add(
+            Status::Fail,
+            "Settings",
+            format!("{:#}", err),
+            Some("Use a built-in theme name or style strings such as \"green.bold\" or \"208\"."),
+        );
+        return;
+    }
+    match settings.log_path() {
+        Ok(Some(path)) if path.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.exists()) => report.add(
+            Status::Warn,
+            "Settings",
+            format!("the directory of log_requests {:?} does not exist", path),
+            Some("Create the directory or change log_requests."),
+        ),
+        _ => report.add(Status::Ok, "Settings", "settings.json is valid", None),
+    }
+}
+
+/// Parses every saved configuration and vars.json file in the tree.
+fn check_namespaces(report: &mut Report, base_dir: &Path) -> Vec<namespace::SavedConfig> {
+    let saved = match namespace::list_saved(base_dir) {
+        Ok(saved) => saved,
+        Err(err) => {
+            report.add(Status::Fail, "Namespaces", format!("{:#}", err), None);
+            return Vec::new();
+        }
+    };
+    let mut broken = Vec::new();
+    for config in &saved {
+        let valid = fs::read_to_string(&config.path)
+            .ok()
+            .is_some_and(|content| serde_json::from_str::<RequestConfig>(&content).is_ok());
+        if !valid {
+            broken.push(config.path.clone());
+        }
+        if let Some(dir) = config.path.parent() {
+            let vars_file = dir.join(VARS_FILE);
+            let vars_valid = fs::read_to_string(&vars_file)
+                .map(|content| serde_json::from_str::<vars::Vars>(&content).is_ok())
+                .unwrap_or(true);
+            if !vars_valid && !broken.contains(&vars_file) {
+                broken.push(vars_file);
+            }
+        }
+    }
+    if broken.is_empty() {
+        report.add(Status::Ok, "Namespaces", format!("{} saved configuration(s) parsed", saved.len()), None);
+    } else {
+        let list: Vec<String> = broken.iter().map(|p| format!("{:?}", p)).collect();
+        report.add(
+            Status::Fail,
+            "Namespaces",
+            format!("cannot parse {}", list.join(", ")),
+            Some("Fix the JSON by hand or restore a snapshot with `config snapshot restore`."),
+        );
+    }
+    saved
+}
+
+fn check_proxies(report: &mut Report) {
+    let set: Vec<(&str, String)> = PROXY_VARS
+        .iter()
+        .filter_map(|name| env::var(name).ok().map(|value| (*name, value)))
+        .collect();
+    if set.is_empty() {
+        report.add(Status::Info, "Proxy", "no proxy environment variables set", None);
+        return;
+    }
+    for (name, value) in set {
+        let is_no_proxy = name.eq_ignore_ascii_case("NO_PROXY");
+        if is_no_proxy || Url::parse(&value).is_ok() {
+            report.add(Status::Info, "Proxy", format!("{}={}", name, value), None);
+        } else {
+            report.add(
+                Status::Warn,
+                "Proxy",
+                format!("{}={} is not a valid URL", name, value),
+                Some("Use a full URL such as http://proxy.example.com:8080."),
+            );
+        }
+    }
+}
+
+fn check_trust_store(report: &mut Report) {
+    for name in ["SSL_CERT_FILE", "SSL_CERT_DIR"] {
+        if let Ok(value) = env::var(name) {
+            if Path::new(&value).exists() {
+                report.add(Status::Ok, "TLS trust store", format!("{}={}", name, value), None);
+            } else {
+                report.add(
+                    Status::Fail,
+                    "TLS trust store",
+                    format!("{}={} does not exist", name, value),
+                    Some("Point the variable at an existing CA bundle or unset it."),
+                );
+            }
+            return;
+        }
+    }
+    if !cfg!(target_os = "linux") {
+        report.add(Status::Ok, "TLS trust store", "using the system certificate store", None);
+        return;
+    }
+    match CA_BUNDLES.iter().map(PathBuf::from).find(|path| path.exists()) {
+        Some(path) => report.add(Status::Ok, "TLS trust store", format!("CA bundle found at {:?}", path), None),
+        None => report.add(
+            Status::Warn,
+            "TLS trust store",
+            "no CA bundle found in the usual locations",
+            Some("Install the ca-certificates package or set SSL_CERT_FILE."),
+        ),
+    }
+}
+
+fn check_credential_store(report: &mut Report) {
+    let store = match credential::open() {
+        Ok(store) => store,
+        Err(err) => {
+            report.add(
+                Status::Fail,
+                "Credential store",
+                format!("{:#}", err),
+                Some("Set \"credential_store\" in settings.json to keyring, file or env."),
+            );
+            return;
+        }
+    };
+    // 存在しない名前を読んで、ストア自体が使えるかだけを確かめる
+    match store.get("ferrapi_tester-doctor-probe") {
+        Ok(_) => report.add(Status::Ok, "Credential store", store.describe(), None),
+        Err(err) => report.add(
+            Status::Warn,
+            "Credential store",
+            format!("{} is not usable: {:#}", store.describe(), err),
+            Some("Without a desktop keychain (CI, containers), use \"credential_store\": \"file\" or \"env\"."),
+        ),
+    }
+}
+
+/// Collects scheme://host:port of every saved URL, with variables resolved.
+fn base_urls(saved: &[namespace::SavedConfig]) -> BTreeSet<(String, String, u16)> {
+    let mut hosts = BTreeSet::new();
+    for config in saved {
+        let Some(url) = fs::read_to_string(&config.path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<RequestConfig>(&content).ok())
+            .and_then(|c| c.url)
+        else {
+            continue;
+        };
+        let rendered = vars::resolve(Some(&config.target))
+            .and_then(|vars| vars::render(&url, &vars))
+            .unwrap_or(url);
+        if let Ok(url) = Url::parse(&rendered) {
+            if let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) {
+                hosts.insert((url.scheme().to_string(), host.to_string(), port));
+            }
+        }
+    }
+    hosts
+}
+
+async fn check_reachability(report: &mut Report, saved: &[namespace::SavedConfig]) {
+    let hosts = base_urls(saved);
+    if hosts.is_empty() {
+        report.add(Status::Info, "Reachability", "no saved URLs to check", None);
+        return;
+    }
+    for (scheme, host, port) in hosts {
+        let name = format!("{}://{}:{}", scheme, host, port);
+        match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+            Ok(Ok(_)) => report.add(Status::Ok, "Reachability", format!("{} accepts connections", name), None),
+            Ok(Err(err)) => report.add(
+                Status::Warn,
+                "Reachability",
+                format!("{}: {}", name, err),
+                Some("Check that the server is running and the host name resolves (VPN, DNS, proxy)."),
+            ),
+            Err(_) => report.add(
+                Status::Warn,
+                "Reachability",
+                format!("{}: no connection within {:?}", name, CONNECT_TIMEOUT),
+                Some("Check firewalls and proxies, or whether the host is only reachable via VPN."),
+            ),
+        }
+    }
+}
+
+pub async fn run(offline: bool) -> Result<()> {
+    let base_dir = get_default_dir()?;
+    let mut report = Report::default();
+    check_base_dir(&mut report, &base_dir);
+    check_settings(&mut report);
+    let saved = check_namespaces(&mut report, &base_dir);
+    check_proxies(&mut report);
+    check_trust_store(&mut report);
+    check_credential_store(&mut report);
+    if !offline {
+        check_reachability(&mut report, &saved).await;
+    }
+    report.print();
+
+    let (warnings, failures) = (report.count(Status::Warn), report.count(Status::Fail));
+    println!("{}", i18n::tf("\n{} warning(s), {} problem(s).", &[&warnings, &failures]));
+    if failures > 0 {
+        bail!("doctor found {} problem(s)", failures);
+    }
+    Ok(())
+}
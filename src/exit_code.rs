@@ -0,0 +1,127 @@
+use anyhow::Result;
+use serde_json::json;
+use std::{error::Error, fmt};
+
+use crate::assertion;
+use crate::cancel::{self, Interrupted};
+use crate::diff::Mismatch;
+use crate::i18n;
+use crate::net_error::NetworkError;
+use crate::output::{self, Table};
+
+pub const SUCCESS: i32 = 0;
+/// Any failure that has no more specific code.
+pub const ERROR: i32 = 1;
+/// Assertions, an expected body, an expected status, or requests of a suite failed.
+pub const ASSERTION: i32 = 2;
+/// The request could not be sent or no response was received.
+pub const NETWORK: i32 = 3;
+/// A configuration, variable, request file or option value is missing or invalid.
+pub const CONFIG: i32 = 4;
+/// Credentials, secrets or a login could not be obtained.
+pub const AUTH: i32 = 5;
+/// The command line could not be parsed (sysexits EX_USAGE).
+pub const USAGE: i32 = 64;
+pub const INTERRUPTED: i32 = cancel::EXIT_INTERRUPTED;
+
+/// Every exit code with its name (also the `kind` of JSON errors) and meaning.
+const CODES: [(i32, &str, &str); 8] = [
+    (SUCCESS, "success", "The command succeeded."),
+    (ERROR, "error", "An unexpected error occurred."),
+    (ASSERTION, "assertion_failed", "An assertion, expected body, or expected status did not hold."),
+    (NETWORK, "network_error", "The request failed before a response was received (DNS, connect, TLS, timeout)."),
+    (CONFIG, "config_error", "A configuration, variable, request file, or option value is missing or invalid."),
+    (AUTH, "auth_error", "Credentials, secrets, or a login could not be obtained."),
+    (USAGE, "usage_error", "The command line could not be parsed."),
+    (INTERRUPTED, "interrupted", "The command was interrupted with Ctrl-C."),
+];
+
+/// Name of CODE, e.g. "config_error".
+pub fn name(code: i32) -> &'static str {
+    CODES.iter().find(|(known, _, _)| *known == code).map_or("error", |(_, name, _)| name)
+}
+
+/// An error that exits with CODE; it shows as the wrapped error.
+#[derive(Debug)]
+pub struct Classified {
+    pub code: i32,
+    error: anyhow::Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl Error for Classified {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Gives ERROR the exit code CODE, unless a more specific code is already known (a
+/// network error stays a network error when it happened while logging in).
+pub fn classify(code: i32, error: anyhow::Error) -> anyhow::Error {
+    if of(&error) != ERROR {
+        return error;
+    }
+    Classified { code, error }.into()
+}
+
+pub fn config(error: anyhow::Error) -> anyhow::Error {
+    classify(CONFIG, error)
+}
+
+pub fn auth(error: anyhow::Error) -> anyhow::Error {
+    classify(AUTH, error)
+}
+
+pub fn assertion(error: anyhow::Error) -> anyhow::Error {
+    classify(ASSERTION, error)
+}
+
+/// The exit code for ERR.
+pub fn of(err: &anyhow::Error) -> i32 {
+    if cancel::is_cancelled() || err.is::<Interrupted>() {
+        return INTERRUPTED;
+    }
+    if let Some(classified) = err.chain().find_map(|cause| cause.downcast_ref::<Classified>()) {
+        return classified.code;
+    }
+    if err.chain().any(|cause| cause.is::<NetworkError>()) {
+        NETWORK
+    } else if err.chain().any(|cause| cause.is::<assertion::Failed>() || cause.is::<Mismatch>()) {
+        ASSERTION
+    } else {
+        ERROR
+    }
+}
+
+/// Prints the exit codes (--list-exit-codes).
+pub fn list() -> Result<()> {
+    let mut table = Table::new(&[("CODE", "code"), ("NAME", "name"), ("DESCRIPTION", "description")]);
+    for (code, name, description) in CODES {
+        table.push(vec![json!(code), json!(name), json!(i18n::t(description))]);
+    }
+    output::renderer()?.print(&table, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn the_first_specific_class_of_an_error_wins() {
+        assert_eq!(of(&anyhow!("boom")), ERROR);
+        let missing = config(anyhow!("Variable 'token' is not defined"));
+        assert_eq!(of(&missing), CONFIG);
+        assert_eq!(missing.to_string(), "Variable 'token' is not defined");
+        // 後から付けた分類や文脈では変わらない
+        assert_eq!(of(&auth(missing).context("Failed to send")), CONFIG);
+        let failed = anyhow::Error::new(assertion::Failed { failures: 1 }).context("step 2");
+        assert_eq!(of(&config(failed)), ASSERTION);
+        assert_eq!(name(of(&auth(anyhow!("login failed")))), "auth_error");
+    }
+}
@@ -0,0 +1,347 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// One step of a path expression such as `.items[0]` or `.items[]`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+    Iterate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A single stage of a `|`-separated filter pipeline.
+#[derive(Debug, Clone, PartialEq)]
+enum Stage {
+    Path(Vec<Segment>),
+    Select {
+        path: Vec<Segment>,
+        comparison: Option<(Op, Value)>,
+    },
+}
+
+/// A compiled jq-style filter supporting paths, `[]` iteration and `select(...)`.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    stages: Vec<Stage>,
+    iterates: bool,
+}
+
+/// Parses a path like `.items[0].name` or `.items[]` into segments. JSONPath such as
+/// `$.items[0].name`, `$['items'][*]` or `$.items.*` is accepted as well.
+pub fn parse_path(expr: &str) -> Result<Vec<Segment>> {
+    let expr = expr.trim();
+    let (expr, json_path) = match expr.strip_prefix('$') {
+        Some(rest) => (rest, true),
+        None => (expr, false),
+    };
+    if !json_path && !expr.starts_with('.') {
+        bail!("Path must start with '.' or '$': {}", expr);
+    }
+    let chars: Vec<char> = expr.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                i += 1;
+                if i < chars.len() && chars[i] == '"' {
+                    let end = chars[i + 1..]
+                        .iter()
+                        .position(|&c| c == '"')
+                        .with_context(|| format!("Unterminated quoted key in {}", expr))?;
+                    segments.push(Segment::Key(chars[i + 1..i + 1 + end].iter().collect()));
+                    i += end + 2;
+                } else {
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+                    let key: String = chars[start..i].iter().collect();
+                    if json_path && key == "*" {
+                        segments.push(Segment::Iterate);
+                    } else if !key.is_empty() {
+                        segments.push(Segment::Key(key));
+                    }
+                }
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .with_context(|| format!("Unterminated '[' in {}", expr))?;
+                let inner: String = chars[i + 1..i + end].iter().collect();
+                let inner = inner.trim();
+                let quoted = |quote| inner.strip_prefix(quote).and_then(|s: &str| s.strip_suffix(quote));
+                if inner.is_empty() || inner == "*" {
+                    segments.push(Segment::Iterate);
+                } else if let Some(key) = quoted('"').or_else(|| quoted('\'')) {
+                    segments.push(Segment::Key(key.to_string()));
+                } else {
+                    let index = inner
+                        .parse::<i64>()
+                        .with_context(|| format!("Invalid array index '{}' in {}", inner, expr))?;
+                    segments.push(Segment::Index(index));
+                }
+                i += end + 1;
+            }
+            other => bail!("Unexpected character '{}' in {}", other, expr),
+        }
+    }
+    Ok(segments)
+}
+
+/// Comparison operators of `select(...)`; two-character ones first so `>=` is not read as `>`.
+const OPERATORS: [(&str, Op); 6] =
+    [("==", Op::Eq), ("!=", Op::Ne), (">=", Op::Ge), ("<=", Op::Le), (">", Op::Gt), ("<", Op::Lt)];
+
+/// Parses the condition inside `select(...)`. The operator is looked for outside quoted
+/// strings, so `select(.a < ">")` compares with the string ">".
+fn parse_condition(cond: &str) -> Result<Stage> {
+    let operator = unquoted(cond).into_iter().find_map(|(i, _)| {
+        OPERATORS.iter().find(|(token, _)| cond[i..].starts_with(token)).map(|(token, op)| (i, token.len(), *op))
+    });
+    if let Some((i, len, op)) = operator {
+        let (lhs, rhs) = (&cond[..i], &cond[i + len..]);
+        let literal: Value = serde_json::from_str(rhs.trim())
+            .with_context(|| format!("Invalid literal in select: {}", rhs.trim()))?;
+        return Ok(Stage::Select {
+            path: parse_path(lhs)?,
+            comparison: Some((op, literal)),
+        });
+    }
+    Ok(Stage::Select {
+        path: parse_path(cond)?,
+        comparison: None,
+    })
+}
+
+/// The characters of EXPR outside quoted strings, with their byte offsets.
+fn unquoted(expr: &str) -> Vec<(usize, char)> {
+    let mut found = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in expr.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) => found.push((i, c)),
+        }
+    }
+    found
+}
+
+/// Splits EXPR into its stages at the pipes outside quoted strings, so that
+/// `select(.name == "a|b")` stays one stage.
+fn split_stages(expr: &str) -> Vec<&str> {
+    let mut stages = Vec::new();
+    let mut start = 0;
+    for (i, c) in unquoted(expr) {
+        if c == '|' {
+            stages.push(&expr[start..i]);
+            start = i + 1;
+        }
+    }
+    stages.push(&expr[start..]);
+    stages
+}
+
+impl Filter {
+    /// Compiles a filter expression such as `.items[] | select(.active)`.
+    pub fn parse(expr: &str) -> Result<Self> {
+        let mut stages = Vec::new();
+        for part in split_stages(expr) {
+            let part = part.trim();
+            if let Some(inner) = part.strip_prefix("select(").and_then(|s| s.strip_suffix(')')) {
+                stages.push(parse_condition(inner.trim())?);
+            } else {
+                stages.push(Stage::Path(parse_path(part)?));
+            }
+        }
+        let iterates = stages
+            .iter()
+            .any(|stage| matches!(stage, Stage::Path(path) if path.contains(&Segment::Iterate)));
+        Ok(Self { stages, iterates })
+    }
+
+    /// Renders RESULT of `apply` like `jq -r`: strings without quotes, and one line per
+    /// result when the filter iterates.
+    pub fn raw(&self, result: &Value) -> String {
+        let line = |value: &Value| match value {
+            Value::String(text) => text.clone(),
+            other => serde_json::to_string_pretty(other).unwrap_or_default(),
+        };
+        match result {
+            Value::Array(items) if self.iterates => items.iter().map(line).collect::<Vec<_>>().join("\n"),
+            other => line(other),
+        }
+    }
+
+    /// Applies the filter. Results of iterating filters are collected into an array.
+    pub fn apply(&self, input: &Value) -> Value {
+        let mut stream = vec![input.clone()];
+        for stage in &self.stages {
+            stream = match stage {
+                Stage::Path(path) => stream.iter().flat_map(|v| resolve(v, path)).collect(),
+                Stage::Select { path, comparison } => stream
+                    .into_iter()
+                    .filter(|v| {
+                        resolve(v, path).iter().any(|found| match comparison {
+                            None => truthy(found),
+                            Some((op, literal)) => compare(found, *op, literal),
+                        })
+                    })
+                    .collect(),
+            };
+        }
+        if !self.iterates && stream.len() == 1 {
+            stream.pop().unwrap_or(Value::Null)
+        } else {
+            Value::Array(stream)
+        }
+    }
+}
+
+/// Resolves a path against a value, yielding zero or more results.
+fn resolve(value: &Value, path: &[Segment]) -> Vec<Value> {
+    let Some((first, rest)) = path.split_first() else {
+        return vec![value.clone()];
+    };
+    match first {
+        Segment::Key(key) => match value.get(key) {
+            Some(next) => resolve(next, rest),
+            None => vec![Value::Null],
+        },
+        Segment::Index(index) => {
+            let next = value.as_array().and_then(|items| {
+                let index = if *index < 0 { items.len() as i64 + index } else { *index };
+                usize::try_from(index).ok().and_then(|i| items.get(i))
+            });
+            match next {
+                Some(next) => resolve(next, rest),
+                None => vec![Value::Null],
+            }
+        }
+        Segment::Iterate => match value {
+            Value::Array(items) => items.iter().flat_map(|item| resolve(item, rest)).collect(),
+            Value::Object(map) => map.values().flat_map(|item| resolve(item, rest)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn compare(lhs: &Value, op: Op, rhs: &Value) -> bool {
+    let ordering = compare_values(lhs, rhs);
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+    }
+}
+
+/// Orders JSON values the way jq does: null < false < true < numbers < strings < arrays < objects.
+pub fn compare_values(lhs: &Value, rhs: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+    match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => {
+            let a = a.as_f64().unwrap_or(0.0);
+            let b = b.as_f64().unwrap_or(0.0);
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(x, y)| compare_values(x, y))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        _ => rank(lhs).cmp(&rank(rhs)).then_with(|| lhs.to_string().cmp(&rhs.to_string())),
+    }
+}
+
+/// Sorts an array response by the value found at `path` in each element.
+pub fn sort_by(value: &mut Value, path: &str, descending: bool) -> Result<()> {
+    let segments = parse_path(path)?;
+    let Value::Array(items) = value else {
+        bail!("--sort-by requires an array response");
+    };
+    let key = |item: &Value| resolve(item, &segments).into_iter().next().unwrap_or(Value::Null);
+    items.sort_by(|a, b| {
+        let ordering = compare_values(&key(a), &key(b));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn json_path_selects_the_same_values_as_jq_paths() {
+        let body = json!({ "data": { "items": [{ "id": 1, "tag": "a" }, { "id": 2, "tag": "b" }] } });
+        for (json_path, jq) in [
+            ("$.data.items[0].id", ".data.items[0].id"),
+            ("$['data']['items'][*].tag", ".data.items[].tag"),
+            ("$.data.items.*.id", ".data.items[].id"),
+            ("$", "."),
+        ] {
+            assert_eq!(Filter::parse(json_path).unwrap().apply(&body), Filter::parse(jq).unwrap().apply(&body));
+        }
+        let tags = Filter::parse("$.data.items[*].tag").unwrap();
+        assert_eq!(tags.raw(&tags.apply(&body)), "a\nb");
+        assert!(parse_path("data.items").is_err());
+    }
+
+    #[test]
+    fn pipes_inside_strings_do_not_split_stages() {
+        let body = json!([{ "name": "a|b" }, { "name": "c" }]);
+        let filter = Filter::parse(r#".[] | select(.name == "a|b") | .name"#).unwrap();
+        assert_eq!(filter.apply(&body), json!(["a|b"]));
+        assert_eq!(split_stages(r#"select(.name == "x\"|y") | .id"#), [r#"select(.name == "x\"|y") "#, " .id"]);
+    }
+
+    #[test]
+    fn operators_inside_strings_are_part_of_the_literal() {
+        let body = json!([{ "a": "<" }, { "a": "=" }, { "a": ">=" }]);
+        let filter = Filter::parse(r#".[] | select(.a < ">") | .a"#).unwrap();
+        assert_eq!(filter.apply(&body), json!(["<", "="]));
+        let filter = Filter::parse(r#".[] | select(.a == ">=") | .a"#).unwrap();
+        assert_eq!(filter.apply(&body), json!([">="]));
+    }
+}
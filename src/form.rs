@@ -0,0 +1,138 @@
+use anyhow::{bail, Context, Result};
+use reqwest::multipart::{Form, Part};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use url::form_urlencoded;
+
+/// One field of a multipart/form-data body (`form` in a saved configuration): a text
+/// `value` or a `file` to upload, e.g. `{"name": "avatar", "file": "./me.png"}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FormPart {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Path of the file to upload; relative paths are resolved from the current directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Content-Type of the file, guessed from its extension when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl FormPart {
+    /// Parses `--form NAME=VALUE`.
+    pub fn field(option: &str) -> Result<FormPart> {
+        let (name, value) = option
+            .split_once('=')
+            .filter(|(name, _)| !name.trim().is_empty())
+            .with_context(|| format!("Invalid --form value (expected NAME=VALUE): {}", option))?;
+        Ok(FormPart { name: name.trim().to_string(), value: Some(value.to_string()), file: None, content_type: None })
+    }
+
+    /// Parses `--file NAME=@PATH`, optionally followed by `;type=CONTENT_TYPE`.
+    pub fn upload(option: &str) -> Result<FormPart> {
+        let (name, path) = option
+            .split_once('=')
+            .filter(|(name, path)| !name.trim().is_empty() && !path.is_empty())
+            .with_context(|| format!("Invalid --file value (expected NAME=@PATH): {}", option))?;
+        let path = path.strip_prefix('@').unwrap_or(path);
+        let (path, content_type) = match path.rsplit_once(";type=") {
+            Some((path, content_type)) => (path, Some(content_type.to_string())),
+            None => (path, None),
+        };
+        Ok(FormPart { name: name.trim().to_string(), value: None, file: Some(path.to_string()), content_type })
+    }
+
+    /// The values that may contain placeholders.
+    pub fn values_mut(&mut self) -> Vec<&mut String> {
+        self.value.iter_mut().chain(self.file.iter_mut()).collect()
+    }
+}
+
+/// Encodes PARTS as an application/x-www-form-urlencoded body, which cannot carry files.
+pub fn urlencode(parts: &[FormPart]) -> Result<String> {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for part in parts {
+        match (&part.value, &part.file) {
+            (Some(value), None) => serializer.append_pair(&part.name, value),
+            _ => bail!("Field {} of form_urlencoded needs a \"value\" (files need a multipart form)", part.name),
+        };
+    }
+    Ok(serializer.finish())
+}
+
+/// Splits an application/x-www-form-urlencoded BODY into fields, or None if it is not
+/// a list of NAME=VALUE pairs.
+pub fn parse_urlencoded(body: &str) -> Option<Vec<FormPart>> {
+    if body.is_empty() || !body.split('&').all(|pair| pair.split_once('=').is_some_and(|(name, _)| !name.is_empty())) {
+        return None;
+    }
+    let fields = form_urlencoded::parse(body.as_bytes())
+        .map(|(name, value)| FormPart {
+            name: name.into_owned(),
+            value: Some(value.into_owned()),
+            file: None,
+            content_type: None,
+        })
+        .collect();
+    Some(fields)
+}
+
+/// A field with its file already read, so that every retry sends the same bytes.
+pub struct LoadedPart {
+    name: String,
+    value: Vec<u8>,
+    file_name: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Reads the files of PARTS.
+pub fn load(parts: &[FormPart]) -> Result<Vec<LoadedPart>> {
+    parts
+        .iter()
+        .map(|part| {
+            let loaded = match (&part.value, &part.file) {
+                (Some(value), None) => LoadedPart {
+                    name: part.name.clone(),
+                    value: value.clone().into_bytes(),
+                    file_name: None,
+                    content_type: part.content_type.clone(),
+                },
+                (None, Some(file)) => {
+                    let path = Path::new(file);
+                    let content = fs::read(path)
+                        .with_context(|| format!("Failed to read {:?} for form field {}", path, part.name))?;
+                    let guessed = mime_guess::from_path(path).first().map(|mime| mime.to_string());
+                    LoadedPart {
+                        name: part.name.clone(),
+                        value: content,
+                        file_name: path.file_name().map(|name| name.to_string_lossy().into_owned()),
+                        content_type: part.content_type.clone().or(guessed),
+                    }
+                }
+                _ => bail!("Form field {} needs exactly one of \"value\" and \"file\"", part.name),
+            };
+            Ok(loaded)
+        })
+        .collect()
+}
+
+/// Builds the multipart body; called once per attempt because a form cannot be reused.
+pub fn build(parts: &[LoadedPart]) -> Result<Form> {
+    let mut form = Form::new();
+    for part in parts {
+        let mut body = Part::bytes(part.value.clone());
+        if let Some(ref file_name) = part.file_name {
+            body = body.file_name(file_name.clone());
+        }
+        if let Some(ref content_type) = part.content_type {
+            body = body
+                .mime_str(content_type)
+                .with_context(|| format!("Invalid content type {} for form field {}", content_type, part.name))?;
+        }
+        form = form.part(part.name.clone(), body);
+    }
+    Ok(form)
+}
@@ -0,0 +1,200 @@
+use anyhow::{bail, Result};
+use clap::Subcommand;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::request::RequestConfig;
+use crate::vars::{Vars, VARS_FILE};
+use crate::{get_config_path, get_default_dir, i18n, namespace, store};
+
+/// Subcommands of `new`: scaffolds of saved requests for common API styles.
+#[derive(Subcommand, Debug)]
+pub enum NewCommand {
+    /// リソースの一覧・作成（TARGET）と取得・更新・削除（TARGET/item）の設定を生成します。
+    RestCrud {
+        /// 生成先の名前空間。最後の要素がリソース名になります（例: SystemA/widgets）
+        target: String,
+        /// API のベース URL（例: https://api.example.com/v1）
+        #[arg(long = "base")]
+        base: String,
+        /// 既存のファイルを上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// GraphQL エンドポイントへのクエリ（POST）の設定を生成します。
+    Graphql {
+        /// 生成先の名前空間（例: SystemA/graphql）
+        target: String,
+        /// GraphQL エンドポイントの URL
+        #[arg(long = "url")]
+        url: String,
+        /// 既存のファイルを上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Webhook 受信側に送るサンプルイベント（POST）の設定を生成します。
+    Webhook {
+        /// 生成先の名前空間（例: SystemA/webhooks/order_created）
+        target: String,
+        /// Webhook を受け取るリスナーの URL
+        #[arg(long = "url")]
+        url: String,
+        /// イベント名（既定は TARGET の最後の要素）
+        #[arg(long = "event")]
+        event: Option<String>,
+        /// 既存のファイルを上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+}
+
+/// Files to write, checked as a whole before anything is written.
+#[derive(Default)]
+pub struct Scaffold {
+    files: Vec<(PathBuf, String)>,
+}
+
+impl Scaffold {
+    pub fn request(&mut self, target: &str, config: RequestConfig) -> Result<()> {
+        let base_dir = get_default_dir()?;
+        let method = config.method.clone().unwrap_or_else(|| "GET".to_string());
+        let path = get_config_path(&base_dir, target, &method)?;
+        self.files.push((path, serde_json::to_string_pretty(&config)?));
+        Ok(())
+    }
+
+    pub fn vars(&mut self, target: &str, vars: Vars) -> Result<()> {
+        let path = namespace::dir(&get_default_dir()?, target)?.join(VARS_FILE);
+        self.files.push((path, serde_json::to_string_pretty(&vars)?));
+        Ok(())
+    }
+
+    pub fn write(self, targets: &[&str], force: bool) -> Result<()> {
+        let existing: Vec<String> = self
+            .files
+            .iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, _)| format!("{:?}", path))
+            .collect();
+        if !existing.is_empty() && !force {
+            bail!("{} already exist(s). Use --force to overwrite.", existing.join(", "));
+        }
+        let base_dir = get_default_dir()?;
+        for target in targets {
+            namespace::create(&base_dir, target)?;
+        }
+        for (path, content) in &self.files {
+            store::write_atomic(path, content)?;
+            println!("{}", i18n::tf("  created  {}", &[&format!("{:?}", path)]));
+        }
+        Ok(())
+    }
+}
+
+fn json_headers() -> Option<HashMap<String, String>> {
+    Some(
+        [("Content-Type", "application/json"), ("Accept", "application/json")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    )
+}
+
+fn request(method: &str, url: String, data: Option<serde_json::Value>) -> RequestConfig {
+    RequestConfig {
+        url: Some(url),
+        method: Some(method.to_string()),
+        headers: json_headers(),
+        data,
+        timeout: Some(30),
+        ..RequestConfig::default()
+    }
+}
+
+fn last_segment(target: &str) -> Result<String> {
+    Ok(namespace::segments(target)?.pop().unwrap_or_default())
+}
+
+fn rest_crud(target: &str, base: &str, force: bool) -> Result<()> {
+    let target = namespace::normalize(target)?;
+    let item = format!("{}/item", target);
+    let resource = last_segment(&target)?;
+    let collection_url = format!("{}/{}", base.trim_end_matches('/'), resource);
+    let item_url = format!("{}/{{{{id}}}}", collection_url);
+    let sample = json!({ "name": format!("example {}", resource) });
+
+    let mut scaffold = Scaffold::default();
+    scaffold.request(&target, request("GET", collection_url.clone(), None))?;
+    scaffold.request(&target, request("POST", collection_url, Some(sample.clone())))?;
+    scaffold.request(&item, request("GET", item_url.clone(), None))?;
+    scaffold.request(&item, request("PUT", item_url.clone(), Some(sample)))?;
+    scaffold.request(&item, request("DELETE", item_url, None))?;
+    // 個別操作の URL で使う {{id}} の既定値
+    scaffold.vars(&item, Vars::from([("id".to_string(), "1".to_string())]))?;
+    scaffold.write(&[&target, &item], force)?;
+    println!(
+        "{}",
+        i18n::tf(
+            "List/create with `ferrapi_tester -- {}` / `ferrapi_tester -X POST -- {}`; get/update/delete one with `-- {}` and --var id=...",
+            &[&target, &target, &item]
+        )
+    );
+    Ok(())
+}
+
+fn graphql(target: &str, url: &str, force: bool) -> Result<()> {
+    let target = namespace::normalize(target)?;
+    let body = json!({
+        "query": "query Example($first: Int) {\n  __typename\n}",
+        "variables": { "first": 10 },
+        "operationName": "Example",
+    });
+    let mut scaffold = Scaffold::default();
+    scaffold.request(&target, request("POST", url.to_string(), Some(body)))?;
+    scaffold.write(&[&target], force)?;
+    println!(
+        "{}",
+        i18n::tf("Edit the query in the saved POST.json, then run `ferrapi_tester -X POST -- {}`.", &[&target])
+    );
+    Ok(())
+}
+
+fn webhook(target: &str, url: &str, event: Option<String>, force: bool) -> Result<()> {
+    let target = namespace::normalize(target)?;
+    let event = match event {
+        Some(event) => event,
+        None => last_segment(&target)?,
+    };
+    let body = json!({
+        "id": "{{event_id}}",
+        "type": event,
+        "created_at": "{{created_at}}",
+        "data": { "object": { "id": "{{object_id}}" } },
+    });
+    let mut config = request("POST", url.to_string(), Some(body));
+    if let Some(ref mut headers) = config.headers {
+        headers.insert("X-Webhook-Event".to_string(), event.clone());
+        headers.insert("X-Webhook-Signature".to_string(), "{{webhook_signature}}".to_string());
+    }
+    let vars = Vars::from([
+        ("event_id".to_string(), "evt_0001".to_string()),
+        ("created_at".to_string(), "2024-01-01T00:00:00Z".to_string()),
+        ("object_id".to_string(), "obj_0001".to_string()),
+        ("webhook_signature".to_string(), "replace-me".to_string()),
+    ]);
+    let mut scaffold = Scaffold::default();
+    scaffold.request(&target, config)?;
+    scaffold.vars(&target, vars)?;
+    scaffold.write(&[&target], force)?;
+    println!("{}", i18n::tf("Send the sample event to your listener with `ferrapi_tester -X POST -- {}`.", &[&target]));
+    Ok(())
+}
+
+pub fn run(command: NewCommand) -> Result<()> {
+    match command {
+        NewCommand::RestCrud { target, base, force } => rest_crud(&target, &base, force),
+        NewCommand::Graphql { target, url, force } => graphql(&target, &url, force),
+        NewCommand::Webhook { target, url, event, force } => webhook(&target, &url, event, force),
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use dialoguer::{Input, Select};
+use std::collections::HashMap;
+
+use crate::autocomplete::{Sources, Suggestions};
+use crate::i18n;
+
+/// Header names offered when adding a header.
+const COMMON_HEADERS: &[&str] = &[
+    "Accept",
+    "Authorization",
+    "Content-Type",
+    "Cache-Control",
+    "Cookie",
+    "If-None-Match",
+    "User-Agent",
+    "X-API-Key",
+    "X-Request-Id",
+];
+
+/// Well-known values offered for some headers in addition to those from history.
+fn common_values(name: &str) -> &'static [&'static str] {
+    match name.to_ascii_lowercase().as_str() {
+        "accept" | "content-type" => &["application/json", "application/x-www-form-urlencoded", "text/plain", "*/*"],
+        "authorization" => &["Bearer {{token}}", "Basic {{credentials}}"],
+        "cache-control" => &["no-cache", "no-store"],
+        _ => &[],
+    }
+}
+
+fn pick_name(existing: &HashMap<String, String>, sources: &Sources) -> Result<String> {
+    // 履歴・保存済み設定でよく使うヘッダー名を先頭に、続けて一般的なヘッダー名
+    let mut names = sources.header_names();
+    for name in COMMON_HEADERS {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_string());
+        }
+    }
+    let mut items: Vec<String> = names
+        .iter()
+        .filter(|name| !existing.keys().any(|key| key.eq_ignore_ascii_case(name)))
+        .cloned()
+        .collect();
+    items.push(i18n::t("Other...").to_string());
+    let selection = Select::new()
+        .with_prompt(i18n::t("Header name"))
+        .items(&items)
+        .default(0)
+        .interact()?;
+    if selection == items.len() - 1 {
+        let completion = Suggestions(names);
+        let name = Input::<String>::new()
+            .with_prompt(i18n::t("Header name (Tab to complete)"))
+            .completion_with(&completion)
+            .interact_text()?;
+        return Ok(name.trim().to_string());
+    }
+    Ok(items[selection].clone())
+}
+
+fn pick_value(name: &str, current: Option<&str>, sources: &Sources) -> Result<String> {
+    let mut suggestions = sources.header_values(name);
+    for value in common_values(name) {
+        if !suggestions.iter().any(|s| s == value) {
+            suggestions.push(value.to_string());
+        }
+    }
+    suggestions.retain(|s| Some(s.as_str()) != current);
+    if !suggestions.is_empty() {
+        let mut items = suggestions.clone();
+        items.push(i18n::t("Enter a value...").to_string());
+        let selection = Select::new()
+            .with_prompt(i18n::tf("Value for {}", &[&name]))
+            .items(&items)
+            .default(0)
+            .interact()?;
+        if selection < suggestions.len() {
+            return Ok(suggestions[selection].clone());
+        }
+    }
+    let completion = Suggestions(sources.header_values(name));
+    let value = Input::<String>::new()
+        .with_prompt(i18n::tf("Value for {} (Tab to complete)", &[&name]))
+        .with_initial_text(current.unwrap_or_default())
+        .completion_with(&completion)
+        .interact_text()?;
+    Ok(value)
+}
+
+/// Interactive editor for a set of headers: add, edit, and delete rows, with
+/// header names picked from a list and values suggested from history and saved
+/// configurations, most frequent and recent first.
+pub fn edit(headers: &mut HashMap<String, String>, sources: &Sources) -> Result<()> {
+    loop {
+        let mut keys: Vec<String> = headers.keys().cloned().collect();
+        keys.sort();
+        let mut items: Vec<String> = keys.iter().map(|k| format!("{}: {}", k, headers[k])).collect();
+        items.push(i18n::t("+ Add a header").to_string());
+        items.push(i18n::t("Done").to_string());
+        let selection = Select::new()
+            .with_prompt(i18n::t("Headers"))
+            .items(&items)
+            .default(keys.len())
+            .interact()?;
+        if selection == items.len() - 1 {
+            return Ok(());
+        }
+        if selection == items.len() - 2 {
+            let name = pick_name(headers, sources)?;
+            if name.is_empty() {
+                continue;
+            }
+            let value = pick_value(&name, None, sources)?;
+            headers.insert(name, value);
+            continue;
+        }
+        let key = keys[selection].clone();
+        let action = Select::new()
+            .with_prompt(&key)
+            .items(&[i18n::t("Edit value"), i18n::t("Rename"), i18n::t("Delete"), i18n::t("Back")])
+            .default(0)
+            .interact()?;
+        match action {
+            0 => {
+                let value = pick_value(&key, headers.get(&key).map(String::as_str), sources)?;
+                headers.insert(key, value);
+            }
+            1 => {
+                let name: String = Input::new().with_prompt(i18n::t("Header name")).with_initial_text(&key).interact_text()?;
+                if let Some(value) = headers.remove(&key) {
+                    headers.insert(name.trim().to_string(), value);
+                }
+            }
+            2 => {
+                headers.remove(&key);
+            }
+            _ => {}
+        }
+    }
+}
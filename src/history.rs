@@ -0,0 +1,637 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::cancel;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::output::{self, Table};
+use crate::request::{self, Exchange, RequestConfig};
+use crate::retry::RetryArgs;
+use crate::history_db;
+use crate::settings::{self, HistoryBackend, HistorySettings, Settings};
+use crate::stats;
+use crate::store;
+use crate::vars;
+
+/// Subcommands of `history`.
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommand {
+    /// 実行したリクエストの履歴を新しい順に表示します。
+    List {
+        /// 対象の名前空間または URL の前方一致（省略時はすべて）
+        target: Option<String>,
+        /// この期間内の履歴だけを表示（例: 1h, 7d）
+        #[arg(long = "since", value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+        /// 表示する最大件数
+        #[arg(short = 'n', long = "limit", default_value = "20")]
+        limit: usize,
+    },
+    /// URL・ターゲット・エラー・レスポンスのヘッダーとボディから語句を含む履歴を検索します。
+    Search {
+        /// 検索する語句（例: "error_code=E1234"。英数字の並びで照合し、大文字小文字は区別しません。省略時はすべて）
+        text: Option<String>,
+        /// HTTP メソッドで絞り込み（例: POST）
+        #[arg(short = 'X', long = "method")]
+        method: Option<String>,
+        /// ステータスコードで絞り込み（例: 404、5xx）
+        #[arg(long = "status", value_parser = parse_status)]
+        status: Option<(u16, u16)>,
+        /// 失敗したリクエスト（ネットワークエラーまたは 400 以上）だけを表示します。
+        #[arg(long = "failed")]
+        failed: bool,
+        /// この期間内の履歴だけを検索（例: 1h, 7d）
+        #[arg(long = "since", value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+        /// 表示する最大件数
+        #[arg(short = 'n', long = "limit", default_value = "20")]
+        limit: usize,
+        /// 古い順に表示します（エラーが最初に返された時期の確認などに）。
+        #[arg(long = "oldest-first")]
+        oldest_first: bool,
+    },
+    /// 履歴のエントリ（history list の # 列の番号）の詳細を表示します。
+    Show {
+        /// エントリの番号（1 が最新）
+        number: usize,
+    },
+    /// 履歴のエントリ（history list の # 列の番号）のリクエストを再送信します。
+    Replay {
+        /// エントリの番号（1 が最新）
+        number: usize,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+    },
+    /// 保持ポリシーに従って履歴を削除します（オプション指定時はその値を優先）。
+    Prune {
+        /// 保持する最大件数
+        #[arg(long = "max-entries")]
+        max_entries: Option<usize>,
+        /// これより古い履歴を削除（例: 30d）
+        #[arg(long = "max-age", value_parser = humantime::parse_duration)]
+        max_age: Option<Duration>,
+        /// ファイルサイズの上限（例: 50MB）
+        #[arg(long = "max-size", value_parser = settings::parse_size)]
+        max_size: Option<u64>,
+        /// すべての履歴を削除します。
+        #[arg(long = "all", conflicts_with_all = ["max_entries", "max_age", "max_size"])]
+        all: bool,
+    },
+}
+
+/// Parses --status: a code such as 404, or a class such as 5xx.
+fn parse_status(input: &str) -> Result<(u16, u16)> {
+    let lower = input.to_ascii_lowercase();
+    let class = lower.strip_suffix("xx").and_then(|digit| digit.parse::<u16>().ok());
+    if let Some(class) = class.filter(|class| (1..=5).contains(class)) {
+        return Ok((class * 100, class * 100 + 99));
+    }
+    match input.parse::<u16>() {
+        Ok(code) => Ok((code, code)),
+        Err(_) => bail!("Invalid status '{}' (e.g. 404 or 5xx)", input),
+    }
+}
+
+/// Splits TEXT into lowercase words of letters and digits, the units `history search`
+/// matches; "error_code=E1234" and `"error_code": "E1234"` give the same words.
+pub fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// True when the words of QUERY appear consecutively in TEXT, the last one as a prefix.
+fn contains_phrase(text: &str, query: &[String]) -> bool {
+    let Some((last, rest)) = query.split_last() else {
+        return true;
+    };
+    let words = words(text);
+    words
+        .windows(query.len())
+        .any(|window| window[..rest.len()] == *rest && window[rest.len()].starts_with(last.as_str()))
+}
+
+/// Conditions of `history search`.
+#[derive(Debug, Clone)]
+pub struct SearchFilter {
+    /// Words found in this order in the URL, target, error, response headers or body.
+    pub text: Option<String>,
+    pub method: Option<String>,
+    /// Inclusive range of status codes.
+    pub status: Option<(u16, u16)>,
+    /// Only network errors and statuses of 400 or more.
+    pub failed: bool,
+    pub cutoff_ms: u64,
+    pub limit: usize,
+    pub oldest_first: bool,
+}
+
+impl SearchFilter {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let query = self.text.as_deref().map(words).unwrap_or_default();
+        entry.timestamp_ms >= self.cutoff_ms
+            && (query.is_empty() || entry.search_fields().iter().any(|field| contains_phrase(field, &query)))
+            && self.method.as_deref().is_none_or(|method| entry.method().eq_ignore_ascii_case(method))
+            && self.status.is_none_or(|(min, max)| entry.status.is_some_and(|status| (min..=max).contains(&status)))
+            && (!self.failed || !entry.succeeded())
+    }
+}
+
+/// Resolved retention limits for the history store.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retention {
+    pub max_entries: Option<usize>,
+    pub max_age: Option<Duration>,
+    pub max_size: Option<u64>,
+}
+
+impl Retention {
+    pub fn from_settings(settings: &HistorySettings) -> Result<Retention> {
+        Ok(Retention {
+            max_entries: settings.max_entries,
+            max_age: settings
+                .max_age
+                .as_deref()
+                .map(humantime::parse_duration)
+                .transpose()
+                .with_context(|| "Invalid history.max_age in settings")?,
+            max_size: settings.max_size.as_deref().map(settings::parse_size).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.max_entries.is_none() && self.max_age.is_none() && self.max_size.is_none()
+    }
+
+    /// The limits pruned to once one is exceeded: 10% below them, so the store is
+    /// not rewritten on every request once it is full.
+    pub fn slack(&self) -> Retention {
+        Retention {
+            max_entries: self.max_entries.map(|max| max - max / 10),
+            max_age: self.max_age,
+            max_size: self.max_size.map(|max| max - max / 10),
+        }
+    }
+}
+
+fn backend() -> Result<HistoryBackend> {
+    Ok(Settings::load()?.history.backend)
+}
+
+/// One executed request in ~/.ferrapi_tester/history.jsonl.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub timestamp_ms: u64,
+    #[serde(default)]
+    pub target: Option<String>,
+    /// The request as sent, with variables resolved (secrets stay as placeholders).
+    #[serde(with = "crate::compress")]
+    pub request: RequestConfig,
+    /// The request as written, before any placeholder was resolved; `history replay` sends
+    /// this one, so that `{{...}}` text inside variable values is never resolved again.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::compress")]
+    pub written: Option<RequestConfig>,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub elapsed_ms: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_headers: Option<BTreeMap<String, String>>,
+    /// The response body as text, cut at `history.response_body_limit`.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::compress")]
+    pub response_body: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn method(&self) -> &str {
+        self.request.method.as_deref().unwrap_or("GET")
+    }
+
+    pub fn url(&self) -> &str {
+        self.request.url.as_deref().unwrap_or("")
+    }
+
+    /// True when a response was received with a non-error (< 400) status.
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none() && self.status.is_some_and(|s| s < 400)
+    }
+
+    /// The texts `history search` looks in: URL, target, error, response headers and body.
+    pub fn search_fields(&self) -> [String; 5] {
+        let headers = self
+            .response_headers
+            .iter()
+            .flatten()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n");
+        [
+            self.url().to_string(),
+            self.target.clone().unwrap_or_default(),
+            self.error.clone().unwrap_or_default(),
+            headers,
+            self.response_body.clone().unwrap_or_default(),
+        ]
+    }
+
+    /// Groups entries by endpoint: the saved TARGET if any, otherwise the URL without its query.
+    pub fn endpoint(&self) -> String {
+        endpoint(self.target.as_deref(), self.method(), self.url())
+    }
+}
+
+/// The endpoint of a request (see `HistoryEntry::endpoint`).
+pub fn endpoint(target: Option<&str>, method: &str, url: &str) -> String {
+    match target {
+        Some(target) => format!("{} {}", method.to_uppercase(), target),
+        None => format!("{} {}", method.to_uppercase(), url.split('?').next().unwrap_or("")),
+    }
+}
+
+/// The newest recorded response of ENDPOINT with a JSON body, and that body.
+/// Bodies cut at `history.response_body_limit` are not JSON and are skipped.
+pub fn previous_json(endpoint: &str) -> Result<Option<(HistoryEntry, Value)>> {
+    let found = load()?.into_iter().rev().filter(|entry| entry.endpoint() == endpoint).find_map(|entry| {
+        let body = serde_json::from_str(entry.response_body.as_deref()?).ok()?;
+        Some((entry, body))
+    });
+    Ok(found)
+}
+
+pub fn history_path() -> Result<PathBuf> {
+    Ok(get_default_dir()?.join("history.jsonl"))
+}
+
+/// Cuts TEXT to at most LIMIT bytes, at a character boundary.
+fn truncate(mut text: String, limit: usize) -> String {
+    if text.len() > limit {
+        let mut end = limit;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+/// Appends the executed request to the history store. WRITTEN is the request before its
+/// placeholders were resolved into CONFIG.
+pub fn record(
+    target: Option<&str>,
+    written: &RequestConfig,
+    config: &RequestConfig,
+    outcome: &Result<Exchange>,
+) -> Result<()> {
+    let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let settings = Settings::load()?.history;
+    let body_limit = settings::parse_size(&settings.response_body_limit)
+        .context("Invalid history.response_body_limit in settings")? as usize;
+    let exchange = outcome.as_ref().ok();
+    let entry = HistoryEntry {
+        timestamp_ms,
+        target: target.map(str::to_string),
+        request: config.clone(),
+        written: Some(written.clone()),
+        status: exchange.map(|e| e.status.as_u16()),
+        elapsed_ms: exchange.map(|e| e.elapsed.as_millis() as u64),
+        error: outcome.as_ref().err().map(|e| e.to_string()),
+        response_headers: exchange.map(|e| {
+            e.headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+                .collect()
+        }),
+        response_body: exchange
+            .filter(|e| body_limit > 0 && !e.body.is_empty())
+            .map(|e| truncate(e.text(), body_limit)),
+    };
+    if settings.backend == HistoryBackend::Sqlite {
+        history_db::append(&entry)?;
+        return history_db::enforce(&Retention::from_settings(&settings)?);
+    }
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let _lock = store::lock(&path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open history {:?}", path))?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+        .with_context(|| format!("Failed to write history {:?}", path))?;
+    drop(file);
+    enforce_locked(&Retention::from_settings(&settings)?)
+}
+
+pub fn cutoff_ms(max_age: Duration) -> u64 {
+    SystemTime::now()
+        .checked_sub(max_age)
+        .unwrap_or(UNIX_EPOCH)
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Cheaply checks the limits (line count, oldest entry, file size) and prunes only
+/// when one is exceeded, down to the `slack` limits.
+fn enforce_locked(retention: &Retention) -> Result<()> {
+    if retention.is_empty() {
+        return Ok(());
+    }
+    let path = history_path()?;
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read history {:?}", path))?;
+    let lines = bytes.iter().filter(|&&b| b == b'\n').count();
+    let too_many = retention.max_entries.is_some_and(|max| lines > max);
+    let too_big = retention.max_size.is_some_and(|max| bytes.len() as u64 > max);
+    let too_old = retention.max_age.is_some_and(|age| {
+        bytes
+            .split(|&b| b == b'\n')
+            .next()
+            .and_then(|first| serde_json::from_slice::<HistoryEntry>(first).ok())
+            .is_some_and(|oldest| oldest.timestamp_ms < cutoff_ms(age))
+    });
+    if too_many || too_big || too_old {
+        prune_locked(&retention.slack())?;
+    }
+    Ok(())
+}
+
+/// Rewrites the history keeping only entries within the limits; returns how many were removed.
+pub fn prune(retention: &Retention) -> Result<usize> {
+    if backend()? == HistoryBackend::Sqlite {
+        return history_db::prune(retention);
+    }
+    let _lock = store::lock(&history_path()?)?;
+    prune_locked(retention)
+}
+
+fn prune_locked(retention: &Retention) -> Result<usize> {
+    let entries = load_jsonl()?;
+    let before = entries.len();
+    let mut kept: Vec<HistoryEntry> = match retention.max_age {
+        Some(age) => {
+            let cutoff = cutoff_ms(age);
+            entries.into_iter().filter(|e| e.timestamp_ms >= cutoff).collect()
+        }
+        None => entries,
+    };
+    if let Some(max) = retention.max_entries {
+        let excess = kept.len().saturating_sub(max);
+        kept.drain(..excess);
+    }
+    let mut lines: Vec<String> = kept
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<_, _>>()?;
+    if let Some(max) = retention.max_size {
+        let mut size: u64 = lines.iter().map(|l| l.len() as u64 + 1).sum();
+        let mut excess = 0;
+        while size > max && excess < lines.len() {
+            size -= lines[excess].len() as u64 + 1;
+            excess += 1;
+        }
+        lines.drain(..excess);
+    }
+    let removed = before - lines.len();
+    if removed > 0 {
+        let path = history_path()?;
+        let mut content = lines.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        store::write_atomic(&path, content).with_context(|| format!("Failed to write history {:?}", path))?;
+    }
+    Ok(removed)
+}
+
+/// Returns the NUMBER-th most recent entry, as numbered by `history list`.
+fn nth(number: usize) -> Result<HistoryEntry> {
+    if backend()? == HistoryBackend::Sqlite {
+        return match history_db::nth(number)? {
+            Some(entry) => Ok(entry),
+            None => bail!("No history entry #{} (there are {} entries)", number, history_db::count()?),
+        };
+    }
+    let entries = load()?;
+    let count = entries.len();
+    match number.checked_sub(1).and_then(|index| entries.into_iter().rev().nth(index)) {
+        Some(entry) => Ok(entry),
+        None => bail!("No history entry #{} (there are {} entries)", number, count),
+    }
+}
+
+/// The request `history replay` sends for ENTRY (#NUMBER): the request as written. Entries
+/// recorded before that was kept only have the resolved request, whose placeholders may
+/// come from variable values, so those are replayed only when they contain none.
+fn replayed(entry: &HistoryEntry, number: usize) -> Result<&RequestConfig> {
+    if let Some(ref written) = entry.written {
+        return Ok(written);
+    }
+    if vars::has_placeholder(&serde_json::to_string(&entry.request)?) {
+        bail!(
+            "History entry #{} contains {{{{...}}}} placeholders and cannot be replayed; send the request again",
+            number
+        );
+    }
+    Ok(&entry.request)
+}
+
+/// Prints numbered entries as the `history list` table.
+fn print_entries<'a>(entries: impl Iterator<Item = (usize, &'a HistoryEntry)>) -> Result<()> {
+    let mut table = Table::new(&[
+        ("#", "number"),
+        ("TIME", "timestamp"),
+        ("METHOD", "method"),
+        ("REQUEST", "request"),
+        ("STATUS", "status"),
+        ("TIME(ms)", "elapsed_ms"),
+        ("ERROR", "error"),
+    ]);
+    for (number, entry) in entries {
+        let time = UNIX_EPOCH + Duration::from_millis(entry.timestamp_ms);
+        table.push(vec![
+            json!(number),
+            json!(humantime::format_rfc3339_seconds(time).to_string()),
+            json!(entry.method()),
+            json!(entry.target.as_deref().unwrap_or_else(|| entry.url())),
+            json!(entry.status),
+            json!(entry.elapsed_ms),
+            json!(entry.error),
+        ]);
+    }
+    output::renderer()?.print(&table, i18n::t("No history entries match."))
+}
+
+pub async fn run(command: HistoryCommand) -> Result<()> {
+    match command {
+        HistoryCommand::List { target, since, limit } => {
+            let cutoff = since.map(cutoff_ms).unwrap_or(0);
+            // 番号は絞り込みに関係なく履歴全体で新しい順に振り、show / replay でそのまま使えるようにする
+            let entries = load()?;
+            let selected = entries
+                .iter()
+                .rev()
+                .enumerate()
+                .filter(|(_, entry)| entry.timestamp_ms >= cutoff)
+                .filter(|(_, entry)| target.as_deref().is_none_or(|t| stats::matches_target(entry, t)))
+                .take(limit)
+                .map(|(index, entry)| (index + 1, entry));
+            print_entries(selected)?;
+        }
+        HistoryCommand::Search { text, method, status, failed, since, limit, oldest_first } => {
+            let filter = SearchFilter {
+                text,
+                method,
+                status,
+                failed,
+                cutoff_ms: since.map(cutoff_ms).unwrap_or(0),
+                limit,
+                oldest_first,
+            };
+            let found = search(&filter)?;
+            print_entries(found.iter().map(|(number, entry)| (*number, entry)))?;
+        }
+        HistoryCommand::Show { number } => {
+            let entry = nth(number)?;
+            // 圧縮して保存されたリクエストも展開して表示する
+            let mut shown = serde_json::to_value(&entry)?;
+            shown["request"] = serde_json::to_value(&entry.request)?;
+            if entry.written.is_some() {
+                shown["written"] = serde_json::to_value(&entry.written)?;
+            }
+            if entry.response_body.is_some() {
+                shown["response_body"] = serde_json::to_value(&entry.response_body)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&shown)?);
+        }
+        HistoryCommand::Replay { number, retry } => {
+            let entry = nth(number)?;
+            let renderer = output::renderer()?;
+            renderer.note(&i18n::tf("Replaying #{}: {} {}", &[&number, &entry.method(), &entry.url()]));
+            let request = replayed(&entry, number)?;
+            let exchange = cancel::guard(request::execute(request, entry.target.as_deref(), &retry.policy())).await?;
+            output::emit(&renderer.response(&exchange, &exchange.text())?, None)?;
+        }
+        HistoryCommand::Prune { max_entries, max_age, max_size, all } => {
+            let retention = if all {
+                Retention {
+                    max_entries: Some(0),
+                    ..Retention::default()
+                }
+            } else if max_entries.is_some() || max_age.is_some() || max_size.is_some() {
+                Retention { max_entries, max_age, max_size }
+            } else {
+                Retention::from_settings(&Settings::load()?.history)?
+            };
+            let removed = prune(&retention)?;
+            if removed == 1 {
+                println!("{}", i18n::t("Removed 1 history entry."));
+            } else {
+                println!("{}", i18n::tf("Removed {} history entries.", &[&removed]));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads all history entries, oldest first.
+pub fn load() -> Result<Vec<HistoryEntry>> {
+    load_since(0)
+}
+
+/// Loads the entries recorded at or after CUTOFF_MS, oldest first.
+pub fn load_since(cutoff_ms: u64) -> Result<Vec<HistoryEntry>> {
+    if backend()? == HistoryBackend::Sqlite {
+        return history_db::load_since(cutoff_ms);
+    }
+    let mut entries = load_jsonl()?;
+    entries.retain(|entry| entry.timestamp_ms >= cutoff_ms);
+    Ok(entries)
+}
+
+/// Returns the newest entries matching FILTER with their `history list` numbers.
+pub fn search(filter: &SearchFilter) -> Result<Vec<(usize, HistoryEntry)>> {
+    if backend()? == HistoryBackend::Sqlite {
+        return history_db::search(filter);
+    }
+    let entries = load_jsonl()?;
+    let count = entries.len();
+    let numbered = entries.into_iter().enumerate().map(|(index, entry)| (count - index, entry));
+    let matching = numbered.filter(|(_, entry)| filter.matches(entry));
+    Ok(if filter.oldest_first {
+        matching.take(filter.limit).collect()
+    } else {
+        let mut found: Vec<_> = matching.collect();
+        found.reverse();
+        found.truncate(filter.limit);
+        found
+    })
+}
+
+/// Loads all entries of history.jsonl, oldest first. Unreadable lines are skipped.
+pub fn load_jsonl() -> Result<Vec<HistoryEntry>> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(&path).with_context(|| format!("Failed to open history {:?}", path))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn search_matches_words_in_order() {
+        let body = r#"{"error": {"error_code": "E1234", "message": "Out of stock"}}"#;
+        assert!(contains_phrase(body, &words("error_code=E1234")));
+        assert!(contains_phrase(body, &words("out of st")));
+        assert!(!contains_phrase(body, &words("E1234 error_code")));
+        assert!(!contains_phrase(body, &words("E12345")));
+    }
+
+    #[test]
+    fn replay_does_not_resolve_placeholders_from_variable_values() {
+        // `vars set token '{{cmd:touch pwned}}'` then `send -H 'X-Tok: {{token}}'`
+        let header = |value: &str| RequestConfig {
+            url: Some("http://localhost/".to_string()),
+            headers: Some(HashMap::from([("X-Tok".to_string(), value.to_string())])),
+            ..RequestConfig::default()
+        };
+        let mut entry = HistoryEntry {
+            timestamp_ms: 0,
+            target: None,
+            request: header("{{cmd:touch pwned}}"),
+            written: Some(header("{{token}}")),
+            status: Some(200),
+            elapsed_ms: None,
+            error: None,
+            response_headers: None,
+            response_body: None,
+        };
+        assert_eq!(replayed(&entry, 1).unwrap().headers, header("{{token}}").headers);
+        entry.written = None;
+        assert!(replayed(&entry, 1).is_err());
+        entry.request = header("abc");
+        assert_eq!(replayed(&entry, 1).unwrap().headers, header("abc").headers);
+    }
+}
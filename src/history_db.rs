@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension, TransactionBehavior};
+use std::{path::PathBuf, time::Duration};
+
+use crate::get_default_dir;
+use crate::history::{self, HistoryEntry, Retention, SearchFilter};
+use crate::i18n;
+
+/// File (under the base directory) of the SQLite history backend.
+pub const DB_FILE: &str = "history.sqlite3";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp_ms INTEGER NOT NULL,
+    target TEXT,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    status INTEGER,
+    elapsed_ms INTEGER,
+    error TEXT,
+    entry TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entries_timestamp ON entries (timestamp_ms);
+CREATE INDEX IF NOT EXISTS entries_target ON entries (target);
+";
+
+/// Full-text index for `history search`; rowid is the id in `entries`.
+const SEARCH_SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_search USING fts5 (url, target, error, headers, body);
+";
+
+/// Version of the schema, kept in `PRAGMA user_version` (2 added the search index).
+const SCHEMA_VERSION: i64 = 2;
+
+pub fn db_path() -> Result<PathBuf> {
+    Ok(get_default_dir()?.join(DB_FILE))
+}
+
+/// Opens the database, creating it on first use. A new database takes over the
+/// entries of an existing history.jsonl so switching backends keeps the history.
+fn open() -> Result<Connection> {
+    let path = db_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let mut connection = Connection::open(&path).with_context(|| format!("Failed to open history {:?}", path))?;
+    // 並列実行中の書き込みは待って順に処理する
+    connection.busy_timeout(Duration::from_secs(10))?;
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version < SCHEMA_VERSION {
+        // 同時に起動した別のプロセスが先に更新していないか、書き込みロックを取ってから確かめる
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: i64 = transaction.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version == 0 {
+            transaction.execute_batch(SCHEMA)?;
+            transaction.execute_batch(SEARCH_SCHEMA)?;
+            let entries = history::load_jsonl()?;
+            for entry in &entries {
+                insert(&transaction, entry)?;
+            }
+            if !entries.is_empty() {
+                let from = format!("{:?}", history::history_path()?);
+                eprintln!(
+                    "{}",
+                    i18n::tf("Imported {} entries from {} into {}.", &[&entries.len(), &from, &format!("{:?}", path)])
+                );
+            }
+        } else if version == 1 {
+            transaction.execute_batch(SEARCH_SCHEMA)?;
+            let mut statement = transaction.prepare("SELECT id, entry FROM entries")?;
+            let rows: Vec<(i64, String)> =
+                statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<_>>()?;
+            drop(statement);
+            for (id, json) in rows {
+                if let Some(entry) = parse(json) {
+                    index(&transaction, id, &entry)?;
+                }
+            }
+        }
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+        transaction.commit()?;
+    }
+    Ok(connection)
+}
+
+fn insert(connection: &Connection, entry: &HistoryEntry) -> Result<()> {
+    connection.execute(
+        "INSERT INTO entries (timestamp_ms, target, method, url, status, elapsed_ms, error, entry)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            entry.timestamp_ms as i64,
+            entry.target,
+            entry.method(),
+            entry.url(),
+            entry.status,
+            entry.elapsed_ms.map(|ms| ms as i64),
+            entry.error,
+            serde_json::to_string(entry)?,
+        ],
+    )?;
+    index(connection, connection.last_insert_rowid(), entry)
+}
+
+fn index(connection: &Connection, id: i64, entry: &HistoryEntry) -> Result<()> {
+    let [url, target, error, headers, body] = entry.search_fields();
+    connection.execute(
+        "INSERT INTO entries_search (rowid, url, target, error, headers, body) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, url, target, error, headers, body],
+    )?;
+    Ok(())
+}
+
+/// Appends an entry.
+pub fn append(entry: &HistoryEntry) -> Result<()> {
+    insert(&open()?, entry)
+}
+
+fn parse(json: String) -> Option<HistoryEntry> {
+    serde_json::from_str(&json).ok()
+}
+
+/// Loads the entries recorded at or after CUTOFF_MS, oldest first.
+pub fn load_since(cutoff_ms: u64) -> Result<Vec<HistoryEntry>> {
+    let connection = open()?;
+    let mut statement = connection.prepare("SELECT entry FROM entries WHERE timestamp_ms >= ?1 ORDER BY id")?;
+    let rows = statement.query_map([cutoff_ms as i64], |row| row.get::<_, String>(0))?;
+    Ok(rows.filter_map(|row| row.ok().and_then(parse)).collect())
+}
+
+/// Returns the NUMBER-th most recent entry (1 is the newest).
+pub fn nth(number: usize) -> Result<Option<HistoryEntry>> {
+    let connection = open()?;
+    let json: Option<String> = connection
+        .query_row(
+            "SELECT entry FROM entries ORDER BY id DESC LIMIT 1 OFFSET ?1",
+            [number.saturating_sub(1) as i64],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(json.and_then(parse))
+}
+
+pub fn count() -> Result<usize> {
+    let count: i64 = open()?.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+    Ok(count as usize)
+}
+
+/// Returns the newest entries matching FILTER with their `history list` numbers.
+pub fn search(filter: &SearchFilter) -> Result<Vec<(usize, HistoryEntry)>> {
+    let connection = open()?;
+    let mut conditions = vec!["timestamp_ms >= ?".to_string()];
+    let mut values = vec![SqlValue::Integer(filter.cutoff_ms as i64)];
+    let words = filter.text.as_deref().map(history::words).unwrap_or_default();
+    if !words.is_empty() {
+        // 語を引用符で囲んだフレーズにするので、FTS の演算子として解釈されない
+        conditions.push("id IN (SELECT rowid FROM entries_search WHERE entries_search MATCH ?)".to_string());
+        values.push(SqlValue::Text(format!("\"{}\" *", words.join(" "))));
+    }
+    if let Some(ref method) = filter.method {
+        conditions.push("method = ?".to_string());
+        values.push(SqlValue::Text(method.to_uppercase()));
+    }
+    if let Some((min, max)) = filter.status {
+        conditions.push("status BETWEEN ? AND ?".to_string());
+        values.extend([SqlValue::Integer(min as i64), SqlValue::Integer(max as i64)]);
+    }
+    if filter.failed {
+        conditions.push("(error IS NOT NULL OR status >= 400)".to_string());
+    }
+    // 番号は history list と同じく絞り込み前の全件に対して振る
+    let sql = format!(
+        "SELECT number, entry FROM (SELECT ROW_NUMBER() OVER (ORDER BY id DESC) AS number, * FROM entries)
+         WHERE {} ORDER BY id {} LIMIT {}",
+        conditions.join(" AND "),
+        if filter.oldest_first { "ASC" } else { "DESC" },
+        filter.limit
+    );
+    let mut statement = connection.prepare(&sql)?;
+    let rows =
+        statement.query_map(params_from_iter(values), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+    Ok(rows
+        .filter_map(|row| row.ok())
+        .filter_map(|(number, json)| parse(json).map(|entry| (number as usize, entry)))
+        .collect())
+}
+
+/// Cheaply checks the limits and prunes 10% below them when one is exceeded,
+/// like the history.jsonl backend.
+pub fn enforce(retention: &Retention) -> Result<()> {
+    if retention.is_empty() {
+        return Ok(());
+    }
+    let connection = open()?;
+    let (count, oldest, size): (i64, Option<i64>, Option<i64>) = connection.query_row(
+        "SELECT COUNT(*), MIN(timestamp_ms), SUM(length(entry)) FROM entries",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let too_many = retention.max_entries.is_some_and(|max| count as usize > max);
+    let too_big = retention.max_size.is_some_and(|max| size.unwrap_or(0) as u64 > max);
+    let too_old = retention
+        .max_age
+        .is_some_and(|age| oldest.is_some_and(|oldest| (oldest as u64) < history::cutoff_ms(age)));
+    if too_many || too_big || too_old {
+        prune_with(&connection, &retention.slack())?;
+    }
+    Ok(())
+}
+
+/// Deletes the entries outside the limits; returns how many were removed.
+/// The size limit counts the stored entries, not the database file.
+pub fn prune(retention: &Retention) -> Result<usize> {
+    let connection = open()?;
+    let removed = prune_with(&connection, retention)?;
+    if removed > 0 {
+        // 削除した分だけファイルを小さくする
+        connection.execute_batch("VACUUM")?;
+    }
+    Ok(removed)
+}
+
+fn prune_with(connection: &Connection, retention: &Retention) -> Result<usize> {
+    let mut removed = 0;
+    if let Some(age) = retention.max_age {
+        removed += connection.execute("DELETE FROM entries WHERE timestamp_ms < ?1", [history::cutoff_ms(age) as i64])?;
+    }
+    if let Some(max) = retention.max_entries {
+        removed += connection.execute(
+            "DELETE FROM entries WHERE id NOT IN (SELECT id FROM entries ORDER BY id DESC LIMIT ?1)",
+            [max as i64],
+        )?;
+    }
+    if let Some(max) = retention.max_size {
+        // 新しい順に積算して、上限を超えた位置より古いものを消す
+        let boundary: Option<i64> = connection
+            .query_row(
+                "SELECT id FROM (SELECT id, SUM(length(entry) + 1) OVER (ORDER BY id DESC) AS total FROM entries)
+                 WHERE total > ?1 ORDER BY id DESC LIMIT 1",
+                [max as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(boundary) = boundary {
+            removed += connection.execute("DELETE FROM entries WHERE id <= ?1", [boundary])?;
+        }
+    }
+    if removed > 0 {
+        connection.execute("DELETE FROM entries_search WHERE rowid NOT IN (SELECT id FROM entries)", [])?;
+    }
+    Ok(removed)
+}
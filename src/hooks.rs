@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use crate::get_default_dir;
+use crate::i18n;
+use crate::mount;
+use crate::output::Renderer;
+use crate::retry::RetryPolicy;
+use crate::transaction::{self, Call};
+
+/// File with suite-level setup and teardown requests, looked up from the run-all
+/// TARGET namespace upwards.
+pub const HOOKS_FILE: &str = "_hooks.yaml";
+
+#[derive(Deserialize, Debug, Clone)]
+struct Hook {
+    /// Names the hook's response body for `{{.name.path}}` placeholders in later hooks.
+    name: Option<String>,
+    #[serde(flatten)]
+    call: Call,
+}
+
+/// Setup requests run before the suite and teardown requests run after it.
+#[derive(Deserialize, Debug, Default)]
+pub struct Hooks {
+    #[serde(default)]
+    before: Vec<Hook>,
+    #[serde(default)]
+    after: Vec<Hook>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Hooks {
+    /// Loads the nearest hooks file for TARGET (the namespace itself, then its parents,
+    /// then the configuration directory), or None if there is none.
+    pub fn find(target: Option<&str>) -> Result<Option<Hooks>> {
+        let base_dir = get_default_dir()?;
+        let mut dirs = vec![base_dir.clone()];
+        if let Some(target) = target {
+            dirs.extend(mount::ancestry(target)?);
+        }
+        let Some(path) = dirs.into_iter().rev().map(|dir| dir.join(HOOKS_FILE)).find(|path| path.exists()) else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read hooks from {:?}", path))?;
+        let mut hooks: Hooks =
+            serde_yaml::from_str(&content).with_context(|| format!("Failed to parse hooks in {:?}", path))?;
+        hooks.path = path;
+        Ok(Some(hooks))
+    }
+
+    /// Runs the before hooks, then SUITE if they all succeeded, then the after hooks,
+    /// which run even when setup or the suite failed so fixtures are cleaned up.
+    pub async fn wrap<T>(
+        &self,
+        retry: &RetryPolicy,
+        renderer: &dyn Renderer,
+        suite: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        renderer.note(&i18n::tf("Running hooks from {}", &[&format!("{:?}", self.path)]));
+        let mut bodies = Map::new();
+        let setup = run_hooks("before", &self.before, &self.path, &mut bodies, false, retry, renderer).await;
+        let result = match setup {
+            Ok(()) => suite.await,
+            Err(err) => Err(err.context("Suite setup failed; no configurations were run")),
+        };
+        let teardown = run_hooks("after", &self.after, &self.path, &mut bodies, true, retry, renderer).await;
+        match (result, teardown) {
+            (Ok(value), Ok(())) => Ok(value),
+            (Ok(_), Err(err)) => Err(err.context("Suite teardown failed; manual cleanup may be needed")),
+            // スイートの失敗を優先して返し、後片付けの失敗は警告にとどめる
+            (Err(err), Ok(())) => Err(err),
+            (Err(err), Err(teardown)) => {
+                eprintln!(
+                    "{}",
+                    i18n::tf(
+                        "Warning: suite teardown failed: {}; manual cleanup may be needed.",
+                        &[&format!("{:#}", teardown)]
+                    )
+                );
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Runs HOOKS in order and returns the first failure; unless KEEP_GOING is set, the
+/// remaining hooks are skipped after it. Named hooks add their response body to BODIES,
+/// from which `{{.name.path}}` placeholders are filled.
+async fn run_hooks(
+    phase: &str,
+    hooks: &[Hook],
+    file: &Path,
+    bodies: &mut Map<String, Value>,
+    keep_going: bool,
+    retry: &RetryPolicy,
+    renderer: &dyn Renderer,
+) -> Result<()> {
+    let mut failure = None;
+    for (index, hook) in hooks.iter().enumerate() {
+        let label = hook.name.clone().unwrap_or_else(|| format!("{} hook {}", phase, index + 1));
+        let result = async {
+            let config = transaction::fill_placeholders(&hook.call.resolve()?, &Value::Object(bodies.clone()))?;
+            let (status, body) = hook.call.execute(&config, retry, file).await?;
+            anyhow::Ok((transaction::describe(&config), status, body))
+        }
+        .await;
+        match result {
+            Ok((request, status, body)) => {
+                renderer.note(&format!("[{}] {}: {} -> {}", phase, label, request, status));
+                if let Some(ref name) = hook.name {
+                    bodies.insert(name.clone(), body);
+                }
+            }
+            Err(err) => {
+                renderer.note(&i18n::tf("[{}] {}: failed: {}", &[&phase, &label, &err]));
+                failure.get_or_insert(err.context(format!("Hook '{}' failed", label)));
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+    }
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,1246 @@
+use clap::{Command, ValueEnum};
+use std::{
+    env,
+    ffi::OsString,
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Language of help text, prompts and messages.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Ja,
+}
+
+static JAPANESE: AtomicBool = AtomicBool::new(false);
+
+/// Sets the language for the rest of the process.
+pub fn set(lang: Lang) {
+    JAPANESE.store(lang == Lang::Ja, Ordering::Relaxed);
+}
+
+pub fn current() -> Lang {
+    if JAPANESE.load(Ordering::Relaxed) {
+        Lang::Ja
+    } else {
+        Lang::En
+    }
+}
+
+/// Picks the language from `--lang` in ARGS, then FERRAPI_LANG, LC_ALL, LC_MESSAGES
+/// and LANG. The command line is scanned before clap parses it because the help
+/// text has to be translated before clap can print it.
+pub fn detect(args: impl IntoIterator<Item = OsString>) -> Lang {
+    let args: Vec<String> = args.into_iter().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let from_args = args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--lang") {
+        Some(value) if value.starts_with('=') => Some(value[1..].to_string()),
+        Some("") => args.get(i + 1).cloned(),
+        _ => None,
+    });
+    let from_env = || {
+        ["FERRAPI_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+    };
+    match from_args.or_else(from_env) {
+        Some(value) if value.to_lowercase().starts_with("ja") => Lang::Ja,
+        _ => Lang::En,
+    }
+}
+
+/// Runtime messages as (English, Japanese). The English text is the key, so call
+/// sites stay readable; `{}` marks where `tf` inserts its arguments.
+const MESSAGES: &[(&str, &str)] = &[
+    ("Error: ", "エラー: "),
+    ("Hint: ", "ヒント: "),
+    ("Select a namespace in {}", "{} 内の名前空間を選択してください"),
+    ("Do you want to select a subdirectory further?", "さらに下の階層を選択しますか？"),
+    ("Selected namespace: {}", "選択された名前空間: {}"),
+    ("Default configuration directory: {}", "デフォルト設定ディレクトリ: {}"),
+    ("Namespace directory {} deleted.", "名前空間ディレクトリ {} を削除しました。"),
+    ("No namespace directory found at {}", "{} に名前空間ディレクトリがありません"),
+    ("Configuration at {} deleted.", "{} の設定を削除しました。"),
+    ("No configuration found at {}", "{} に設定がありません"),
+    ("URL (Tab to complete)", "URL（Tab で補完）"),
+    ("Configuration saved to {}", "設定を {} に保存しました"),
+    (
+        "No earlier JSON response of this request in the history to compare types with.",
+        "型を比較できる、このリクエストの以前の JSON レスポンスが履歴にありません。",
+    ),
+    ("--type-diff requires a JSON response body", "--type-diff には JSON のレスポンスボディが必要です"),
+    ("Response types match the previous run ({}).", "レスポンスの型は前回の実行（{}）と一致しています。"),
+    ("Response types changed since the previous run ({}):", "前回の実行（{}）からレスポンスの型が変わりました:"),
+    ("Note: this hides the read-only configuration at {}", "注意: 読み取り専用の設定 {} はこの設定で隠されます"),
+    (
+        "No configuration is saved for {} {}; only flags would be sent.",
+        "{} {} の設定は保存されていません。フラグで指定した内容のみが送信されます。",
+    ),
+    ("--save is ignored because TARGET is not specified.", "TARGET が指定されていないため --save は無視されます。"),
+    (
+        "Dashboard of {} endpoint(s) and {} run(s) written to {}",
+        "{} 件のエンドポイントと {} 件の実行のダッシュボードを {} に書き込みました",
+    ),
+    (
+        "--save-example is ignored because TARGET is not specified.",
+        "TARGET が指定されていないため --save-example は無視されます。",
+    ),
+    (
+        "Header {} is not in the response; {} is not captured.",
+        "レスポンスにヘッダー {} がないため、{} は保存されません。",
+    ),
+    (
+        "Header {} contains '{{' and is not captured into {}.",
+        "ヘッダー {} の値に '{{' が含まれるため、{} には保存しません。",
+    ),
+    (
+        "Callback parameter {} contains '{{' and is not saved.",
+        "コールバックのパラメーター {} の値に '{{' が含まれるため、保存しません。",
+    ),
+    ("Captured {} into {}", "{} を {} に保存しました"),
+    ("Header {} contains '{{' and is not recorded.", "ヘッダー {} の値に '{{' が含まれるため、記録しません。"),
+    (
+        "The request body contains '{{' and is not recorded.",
+        "リクエストボディに '{{' が含まれるため、記録しません。",
+    ),
+    ("The command succeeded.", "コマンドが成功しました。"),
+    ("An unexpected error occurred.", "予期しないエラーが発生しました。"),
+    (
+        "An assertion, expected body, or expected status did not hold.",
+        "アサーション、期待するボディ、または期待するステータスを満たしませんでした。",
+    ),
+    (
+        "The request failed before a response was received (DNS, connect, TLS, timeout).",
+        "レスポンスを受信する前にリクエストが失敗しました（DNS・接続・TLS・タイムアウト）。",
+    ),
+    (
+        "A configuration, variable, request file, or option value is missing or invalid.",
+        "設定・変数・リクエストファイル・オプションの値がないか、不正です。",
+    ),
+    ("Credentials, secrets, or a login could not be obtained.", "認証情報・シークレットの取得、またはログインに失敗しました。"),
+    ("The command line could not be parsed.", "コマンドラインを解析できませんでした。"),
+    ("The command was interrupted with Ctrl-C.", "Ctrl-C でコマンドが中断されました。"),
+    ("No suggested paths found.", "候補となるパスは見つかりませんでした。"),
+    ("Suggested paths:", "候補のパス:"),
+    ("Response body differs from {}:", "レスポンスボディが {} と異なります:"),
+    ("Response body matches the expected fixture.", "レスポンスボディは期待値と一致しました。"),
+    ("{} of {} assertion(s) failed:", "{} 件のアサーションが失敗しました（全 {} 件）:"),
+    ("All {} assertion(s) passed.", "{} 件のアサーションがすべて成功しました。"),
+    ("{} assertion warning(s):", "{} 件のアサーションの警告:"),
+    ("Response Status: {}", "レスポンスステータス: {}"),
+    ("Response Body:", "レスポンスボディ:"),
+    ("Response Headers:", "レスポンスヘッダー:"),
+    ("Output written to {}", "出力を {} に書き込みました"),
+    ("Response body ({} bytes) written to {}", "レスポンスボディ（{} バイト）を {} に書き込みました"),
+    ("Server error summary ({})", "サーバーエラーの要約 ({})"),
+    ("Error code", "エラーコード"),
+    ("Message", "メッセージ"),
+    ("Correlation id", "相関 ID"),
+    ("Stack trace:", "スタックトレース:"),
+    ("... {} more line(s)", "... ほか {} 行"),
+    (
+        "Shuffled the execution order with seed {} (reproduce with --shuffle --seed {}).",
+        "シード {} で実行順序をシャッフルしました（--shuffle --seed {} で再現できます）。",
+    ),
+    ("\n{} passed, {} failed, {} total.", "\n成功 {}、失敗 {}、合計 {}。"),
+    ("\n{} passed, {} failed, {} quarantined, {} total.", "\n成功 {}、失敗 {}、隔離 {}、合計 {}。"),
+    ("Latency: {} (min {}ms, median {}ms, max {}ms)", "レスポンス時間: {}（最小 {}ms、中央値 {}ms、最大 {}ms）"),
+    ("; {} of {} over the SLO", "、{} / {} 件が SLO 超過"),
+    ("Aborted the run after {}; {} configuration(s) skipped.", "{} のため実行を中止しました。{} 件の設定をスキップしました。"),
+    ("The queue is empty.", "キューは空です。"),
+    ("No history entries match.", "条件に一致する履歴はありません。"),
+    ("Latency of {} ({} request(s)):", "{} のレスポンス時間（{} 件）:"),
+    (
+        "No recorded sessions. Record one with `proxy --record NAMESPACE`.",
+        "記録済みのセッションはありません。`proxy --record NAMESPACE` で記録してください。",
+    ),
+    ("No variables defined in {}", "{} に変数は定義されていません"),
+    ("No environments defined in {}", "{} に環境は定義されていません"),
+    ("No saved configurations found.", "保存済みの設定はありません。"),
+    ("Variables in {}", "{} の変数"),
+    ("Variables saved to {}", "変数を {} に保存しました"),
+    ("+ Add a variable", "+ 変数を追加"),
+    ("Save and exit", "保存して終了"),
+    ("Name", "名前"),
+    ("Value", "値"),
+    ("Edit value", "値を編集"),
+    ("Rename", "名前を変更"),
+    ("Delete", "削除"),
+    ("Back", "戻る"),
+    ("Headers", "ヘッダー"),
+    ("+ Add a header", "+ ヘッダーを追加"),
+    ("Done", "完了"),
+    ("Header name", "ヘッダー名"),
+    ("Header name (Tab to complete)", "ヘッダー名（Tab で補完）"),
+    ("Other...", "その他..."),
+    ("Value for {}", "{} の値"),
+    ("Value for {} (Tab to complete)", "{} の値（Tab で補完）"),
+    ("Enter a value...", "値を入力..."),
+    ("Install shell completions for {}?", "{} のシェル補完をインストールしますか？"),
+    ("Update to {}?", "{} に更新しますか？"),
+    ("Update cancelled.", "更新を中止しました。"),
+    (
+        "ferrapi_tester {} is up to date (latest release: {}).",
+        "ferrapi_tester {} は最新です（最新リリース: {}）。",
+    ),
+    ("A new version is available: {} -> {}", "新しいバージョンがあります: {} -> {}"),
+    ("Updated {} to {}.", "{} を {} に更新しました。"),
+    ("Request failed ({}); retrying in {} ({}/{})", "リクエストが失敗しました（{}）。{} 後に再試行します（{}/{}）"),
+    (
+        "{} is not idempotent, so it was not retried. Pass --retry-unsafe to retry it anyway.",
+        "{} は冪等ではないため再試行しませんでした。再試行するには --retry-unsafe を指定してください。",
+    ),
+    ("{} was changed both locally and in the bundle:", "{} はローカルとバンドルの両方で変更されています:"),
+    ("Keep local", "ローカルの内容を残す"),
+    ("Take the bundle's version", "バンドルの内容を採用する"),
+    ("Merge field by field", "フィールドごとにマージする"),
+    ("local:  {}", "ローカル:   {}"),
+    ("bundle: {}", "バンドル: {}"),
+    (
+        "\nInterrupted; finishing up (press Ctrl-C again to quit immediately).",
+        "\n中断しました。後処理を行っています（すぐに終了するにはもう一度 Ctrl-C を押してください）。",
+    ),
+    (
+        "Check the host name in the URL and your DNS or VPN settings.",
+        "URL のホスト名と、DNS や VPN の設定を確認してください。",
+    ),
+    (
+        "The host did not accept the connection in time. Check the host, port and firewall, or raise --connect-timeout.",
+        "ホストが時間内に接続を受け付けませんでした。ホスト・ポート・ファイアウォールを確認するか、--connect-timeout を延ばしてください。",
+    ),
+    (
+        "Nothing is listening on that port. Is the server running?",
+        "そのポートで待ち受けているプロセスがありません。サーバーは起動していますか？",
+    ),
+    (
+        "Check the certificate of the server. For self-signed certificates in test environments, pass --insecure.",
+        "サーバーの証明書を確認してください。テスト環境の自己署名証明書なら --insecure を指定してください。",
+    ),
+    (
+        "The server accepted the connection but did not answer in time. Raise --timeout or check the server.",
+        "サーバーは接続を受け付けましたが、時間内に応答しませんでした。--timeout を延ばすか、サーバーを確認してください。",
+    ),
+    (
+        "The server stopped sending data mid-response. Raise --timeout or check the server logs.",
+        "レスポンスの途中でサーバーからのデータが止まりました。--timeout を延ばすか、サーバーのログを確認してください。",
+    ),
+    (
+        "The server or a proxy closed the connection; check the server logs.",
+        "サーバーまたはプロキシが接続を閉じました。サーバーのログを確認してください。",
+    ),
+    ("Queued #{}: {}", "キューに追加しました #{}: {}"),
+    ("Delivered {} of {} request(s); {} remaining in queue.", "{} / {} 件のリクエストを送信しました。キューには {} 件残っています。"),
+    ("Queue cleared.", "キューを空にしました。"),
+    ("Replaying #{}: {} {}", "#{} を再送します: {} {}"),
+    ("Removed 1 history entry.", "履歴を 1 件削除しました。"),
+    ("Removed {} history entries.", "履歴を {} 件削除しました。"),
+    ("Warning: Failed to record the request in the history: {}", "警告: リクエストを履歴に記録できませんでした: {}"),
+    ("Warning: Failed to write the request log: {}", "警告: リクエストログを書き込めませんでした: {}"),
+    ("Warning: {}; skipping the server error summary.", "警告: {}。サーバーエラーの要約は表示しません。"),
+    ("--suggest-paths requires a JSON response body", "--suggest-paths には JSON のレスポンスボディが必要です"),
+    ("Waiting for the login to redirect to {}", "ログイン後の {} へのリダイレクトを待っています"),
+    ("Open this URL in your browser:\n  {}", "ブラウザーでこの URL を開いてください:\n  {}"),
+    ("Could not open a browser ({}). Open this URL manually:\n  {}", "ブラウザーを開けませんでした（{}）。この URL を手動で開いてください:\n  {}"),
+    ("Stored {} in {}", "{} を {} に保存しました"),
+    ("Use them as header placeholders, e.g. {{secret:{}}}", "ヘッダーのプレースホルダーとして使用できます（例: {{secret:{}}}）"),
+    ("Saved {} to {}", "{} を {} に保存しました"),
+    ("Use them as placeholders, e.g. {{{}}}", "プレースホルダーとして使用できます（例: {{{}}}）"),
+    ("Warning: Could not show the desktop notification: {}", "警告: デスクトップ通知を表示できませんでした: {}"),
+    ("Warning: {}; using the default theme.", "警告: {}。デフォルトのテーマを使用します。"),
+    ("Request failed: {}", "リクエストが失敗しました: {}"),
+    ("Bug report written to {} ({} files, secrets redacted).", "バグレポートを {} に書き出しました（{} ファイル、秘密情報は伏せ字）。"),
+    ("Current state saved as snapshot {}", "現在の状態をスナップショット {} として保存しました"),
+    ("Restored {} to snapshot {} ({} file(s)).", "{} をスナップショット {} に復元しました（{} ファイル）。"),
+    ("Created snapshot {} of {} ({} file(s)).", "スナップショット {}（{}、{} ファイル）を作成しました。"),
+    ("No snapshots found.", "スナップショットがありません。"),
+    ("{}  {}  {}  {} file(s){}", "{}  {}  {}  {} ファイル{}"),
+    ("Stored secret '{}' in {}. Use it as {{secret:{}}}", "シークレット '{}' を {} に保存しました。{{secret:{}}} として使用できます"),
+    ("Deleted secret '{}' from {}", "シークレット '{}' を {} から削除しました"),
+    ("Warning: the body is not JSON and is saved as a JSON string: {}", "警告: ボディが JSON ではないため、JSON 文字列として保存します: {}"),
+    ("Paste the curl command, then press Ctrl-D:", "curl コマンドを貼り付けてから Ctrl-D を押してください:"),
+    (
+        "Note: the OAuth2 access token is not included; add -H 'Authorization: Bearer TOKEN'.",
+        "注意: OAuth2 のアクセストークンは含まれません。-H 'Authorization: Bearer TOKEN' を追加してください。",
+    ),
+    ("Set {} in {}", "{} を {} に設定しました"),
+    ("Unset {} in {}", "{} を {} から削除しました"),
+    ("Variable '{}' is not defined in {}", "変数 '{}' は {} に定義されていません"),
+    ("Skipped (not a plain KEY=VALUE): {}", "スキップしました（単純な KEY=VALUE ではありません）: {}"),
+    ("Imported {} variable(s) into {}", "{} 個の変数を {} に取り込みました"),
+    ("  kept     {} (already exists)", "  保持     {}（既に存在します）"),
+    ("  created  {}", "  作成     {}"),
+    ("  updated  {}", "  更新     {}"),
+    (
+        "Automatic installation is not supported for {}; run `ferrapi_tester completions {}` and load the output in your profile.",
+        "{} への自動インストールには対応していません。`ferrapi_tester completions {}` を実行し、出力をプロファイルで読み込んでください。",
+    ),
+    (
+        "Add `fpath=(~/.zfunc $fpath)` before `compinit` in ~/.zshrc if it is not there yet.",
+        "~/.zshrc の `compinit` より前に `fpath=(~/.zfunc $fpath)` がなければ追加してください。",
+    ),
+    ("Base directory: {}", "ベースディレクトリ: {}"),
+    (
+        "Could not detect your shell; run `ferrapi_tester completions <SHELL>` to generate completions.",
+        "シェルを判別できませんでした。`ferrapi_tester completions <SHELL>` で補完スクリプトを生成してください。",
+    ),
+    ("\nDone. Try the example request:\n  ferrapi_tester -- {}", "\n完了しました。例のリクエストを試してください:\n  ferrapi_tester -- {}"),
+    (
+        "List/create with `ferrapi_tester -- {}` / `ferrapi_tester -X POST -- {}`; get/update/delete one with `-- {}` and --var id=...",
+        "一覧・作成は `ferrapi_tester -- {}` / `ferrapi_tester -X POST -- {}`、1 件の取得・更新・削除は `-- {}` と --var id=... で行います",
+    ),
+    (
+        "Edit the query in the saved POST.json, then run `ferrapi_tester -X POST -- {}`.",
+        "保存した POST.json のクエリを編集してから `ferrapi_tester -X POST -- {}` を実行してください。",
+    ),
+    (
+        "Send the sample event to your listener with `ferrapi_tester -X POST -- {}`.",
+        "`ferrapi_tester -X POST -- {}` でサンプルのイベントをリスナーに送信できます。",
+    ),
+    ("Imported {} operation(s) from {} into {}.", "{} 個のオペレーションを {} から {} に取り込みました。"),
+    (
+        "Run them all with `ferrapi_tester run {}`; credentials for secured operations are left as {{name}} placeholders to define with `vars set`.",
+        "`ferrapi_tester run {}` ですべて実行できます。認証が必要なオペレーションの資格情報は {{name}} プレースホルダーのままなので、`vars set` で定義してください。",
+    ),
+    (
+        "{} request(s) checked. Documented operations exercised: {}/{} ({}%)",
+        "{} 件のリクエストを確認しました。実行されたドキュメント記載のオペレーション: {}/{}（{}%）",
+    ),
+    ("Exercised", "実行済み"),
+    ("Never exercised", "未実行"),
+    ("\nUndocumented endpoints in use:", "\n使用されているドキュメントにないエンドポイント:"),
+    ("\nUndocumented status codes:", "\nドキュメントにないステータスコード:"),
+    ("Coverage report written to {}", "カバレッジレポートを {} に書き出しました"),
+    (
+        "\nAPI coverage against {}: operations {}/{} ({}%), responses {}/{} ({}%)",
+        "\n{} に対する API カバレッジ: オペレーション {}/{}（{}%）、レスポンス {}/{}（{}%）",
+    ),
+    ("{} (never called)", "{}（呼び出しなし）"),
+    ("{}: {} not exercised", "{}: {} は未実行"),
+    ("Not covered:", "カバーされていないもの:"),
+    ("Undocumented endpoints called:", "呼び出されたドキュメントにないエンドポイント:"),
+    ("Reloaded {} stub(s) from {}", "{} 個のスタブを {} から再読み込みしました"),
+    ("Added stub 0 through the admin API", "管理 API でスタブ 0 を追加しました"),
+    ("Deleted stub {} through the admin API", "管理 API でスタブ {} を削除しました"),
+    ("Replaced stub {} through the admin API", "管理 API でスタブ {} を置き換えました"),
+    ("{} {} -> dropped (injected)", "{} {} -> 切断（注入）"),
+    ("{} {} -> {} (injected)", "{} {} -> {}（注入）"),
+    ("{} {} -> 404 (no stub)", "{} {} -> 404（スタブなし）"),
+    ("{} {} -> {} (after {} ms)", "{} {} -> {}（{} ms 後）"),
+    ("{} {} -> 502 (backend error: {})", "{} {} -> 502（バックエンドのエラー: {}）"),
+    ("{} {} -> {} (recorded from {})", "{} {} -> {}（{} から記録）"),
+    ("{} {} -> {} (from {}; not recorded: {})", "{} {} -> {}（{} から。記録できませんでした: {}）"),
+    ("Loaded {} stub(s) from {}", "{} 個のスタブを {} から読み込みました"),
+    ("Serving {} recorded response(s) from {}", "{} 件の記録済みレスポンスを {} から返します"),
+    ("Recording backend responses to {}", "バックエンドのレスポンスを {} に記録します"),
+    (
+        "Mock server listening on https://{} (admin API under {}, Ctrl-C to stop)",
+        "モックサーバーが https://{} で待ち受けています（管理 API は {} 以下、Ctrl-C で停止）",
+    ),
+    (
+        "Mock server listening on http://{} (admin API under {}, Ctrl-C to stop)",
+        "モックサーバーが http://{} で待ち受けています（管理 API は {} 以下、Ctrl-C で停止）",
+    ),
+    ("Serving HTTPS with the certificate at {}", "{} の証明書で HTTPS を提供します"),
+    ("Serving HTTPS with a certificate signed by the CA at {}.", "{} の CA が署名した証明書で HTTPS を提供します。"),
+    (
+        "Add it to the trust store of the client (browser, OS, or e.g. NODE_EXTRA_CA_CERTS).",
+        "クライアント（ブラウザー、OS、または NODE_EXTRA_CA_CERTS など）の信頼ストアに追加してください。",
+    ),
+    ("TLS handshake failed: {}", "TLS ハンドシェイクに失敗しました: {}"),
+    ("{} {} -> error: {}", "{} {} -> エラー: {}"),
+    ("Warning: failed to record {} {}: {}", "警告: {} {} を記録できませんでした: {}"),
+    ("HTTPS traffic is decrypted with the CA certificate at {}.", "HTTPS の通信は {} の CA 証明書で復号します。"),
+    ("Proxy listening on http://{} (Ctrl-C to stop)", "プロキシが http://{} で待ち受けています（Ctrl-C で停止）"),
+    ("Recording into {} (session {})", "{} に記録します（セッション {}）"),
+    ("Not recording; pass --record NAMESPACE to save the traffic.", "記録していません。通信を保存するには --record NAMESPACE を指定してください。"),
+    ("\nLeast reliable endpoints:", "\n失敗の多いエンドポイント:"),
+    ("  {} ({}/{} failed): {}", "  {}（{}/{} 件失敗）: {}"),
+    ("\nMost frequent errors:", "\n多いエラー:"),
+    ("[{}/{}] {}: {} -> failed: {}", "[{}/{}] {}: {} -> 失敗: {}"),
+    ("Chain completed: {} step(s) succeeded.", "チェーンが完了しました: {} ステップが成功しました。"),
+    ("Warning: assertion failed: {}", "警告: アサーションが失敗しました: {}"),
+    ("[{}/{}] {}: skipped (--filter)", "[{}/{}] {}: スキップ（--filter）"),
+    ("Transaction completed: {} step(s) succeeded.", "トランザクションが完了しました: {} ステップが成功しました。"),
+    ("Rolling back {} completed step(s)...", "完了した {} ステップをロールバックしています..."),
+    ("  {}: no compensation defined, skipped", "  {}: 補償処理が定義されていないためスキップ"),
+    ("  {}: compensation failed: {}", "  {}: 補償処理が失敗しました: {}"),
+    ("Running hooks from {}", "{} のフックを実行します"),
+    (
+        "Warning: suite teardown failed: {}; manual cleanup may be needed.",
+        "警告: スイートの後処理が失敗しました: {}。手動での片付けが必要な場合があります。",
+    ),
+    ("[{}] {}: failed: {}", "[{}] {}: 失敗: {}"),
+    ("Imported {} file(s) into {} ({} unchanged, {} kept local).", "{} ファイルを {} に取り込みました（変更なし {}、ローカルを維持 {}）。"),
+    (
+        "Warning: {} run shell commands through {{cmd:...}} placeholders; review them before sending requests.",
+        "警告: {} は {{cmd:...}} プレースホルダーでシェルコマンドを実行します。リクエストを送る前に内容を確認してください。",
+    ),
+    ("Imported {} entries from {} into {}.", "{} 件のエントリを {} から {} に取り込みました。"),
+    ("\nInterrupted; {} of {} request(s) were not replayed.", "\n中断しました。{} / {} 件のリクエストは再生されていません。"),
+    ("\n{} matched, {} differed or failed, {} replayed.", "\n一致 {}、差異または失敗 {}、再生 {}。"),
+    ("Warning: skipping {}: {}", "警告: {} をスキップします: {}"),
+    ("Budget warning: {}", "予算の警告: {}"),
+    ("fix: {}", "対処: {}"),
+    ("\n{} warning(s), {} problem(s).", "\n警告 {} 件、問題 {} 件。"),
+    ("{} of {} bytes sent, {} bytes received, connection open for {}s", "{} / {} バイト送信、{} バイト受信、接続時間 {} 秒"),
+    ("--- request ({} bytes) ---", "--- リクエスト（{} バイト） ---"),
+    ("--- response ({} bytes) ---", "--- レスポンス（{} バイト） ---"),
+];
+
+/// Translates a runtime message.
+pub fn t(message: &'static str) -> &'static str {
+    if current() == Lang::En {
+        return message;
+    }
+    MESSAGES
+        .iter()
+        .find(|(en, _)| *en == message)
+        .map(|(_, ja)| *ja)
+        .unwrap_or(message)
+}
+
+/// Translates a message and fills its `{}` markers with ARGS in order.
+pub fn tf(message: &'static str, args: &[&dyn Display]) -> String {
+    let mut filled = String::new();
+    for (i, part) in t(message).split("{}").enumerate() {
+        if i > 0 {
+            if let Some(arg) = args.get(i - 1) {
+                filled.push_str(&arg.to_string());
+            }
+        }
+        filled.push_str(part);
+    }
+    filled
+}
+
+/// English help text, keyed by the Japanese doc comments the commands are declared with.
+const HELP: &[(&str, &str)] = &[
+    (
+        "FerrAPI Tester - API testing CLI tool.\n\nこのツールは、HTTP リクエストの設定をコマンドラインで指定し、 必要に応じて設定を保存・読み込みして API のテストを行います。 TARGET（名前空間）が指定されなければ、--url オプションのみで API を呼び出します。",
+        "FerrAPI Tester - API testing CLI tool.\n\nSpecify HTTP request settings on the command line, and save or load them as needed to test APIs. Without a TARGET (namespace), the API is called with the --url option only.",
+    ),
+    (
+        "HTTP メソッド (GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS, TRACE や PROPFIND などの独自メソッド) [default: GET]",
+        "HTTP method (GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS, TRACE, or a custom verb such as PROPFIND) [default: GET]",
+    ),
+    ("ヘッダーの指定（例: -H \"Content-Type: application/json\"）", "Request header (e.g. -H \"Content-Type: application/json\")"),
+    (
+        "コマンドの出力をヘッダー値として使用します（例: --header-from-cmd \"Authorization: op read op://vault/api/token\"）。 コマンドはリクエストのたびに実行され、値は保存・記録されません。",
+        "Use the output of a command as a header value (e.g. --header-from-cmd \"Authorization: op read op://vault/api/token\"). The command runs for every request and the value is never saved or logged.",
+    ),
+    ("リクエストボディの文字列（-d または -v で指定）", "Request body as a string (-d or -v)"),
+    (
+        "JSON 形式でのリクエストボディ。保存済み設定の data にディープマージします （オブジェクトはキーごとにマージ、配列やその他の値は置き換え）",
+        "Request body as JSON, deep-merged into the saved data (objects merge key by key; arrays and other values are replaced)",
+    ),
+    ("-v の値でマージせず、保存済みの data 全体を置き換えます", "Replace the whole saved data with -v instead of merging"),
+    ("JSON 形式でのリクエストボディ（-j を使う場合、-v と競合します）", "Request body as JSON (-j; conflicts with -v)"),
+    (
+        "ボディのフィールドを上書きします（例: --set .user.name=Alice、JSON 値は --set .count:=5）",
+        "Override a body field (e.g. --set .user.name=Alice, or a JSON value with --set .count:=5)",
+    ),
+    ("ボディからフィールドを削除します（例: --unset .debug。--set の後に適用）", "Remove a body field (e.g. --unset .debug; applied after --set)"),
+    ("リクエスト先の URL。この URL は保存する際にも使用されます。", "URL to send the request to; also used when saving."),
+    ("タイムアウト秒数（デフォルトは 30 秒）", "Timeout in seconds (default 30)"),
+    ("接続確立までのタイムアウト秒数（省略時は --timeout のみ適用）", "Timeout in seconds for establishing the connection (only --timeout applies when omitted)"),
+    ("TLS 証明書の検証を行いません（自己署名証明書のテスト環境向け）", "Skip TLS certificate verification (for test environments with self-signed certificates)"),
+    (
+        "追加で信頼する CA 証明書の PEM ファイル（社内 CA や自己署名証明書向け。設定にも保存）",
+        "PEM file of CA certificates to trust in addition to the system roots (for internal CAs and self-signed certificates; also saved in the configuration)",
+    ),
+    (
+        "相互 TLS（mTLS）のクライアント証明書の PEM ファイル（秘密鍵を含めても可。設定にも保存）",
+        "PEM file of the client certificate for mutual TLS (mTLS); it may also contain the private key (also saved in the configuration)",
+    ),
+    (
+        "--cert の秘密鍵の PEM ファイル（PKCS#8。設定にも保存）",
+        "PEM file of the private key of --cert (PKCS#8; also saved in the configuration)",
+    ),
+    (
+        "名前空間のクッキージャー（cookies.json）でクッキーを保存し、以降のリクエストで送信します（設定にも保存）",
+        "Store cookies in the cookie jar of the namespace (cookies.json) and send them with later requests (also saved in the configuration)",
+    ),
+    ("設定に付けるタグ（複数指定可。run-all --tag での絞り込みに使用）", "Tag for the configuration (repeatable; used to filter with run-all --tag)"),
+    (
+        "レスポンスに対するアサーション（複数指定可。例: --assert \"status == 201\" --assert \".count >= 10\"）。 保存済み設定のアサーションに追加されます",
+        "Assertion on the response (repeatable, e.g. --assert \"status == 201\" --assert \".count >= 10\"). Added to the assertions of the saved configuration",
+    ),
+    (
+        "すべてのリクエスト/レスポンスを JSON Lines 形式で追記するファイル （settings.json の log_requests より優先されます）",
+        "Append every request/response to this file as JSON Lines (takes precedence over log_requests in settings.json)",
+    ),
+    (
+        "帯域を制限して低速なネットワークを再現します（例: 256kbps、1.5mbps、64KB/s）。 リクエストとレスポンスのボディの両方に適用されます",
+        "Limit the bandwidth to simulate a slow network (e.g. 256kbps, 1.5mbps, 64KB/s). Applies to both request and response bodies",
+    ),
+    ("各リクエストの送信前に待機する遅延（例: 200ms、1s）", "Delay added before each request is sent (e.g. 200ms, 1s)"),
+    (
+        "テンプレート変数（例: --var token=abc）。{{token}} のようなプレースホルダーを置換します。 優先順位: --var > 環境変数 FERRAPI_VAR_* > --env の環境ファイル > 名前空間の vars.json > デフォルトの vars.json",
+        "Template variable (e.g. --var token=abc) replacing placeholders such as {{token}}. Precedence: --var > FERRAPI_VAR_* environment variables > --env environment file > namespace vars.json > default vars.json",
+    ),
+    (
+        "変数を読み込む環境（~/.ferrapi_tester/envs/NAME.json、例: --env dev）",
+        "Environment to load variables from (~/.ferrapi_tester/envs/NAME.json, e.g. --env dev)",
+    ),
+    ("現在のリクエスト設定を保存するフラグ", "Save the current request configuration"),
+    (
+        "TARGET: 保存済み設定の名前空間パス（例: \"SystemA/example\"）。 省略された場合は、--url のみで API を呼び出します。 ValueHint::DirPath により、シェルのネイティブ補完が働きます。",
+        "TARGET: namespace path of a saved configuration (e.g. \"SystemA/example\"). Without it, the request is sent to --url only. Shells complete it as a directory path.",
+    ),
+    ("TARGET（名前空間）に保存されている設定を削除するフラグ（ファイル単位）", "Delete the configuration saved for TARGET (a single file)"),
+    (
+        "指定した名前空間ディレクトリとその内容全体を削除するオプション。 例: `ferrapi_tester --delete-all SystemB` で ~/.ferrapi_tester/SystemB 以下全体を削除",
+        "Delete the namespace directory and everything in it. For example, `ferrapi_tester --delete-all SystemB` removes ~/.ferrapi_tester/SystemB entirely",
+    ),
+    ("対話モードで名前空間候補を表示して選択します。", "Select the namespace interactively."),
+    ("デフォルト設定ディレクトリを表示します。", "Show the default configuration directory."),
+    (
+        "ログイン → 作成 → 取得のように、前のステップのレスポンスから取り出した値（JSONPath）を後続のステップで使うリクエストの連鎖を実行します。",
+        "Run a chain of requests where values extracted from earlier responses (JSONPath) are used by later steps, such as login, create, then fetch.",
+    ),
+    (
+        "チェーンファイル（YAML または JSON）のステップを順に実行し、レスポンスから取り出した値を後続のステップの変数として使います。",
+        "Run the steps of a chain file (YAML or JSON) in order, using values extracted from responses as variables of the following steps.",
+    ),
+    ("ステップを定義したチェーンファイル（例: flow.yaml）", "Chain file defining the steps (e.g. flow.yaml)"),
+    (
+        "終了コードの一覧（0 成功、2 アサーション失敗、3 ネットワークエラー、4 設定エラー、5 認証エラーなど）を表示します。",
+        "List the exit codes (0 success, 2 assertion failure, 3 network error, 4 config error, 5 auth error, and more).",
+    ),
+    (
+        "レスポンスと実行結果の出力形式（human / table / minimal / csv / json / template）。 省略時は settings.json の output_format、なければ human",
+        "Output format of responses and results (human / table / minimal / csv / json / template). Defaults to output_format in settings.json, then human",
+    ),
+    (
+        "template 形式で使うテンプレート（例: '{{status}} {{.id}}'、結果の表では '{{target}} {{status}}' のように列名）",
+        "Template for the template format (e.g. '{{status}} {{.id}}'; for results, column names such as '{{target}} {{status}}')",
+    ),
+    (
+        "レスポンスの出力形式（--output-format と同じ）。json ではステータス・ヘッダー・ボディ・所要時間と解決済みのリクエストを 1 つの JSON で出力します",
+        "Output format of the response (same as --output-format). json prints the status, headers, body, latency and the resolved request as one JSON object",
+    ),
+    (
+        "CSV 出力で使用する列（カンマ区切り、\"user.name\" のようなドット区切りパスも可）。 省略時は全オブジェクトのキーをソート順に使用します。",
+        "Columns for CSV output (comma-separated; dotted paths such as \"user.name\" are allowed). Defaults to the keys of all objects in sorted order.",
+    ),
+    (
+        "レスポンスボディを標準出力ではなく指定したファイルにそのまま（バイナリも）書き込みます。 csv / table / json / template 形式では整形した出力を書き込みます",
+        "Write the response body to this file as received (binary-safe) instead of stdout. With csv, table, json or template output, the rendered output is written",
+    ),
+    (
+        "各レスポンスボディを DIR/<TARGET>/<METHOD>.<拡張子> にそのまま（バイナリも）書き込みます",
+        "Write each response body as received (binary-safe) to DIR/<TARGET>/<METHOD>.<extension>",
+    ),
+    (
+        "レスポンスに適用する jq 風フィルタ、または JSONPath（例: '.items[] | select(.active)'、'$.data.items[0].id'）",
+        "jq-like filter or JSONPath applied to the response (e.g. '.items[] | select(.active)', '$.data.items[0].id')",
+    ),
+    (
+        "--filter の結果だけを jq -r のように表示します（文字列は引用符なし、反復した結果は 1 行に 1 件）。",
+        "Print only the result of --filter, like jq -r (strings without quotes, one line per result when iterating).",
+    ),
+    ("配列レスポンスを指定したパスの値でソートします（例: .created_at）", "Sort an array response by the value at this path (e.g. .created_at)"),
+    ("--sort-by を降順で適用します。", "Sort descending with --sort-by."),
+    (
+        "レスポンスボディを比較する期待値 JSON ファイル（--filter / --sort-by 適用後の本文と比較）",
+        "JSON file with the expected response body (compared after --filter / --sort-by)",
+    ),
+    (
+        "--expect-body-file の比較で無視するパス（カンマ区切り、例: .timestamp,.items[].id）",
+        "Paths ignored when comparing with --expect-body-file (comma-separated, e.g. .timestamp,.items[].id)",
+    ),
+    (
+        "タイムアウトや接続エラー時のリトライ回数（冪等なメソッドのみ対象、0 でリトライしない）",
+        "Retries on timeouts and connection errors (idempotent methods only; 0 disables retries)",
+    ),
+    ("最初のリトライまでの待機ミリ秒（以降は倍々に増加）", "Milliseconds before the first retry (doubling afterwards)"),
+    ("POST などの非冪等メソッドもリトライ対象にします（リソースの二重作成に注意）", "Also retry non-idempotent methods such as POST (beware of creating resources twice)"),
+    (
+        "レスポンス中の ID・トークン・名前などのパスと値を上位 N 件表示します（既定 10 件）。 表示されるパスは --filter でそのまま使えます。",
+        "Show the top N paths and values of ids, tokens, names, etc. in the response (default 10). The paths can be used with --filter as is.",
+    ),
+    ("送信（と --save）の前にヘッダーを対話的に追加・編集・削除します。", "Add, edit and delete headers interactively before sending (and --save)."),
+    ("表示・メッセージの言語（省略時は FERRAPI_LANG や LANG などのロケールから判定）", "Language of help and messages (detected from FERRAPI_LANG, LANG and other locale variables when omitted)"),
+    (
+        "完了時にデスクトップ通知を表示します（--notify-after より時間がかかったリクエスト、run、run-all、chain など）。 長時間かかるインポート処理などを実行して別のウィンドウで作業するときに便利です",
+        "Show a desktop notification when the command finishes (requests, run, run-all, chain and so on that took longer than --notify-after). Useful when starting a long data import and switching windows",
+    ),
+    ("--notify で通知する実行時間のしきい値（例: 30s、2m）", "Minimum duration of a command for --notify to show a notification (e.g. 30s, 2m)"),
+    ("後で送信するリクエストのキューを操作します（add / list / flush / clear）", "Manage the queue of requests to send later (add / list / flush / clear)"),
+    (
+        "リクエストをキューに追加します（TARGET の保存済み設定と CLI オプションを解決して保存）",
+        "Add a request to the queue (resolving the saved TARGET configuration and command-line options)",
+    ),
+    ("TARGET: 保存済み設定の名前空間パス（例: \"SystemA/example\"）", "TARGET: namespace path of a saved configuration (e.g. \"SystemA/example\")"),
+    ("キューに溜まっているリクエストを一覧表示します。", "List the queued requests."),
+    (
+        "キュー内のリクエストを送信します。送信に成功したものはキューから取り除かれます。 送信中は他の flush や add はこの flush の完了を待ちます。",
+        "Send the queued requests; delivered ones are removed from the queue. Other flushes and adds wait until the flush has finished.",
+    ),
+    ("同時に送信するリクエスト数（1 の場合は追加順に逐次送信）", "Requests sent concurrently (1 sends them one by one in queue order)"),
+    ("キューを空にします。", "Empty the queue."),
+    (
+        "トランザクションファイルに定義されたリクエスト、または名前空間以下の保存済み設定をすべて実行します。",
+        "Run the requests of a transaction file in order, or every saved config under a namespace.",
+    ),
+    (
+        "ステップ（と補償リクエスト）を定義した JSON ファイル、またはすべて実行する名前空間（例: SystemA）",
+        "JSON file defining the steps (and compensating requests), or a namespace to run entirely (e.g. SystemA)",
+    ),
+    (
+        "URL・ヘッダー・ボディ・アサーションを定義した 1 件のリクエスト（YAML または JSON）を送信します。 \"-\" を指定すると標準入力から読み込みます",
+        "Send one request (YAML or JSON) defining its URL, headers, body and assertions. \"-\" reads it from stdin",
+    ),
+    (
+        "名前空間を実行する場合に同時に送信するリクエスト数（既定では 1 件ずつ順番に実行）",
+        "Requests sent at once when running a namespace (one at a time in order by default)",
+    ),
+    (
+        "途中のステップが失敗した場合、完了済みステップの補償リクエストを逆順に実行します。",
+        "When a step fails, run the compensations of completed steps in reverse order.",
+    ),
+    (
+        "送信したリクエストを照合してカバレッジを報告する OpenAPI 仕様（省略時は settings.json の openapi）",
+        "OpenAPI spec to report coverage against (defaults to openapi in settings.json)",
+    ),
+    ("カバレッジレポートを HTML で書き出すファイル", "Write the coverage report as HTML to this file"),
+    ("SSO などの認証情報を取得して名前空間の変数に保存します。", "Capture credentials such as SSO tokens into namespace variables."),
+    (
+        "ブラウザで SSO (SAML/OIDC) ログインを行い、コールバックで受け取ったトークンや Cookie を名前空間の変数に保存します。",
+        "Log in through SSO (SAML/OIDC) in the browser and save the tokens or cookies from the callback as namespace variables.",
+    ),
+    ("ログインページの URL", "URL of the login page"),
+    ("保存先の名前空間（例: \"SystemA\"）", "Namespace to save into (e.g. \"SystemA\")"),
+    ("ログイン後のリダイレクト先として IdP に登録したローカル URL", "Local URL registered with the IdP as the redirect after login"),
+    ("コールバックを待つ最大時間（例: 5m）", "How long to wait for the callback (e.g. 5m)"),
+    ("保存する変数名の接頭辞（例: sso_ → sso_access_token）", "Prefix of the saved variable names (e.g. sso_ gives sso_access_token)"),
+    ("ブラウザを開かず、URL を表示するだけにします。", "Print the URL instead of opening the browser."),
+    (
+        "受け取った値を vars.json ではなく資格情報ストアに保存します（{{secret:NAME}} で参照）。",
+        "Store the captured values in the credential store instead of vars.json (use them as {{secret:NAME}}).",
+    ),
+    (
+        "ローカルの HTTP(S) プロキシを起動し、通過したリクエストを設定ツリー・履歴として記録します。",
+        "Start a local HTTP(S) proxy and record the requests passing through as configurations and history.",
+    ),
+    ("待ち受けるポート", "Port to listen on"),
+    (
+        "通過したリクエストを設定・履歴・セッションとして保存する名前空間（例: SystemA）",
+        "Namespace to save the traffic into as configurations, history and a session (e.g. SystemA)",
+    ),
+    (
+        "HTTPS 通信も復号して記録します（生成される CA 証明書をクライアントに信頼させる必要があります）",
+        "Decrypt and record HTTPS traffic too (clients must trust the generated CA certificate)",
+    ),
+    ("転送先の TLS 証明書を検証しません。", "Skip TLS verification of upstream servers."),
+    (
+        "proxy --record で記録したセッションを再生し、記録時のレスポンスと比較します。",
+        "Replay a session recorded with proxy --record and compare the responses with the recording.",
+    ),
+    (
+        "セッションファイル（パス、または ~/.ferrapi_tester/.sessions 内の名前）。省略時は一覧を表示します",
+        "Session file (a path or a name in ~/.ferrapi_tester/.sessions); lists the sessions when omitted",
+    ),
+    (
+        "記録時と異なる送信先（例: https://staging.example.com）。スキーム・ホスト・ポートを置き換え、パスは先頭に付けます",
+        "Send to another base URL (e.g. https://staging.example.com), replacing scheme, host and port and prefixing its path",
+    ),
+    (
+        "再生速度の倍率（1 は記録時と同じ間隔、2 は 2 倍速、0 は待たずに順次送信）",
+        "Replay speed (1 keeps the recorded timing, 2 is twice as fast, 0 sends one after another without waiting)",
+    ),
+    ("ボディの比較で無視するパス（カンマ区切り、例: .timestamp,.items[].id）", "Paths ignored when comparing bodies (comma-separated, e.g. .timestamp,.items[].id)"),
+    ("差異のあったリクエストについてボディの差分を表示します。", "Show body differences of mismatching requests."),
+    ("TLS 証明書の検証を行いません。", "Skip TLS certificate verification."),
+    (
+        "履歴や記録済みセッションを OpenAPI 仕様と照合し、未文書化のエンドポイントや未使用の操作を報告します。",
+        "Check history and recorded sessions against an OpenAPI spec, reporting undocumented endpoints and unused operations.",
+    ),
+    ("OpenAPI 仕様ファイル（JSON または YAML）", "OpenAPI spec file (JSON or YAML)"),
+    ("照合する記録済みセッション（複数指定可。proxy --record で記録したもの）", "Recorded session to check (repeatable; recorded with proxy --record)"),
+    ("履歴（history.jsonl）を照合の対象に含めません。", "Do not check the history (history.jsonl)."),
+    ("履歴のうち対象とする名前空間または URL の接頭辞", "Namespace or URL prefix of the history entries to check"),
+    ("履歴の対象期間（例: 7d）", "Time window of the history to check (e.g. 7d)"),
+    (
+        "仕様に無いエンドポイントやステータスコードが使われていた場合にエラーで終了します。",
+        "Fail when endpoints or status codes missing from the spec were used.",
+    ),
+    ("名前空間のサブツリー以下の保存済み設定をすべて実行し、結果を表にまとめます。", "Run every saved configuration under a namespace subtree and summarize the results."),
+    ("実行する名前空間のサブツリー（例: SystemA/）。省略時はすべての保存済み設定", "Namespace subtree to run (e.g. SystemA/); all saved configurations when omitted"),
+    ("指定したメソッドの設定だけを実行します（例: GET）", "Only run configurations with this method (e.g. GET)"),
+    ("指定したタグのいずれかを持つ設定だけを実行します（複数指定可）", "Only run configurations with any of these tags (repeatable)"),
+    ("同時に送信するリクエスト数", "Requests sent concurrently"),
+    (
+        "結果の下に表示するレスポンス時間の SLO（例: 500ms）。超えたリクエストは赤で表示します。 省略時は各設定の budget の max_latency（settings.json）",
+        "Response time SLO for the latency line under the results (e.g. 500ms); requests over it are shown in red. Defaults to the max_latency budget of each config (settings.json)",
+    ),
+    ("失敗した設定が N 件に達したら、残りの設定を実行せずに中止します", "Abort the run, skipping the remaining configs, once N configs have failed"),
+    (
+        "5 件以上終わった時点で失敗率がこの値（例: 20%）以上になったら、残りの設定を実行せずに中止します",
+        "Abort the run, skipping the remaining configs, once the failure rate reaches this value (e.g. 20%) after at least 5 configs",
+    ),
+    (
+        "名前（例: \"SystemA/users/create POST\"）がパターンに一致する設定だけを実行します（複数指定可）。 ワイルドカード（SystemA/*/POST）または正規表現（users.*create）で指定します",
+        "Only run configurations whose name (e.g. \"SystemA/users/create POST\") matches the pattern (repeatable). Use wildcards (SystemA/*/POST) or a regular expression (users.*create)",
+    ),
+    (
+        "名前（例: \"checkout/create order\"）がパターンに一致するステップだけを実行します（複数指定可）。 ワイルドカードまたは正規表現で指定します",
+        "Only run steps whose name (e.g. \"checkout/create order\") matches the pattern (repeatable). Use wildcards or a regular expression",
+    ),
+    (
+        "設定をランダムな順序で実行し、保存済みリクエスト間の隠れた順序依存を洗い出します",
+        "Run the configurations in random order to flush out hidden ordering dependencies between saved requests",
+    ),
+    (
+        "--shuffle の乱数シード。前回の実行順序を再現する場合に指定します",
+        "Random seed for --shuffle; pass it to reproduce the order of an earlier run",
+    ),
+    (
+        "名前空間の _hooks.yaml に定義された前後処理（before/after）を実行しません",
+        "Don't run the setup and teardown requests (before/after) defined in the namespace's _hooks.yaml",
+    ),
+    (
+        "名前空間の request_schema（settings.json）によるリクエストボディの事前検証を行いません",
+        "Skip checking request bodies against the namespace's request_schema (settings.json) before sending",
+    ),
+    (
+        "送信前にボディを名前空間の request_schema（settings.json）で検証し、違反があれば送信しません。",
+        "Check the body against the namespace's request_schema (settings.json) before sending, and don't send it if it violates the schema.",
+    ),
+    (
+        "JSON のレスポンスを正規化した形（キー順・数値表記を統一した 1 行）で表示します。",
+        "Show a JSON response in canonical form (sorted keys, normalized numbers, on one line).",
+    ),
+    (
+        "同じリクエストの前回の記録と JSON レスポンスの構造と型だけを比較し、追加・削除・型が変わったフィールドを表示します。",
+        "Compare only the structure and types of the JSON response with the previous recorded run and show added, removed and retyped fields.",
+    ),
+    (
+        "レスポンスを保存済み設定の例（examples）として記録します（docs generate で表示）。説明を付けることもできます。 同じステータスの例は置き換えます",
+        "Record the response as an example of the saved configuration (examples, shown by docs generate), optionally with a description. An example with the same status is replaced",
+    ),
+    ("ステータスとボディに加えてレスポンスヘッダーを表示します。", "Show the response headers along with the status and body."),
+    (
+        "レスポンスヘッダーの値を変数に保存し、後続のリクエストで {{VAR}} として使えるようにします（例: \"X-Request-Id=reqid\"）。 --env 指定時はその環境ファイル、それ以外は TARGET の vars.json に書き込みます",
+        "Store a response header value in a variable for later requests to use as {{VAR}} (e.g. \"X-Request-Id=reqid\"). It is written to the --env environment file if given, otherwise to the vars.json of TARGET",
+    ),
+    (
+        "ボディを正規化した JSON（キー順・数値表記を統一）で送信し、設定にも正規化して保存します。",
+        "Send the body as canonical JSON (sorted keys, normalized numbers) and save it in that form.",
+    ),
+    (
+        "Bearer トークンで認証します（Authorization: Bearer TOKEN として送信し、設定の auth に保存）",
+        "Authenticate with a bearer token (sent as Authorization: Bearer TOKEN and saved as the config's auth)",
+    ),
+    (
+        "Basic 認証を行います（USER:PASSWORD を Base64 エンコードして送信し、設定の auth に保存）",
+        "Use Basic authentication (USER:PASSWORD is sent Base64-encoded and saved as the config's auth)",
+    ),
+    (
+        "API キーをヘッダーで送信します（例: --api-key \"X-Api-Key=abc\"。設定の auth に保存）",
+        "Send an API key in a header (e.g. --api-key \"X-Api-Key=abc\"; saved as the config's auth)",
+    ),
+    (
+        "multipart/form-data のフィールド（複数指定可。例: --form title=Report）",
+        "A multipart/form-data field (repeatable, e.g. --form title=Report)",
+    ),
+    (
+        "multipart/form-data でアップロードするファイル（複数指定可。例: --file avatar=@./me.png;type=image/png）",
+        "A file to upload as multipart/form-data (repeatable, e.g. --file avatar=@./me.png;type=image/png)",
+    ),
+    (
+        "application/x-www-form-urlencoded のフィールド（複数指定可。例: --form-urlencoded grant_type=client_credentials）",
+        "An application/x-www-form-urlencoded field (repeatable, e.g. --form-urlencoded grant_type=client_credentials)",
+    ),
+    (
+        "URL に追加するクエリパラメータ（複数指定可。例: --query \"q=a&b\"。値は送信時にエンコードされます）",
+        "A query parameter added to the URL (repeatable, e.g. --query \"q=a&b\"; the value is encoded when sent)",
+    ),
+    (
+        "名前空間ごとに並列実行するワーカー数。同じ名前空間の設定は保存順に逐次実行します （--concurrency の代わりに使用）",
+        "Number of parallel workers, one namespace at a time. Configurations in the same namespace run one by one in saved order (replaces --concurrency)",
+    ),
+    (
+        "失敗した設定を再実行する回数（設定ファイルの test_retries が優先）",
+        "Times a failing configuration is run again (test_retries in the configuration takes precedence)",
+    ),
+    (
+        "既知の不安定なターゲットを列挙した隔離リスト（省略時は ~/.ferrapi_tester/quarantine.txt）。 隔離中の設定の失敗は別に集計され、終了コードに影響しません",
+        "Quarantine list of known-flaky targets (default ~/.ferrapi_tester/quarantine.txt). Failures of quarantined configurations are counted separately and don't affect the exit code",
+    ),
+    (
+        "名前空間全体（設定・変数・その他のファイル）を 1 つの JSON バンドルとして、または保存済み設定を curl コマンド（export curl）として標準出力に書き出します。",
+        "Write a whole namespace (configurations, variables and other files) to stdout as one JSON bundle, or a saved config as a curl command (export curl).",
+    ),
+    ("書き出す名前空間（例: \"SystemA\"）", "Namespace to export (e.g. \"SystemA\")"),
+    (
+        "保存済み設定と同じリクエストを送る curl コマンドを表示します（変数は展開されます）。",
+        "Print a curl command sending the same request as a saved config (with variables resolved).",
+    ),
+    ("対象の保存済み設定（例: SystemA/example）", "Saved config (e.g. SystemA/example)"),
+    (
+        "export で作成したバンドル、OpenAPI 仕様（import openapi）、または curl コマンド（import curl）から設定を取り込みます。",
+        "Import a bundle created with export, or configs from an OpenAPI spec (import openapi) or a curl command (import curl).",
+    ),
+    (
+        "OpenAPI 3（または Swagger 2）仕様の各パス・メソッドから保存済み設定を生成します。",
+        "Generate a saved config for every path and method of an OpenAPI 3 (or Swagger 2) spec.",
+    ),
+    ("生成先の名前空間（例: SystemA）", "Namespace to generate into (e.g. SystemA)"),
+    (
+        "仕様の servers の代わりに使うベース URL（例: https://staging.example.com/v1）",
+        "Base URL to use instead of the spec's servers (e.g. https://staging.example.com/v1)",
+    ),
+    (
+        "curl のコマンドラインを保存済み設定に変換します（省略時は標準入力から読み込みます）。",
+        "Convert a curl command line into a saved config (read from stdin when omitted).",
+    ),
+    (
+        "curl コマンド全体を引用符で囲んだもの（例: \"curl -X POST https://... -d '{...}'\"）",
+        "The whole curl command in quotes (e.g. \"curl -X POST https://... -d '{...}'\")",
+    ),
+    ("設定を表示する代わりに TARGET として保存します。", "Save the config as TARGET instead of printing it."),
+    ("既存の設定を上書きします。", "Overwrite an existing config."),
+    ("バンドルファイル", "Bundle file"),
+    ("取り込み先の名前空間（省略時はバンドルに記録された名前空間）", "Namespace to import into (defaults to the one recorded in the bundle)"),
+    ("内容が異なる既存ファイルを上書きします。", "Overwrite existing files with different content."),
+    ("設定ディレクトリの管理（スナップショットなど）を行います。", "Manage the configuration directory (snapshots, ...)."),
+    ("名前空間のスナップショットを作成・一覧・復元します。", "Create, list and restore namespace snapshots."),
+    ("名前空間の現在の状態をスナップショットとして保存します。", "Save the current state of a namespace as a snapshot."),
+    ("対象の名前空間（例: \"SystemA\"）", "Namespace (e.g. \"SystemA\")"),
+    ("スナップショットの説明", "Description of the snapshot"),
+    ("スナップショットを新しい順に一覧表示します。", "List snapshots, newest first."),
+    ("対象の名前空間（省略時はすべて）", "Namespace (all when omitted)"),
+    (
+        "スナップショットの状態に名前空間を戻します（復元前の状態も自動で保存されます）。",
+        "Restore a namespace to a snapshot (the state before restoring is saved automatically).",
+    ),
+    ("対象の名前空間", "Namespace"),
+    ("スナップショット ID（先頭の数文字でも可）", "Snapshot id (a unique prefix is enough)"),
+    ("リクエスト履歴（history.jsonl）を管理します。", "Manage the request history (history.jsonl)."),
+    ("実行したリクエストの履歴を新しい順に表示します。", "Show executed requests, newest first."),
+    ("対象の名前空間または URL の前方一致（省略時はすべて）", "Namespace or URL prefix (all when omitted)"),
+    ("この期間内の履歴だけを表示（例: 1h, 7d）", "Only show entries within this time window (e.g. 1h, 7d)"),
+    ("表示する最大件数", "Maximum number of entries to show"),
+    (
+        "履歴のエントリ（history list の # 列の番号）の詳細を表示します。",
+        "Show the details of a history entry (by its number in the # column of history list).",
+    ),
+    (
+        "履歴のエントリ（history list の # 列の番号）のリクエストを再送信します。",
+        "Send the request of a history entry again (by its number in the # column of history list).",
+    ),
+    ("エントリの番号（1 が最新）", "Number of the entry (1 is the most recent)"),
+    (
+        "URL・ターゲット・エラー・レスポンスのヘッダーとボディから語句を含む履歴を検索します。",
+        "Search the URL, target, error, response headers and body of history entries for words.",
+    ),
+    (
+        "検索する語句（例: \"error_code=E1234\"。英数字の並びで照合し、大文字小文字は区別しません。省略時はすべて）",
+        "Words to search for (e.g. \"error_code=E1234\"; matched as a sequence of words, case-insensitive; all entries if omitted)",
+    ),
+    ("HTTP メソッドで絞り込み（例: POST）", "Only entries with this HTTP method (e.g. POST)"),
+    ("ステータスコードで絞り込み（例: 404、5xx）", "Only entries with this status code or class (e.g. 404, 5xx)"),
+    (
+        "失敗したリクエスト（ネットワークエラーまたは 400 以上）だけを表示します。",
+        "Only show failed requests (network errors or a status of 400 or more).",
+    ),
+    ("この期間内の履歴だけを検索（例: 1h, 7d）", "Only search entries within this time window (e.g. 1h, 7d)"),
+    (
+        "古い順に表示します（エラーが最初に返された時期の確認などに）。",
+        "Show the oldest matches first (e.g. to find when an error first appeared).",
+    ),
+    ("保持ポリシーに従って履歴を削除します（オプション指定時はその値を優先）。", "Prune the history by the retention policy (options take precedence)."),
+    ("保持する最大件数", "Maximum number of entries to keep"),
+    ("これより古い履歴を削除（例: 30d）", "Remove entries older than this (e.g. 30d)"),
+    ("ファイルサイズの上限（例: 50MB）", "Maximum file size (e.g. 50MB)"),
+    ("すべての履歴を削除します。", "Remove the whole history."),
+    (
+        "履歴からエンドポイントごとの成功率・レイテンシ傾向・頻出エラーを集計します。",
+        "Summarize success rates, latency trends and frequent errors per endpoint from the history.",
+    ),
+    ("集計対象の名前空間または URL の接頭辞（省略時はすべて）", "Namespace or URL prefix to summarize (all when omitted)"),
+    ("集計期間（例: 7d, 12h, 30m）", "Time window to summarize (e.g. 7d, 12h, 30m)"),
+    ("履歴のレスポンス時間をヒストグラムとパーセンタイルで表示します。", "Show a histogram and percentiles of response times from the history."),
+    ("集計対象の名前空間または URL の接頭辞", "Namespace or URL prefix to summarize"),
+    ("ヒストグラムの区間数", "Number of histogram buckets"),
+    (
+        "リクエストを送信し、リクエスト・レスポンス・所要時間・環境情報を秘密情報を伏せて ZIP にまとめます（バグ報告用）。",
+        "Send a request and package the request, response, timing and environment, with secrets redacted, into a ZIP for bug reports.",
+    ),
+    ("TARGET: 保存済み設定の名前空間パス（例: \"SystemA/example\"）または URL", "TARGET: namespace path of a saved config (e.g. \"SystemA/example\") or a URL"),
+    ("書き出す ZIP ファイルのパス", "Path of the ZIP file to write"),
+    (
+        "重複した Content-Length や不正なチャンクサイズなど、意図的に不正なリクエストを TCP/TLS で直接送信します（--unsafe-raw が必要）。",
+        "Send a deliberately malformed request, e.g. with duplicate Content-Length or a bad chunk size, directly over TCP/TLS (requires --unsafe-raw).",
+    ),
+    ("送信先の URL（http:// または https://）", "URL to send to (http:// or https://)"),
+    ("不正なリクエストを送信することを承知したうえで指定します（必須）", "Confirm that you mean to send malformed requests (required)"),
+    ("HTTP メソッド", "HTTP method"),
+    ("そのまま送信するヘッダー（例: \"X-Test: 1\"、複数指定可）", "Header sent as is (e.g. \"X-Test: 1\", repeatable)"),
+    ("リクエストボディ", "Request body"),
+    (
+        "正しい Content-Length に加えて、この値の Content-Length ヘッダーをもう 1 つ送ります",
+        "Send a second Content-Length header with this value after the correct one",
+    ),
+    (
+        "ボディを chunked で送り、実際の長さの代わりにこのチャンクサイズを宣言します（例: ffffffff、zz）",
+        "Send the body chunked, declaring this chunk size instead of the real length (e.g. ffffffff, zz)",
+    ),
+    ("指定したバイト数の値を持つヘッダーを追加します", "Add a header whose value is this many bytes long"),
+    (
+        "ファイルの内容を一切変更せずにリクエストとして送信します（URL は接続先にのみ使用）",
+        "Send the file's bytes unchanged as the request (the URL only chooses where to connect)",
+    ),
+    (
+        "応答を待つ秒数（この間データが届かなければ読み取りを終えます）",
+        "Seconds to wait for the response (reading stops when no data arrives for this long)",
+    ),
+    (
+        "ヘッダーを含むリクエスト全体をこの速度でゆっくり送信します（例: 10B/s、1kbps）",
+        "Send the whole request, headers included, slowly at this rate (e.g. 10B/s, 1kbps)",
+    ),
+    ("指定したバイト数を送信した時点で --pause の間送信を止めます", "Stop sending for --pause once this many bytes are sent"),
+    ("--pause-after-bytes で送信を止める時間（例: 30s）", "How long to stop sending at --pause-after-bytes (e.g. 30s)"),
+    ("同時に開く接続数（それぞれで同じリクエストを送信します）", "Number of connections to open at once (each sends the same request)"),
+    (
+        "資格情報ストア（キーチェーン・暗号化ファイル・環境変数）のシークレットを操作します。",
+        "Manage secrets in the credential store (keychain, encrypted file or environment variables).",
+    ),
+    (
+        "シークレットを資格情報ストアに保存します（値は標準入力または入力プロンプトから読み取ります）。",
+        "Store a secret in the credential store (the value is read from stdin or a prompt).",
+    ),
+    ("シークレット名（{{secret:NAME}} で参照します）", "Secret name (referenced as {{secret:NAME}})"),
+    ("シークレット名", "Secret name"),
+    ("シークレットの値を表示します。", "Print the value of a secret."),
+    ("シークレットを資格情報ストアから削除します。", "Delete a secret from the credential store."),
+    ("名前空間ごとのテンプレート変数（vars.json）を操作します。", "Manage template variables (vars.json) per namespace."),
+    ("変数を設定します。", "Set a variable."),
+    (
+        "対象の名前空間（省略時は ~/.ferrapi_tester/vars.json のデフォルト値）",
+        "Namespace (defaults to the default values in ~/.ferrapi_tester/vars.json)",
+    ),
+    ("変数の値を表示します。", "Print the value of a variable."),
+    ("対象の名前空間（省略時はデフォルト値）", "Namespace (defaults to the default values)"),
+    ("名前空間ファイルだけでなく、全レイヤーを解決した実際の値を表示します。", "Print the effective value resolved through all layers, not just the namespace file."),
+    ("変数を削除します。", "Remove a variable."),
+    ("変数を一覧表示します。", "List variables."),
+    ("対話モードで変数を追加・編集・削除します。", "Add, edit and delete variables interactively."),
+    (
+        "API のスタイル（REST CRUD・GraphQL・Webhook）ごとの保存済みリクエスト一式を生成します。",
+        "Generate a set of saved requests for an API style (REST CRUD, GraphQL, webhook).",
+    ),
+    (
+        "リソースの一覧・作成（TARGET）と取得・更新・削除（TARGET/item）の設定を生成します。",
+        "Generate list/create (TARGET) and get/update/delete (TARGET/item) configurations for a resource.",
+    ),
+    (
+        "生成先の名前空間。最後の要素がリソース名になります（例: SystemA/widgets）",
+        "Namespace to generate into; the last segment is the resource name (e.g. SystemA/widgets)",
+    ),
+    ("API のベース URL（例: https://api.example.com/v1）", "Base URL of the API (e.g. https://api.example.com/v1)"),
+    ("既存のファイルを上書きします。", "Overwrite existing files."),
+    ("GraphQL エンドポイントへのクエリ（POST）の設定を生成します。", "Generate a query (POST) configuration for a GraphQL endpoint."),
+    ("生成先の名前空間（例: SystemA/graphql）", "Namespace to generate into (e.g. SystemA/graphql)"),
+    ("GraphQL エンドポイントの URL", "URL of the GraphQL endpoint"),
+    ("Webhook 受信側に送るサンプルイベント（POST）の設定を生成します。", "Generate a sample event (POST) configuration for a webhook receiver."),
+    ("生成先の名前空間（例: SystemA/webhooks/order_created）", "Namespace to generate into (e.g. SystemA/webhooks/order_created)"),
+    ("Webhook を受け取るリスナーの URL", "URL of the listener receiving the webhook"),
+    ("イベント名（既定は TARGET の最後の要素）", "Event name (defaults to the last segment of TARGET)"),
+    (
+        "初回セットアップ: ベースディレクトリ、設定ファイル、例の名前空間と変数を作成し、シェル補完をインストールします。",
+        "First-run setup: create the base directory, settings, an example namespace and variables, and install shell completions.",
+    ),
+    ("質問せずにすべて既定の回答（はい）で進めます。", "Answer yes to every question."),
+    ("シェル補完のインストールを行いません。", "Do not install shell completions."),
+    (
+        "設定ディレクトリ・設定ファイル・プロキシ・TLS・接続先への到達性などを診断し、対処方法を表示します。",
+        "Diagnose the configuration directory, settings, proxies, TLS and reachability of saved URLs, with fixes.",
+    ),
+    ("保存済み URL への接続確認を行いません。", "Do not check connectivity to saved URLs."),
+    ("シェル補完スクリプトを標準出力に書き出します。", "Write a shell completion script to stdout."),
+    ("対象のシェル", "Shell"),
+    (
+        "コマンド・フラグ・値の型と設定ファイルのスキーマを出力します（エディタや GUI、補完エンジンとの連携向け）。",
+        "Print the commands, flags, value types, and the schema of configuration files (for editors, GUIs, and completion engines).",
+    ),
+    ("JSON で出力します", "Print as JSON"),
+    (
+        "設定ファイルや環境ファイルなどの JSON Schema を扱います。",
+        "Work with the JSON Schemas of configuration files, environment files, and more.",
+    ),
+    (
+        "手で編集するファイルの JSON Schema を出力します（エディタでの検証と補完用）。",
+        "Print the JSON Schema of a file you edit by hand (for validation and autocompletion in editors).",
+    ),
+    ("ファイルの種類", "Kind of file"),
+    (
+        "設定ファイル・環境ファイル・トランザクション/チェーンファイルを検証する簡易な言語サーバーを標準入出力で起動します。 {{変数}} の未定義の警告、値のホバー表示、定義へのジャンプに対応します",
+        "Start a minimal language server on stdin/stdout that validates configuration, environment, transaction, and chain files. It warns about undefined {{variables}}, shows their values on hover, and jumps to their definitions",
+    ),
+    (
+        "GitHub の最新リリースを確認し、チェックサムを検証したうえで実行ファイルを置き換えます。",
+        "Check the latest GitHub release and replace the executable after verifying its checksum.",
+    ),
+    ("更新の有無を確認するだけで、置き換えは行いません。", "Only check for an update without installing it."),
+    ("確認プロンプトを表示せずに更新します。", "Update without asking for confirmation."),
+    (
+        "スタブファイルや記録済みセッションのレスポンスを返すモックサーバーを起動します（障害・遅延の注入も可能）。",
+        "Start a mock server answering from stubs files and recorded sessions (with optional failure and delay injection).",
+    ),
+    (
+        "応答に使う記録済みセッション（パス、または ~/.ferrapi_tester/.sessions 内の名前）",
+        "Recorded session to answer from (a path, or a name in ~/.ferrapi_tester/.sessions)",
+    ),
+    (
+        "マッチング条件と応答テンプレートを定義したスタブファイル（YAML/JSON、複数指定可）",
+        "Stubs file with request matching rules and response templates (YAML/JSON, repeatable)",
+    ),
+    (
+        "記録時の応答時間だけ待ってから記録済みの応答を返します。 倍率を指定すると時間を伸縮します（例: 0.5 で半分、2 で倍）",
+        "Wait as long as the recorded response took before sending it. A scale stretches the time (e.g. 0.5 for half, 2 for double)",
+    ),
+    (
+        "HTTPS で待ち受けます（ローカル CA で署名した localhost 用の証明書を自動生成します）",
+        "Serve HTTPS (with a generated certificate for localhost signed by the local CA)",
+    ),
+    ("HTTPS に使う証明書ファイル（PEM、--tls-key と併用）", "Certificate file for HTTPS (PEM, used with --tls-key)"),
+    ("HTTPS に使う秘密鍵ファイル（PKCS#8 PEM、--tls-cert と併用）", "Private key file for HTTPS (PKCS#8 PEM, used with --tls-cert)"),
+    (
+        "スタブのないリクエストをこのバックエンド（例: https://api.example.com）へ転送し、 応答をセッションに記録して次回からのスタブにします",
+        "Forward requests without a stub to this backend (e.g. https://api.example.com) and record the responses in the session as new stubs",
+    ),
+    ("障害を注入するリクエストの割合（例: 10%、0.1）", "Share of requests that get an injected failure (e.g. 10%, 0.1)"),
+    ("注入する障害のステータスコード", "Status code of injected failures"),
+    (
+        "応答前にランダムに待機する時間の範囲（例: 0-2s、100ms-500ms）",
+        "Range of the random delay before each response (e.g. 0-2s, 100ms-500ms)",
+    ),
+    (
+        "障害を注入するリクエストでは、エラーを返す代わりに応答せず接続を切断します",
+        "Close the connection without a response instead of returning an error for injected failures",
+    ),
+    (
+        "リクエストを送信します（サブコマンドを付けないトップレベルのフラグと同じ動作）。",
+        "Send a request (the same as the top-level flags without a subcommand).",
+    ),
+    (
+        "保存済み設定の名前空間パス（例: \"SystemA/example\"）。省略時は --url のみで呼び出します",
+        "Namespace path of a saved configuration (e.g. \"SystemA/example\"). Without it, only --url is called",
+    ),
+    ("リクエストを送信せずに、設定を名前空間に保存します。", "Save the configuration in a namespace without sending the request."),
+    ("保存先の名前空間パス（例: \"SystemA/example\"）", "Namespace path to save to (e.g. \"SystemA/example\")"),
+    ("保存の前にヘッダーを対話的に追加・編集・削除します。", "Add, edit, and remove headers interactively before saving."),
+    ("保存済み設定を一覧表示します。", "List saved configurations."),
+    ("一覧する名前空間（省略時はすべて）", "Namespace to list (all when omitted)"),
+    ("保存済み設定、または名前空間全体を削除します。", "Delete a saved configuration or a whole namespace."),
+    ("削除する設定の名前空間パス", "Namespace path of the configuration to delete"),
+    ("削除する設定の HTTP メソッド", "HTTP method of the configuration to delete"),
+    ("名前空間ディレクトリとその内容全体を削除します。", "Delete the namespace directory with everything in it."),
+    (
+        "TARGET へのリクエストで使われる設定ファイルと、変数・設定・フックのファイルを優先順に表示します。",
+        "Show the configuration file a request to TARGET would use, and its vars, settings and hooks files, in order of precedence.",
+    ),
+    ("調べる名前空間パス", "Namespace path to look up"),
+    ("調べる設定の HTTP メソッド", "HTTP method of the configuration to look up"),
+    (
+        "履歴（テストスイートや定期実行の結果）から、成功率とエンドポイントごとのレイテンシの推移を示す静的 HTML ダッシュボードを生成します。 CI からステータスページとして公開できます",
+        "Render the history (test suite and scheduled runs) into a static HTML dashboard of pass rates and latency trends per endpoint. It can be published from CI as a status page",
+    ),
+    (
+        "出力先ディレクトリ（index.html と results.json を書き込みます）",
+        "Output directory (index.html and results.json are written there)",
+    ),
+    ("ページのタイトル", "Title of the page"),
+    ("保存済み設定から API リファレンスを生成します。", "Generate an API reference from the saved configurations."),
+    (
+        "保存済み設定から API リファレンス（エンドポイント・パラメータ・ヘッダー・リクエストとレスポンスの例）を生成します。",
+        "Generate an API reference (endpoints, parameters, headers, example requests and responses) from the saved configurations.",
+    ),
+    ("対象の名前空間（省略時はすべて）", "Namespace to document (all when omitted)"),
+    ("出力形式", "Output format"),
+    ("標準出力ではなく指定したファイルに書き込みます。", "Write to this file instead of stdout."),
+    (
+        "--env で選ぶ環境ファイル（~/.ferrapi_tester/envs/NAME.json）を操作します。",
+        "Manage the environment files selected with --env (~/.ferrapi_tester/envs/NAME.json).",
+    ),
+    ("環境の一覧を表示します。", "List environments."),
+    ("環境の変数を表示します。", "Show the variables of an environment."),
+    ("環境に変数を設定します（ファイルがなければ作成します）。", "Set a variable in an environment (creating the file if needed)."),
+    ("環境から変数を削除します。", "Remove a variable from an environment."),
+    (
+        "シェルの環境変数または .envrc / .env ファイルの変数を環境に取り込みます。",
+        "Import variables into an environment from the shell or a .envrc / .env file.",
+    ),
+    ("取り込み先の環境（例: dev）", "Environment to import into (e.g. dev)"),
+    (
+        "この接頭辞で始まる、現在のプロセスの環境変数を取り込みます（例: MYAPP_）",
+        "Import the process environment variables starting with this prefix (e.g. MYAPP_)",
+    ),
+    (
+        "KEY=VALUE（export 付きも可）の行を読み込むファイル（例: .envrc）",
+        "File whose KEY=VALUE (or export KEY=VALUE) lines are read (e.g. .envrc)",
+    ),
+    (
+        "変数名から接頭辞を取り除きます（例: MYAPP_BASE_URL → BASE_URL）",
+        "Remove the prefix from the variable names (e.g. MYAPP_BASE_URL → BASE_URL)",
+    ),
+];
+
+/// Japanese for the help text clap generates itself and the English root description.
+const CLAP_JA: &[(&str, &str)] = &[
+    ("Print help", "ヘルプを表示します"),
+    ("Print help (see more with '--help')", "ヘルプを表示します（'--help' で詳細）"),
+    ("Print help (see a summary with '-h')", "ヘルプを表示します（'-h' で概要）"),
+    ("Print version", "バージョンを表示します"),
+    ("FerrAPI Tester - API testing CLI tool", "FerrAPI Tester - API テスト用 CLI ツール"),
+    (
+        "FerrAPI Tester - API testing CLI tool.\n\nこのツールは、HTTP リクエストの設定をコマンドラインで指定し、 必要に応じて設定を保存・読み込みして API のテストを行います。 TARGET（名前空間）が指定されなければ、--url オプションのみで API を呼び出します。",
+        "FerrAPI Tester - API テスト用 CLI ツール。\n\nこのツールは、HTTP リクエストの設定をコマンドラインで指定し、 必要に応じて設定を保存・読み込みして API のテストを行います。 TARGET（名前空間）が指定されなければ、--url オプションのみで API を呼び出します。",
+    ),
+    ("Print this message or the help of the given subcommand(s)", "このメッセージ、または指定したサブコマンドのヘルプを表示します"),
+];
+
+fn translate(table: &[(&'static str, &'static str)], text: &str) -> Option<&'static str> {
+    table.iter().find(|(from, _)| *from == text).map(|(_, to)| *to)
+}
+
+/// Translates the help of COMMAND and all of its arguments and subcommands
+/// into the current language. Untranslated text is left as declared.
+pub fn localize(mut command: Command) -> Command {
+    // ヘルプ用の引数・サブコマンドは build 時に生成されるので、先に build しておく
+    command.build();
+    localize_built(command)
+}
+
+fn localize_built(command: Command) -> Command {
+    let (table, japanese) = match current() {
+        Lang::En => (HELP, false),
+        Lang::Ja => (CLAP_JA, true),
+    };
+    let lookup = |text: Option<String>| text.and_then(|text| translate(table, &text));
+    let about = lookup(command.get_about().map(ToString::to_string));
+    let long_about = lookup(command.get_long_about().map(ToString::to_string));
+    let mut command = command
+        .mut_args(|arg| {
+            let help = lookup(arg.get_help().map(ToString::to_string));
+            let long_help = lookup(arg.get_long_help().map(ToString::to_string));
+            let arg = match help {
+                Some(help) => arg.help(help),
+                None => arg,
+            };
+            match long_help {
+                Some(long_help) => arg.long_help(long_help),
+                None => arg,
+            }
+        })
+        .mut_subcommands(localize_built);
+    if let Some(about) = about {
+        command = command.about(about);
+    }
+    if let Some(long_about) = long_about {
+        command = command.long_about(long_about);
+    }
+    if japanese {
+        command = command
+            .help_template("{before-help}{about-with-newline}\n使い方: {usage}\n\n{all-args}{after-help}")
+            .subcommand_help_heading("コマンド")
+            .next_help_heading("オプション");
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn untranslated(command: &Command, missing: &mut Vec<String>) {
+        let texts = command
+            .get_arguments()
+            .filter_map(|arg| arg.get_help())
+            .chain(command.get_about())
+            .chain(command.get_long_about())
+            .map(ToString::to_string);
+        for text in texts {
+            if translate(HELP, &text).is_none() && translate(CLAP_JA, &text).is_none() && !missing.contains(&text) {
+                missing.push(text);
+            }
+        }
+        for subcommand in command.get_subcommands() {
+            untranslated(subcommand, missing);
+        }
+    }
+
+    #[test]
+    fn every_help_text_has_an_english_translation() {
+        let mut command = crate::Args::command();
+        command.build();
+        let mut missing = Vec::new();
+        untranslated(&command, &mut missing);
+        assert!(missing.is_empty(), "help text without an entry in i18n::HELP: {:#?}", missing);
+    }
+
+    /// The string literals passed to `i18n::t` and `i18n::tf` in SOURCE, unescaped.
+    fn message_keys(source: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        for function in ["t", "tf"] {
+            let call = format!("i18n::{}(", function);
+            for (start, _) in source.match_indices(&call) {
+                let rest = source[start + call.len()..].trim_start();
+                let Some(literal) = rest.strip_prefix('"') else {
+                    continue;
+                };
+                let mut key = String::new();
+                let mut chars = literal.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => key.push('\n'),
+                            Some('t') => key.push('\t'),
+                            Some(other) => key.push(other),
+                            None => {}
+                        },
+                        c => key.push(c),
+                    }
+                }
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn every_message_has_a_japanese_translation() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut missing = Vec::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            for key in message_keys(&source) {
+                if translate(MESSAGES, &key).is_none() && !missing.contains(&key) {
+                    missing.push(key);
+                }
+            }
+        }
+        assert!(missing.is_empty(), "messages without an entry in i18n::MESSAGES: {:#?}", missing);
+        for (en, ja) in MESSAGES {
+            assert_eq!(en.matches("{}").count(), ja.matches("{}").count(), "placeholders differ: {:?}", en);
+        }
+    }
+
+    #[test]
+    fn lang_flag_takes_precedence() {
+        let args = |list: &[&str]| list.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(detect(args(&["ferrapi_tester", "--lang", "ja"])), Lang::Ja);
+        assert_eq!(detect(args(&["ferrapi_tester", "--lang=en"])), Lang::En);
+    }
+
+    #[test]
+    fn tf_fills_placeholders_in_order() {
+        set(Lang::En);
+        assert_eq!(tf("\n{} passed, {} failed, {} total.", &[&1, &2, &3]), "\n1 passed, 2 failed, 3 total.");
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use dialoguer::Confirm;
+use directories::UserDirs;
+use serde_json::json;
+use std::{env, fs, path::Path, path::PathBuf};
+
+use crate::i18n;
+use crate::request::RequestConfig;
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::vars::VARS_FILE;
+use crate::{get_config_path, get_default_dir, namespace, store, Args};
+
+/// Namespace created by `init` to show how saved requests and variables fit together.
+const EXAMPLE_NAMESPACE: &str = "example/users";
+
+/// Options of `init`.
+#[derive(clap::Args, Debug)]
+pub struct InitArgs {
+    /// 質問せずにすべて既定の回答（はい）で進めます。
+    #[arg(short = 'y', long = "yes")]
+    yes: bool,
+
+    /// シェル補完のインストールを行いません。
+    #[arg(long = "no-completions")]
+    no_completions: bool,
+}
+
+/// Writes `contents` unless the file exists, reporting what happened.
+fn create_file(path: &Path, contents: &str) -> Result<()> {
+    if path.exists() {
+        println!("{}", i18n::tf("  kept     {} (already exists)", &[&format!("{:?}", path)]));
+        return Ok(());
+    }
+    store::write_atomic(path, contents)?;
+    println!("{}", i18n::tf("  created  {}", &[&format!("{:?}", path)]));
+    Ok(())
+}
+
+/// Prints the completion script for SHELL to stdout.
+pub fn print_completions(shell: Shell) {
+    clap_complete::generate(shell, &mut Args::command(), "ferrapi_tester", &mut std::io::stdout());
+}
+
+/// The shell the user is running, guessed from $SHELL.
+fn detect_shell() -> Option<Shell> {
+    let shell = env::var("SHELL").ok()?;
+    Shell::from_shell_path(shell)
+}
+
+/// Where the completion script is picked up automatically (or with a one-line setup).
+fn completion_path(shell: Shell) -> Option<PathBuf> {
+    let home = UserDirs::new()?.home_dir().to_path_buf();
+    match shell {
+        Shell::Bash => Some(home.join(".local/share/bash-completion/completions/ferrapi_tester")),
+        Shell::Zsh => Some(home.join(".zfunc/_ferrapi_tester")),
+        Shell::Fish => Some(home.join(".config/fish/completions/ferrapi_tester.fish")),
+        _ => None,
+    }
+}
+
+fn install_completions(shell: Shell) -> Result<()> {
+    let Some(path) = completion_path(shell) else {
+        println!(
+            "{}",
+            i18n::tf(
+                "Automatic installation is not supported for {}; run `ferrapi_tester completions {}` and load the output in your profile.",
+                &[&shell, &shell]
+            )
+        );
+        return Ok(());
+    };
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Args::command(), "ferrapi_tester", &mut script);
+    store::write_atomic(&path, script)?;
+    println!("{}", i18n::tf("  created  {}", &[&format!("{:?}", path)]));
+    if shell == Shell::Zsh {
+        println!("{}", i18n::t("Add `fpath=(~/.zfunc $fpath)` before `compinit` in ~/.zshrc if it is not there yet."));
+    }
+    Ok(())
+}
+
+pub fn run(args: InitArgs) -> Result<()> {
+    let base_dir = get_default_dir()?;
+    fs::create_dir_all(&base_dir).with_context(|| format!("Failed to create directory {:?}", base_dir))?;
+    println!("{}", i18n::tf("Base directory: {}", &[&format!("{:?}", base_dir)]));
+
+    create_file(&base_dir.join(SETTINGS_FILE), &serde_json::to_string_pretty(&Settings::default())?)?;
+
+    // 例の名前空間: 変数（接続先）と、それを参照する保存済みリクエスト
+    let example_dir = namespace::create(&base_dir, EXAMPLE_NAMESPACE)?;
+    create_file(
+        &example_dir.parent().unwrap_or(&example_dir).join(VARS_FILE),
+        &serde_json::to_string_pretty(&json!({ "base_url": "https://jsonplaceholder.typicode.com" }))?,
+    )?;
+    let example = RequestConfig {
+        url: Some("{{base_url}}/users".to_string()),
+        method: Some("GET".to_string()),
+        headers: Some([("Accept".to_string(), "application/json".to_string())].into_iter().collect()),
+        timeout: Some(30),
+        ..RequestConfig::default()
+    };
+    create_file(
+        &get_config_path(&base_dir, EXAMPLE_NAMESPACE, "GET")?,
+        &serde_json::to_string_pretty(&example)?,
+    )?;
+
+    if !args.no_completions {
+        match detect_shell() {
+            Some(shell) => {
+                let install = args.yes
+                    || Confirm::new()
+                        .with_prompt(i18n::tf("Install shell completions for {}?", &[&shell]))
+                        .default(true)
+                        .interact()?;
+                if install {
+                    install_completions(shell)?;
+                }
+            }
+            None => println!(
+                "{}",
+                i18n::t(
+                    "Could not detect your shell; run `ferrapi_tester completions <SHELL>` to generate completions."
+                )
+            ),
+        }
+    }
+
+    println!("{}", i18n::tf("\nDone. Try the example request:\n  ferrapi_tester -- {}", &[&EXAMPLE_NAMESPACE]));
+    Ok(())
+}
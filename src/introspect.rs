@@ -0,0 +1,186 @@
+use anyhow::Result;
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use schemars::schema::RootSchema;
+use serde::Serialize;
+use serde_json::json;
+use std::{any::TypeId, path::PathBuf, time::Duration};
+
+use crate::i18n;
+use crate::output::{self, OutputFormat, Table};
+use crate::schema::{self, SchemaKind};
+use crate::Args;
+
+/// An argument of a command. `type` is one of boolean, count, enum, integer, number,
+/// duration, path or string.
+#[derive(Serialize)]
+struct ArgInfo {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short: Option<char>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    positional: bool,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    possible_values: Vec<String>,
+    multiple: bool,
+    required: bool,
+    global: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    default: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CommandInfo {
+    name: String,
+    /// The words typed to run the command, e.g. "history show".
+    path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    args: Vec<ArgInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subcommands: Vec<CommandInfo>,
+}
+
+/// Everything `introspect --json` prints.
+#[derive(Serialize)]
+struct Introspection {
+    name: String,
+    version: String,
+    command: CommandInfo,
+    /// JSON Schema of the saved configuration files.
+    config_schema: RootSchema,
+}
+
+/// Type of the values ARG takes, from its action and value parser.
+fn kind(arg: &Arg) -> &'static str {
+    match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse | ArgAction::Help | ArgAction::Version => return "boolean",
+        ArgAction::HelpShort | ArgAction::HelpLong => return "boolean",
+        ArgAction::Count => return "count",
+        _ => {}
+    }
+    if !arg.get_possible_values().is_empty() {
+        return "enum";
+    }
+    let type_id = arg.get_value_parser().type_id();
+    let integers = [TypeId::of::<u16>(), TypeId::of::<u32>(), TypeId::of::<u64>(), TypeId::of::<usize>()];
+    if integers.iter().any(|integer| type_id == *integer) {
+        "integer"
+    } else if type_id == TypeId::of::<f64>() {
+        "number"
+    } else if type_id == TypeId::of::<Duration>() {
+        "duration"
+    } else if type_id == TypeId::of::<PathBuf>() {
+        "path"
+    } else {
+        "string"
+    }
+}
+
+fn describe_arg(arg: &Arg) -> ArgInfo {
+    let help = arg.get_long_help().or(arg.get_help()).map(ToString::to_string);
+    ArgInfo {
+        name: arg.get_id().to_string(),
+        long: arg.get_long().map(str::to_string),
+        short: arg.get_short(),
+        aliases: arg.get_visible_aliases().unwrap_or_default().into_iter().map(str::to_string).collect(),
+        positional: arg.is_positional(),
+        kind: kind(arg),
+        value_name: arg
+            .get_value_names()
+            .filter(|_| arg.get_action().takes_values())
+            .map(|names| names.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")),
+        possible_values: arg
+            .get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect(),
+        multiple: matches!(arg.get_action(), ArgAction::Append)
+            || arg.get_num_args().is_some_and(|range| range.max_values() > 1),
+        required: arg.is_required_set(),
+        global: arg.is_global_set(),
+        default: arg.get_default_values().iter().map(|value| value.to_string_lossy().into_owned()).collect(),
+        help,
+    }
+}
+
+fn describe(command: &Command, parent: Option<&str>) -> CommandInfo {
+    let path = match parent {
+        Some(parent) => format!("{} {}", parent, command.get_name()),
+        None => command.get_name().to_string(),
+    };
+    CommandInfo {
+        name: command.get_name().to_string(),
+        aliases: command.get_visible_aliases().map(str::to_string).collect(),
+        about: command.get_long_about().or(command.get_about()).map(ToString::to_string),
+        args: command.get_arguments().filter(|arg| !arg.is_hide_set()).map(describe_arg).collect(),
+        subcommands: command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .map(|subcommand| describe(subcommand, Some(&path)))
+            .collect(),
+        path,
+    }
+}
+
+/// Every command below COMMAND, depth first.
+fn flatten<'a>(command: &'a CommandInfo, commands: &mut Vec<&'a CommandInfo>) {
+    commands.push(command);
+    for subcommand in &command.subcommands {
+        flatten(subcommand, commands);
+    }
+}
+
+/// Prints the commands, their arguments and the configuration schema: as JSON with
+/// JSON (for editors, GUIs and completion engines), otherwise as a table of commands.
+pub fn run(json: bool) -> Result<()> {
+    let command = i18n::localize(Args::command());
+    let introspection = Introspection {
+        name: command.get_name().to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        command: describe(&command, None),
+        config_schema: schema::json_schema(SchemaKind::Config),
+    };
+    if json || output::format() == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&introspection)?);
+        return Ok(());
+    }
+    let mut commands = Vec::new();
+    flatten(&introspection.command, &mut commands);
+    let mut table = Table::new(&[("COMMAND", "command"), ("OPTIONS", "options"), ("DESCRIPTION", "description")]);
+    for info in commands {
+        let options = info.args.iter().filter(|arg| !arg.positional).count();
+        let about = info.about.as_deref().and_then(|about| about.lines().next()).unwrap_or_default();
+        table.push(vec![json!(info.path), json!(options), json!(about)]);
+    }
+    output::renderer()?.print(&table, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments_are_described_with_their_types() {
+        let command = describe(&Args::command(), None);
+        let send = command.subcommands.iter().find(|subcommand| subcommand.name == "send").unwrap();
+        let arg = |long: &str| send.args.iter().find(|arg| arg.long.as_deref() == Some(long)).unwrap();
+        assert_eq!(arg("insecure").kind, "boolean");
+        assert_eq!(arg("timeout").kind, "integer");
+        assert_eq!(arg("timeout").default, ["30"]);
+        assert!(arg("header").multiple);
+        let history = command.subcommands.iter().find(|subcommand| subcommand.name == "history").unwrap();
+        assert!(history.subcommands.iter().any(|subcommand| subcommand.path == "ferrapi_tester history show"));
+    }
+}
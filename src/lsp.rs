@@ -0,0 +1,429 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+use url::Url;
+
+use crate::mount;
+use crate::namespace;
+use crate::schema::{self, SchemaKind};
+use crate::vars::{self, ENV_PREFIX, ENVS_DIR};
+use crate::get_default_dir;
+
+/// DiagnosticSeverity of the protocol.
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+/// A `{{name}}` placeholder: the byte range of NAME in the document.
+struct Placeholder {
+    start: usize,
+    end: usize,
+    name: String,
+}
+
+/// Where a variable is given a value.
+struct Definition {
+    name: String,
+    value: String,
+    uri: Url,
+    range: Value,
+}
+
+fn placeholders(text: &str) -> Vec<Placeholder> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("{{").map(|i| from + i) {
+        let Some(len) = text[start..].find("}}") else {
+            break;
+        };
+        let inner = &text[start + 2..start + len];
+        let name = inner.trim();
+        if vars::is_variable(name) {
+            let start = start + 2 + inner.len() - inner.trim_start().len();
+            found.push(Placeholder { start, end: start + name.len(), name: name.to_string() });
+        }
+        from = start + len + 2;
+    }
+    found
+}
+
+/// Position (line, UTF-16 column) of the byte OFFSET of TEXT.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({"line": before.matches('\n').count(), "character": before[line_start..].encode_utf16().count()})
+}
+
+/// Byte offset of the POSITION of a request in TEXT.
+fn offset(text: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let line_start = match line {
+        0 => 0,
+        _ => text.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(text.len())
+}
+
+fn range(text: &str, start: usize, end: usize) -> Value {
+    json!({"start": position(text, start), "end": position(text, end)})
+}
+
+fn diagnostic(text: &str, (start, end): (usize, usize), severity: u8, message: &str) -> Value {
+    json!({"range": range(text, start, end), "severity": severity, "source": "ferrapi_tester", "message": message})
+}
+
+/// Byte range of the first KEY at or after FROM, as a JSON or YAML key.
+fn find_key(text: &str, key: &str, from: usize) -> Option<(usize, usize)> {
+    [format!("\"{}\"", key), format!("{}:", key)]
+        .iter()
+        .filter_map(|needle| text[from..].find(needle.as_str()).map(|i| (from + i, from + i + needle.len())))
+        .min()
+}
+
+/// Range to underline for a schema violation at PATH (e.g. ".steps[0].url"): the keys of
+/// the path searched one after another, then the unknown property named in MESSAGE.
+fn locate(text: &str, path: &str, message: &str) -> (usize, usize) {
+    let mut keys: Vec<&str> = path.split(['.', '[']).filter(|key| !key.is_empty() && !key.ends_with(']')).collect();
+    keys.extend(message.split("unknown property \"").nth(1).and_then(|rest| rest.split('"').next()));
+    let mut found = (0, 0);
+    for key in keys {
+        match find_key(text, key, found.1) {
+            Some(range) => found = range,
+            None => break,
+        }
+    }
+    found
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml")
+}
+
+/// Parses TEXT as YAML or JSON; errors carry the 1-based line they were found on.
+fn parse(path: &Path, text: &str) -> Result<Value, (usize, String)> {
+    if is_yaml(path) {
+        serde_yaml::from_str(text).map_err(|e| (e.location().map_or(1, |location| location.line()), e.to_string()))
+    } else {
+        serde_json::from_str(text).map_err(|e| (e.line(), e.to_string()))
+    }
+}
+
+/// The kind of file at PATH: environments and vars.json by name, saved configurations by
+/// their METHOD.json name, and transaction and chain files by their steps.
+fn kind_of(path: &Path, document: &Value) -> Option<SchemaKind> {
+    let name = path.file_name()?.to_string_lossy();
+    let in_envs = path.parent().and_then(Path::file_name).is_some_and(|dir| dir == ENVS_DIR);
+    if name == vars::VARS_FILE || (in_envs && name.ends_with(".json")) {
+        return Some(SchemaKind::Env);
+    }
+    if namespace::is_config_file(&name) {
+        return Some(SchemaKind::Config);
+    }
+    let steps = document.get("steps")?.as_array()?;
+    // トランザクションファイルは JSON のみで、vars と extract はチェーンファイルにしかない
+    let extracts = steps.iter().any(|step| step.get("extract").is_some());
+    let chain = is_yaml(path) || document.get("vars").is_some() || extracts;
+    Some(if chain { SchemaKind::Chain } else { SchemaKind::Transaction })
+}
+
+/// TARGET of a saved configuration at PATH in the base directory or a mount.
+fn target_of(path: &Path) -> Option<String> {
+    let dir = path.parent()?;
+    let roots = get_default_dir().into_iter().chain(mount::roots().unwrap_or_default());
+    for root in roots {
+        let Ok(relative) = dir.strip_prefix(&root) else {
+            continue;
+        };
+        let mut current = root.clone();
+        let mut names = Vec::new();
+        for component in relative.components() {
+            current.push(component);
+            names.push(namespace::display_name(&current));
+        }
+        if !names.is_empty() {
+            return Some(namespace::format_target(&names));
+        }
+    }
+    None
+}
+
+/// Targets whose variables a document sees: its own for a configuration, those of the
+/// steps for transaction and chain files.
+fn targets(path: &Path, kind: SchemaKind, document: &Value) -> Vec<Option<String>> {
+    match kind {
+        SchemaKind::Config => vec![target_of(path)],
+        SchemaKind::Env => Vec::new(),
+        SchemaKind::Transaction | SchemaKind::Chain => {
+            let steps = document["steps"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let calls = steps.iter().flat_map(|step| [step, &step["compensate"]]);
+            let mut targets: Vec<Option<String>> =
+                calls.filter_map(|call| call["target"].as_str()).map(|target| Some(target.to_string())).collect();
+            targets.push(None);
+            targets
+        }
+    }
+}
+
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Variables defined by the keys of OBJECT in TEXT, the contents of URI.
+fn keys_of(object: &Value, text: &str, uri: &Url, from: usize, definitions: &mut Vec<Definition>) {
+    for (name, value) in object.as_object().into_iter().flatten() {
+        let (start, end) = find_key(text, name, from).unwrap_or((0, 0));
+        let range = range(text, start, end);
+        definitions.push(Definition { name: name.clone(), value: text_value(value), uri: uri.clone(), range });
+    }
+}
+
+/// Every definition of the variables a document sees, lowest precedence first: vars.json and
+/// environment files, then the `vars` and `extract` entries of a chain file itself.
+fn definitions(uri: &Url, text: &str, kind: SchemaKind, document: &Value, path: &Path) -> Vec<Definition> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for target in targets(path, kind, document) {
+        for file in vars::definition_files(target.as_deref()).unwrap_or_default() {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    let mut found = Vec::new();
+    for file in files {
+        let (Ok(content), Ok(file_uri)) = (fs::read_to_string(&file), Url::from_file_path(&file)) else {
+            continue;
+        };
+        if let Ok(object) = serde_json::from_str::<Value>(&content) {
+            keys_of(&object, &content, &file_uri, 0, &mut found);
+        }
+    }
+    if kind == SchemaKind::Chain {
+        keys_of(&document["vars"], text, uri, find_key(text, "vars", 0).map_or(0, |(_, end)| end), &mut found);
+        let mut from = 0;
+        for step in document["steps"].as_array().into_iter().flatten() {
+            from = find_key(text, "extract", from).map_or(from, |(_, end)| end);
+            for (name, path) in step["extract"].as_object().into_iter().flatten() {
+                let (start, end) = find_key(text, name, from).unwrap_or((0, 0));
+                let value = format!("extracted from the response: {}", text_value(path));
+                found.push(Definition { name: name.clone(), value, uri: uri.clone(), range: range(text, start, end) });
+            }
+        }
+    }
+    found
+}
+
+/// Diagnostics of a document: parse errors, schema violations and undefined variables.
+fn diagnose(path: &Path, uri: &Url, text: &str) -> Vec<Value> {
+    let document = match parse(path, text) {
+        Ok(document) => document,
+        Err((line, message)) => {
+            let start = offset(text, &json!({"line": line.saturating_sub(1), "character": 0})).unwrap_or(0);
+            let end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+            return vec![diagnostic(text, (start, end), ERROR, &message)];
+        }
+    };
+    let Some(kind) = kind_of(path, &document) else {
+        return Vec::new();
+    };
+    let schema = serde_json::to_value(schema::json_schema(kind)).unwrap_or_default();
+    let mut diagnostics: Vec<Value> = schema::validate(&schema, &document)
+        .iter()
+        .map(|error| {
+            let (at, message) = error.split_once(": ").unwrap_or((".", error));
+            diagnostic(text, locate(text, at, message), ERROR, error)
+        })
+        .collect();
+    if kind != SchemaKind::Env {
+        let defined: Vec<String> = definitions(uri, text, kind, &document, path).into_iter().map(|d| d.name).collect();
+        let from_env = |name: &str| env::var_os(format!("{}{}", ENV_PREFIX, name)).is_some();
+        for placeholder in placeholders(text) {
+            if !defined.contains(&placeholder.name) && !from_env(&placeholder.name) {
+                let message = format!(
+                    "Variable '{}' is not defined in vars.json or an environment file (pass it with --var)",
+                    placeholder.name
+                );
+                diagnostics.push(diagnostic(text, (placeholder.start, placeholder.end), WARNING, &message));
+            }
+        }
+    }
+    diagnostics
+}
+
+/// The placeholder at POSITION and its definitions.
+fn lookup(uri: &Url, text: &str, position: &Value) -> Option<(Placeholder, Vec<Definition>)> {
+    let path = uri.to_file_path().ok()?;
+    let at = offset(text, position)?;
+    let placeholder = placeholders(text).into_iter().find(|p| p.start <= at && at <= p.end)?;
+    let document = parse(&path, text).ok()?;
+    let kind = kind_of(&path, &document)?;
+    let definitions = definitions(uri, text, kind, &document, &path)
+        .into_iter()
+        .filter(|definition| definition.name == placeholder.name)
+        .collect();
+    Some((placeholder, definitions))
+}
+
+fn hover(uri: &Url, text: &str, position: &Value) -> Value {
+    let Some((placeholder, definitions)) = lookup(uri, text, position) else {
+        return Value::Null;
+    };
+    let mut lines = vec![format!("**{{{{{}}}}}**\n", placeholder.name)];
+    if let Ok(value) = env::var(format!("{}{}", ENV_PREFIX, placeholder.name)) {
+        lines.push(format!("- `{}` ({}{})", value, ENV_PREFIX, placeholder.name));
+    }
+    // 後に定義されたものほど優先されるので、優先度の高い順に並べる
+    for definition in definitions.iter().rev() {
+        let file = definition.uri.to_file_path().map(|path| path.display().to_string()).unwrap_or_default();
+        lines.push(format!("- `{}` ({})", definition.value, file));
+    }
+    if lines.len() == 1 {
+        lines.push("Not defined in vars.json or an environment file.".to_string());
+    }
+    json!({
+        "contents": {"kind": "markdown", "value": lines.join("\n")},
+        "range": range(text, placeholder.start, placeholder.end)
+    })
+}
+
+fn definition(uri: &Url, text: &str, position: &Value) -> Value {
+    let Some((_, definitions)) = lookup(uri, text, position) else {
+        return Value::Null;
+    };
+    let locations = definitions.iter().rev();
+    locations.map(|definition| json!({"uri": definition.uri.as_str(), "range": definition.range})).collect()
+}
+
+/// Reads one message; None at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(value.trim().parse::<usize>().context("Invalid Content-Length")?);
+            }
+        }
+    }
+    let Some(length) = length else {
+        bail!("Message without Content-Length");
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    Ok(output.flush()?)
+}
+
+fn publish(output: &mut impl Write, uri: &Url, diagnostics: Vec<Value>) -> Result<()> {
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri.as_str(), "diagnostics": diagnostics}
+        }),
+    )
+}
+
+/// Runs a language server on stdin and stdout until the client exits: it validates saved
+/// configurations, environment, transaction and chain files against their schemas, warns
+/// about undefined `{{variables}}`, and shows (hover) and jumps to (definition) their values.
+pub fn run() -> Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout().lock();
+    let mut documents: HashMap<Url, String> = HashMap::new();
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().and_then(|uri| Url::parse(uri).ok());
+        let result = match (method, uri) {
+            ("initialize", _) => json!({
+                "capabilities": {"textDocumentSync": 1, "hoverProvider": true, "definitionProvider": true},
+                "serverInfo": {"name": "ferrapi_tester", "version": env!("CARGO_PKG_VERSION")}
+            }),
+            ("shutdown", _) => Value::Null,
+            ("exit", _) => return Ok(()),
+            ("textDocument/didOpen", Some(uri)) | ("textDocument/didChange", Some(uri)) => {
+                // 全文同期なので、最後の変更が文書全体になる
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .or_else(|| params["contentChanges"].as_array()?.last()?["text"].as_str());
+                if let (Some(text), Ok(path)) = (text, uri.to_file_path()) {
+                    publish(&mut output, &uri, diagnose(&path, &uri, text))?;
+                    documents.insert(uri, text.to_string());
+                }
+                continue;
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                documents.remove(&uri);
+                publish(&mut output, &uri, Vec::new())?;
+                continue;
+            }
+            ("textDocument/hover", Some(uri)) => {
+                documents.get(&uri).map_or(Value::Null, |text| hover(&uri, text, &params["position"]))
+            }
+            ("textDocument/definition", Some(uri)) => {
+                documents.get(&uri).map_or(Value::Null, |text| definition(&uri, text, &params["position"]))
+            }
+            _ => {
+                // 未対応の通知は無視し、リクエストには MethodNotFound を返す
+                if message.get("id").is_some() {
+                    let error = json!({"code": -32601, "message": format!("Unsupported method {}", method)});
+                    write_message(&mut output, &json!({"jsonrpc": "2.0", "id": message["id"], "error": error}))?;
+                }
+                continue;
+            }
+        };
+        write_message(&mut output, &json!({"jsonrpc": "2.0", "id": message["id"], "result": result}))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_and_violations_are_located() {
+        let text = "{\n  \"steps\": [\n    { \"url\": \"{{ base_url }}/é/{{token}}\", \"expect_staus\": 200 }\n  ]\n}";
+        let found: Vec<&str> = placeholders(text).iter().map(|p| &text[p.start..p.end]).collect();
+        assert_eq!(found, ["base_url", "token"]);
+        let token = &placeholders(text)[1];
+        assert_eq!(position(text, token.start), json!({"line": 2, "character": 33}));
+        assert_eq!(offset(text, &json!({"line": 2, "character": 33})), Some(token.start));
+        let (start, end) = locate(text, ".steps[0]", "unknown property \"expect_staus\"");
+        assert_eq!(&text[start..end], "\"expect_staus\"");
+        let document = parse(Path::new("flow.json"), text).unwrap();
+        assert_eq!(kind_of(Path::new("flow.json"), &document), Some(SchemaKind::Transaction));
+        assert_eq!(kind_of(Path::new("flow.yaml"), &document), Some(SchemaKind::Chain));
+        assert_eq!(kind_of(Path::new("/h/.ferrapi_tester/envs/dev.json"), &json!({})), Some(SchemaKind::Env));
+        assert_eq!(kind_of(Path::new("/h/.ferrapi_tester/SystemA/GET.json"), &json!({})), Some(SchemaKind::Config));
+    }
+}
@@ -1,26 +1,114 @@
-use anyhow::{bail, Context, Result};
-use clap::{Parser, ValueHint};
-use dialoguer::{Select, Confirm};
+mod annotation;
+mod assertion;
+mod auth;
+mod autocomplete;
+mod budget;
+mod bundle;
+mod cancel;
+mod chain;
+mod compress;
+mod cookies;
+mod credential;
+mod curl;
+mod dashboard;
+mod diff;
+mod docs;
+mod doctor;
+mod exit_code;
+mod filter;
+mod form;
+mod generate;
+mod header_editor;
+mod history;
+mod history_db;
+mod hooks;
+mod i18n;
+mod init;
+mod introspect;
+mod lsp;
+mod mock;
+mod mount;
+mod namespace;
+mod net_error;
+mod notify;
+mod openapi;
+mod output;
+mod patch;
+mod pattern;
+mod proxy;
+mod queue;
+mod raw;
+mod report;
+mod request;
+mod request_log;
+mod retry;
+mod run_all;
+mod secret;
+mod secret_provider;
+mod saved;
+mod schema;
+mod self_update;
+mod send;
+mod session;
+mod settings;
+mod snapshot;
+mod stats;
+mod store;
+mod suggest;
+mod theme;
+mod throttle;
+mod tls;
+mod transaction;
+mod triage;
+mod vars;
+
+use anyhow::{bail, Result};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueHint};
+use dialoguer::{Confirm, Select};
 use directories::UserDirs;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
     fs,
-    path::PathBuf,
-    time::Duration,
+    path::{Path, PathBuf},
 };
 
+use auth::AuthCommand;
+use chain::ChainCommand;
+use credential::SecretCommand;
+use curl::ExportCommand;
+use docs::DocsCommand;
+use generate::NewCommand;
+use history::HistoryCommand;
+use i18n::Lang;
+use init::InitArgs;
+use mock::MockArgs;
+use net_error::NetworkError;
+use openapi::{ContractArgs, CoverageArgs, ImportCommand};
+use output::OutputFormat;
+use proxy::ProxyArgs;
+use queue::QueueCommand;
+use raw::RawArgs;
+use report::ReportArgs;
+use request::RequestArgs;
+use retry::RetryArgs;
+use run_all::RunAllArgs;
+use schema::SchemaCommand;
+use self_update::SelfUpdateArgs;
+use send::SendArgs;
+use session::ReplayArgs;
+use snapshot::ConfigCommand;
+use stats::StatsCommand;
+use vars::{EnvCommand, VarsCommand};
+
 /// Interactive mode for recursively selecting a namespace.
 fn interactive_select_namespace() -> Result<String> {
     let base_dir = get_default_dir()?;
     let mut current = base_dir.clone();
     loop {
-        let entries: Vec<PathBuf> = fs::read_dir(&current)?
+        let mut entries: Vec<PathBuf> = fs::read_dir(&current)?
             .filter_map(|entry| {
                 if let Ok(entry) = entry {
-                    if entry.file_type().ok()?.is_dir() {
+                    // .snapshots などの内部ディレクトリは名前空間として扱わない
+                    if entry.file_type().ok()?.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
                         Some(entry.path())
                     } else {
                         None
@@ -33,13 +121,11 @@ fn interactive_select_namespace() -> Result<String> {
         if entries.is_empty() {
             break;
         }
-        // 候補のディレクトリ名一覧（相対パス）
-        let mut candidates: Vec<String> = entries.iter()
-            .filter_map(|p| p.file_name().and_then(|os_str| os_str.to_str()).map(|s| s.to_string()))
-            .collect();
-        candidates.sort();
+        // 候補の名前空間名一覧（スラッグ化されたディレクトリは表示名で示す）
+        entries.sort_by_key(|p| namespace::display_name(p));
+        let candidates: Vec<String> = entries.iter().map(|p| namespace::display_name(p)).collect();
         let selection = Select::new()
-            .with_prompt(format!("Select a namespace in {}", current.display()))
+            .with_prompt(i18n::tf("Select a namespace in {}", &[&current.display()]))
             .items(&candidates)
             .default(0)
             .interact()?;
@@ -49,7 +135,8 @@ fn interactive_select_namespace() -> Result<String> {
         let sub_entries: Vec<PathBuf> = fs::read_dir(&current)?
             .filter_map(|entry| {
                 if let Ok(entry) = entry {
-                    if entry.file_type().ok()?.is_dir() {
+                    // .snapshots などの内部ディレクトリは名前空間として扱わない
+                    if entry.file_type().ok()?.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
                         Some(entry.path())
                     } else {
                         None
@@ -64,18 +151,21 @@ fn interactive_select_namespace() -> Result<String> {
         }
         // ユーザーに、さらに深い階層を選択するか確認
         if !Confirm::new()
-            .with_prompt("Do you want to select a subdirectory further?")
+            .with_prompt(i18n::t("Do you want to select a subdirectory further?"))
             .default(true)
             .interact()? {
             break;
         }
     }
-    // 最終的な選択結果を、base_dir からの相対パスとして返す
-    let rel = current.strip_prefix(&get_default_dir()?)
-        .unwrap_or(&current)
-        .to_string_lossy()
-        .to_string();
-    Ok(rel)
+    // 最終的な選択結果を、表示名をつないだ TARGET として返す
+    let mut names = Vec::new();
+    let mut dir = current.as_path();
+    while dir != base_dir {
+        names.push(namespace::display_name(dir));
+        dir = dir.parent().unwrap_or(&base_dir);
+    }
+    names.reverse();
+    Ok(namespace::format_target(&names))
 }
 
 /// Returns the default configuration directory (e.g., ~/.ferrapi_tester).
@@ -88,22 +178,11 @@ fn get_default_dir() -> Result<PathBuf> {
 }
 
 /// Constructs the configuration file path. Example: ~/.ferrapi_tester/SystemA/example/POST.json
-fn get_config_path(base_dir: &PathBuf, target: &str, method: &str) -> PathBuf {
+/// The method is checked here so that no caller can reach outside the namespace directory.
+fn get_config_path(base_dir: &Path, target: &str, method: &str) -> Result<PathBuf> {
+    request::parse_method(method).map_err(exit_code::config)?;
     let method_file = format!("{}.json", method.to_uppercase());
-    base_dir.join(target).join(method_file)
-}
-
-/// Parses header strings in "Key: Value" format into a HashMap.
-fn parse_headers(headers: &[String]) -> Result<HashMap<String, String>> {
-    let mut map = HashMap::new();
-    for header in headers {
-        let parts: Vec<&str> = header.splitn(2, ':').collect();
-        if parts.len() != 2 {
-            bail!("Invalid header format: {}", header);
-        }
-        map.insert(parts[0].trim().to_string(), parts[1].trim().to_string());
-    }
-    Ok(map)
+    Ok(namespace::dir(base_dir, target)?.join(method_file))
 }
 
 /// FerrAPI Tester - API testing CLI tool.
@@ -112,39 +191,36 @@ fn parse_headers(headers: &[String]) -> Result<HashMap<String, String>> {
 /// 必要に応じて設定を保存・読み込みして API のテストを行います。
 /// TARGET（名前空間）が指定されなければ、--url オプションのみで API を呼び出します。
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// HTTP メソッド (GET, POST, PUT, DELETE, etc.) [default: GET]
-    #[arg(short = 'X', long = "request", default_value = "GET")]
-    method: String,
-
-    /// ヘッダーの指定（例: -H "Content-Type: application/json"）
-    #[arg(short = 'H', long = "header")]
-    headers: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// リクエストボディの文字列（-d または -v で指定）
-    #[arg(short = 'd', long = "data")]
-    data: Option<String>,
+    #[command(flatten)]
+    send: SendArgs,
 
-    /// JSON 形式でのリクエストボディ（-v を使う場合、保存済み設定とマージします）
-    #[arg(short = 'v', long = "value", conflicts_with = "json")]
-    value: Option<String>,
+    /// すべてのリクエスト/レスポンスを JSON Lines 形式で追記するファイル
+    /// （settings.json の log_requests より優先されます）
+    #[arg(long = "log-requests", global = true, value_hint = ValueHint::FilePath)]
+    log_requests: Option<PathBuf>,
 
-    /// JSON 形式でのリクエストボディ（-j を使う場合、-v と競合します）
-    #[arg(short = 'j', long = "json", conflicts_with = "value")]
-    json: Option<String>,
+    /// 帯域を制限して低速なネットワークを再現します（例: 256kbps、1.5mbps、64KB/s）。
+    /// リクエストとレスポンスのボディの両方に適用されます
+    #[arg(long = "throttle", global = true, value_name = "RATE", value_parser = throttle::parse_rate)]
+    throttle: Option<u64>,
 
-    /// リクエスト先の URL。この URL は保存する際にも使用されます。
-    #[arg(short = 'u', long = "url")]
-    url: Option<String>,
+    /// 各リクエストの送信前に待機する遅延（例: 200ms、1s）
+    #[arg(long = "latency", global = true, value_parser = humantime::parse_duration)]
+    latency: Option<std::time::Duration>,
 
-    /// タイムアウト秒数（デフォルトは 30 秒）
-    #[arg(long = "timeout", default_value = "30")]
-    timeout: u64,
+    /// テンプレート変数（例: --var token=abc）。{{token}} のようなプレースホルダーを置換します。
+    /// 優先順位: --var > 環境変数 FERRAPI_VAR_* > --env の環境ファイル > 名前空間の vars.json > デフォルトの vars.json
+    #[arg(long = "var", global = true)]
+    vars: Vec<String>,
 
-    /// 現在のリクエスト設定を保存するフラグ
-    #[arg(short = 's', long = "save")]
-    save: bool,
+    /// 変数を読み込む環境（~/.ferrapi_tester/envs/NAME.json、例: --env dev）
+    #[arg(long = "env", global = true, value_name = "NAME")]
+    env: Option<String>,
 
     /// TARGET: 保存済み設定の名前空間パス（例: "SystemA/example"）。
     /// 省略された場合は、--url のみで API を呼び出します。
@@ -161,176 +237,470 @@ struct Args {
     #[arg(long = "delete-all")]
     delete_all: bool,
 
-    /// 対話モードで名前空間候補を表示して選択します。
-    #[arg(long = "comp")]
-    comp: bool,
-
     /// デフォルト設定ディレクトリを表示します。
     #[arg(long = "show-default-dir")]
     show_default_dir: bool,
+
+    /// 終了コードの一覧（0 成功、2 アサーション失敗、3 ネットワークエラー、4 設定エラー、5 認証エラーなど）を表示します。
+    #[arg(long = "list-exit-codes")]
+    list_exit_codes: bool,
+
+    /// レスポンスと実行結果の出力形式（human / table / minimal / csv / json / template）。
+    /// 省略時は settings.json の output_format、なければ human
+    #[arg(long = "output-format", value_enum, global = true)]
+    output_format: Option<OutputFormat>,
+
+    /// template 形式で使うテンプレート（例: '{{status}} {{.id}}'、結果の表では '{{target}} {{status}}' のように列名）
+    #[arg(long = "template", global = true)]
+    template: Option<String>,
+
+    /// 表示・メッセージの言語（省略時は FERRAPI_LANG や LANG などのロケールから判定）
+    #[arg(long = "lang", value_enum, global = true)]
+    lang: Option<Lang>,
+
+    /// 完了時にデスクトップ通知を表示します（--notify-after より時間がかかったリクエスト、run、run-all、chain など）。
+    /// 長時間かかるインポート処理などを実行して別のウィンドウで作業するときに便利です
+    #[arg(long = "notify", global = true)]
+    notify: bool,
+
+    /// --notify で通知する実行時間のしきい値（例: 30s、2m）
+    #[arg(
+        long = "notify-after",
+        global = true,
+        value_name = "DURATION",
+        default_value = "10s",
+        value_parser = humantime::parse_duration
+    )]
+    notify_after: std::time::Duration,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
-struct RequestConfig {
-    url: Option<String>,
-    method: Option<String>,
-    headers: Option<HashMap<String, String>>,
-    data: Option<Value>,
-    timeout: Option<u64>,
+/// Subcommands. Without one, the top-level flags send a single request.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// リクエストを送信します（サブコマンドを付けないトップレベルのフラグと同じ動作）。
+    Send {
+        /// 保存済み設定の名前空間パス（例: "SystemA/example"）。省略時は --url のみで呼び出します
+        #[arg(value_hint = ValueHint::DirPath)]
+        target: Option<String>,
+
+        #[command(flatten)]
+        args: Box<SendArgs>,
+    },
+    /// リクエストを送信せずに、設定を名前空間に保存します。
+    Save {
+        /// 保存先の名前空間パス（例: "SystemA/example"）
+        #[arg(value_hint = ValueHint::DirPath)]
+        target: String,
+
+        #[command(flatten)]
+        request: Box<RequestArgs>,
+
+        /// 保存の前にヘッダーを対話的に追加・編集・削除します。
+        #[arg(long = "edit-headers")]
+        edit_headers: bool,
+    },
+    /// 保存済み設定を一覧表示します。
+    List {
+        /// 一覧する名前空間（省略時はすべて）
+        namespace: Option<String>,
+    },
+    /// 保存済み設定、または名前空間全体を削除します。
+    Delete {
+        /// 削除する設定の名前空間パス
+        #[arg(value_hint = ValueHint::DirPath)]
+        target: String,
+
+        /// 削除する設定の HTTP メソッド
+        #[arg(short = 'X', long = "request", default_value = "GET")]
+        method: String,
+
+        /// 名前空間ディレクトリとその内容全体を削除します。
+        #[arg(long = "all", conflicts_with = "method")]
+        all: bool,
+    },
+    /// TARGET へのリクエストで使われる設定ファイルと、変数・設定・フックのファイルを優先順に表示します。
+    Which {
+        /// 調べる名前空間パス
+        #[arg(value_hint = ValueHint::DirPath)]
+        target: String,
+
+        /// 調べる設定の HTTP メソッド
+        #[arg(short = 'X', long = "request", default_value = "GET")]
+        method: String,
+    },
+    /// --env で選ぶ環境ファイル（~/.ferrapi_tester/envs/NAME.json）を操作します。
+    Env {
+        #[command(subcommand)]
+        action: EnvCommand,
+    },
+    /// 後で送信するリクエストのキューを操作します（add / list / flush / clear）
+    Queue {
+        #[command(subcommand)]
+        action: QueueCommand,
+    },
+    /// トランザクションファイルに定義されたリクエスト、または名前空間以下の保存済み設定をすべて実行します。
+    Run {
+        /// ステップ（と補償リクエスト）を定義した JSON ファイル、またはすべて実行する名前空間（例: SystemA）
+        #[arg(value_hint = ValueHint::AnyPath, required_unless_present = "request_file")]
+        file: Option<PathBuf>,
+
+        /// URL・ヘッダー・ボディ・アサーションを定義した 1 件のリクエスト（YAML または JSON）を送信します。
+        /// "-" を指定すると標準入力から読み込みます
+        #[arg(
+            short = 'f',
+            long = "file",
+            value_name = "FILE",
+            value_hint = ValueHint::FilePath,
+            conflicts_with_all = ["file", "rollback_on_failure", "filter", "concurrency"]
+        )]
+        request_file: Option<PathBuf>,
+
+        /// 途中のステップが失敗した場合、完了済みステップの補償リクエストを逆順に実行します。
+        #[arg(long = "rollback-on-failure")]
+        rollback_on_failure: bool,
+
+        /// 名前（例: "checkout/create order"）がパターンに一致するステップだけを実行します（複数指定可）。
+        /// ワイルドカードまたは正規表現で指定します
+        #[arg(long = "filter", value_name = "PATTERN")]
+        filter: Vec<String>,
+
+        /// 名前空間を実行する場合に同時に送信するリクエスト数（既定では 1 件ずつ順番に実行）
+        #[arg(long = "concurrency", default_value = "1")]
+        concurrency: usize,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+
+        #[command(flatten)]
+        coverage: CoverageArgs,
+    },
+    /// ログイン → 作成 → 取得のように、前のステップのレスポンスから取り出した値（JSONPath）を後続のステップで使うリクエストの連鎖を実行します。
+    Chain {
+        #[command(subcommand)]
+        action: ChainCommand,
+    },
+    /// SSO などの認証情報を取得して名前空間の変数に保存します。
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+    /// ローカルの HTTP(S) プロキシを起動し、通過したリクエストを設定ツリー・履歴として記録します。
+    Proxy(ProxyArgs),
+    /// proxy --record で記録したセッションを再生し、記録時のレスポンスと比較します。
+    Replay(ReplayArgs),
+    /// スタブファイルや記録済みセッションのレスポンスを返すモックサーバーを起動します（障害・遅延の注入も可能）。
+    Mock(MockArgs),
+    /// 履歴や記録済みセッションを OpenAPI 仕様と照合し、未文書化のエンドポイントや未使用の操作を報告します。
+    Contract(ContractArgs),
+    /// 名前空間のサブツリー以下の保存済み設定をすべて実行し、結果を表にまとめます。
+    RunAll(RunAllArgs),
+    /// 名前空間全体（設定・変数・その他のファイル）を 1 つの JSON バンドルとして、または保存済み設定を curl コマンド（export curl）として標準出力に書き出します。
+    #[command(args_conflicts_with_subcommands = true)]
+    Export {
+        #[command(subcommand)]
+        format: Option<ExportCommand>,
+
+        /// 書き出す名前空間（例: "SystemA"）
+        namespace: Option<String>,
+    },
+    /// export で作成したバンドル、OpenAPI 仕様（import openapi）、または curl コマンド（import curl）から設定を取り込みます。
+    #[command(args_conflicts_with_subcommands = true)]
+    Import {
+        #[command(subcommand)]
+        source: Option<ImportCommand>,
+
+        /// バンドルファイル
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: Option<PathBuf>,
+
+        /// 取り込み先の名前空間（省略時はバンドルに記録された名前空間）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+
+        /// 内容が異なる既存ファイルを上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// 設定ディレクトリの管理（スナップショットなど）を行います。
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// リクエスト履歴（history.jsonl）を管理します。
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+    /// 履歴からエンドポイントごとの成功率・レイテンシ傾向・頻出エラーを集計します。
+    #[command(args_conflicts_with_subcommands = true)]
+    Stats {
+        #[command(subcommand)]
+        action: Option<StatsCommand>,
+
+        /// 集計対象の名前空間または URL の接頭辞（省略時はすべて）
+        target: Option<String>,
+
+        /// 集計期間（例: 7d, 12h, 30m）
+        #[arg(long = "since", value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+    },
+    /// 名前空間ごとのテンプレート変数（vars.json）を操作します。
+    Vars {
+        #[command(subcommand)]
+        action: VarsCommand,
+    },
+    /// リクエストを送信し、リクエスト・レスポンス・所要時間・環境情報を秘密情報を伏せて ZIP にまとめます（バグ報告用）。
+    Report(ReportArgs),
+    /// 重複した Content-Length や不正なチャンクサイズなど、意図的に不正なリクエストを TCP/TLS で直接送信します（--unsafe-raw が必要）。
+    Raw(RawArgs),
+    /// 資格情報ストア（キーチェーン・暗号化ファイル・環境変数）のシークレットを操作します。
+    Secret {
+        #[command(subcommand)]
+        action: SecretCommand,
+    },
+    /// API のスタイル（REST CRUD・GraphQL・Webhook）ごとの保存済みリクエスト一式を生成します。
+    New {
+        #[command(subcommand)]
+        kind: NewCommand,
+    },
+    /// 保存済み設定から API リファレンスを生成します。
+    Docs {
+        #[command(subcommand)]
+        action: DocsCommand,
+    },
+    /// 初回セットアップ: ベースディレクトリ、設定ファイル、例の名前空間と変数を作成し、シェル補完をインストールします。
+    Init(InitArgs),
+    /// 設定ディレクトリ・設定ファイル・プロキシ・TLS・接続先への到達性などを診断し、対処方法を表示します。
+    Doctor {
+        /// 保存済み URL への接続確認を行いません。
+        #[arg(long = "offline")]
+        offline: bool,
+    },
+    /// シェル補完スクリプトを標準出力に書き出します。
+    Completions {
+        /// 対象のシェル
+        shell: clap_complete::Shell,
+    },
+    /// コマンド・フラグ・値の型と設定ファイルのスキーマを出力します（エディタや GUI、補完エンジンとの連携向け）。
+    Introspect {
+        /// JSON で出力します
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// 設定ファイル・環境ファイル・トランザクション/チェーンファイルを検証する簡易な言語サーバーを標準入出力で起動します。
+    /// {{変数}} の未定義の警告、値のホバー表示、定義へのジャンプに対応します
+    Lsp,
+    /// 設定ファイルや環境ファイルなどの JSON Schema を扱います。
+    Schema {
+        #[command(subcommand)]
+        action: SchemaCommand,
+    },
+    /// GitHub の最新リリースを確認し、チェックサムを検証したうえで実行ファイルを置き換えます。
+    SelfUpdate(SelfUpdateArgs),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut args = Args::parse();
-
-    // --show-default-dir が指定された場合、デフォルト設定ディレクトリを表示して終了
-    if args.show_default_dir {
-        let dir = get_default_dir()?;
-        println!("Default configuration directory: {:?}", dir);
+    let started = std::time::Instant::now();
+    cancel::install();
+    // ヘルプを翻訳してから解析するため、--lang は clap より先に読み取る
+    i18n::set(i18n::detect(std::env::args_os()));
+    let matches = i18n::localize(Args::command()).try_get_matches();
+    // 通知に表示するコマンド名（サブコマンドなしはトップレベルのフラグによる送信）
+    let command = matches.as_ref().ok().and_then(|m| m.subcommand_name()).unwrap_or("send").to_string();
+    let args = matches.and_then(|matches| Args::from_arg_matches(&matches)).unwrap_or_else(|e| {
+        // clap の既定（2）はアサーション失敗と重なるため、使い方の誤りは EX_USAGE で終了する
+        let _ = e.print();
+        std::process::exit(if e.use_stderr() { exit_code::USAGE } else { exit_code::SUCCESS })
+    });
+    if let Some(lang) = args.lang {
+        i18n::set(lang);
+    }
+    // 列と出力先は単発リクエストの送信時（トップレベルのフラグまたは send）にだけ指定できる
+    let send = match args.command {
+        Some(Command::Send { ref args, .. }) => Some(args.as_ref()),
+        Some(_) => None,
+        None => Some(&args.send),
+    };
+    let format = args.output_format.or(send.and_then(|s| s.format));
+    let columns = send.map(|s| s.columns.clone()).unwrap_or_default();
+    output::select(format, args.template.clone(), columns, send.is_some_and(|s| s.include));
+    theme::configure(send.is_some_and(|s| s.output.is_some()));
+    let json_errors = output::format() == OutputFormat::Json;
+    let notify_after = args.notify.then_some(args.notify_after);
+    let result = run(args).await;
+    budget::report();
+    if let Some(after) = notify_after {
+        notify::finished(&command, started.elapsed(), after, &result);
+    }
+    if result.is_err() || cancel::is_cancelled() {
+        // 対話プロンプトが中断された場合でも端末の状態（カーソル表示）を戻す
+        cancel::restore_terminal();
+    }
+    let Err(err) = result else {
+        if cancel::is_cancelled() {
+            std::process::exit(cancel::EXIT_INTERRUPTED);
+        }
         return Ok(());
+    };
+    let exit_code = exit_code::of(&err);
+    if json_errors {
+        // 自動化向けに、失敗も標準出力へ JSON で出力する
+        println!("{}", serde_json::to_string_pretty(&output::error_report(&err, exit_code))?);
+    } else if cancel::is_cancelled() {
+        eprintln!("{}{}", i18n::t("Error: "), err);
+    } else {
+        eprintln!("{}{:?}", i18n::t("Error: "), err);
+        // ネットワークエラーには原因別の対処方法を添える
+        if let Some(hint) = err.downcast_ref::<NetworkError>().and_then(NetworkError::hint) {
+            eprintln!("\n{}{}", i18n::t("Hint: "), i18n::t(hint));
+        }
     }
+    std::process::exit(exit_code);
+}
 
-    // --comp オプションが指定された場合、対話モードで名前空間を選択
-    if args.comp {
-        let selected = interactive_select_namespace()?;
-        println!("Selected namespace: {}", selected);
-        args.target = Some(selected);
+async fn run(mut args: Args) -> Result<()> {
+    if let Some(path) = args.log_requests.take() {
+        request_log::set_override(path);
+    }
+    vars::set_cli_vars(vars::parse_cli_vars(&args.vars).map_err(exit_code::config)?);
+    if let Some(ref env) = args.env {
+        vars::select_env(env).map_err(exit_code::config)?;
     }
+    throttle::set(throttle::Throttle { bytes_per_sec: args.throttle, latency: args.latency });
 
-    // --delete-all オプションが指定された場合、TARGET に対応するディレクトリ全体を削除して終了
-    if args.delete_all {
-        if let Some(ref target) = args.target {
-            let base_dir = get_default_dir()?;
-            let namespace_dir = base_dir.join(target);
-            if namespace_dir.exists() {
-                fs::remove_dir_all(&namespace_dir)
-                    .with_context(|| format!("Failed to delete namespace directory {:?}", namespace_dir))?;
-                println!("Namespace directory {:?} deleted.", namespace_dir);
-            } else {
-                println!("No namespace directory found at {:?}", namespace_dir);
+    if let Some(command) = args.command {
+        return match command {
+            Command::Send { target, args } => send::run(*args, target).await,
+            Command::Save { target, request, edit_headers } => {
+                let target = namespace::normalize(&target)?;
+                let mut config = request::resolve(&request, Some(&target))?;
+                if edit_headers {
+                    let sources = autocomplete::Sources::load();
+                    header_editor::edit(config.headers.get_or_insert_with(Default::default), &sources)?;
+                }
+                // 保存前にアサーションの構文を確認する
+                assertion::parse_all(config.assertions.as_deref().unwrap_or_default())?;
+                saved::save(&target, &request.method, &config)
             }
-            return Ok(());
-        } else {
-            bail!("--delete-all requires TARGET to be specified.");
-        }
-    }
-    // --delete オプションが指定された場合（ファイル単位の削除）
-    if args.delete {
-        if let Some(ref target) = args.target {
-            let base_dir = get_default_dir()?;
-            let config_path = get_config_path(&base_dir, target, &args.method);
-            if config_path.exists() {
-                fs::remove_file(&config_path)
-                    .with_context(|| format!("Failed to delete configuration at {:?}", config_path))?;
-                println!("Configuration at {:?} deleted.", config_path);
-            } else {
-                println!("No configuration found at {:?}", config_path);
+            Command::List { namespace } => saved::list(namespace.as_deref()),
+            Command::Which { target, method } => mount::which(&namespace::normalize(&target)?, &method),
+            Command::Delete { target, method, all } => {
+                let target = namespace::normalize(&target)?;
+                if all {
+                    saved::delete_namespace(&target)
+                } else {
+                    saved::delete(&target, &method)
+                }
             }
-            return Ok(());
-        } else {
-            bail!("--delete requires TARGET to be specified.");
-        }
+            Command::Env { action } => vars::run_env(action),
+            Command::Queue { action } => queue::run(action).await,
+            Command::Run { file, request_file, rollback_on_failure, filter, concurrency, retry, coverage } => {
+                let Some(file) = file else {
+                    let policy = retry.policy();
+                    let request = transaction::run_one(request_file.as_deref().unwrap_or(Path::new("-")), &policy);
+                    return openapi::with_coverage(&coverage, None, request).await;
+                };
+                // ファイルでなく名前空間が指定された場合は、その下の保存済み設定を run-all と同様に実行する
+                if let Some(target) = file.to_str().filter(|_| !file.is_file()) {
+                    if namespace::dir(&get_default_dir()?, target).is_ok_and(|dir| dir.is_dir()) {
+                        if rollback_on_failure {
+                            bail!("--rollback-on-failure only applies to transaction files");
+                        }
+                        let args = RunAllArgs { filter, concurrency, retry, coverage, ..RunAllArgs::for_target(target)? };
+                        return run_all::run(args).await;
+                    }
+                }
+                let policy = retry.policy();
+                let steps = pattern::NameFilter::parse(&filter)?;
+                let transaction = transaction::run(&file, rollback_on_failure, &steps, &policy);
+                openapi::with_coverage(&coverage, None, transaction).await
+            }
+            Command::RunAll(run_all) => run_all::run(run_all).await,
+            Command::Chain { action } => chain::run(action).await,
+            Command::Auth { action } => auth::run(action).await.map_err(exit_code::auth),
+            Command::Proxy(proxy) => proxy::run(proxy).await,
+            Command::Replay(replay) => session::replay(replay).await,
+            Command::Mock(mock) => mock::run(mock).await,
+            Command::Contract(contract) => openapi::contract(contract),
+            Command::Vars { action } => vars::run(action),
+            Command::Secret { action } => credential::run(action).map_err(exit_code::auth),
+            Command::Report(report) => report::run(report).await,
+            Command::Raw(raw) => raw::run(raw).await,
+            Command::SelfUpdate(update) => self_update::run(update).await,
+            Command::Init(init) => init::run(init),
+            Command::New { kind } => generate::run(kind),
+            Command::Docs { action } => docs::run(action),
+            Command::Doctor { offline } => doctor::run(offline).await,
+            Command::Completions { shell } => {
+                init::print_completions(shell);
+                Ok(())
+            }
+            Command::Introspect { json } => introspect::run(json),
+            Command::Lsp => lsp::run(),
+            Command::Schema { action } => schema::run(action),
+            Command::Config { action } => snapshot::run(action),
+            Command::History { action } => history::run(action).await,
+            Command::Stats { action: Some(action), .. } => stats::run_command(action),
+            Command::Stats { action: None, target, since } => stats::run(target.as_deref(), since),
+            Command::Export { format: Some(ExportCommand::Curl { target, method }), .. } => curl::export(&target, &method),
+            Command::Export { format: None, namespace } => {
+                let Some(namespace) = namespace else {
+                    bail!("Specify a namespace, or `export curl TARGET --method METHOD`");
+                };
+                let bundle = bundle::export(&namespace)?;
+                println!("{}", serde_json::to_string_pretty(&bundle)?);
+                Ok(())
+            }
+            Command::Import { source: Some(source), .. } => openapi::import(source),
+            Command::Import { source: None, file, namespace, force } => {
+                let Some(file) = file else {
+                    bail!("Specify a bundle file, `import openapi SPEC --namespace NAMESPACE`, or `import curl COMMAND`");
+                };
+                bundle::import(&bundle::read(&file)?, namespace.as_deref(), force)
+            }
+        };
     }
 
-    // 通常の API 呼び出しモード
-    // TARGET が指定されている場合は保存／読み込みモード、指定がない場合は --url のみで実行
-    let use_saved_config = args.target.is_some();
-    let target_is_url = args
-        .target
-        .as_ref()
-        .map(|t| t.starts_with("http"))
-        .unwrap_or(false);
-    let url_to_use = if let Some(url) = args.url {
-        url
-    } else if target_is_url {
-        args.target.clone().unwrap_or_default()
-    } else {
-        String::new()
-    };
-
-    let mut config = if use_saved_config && !target_is_url {
-        let base_dir = get_default_dir()?;
-        let config_path = get_config_path(&base_dir, args.target.as_ref().unwrap(), &args.method);
-        if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .with_context(|| format!("Failed to read config from {:?}", config_path))?;
-            serde_json::from_str::<RequestConfig>(&content)
-                .with_context(|| "Failed to parse saved configuration")?
-        } else {
-            RequestConfig::default()
-        }
-    } else {
-        RequestConfig::default()
-    };
-
-    config.method = Some(args.method.to_uppercase());
-    if !url_to_use.is_empty() {
-        config.url = Some(url_to_use);
+    if args.list_exit_codes {
+        return exit_code::list();
     }
-    let cli_headers = parse_headers(&args.headers)?;
-    if let Some(ref mut saved_headers) = config.headers {
-        saved_headers.extend(cli_headers);
-    } else {
-        config.headers = Some(cli_headers);
-    }
-    if let Some(ref val) = args.value {
-        match serde_json::from_str::<Value>(val) {
-            Ok(v) => config.data = Some(v),
-            Err(_) => config.data = Some(json!(val)),
-        }
-    } else if let Some(ref j) = args.json {
-        match serde_json::from_str::<Value>(j) {
-            Ok(v) => config.data = Some(v),
-            Err(_) => config.data = Some(json!(j)),
-        }
-    } else if let Some(ref data) = args.data {
-        config.data = Some(json!(data));
+
+    // --show-default-dir が指定された場合、デフォルト設定ディレクトリを表示して終了
+    if args.show_default_dir {
+        let dir = get_default_dir()?;
+        println!("{}", i18n::tf("Default configuration directory: {}", &[&format!("{:?}", dir)]));
+        return Ok(());
     }
-    config.timeout = Some(args.timeout);
-
-    if args.save {
-        if let Some(ref target) = args.target {
-            let base_dir = get_default_dir()?;
-            let config_path = get_config_path(&base_dir, target, &args.method);
-            if let Some(parent) = config_path.parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("Failed to create directory {:?}", parent))?;
-            }
-            let serialized = serde_json::to_string_pretty(&config)
-                .with_context(|| "Failed to serialize configuration")?;
-            fs::write(&config_path, serialized)
-                .with_context(|| format!("Failed to write configuration to {:?}", config_path))?;
-            println!("Configuration saved to {:?}", config_path);
+
+    if args.delete_all || args.delete {
+        let Some(target) = send::select_target(args.send.comp, args.target)? else {
+            bail!("{} requires TARGET to be specified.", if args.delete_all { "--delete-all" } else { "--delete" });
+        };
+        // --delete-all は TARGET に対応するディレクトリ全体、--delete はファイル単位で削除する
+        return if args.delete_all {
+            saved::delete_namespace(&target)
         } else {
-            println!("--save is ignored because TARGET is not specified.");
-        }
+            saved::delete(&target, &args.send.request.method)
+        };
     }
 
-    let url = config.url.as_ref().context("URL is not specified")?;
-    let client = Client::builder()
-        .timeout(Duration::from_secs(config.timeout.unwrap_or(30)))
-        .build()?;
-    let mut request_builder = match config.method.as_deref() {
-        Some("GET") => client.get(url),
-        Some("POST") => client.post(url),
-        Some("PUT") => client.put(url),
-        Some("DELETE") => client.delete(url),
-        Some(other) => bail!("Unsupported HTTP method: {}", other),
-        None => bail!("HTTP method is not specified"),
-    };
-    if let Some(headers) = config.headers {
-        for (key, value) in headers {
-            request_builder = request_builder.header(key, value);
+    // 通常の API 呼び出しモード
+    send::run(args.send, args.target).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods_cannot_leave_the_namespace_directory() {
+        let base_dir = Path::new("/tmp/ferrapi");
+        let path = get_config_path(base_dir, "SystemA/users", "post").unwrap();
+        assert_eq!(path, base_dir.join("SystemA").join("users").join("POST.json"));
+        for method in ["../../victim", "GET/../..", ""] {
+            let err = get_config_path(base_dir, "SystemA/users", method).unwrap_err();
+            assert_eq!(exit_code::of(&err), exit_code::CONFIG);
         }
     }
-    if let Some(data) = config.data {
-        request_builder = request_builder.json(&data);
-    }
-    let response = request_builder.send().await?;
-    let status = response.status();
-    let text = response.text().await?;
-    println!("Response Status: {}", status);
-    println!("Response Body:\n{}", text);
-
-    Ok(())
 }
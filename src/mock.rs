@@ -0,0 +1,1011 @@
+use anyhow::{bail, Context, Result};
+use hyper::{
+    body::Bytes,
+    server::conn::Http,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode, Uri,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::net::TcpListener;
+use tokio_native_tls::{native_tls, TlsAcceptor};
+
+use crate::cancel;
+use crate::filter::Filter;
+use crate::i18n;
+use crate::net_error::NetworkError;
+use crate::proxy::{Authority, HOP_BY_HOP};
+use crate::request::RequestConfig;
+use crate::session::{self, SessionEntry};
+
+/// Options of `mock`.
+#[derive(clap::Args, Debug)]
+pub struct MockArgs {
+    /// 応答に使う記録済みセッション（パス、または ~/.ferrapi_tester/.sessions 内の名前）
+    #[arg(required_unless_present_any = ["stubs", "record_through"])]
+    session: Option<String>,
+
+    /// マッチング条件と応答テンプレートを定義したスタブファイル（YAML/JSON、複数指定可）
+    #[arg(long = "stubs", value_name = "FILE")]
+    stubs: Vec<PathBuf>,
+
+    /// 待ち受けるポート
+    #[arg(long = "port", default_value = "8080")]
+    port: u16,
+
+    /// スタブのないリクエストをこのバックエンド（例: https://api.example.com）へ転送し、
+    /// 応答をセッションに記録して次回からのスタブにします
+    #[arg(long = "record-through", value_name = "URL", value_parser = parse_upstream)]
+    record_through: Option<reqwest::Url>,
+
+    /// 障害を注入するリクエストの割合（例: 10%、0.1）
+    #[arg(long = "fail-rate", value_name = "RATE", value_parser = parse_rate, default_value = "0")]
+    fail_rate: f64,
+
+    /// 注入する障害のステータスコード
+    #[arg(long = "fail-status", default_value = "503")]
+    fail_status: u16,
+
+    /// 応答前にランダムに待機する時間の範囲（例: 0-2s、100ms-500ms）
+    #[arg(long = "delay-jitter", value_name = "RANGE", value_parser = parse_jitter)]
+    delay_jitter: Option<(Duration, Duration)>,
+
+    /// 障害を注入するリクエストでは、エラーを返す代わりに応答せず接続を切断します
+    #[arg(long = "drop-connections")]
+    drop_connections: bool,
+
+    /// 記録時の応答時間だけ待ってから記録済みの応答を返します。
+    /// 倍率を指定すると時間を伸縮します（例: 0.5 で半分、2 で倍）
+    #[arg(long = "replay-latency", value_name = "SCALE", num_args = 0..=1, default_missing_value = "1", value_parser = parse_scale)]
+    replay_latency: Option<f64>,
+
+    /// HTTPS で待ち受けます（ローカル CA で署名した localhost 用の証明書を自動生成します）
+    #[arg(long = "tls")]
+    tls: bool,
+
+    /// HTTPS に使う証明書ファイル（PEM、--tls-key と併用）
+    #[arg(long = "tls-cert", value_name = "FILE", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// HTTPS に使う秘密鍵ファイル（PKCS#8 PEM、--tls-cert と併用）
+    #[arg(long = "tls-key", value_name = "FILE", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+}
+
+/// Parses the backend of --record-through, which must be an origin without a path.
+fn parse_upstream(input: &str) -> Result<reqwest::Url> {
+    let url = reqwest::Url::parse(input).with_context(|| format!("Invalid URL: {}", input))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        bail!("--record-through needs an http or https URL: {}", input);
+    }
+    if url.path() != "/" || url.query().is_some() {
+        bail!("--record-through takes the backend origin without a path (e.g. https://api.example.com): {}", input);
+    }
+    Ok(url)
+}
+
+/// Parses a fraction such as "10%" or "0.1".
+fn parse_rate(input: &str) -> Result<f64> {
+    let rate = match input.trim().strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => input.trim().parse::<f64>(),
+    }
+    .with_context(|| format!("Invalid rate: {}", input))?;
+    if !(0.0..=1.0).contains(&rate) {
+        bail!("Rate must be between 0% and 100%: {}", input);
+    }
+    Ok(rate)
+}
+
+/// Parses the factor of --replay-latency.
+fn parse_scale(input: &str) -> Result<f64> {
+    let scale: f64 = input.trim().parse().with_context(|| format!("Invalid scale: {}", input))?;
+    if !scale.is_finite() || scale < 0.0 {
+        bail!("Scale must be zero or more: {}", input);
+    }
+    Ok(scale)
+}
+
+/// Parses a delay range such as "0-2s" or "100ms-500ms"; a single duration means 0 to it.
+fn parse_jitter(input: &str) -> Result<(Duration, Duration)> {
+    let (low, high) = input.split_once('-').unwrap_or(("0s", input));
+    let (low, high) = (low.trim(), high.trim());
+    // "0-2s" のように単位が省略された下限には上限の単位を使う
+    let unit = high.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let low = if low.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        format!("{}{}", low, unit)
+    } else {
+        low.to_string()
+    };
+    let low = humantime::parse_duration(&low).with_context(|| format!("Invalid delay range: {}", input))?;
+    let high = humantime::parse_duration(high).with_context(|| format!("Invalid delay range: {}", input))?;
+    if low > high {
+        bail!("The lower bound of the delay range is above the upper bound: {}", input);
+    }
+    Ok((low, high))
+}
+
+/// A canned response for one method and path.
+#[derive(Debug, Clone)]
+struct Stub {
+    method: String,
+    path: String,
+    query: Option<String>,
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+    /// How long the real server took to respond.
+    elapsed: Option<Duration>,
+}
+
+impl Stub {
+    /// Builds a stub from a recorded exchange; entries without a response are skipped.
+    fn from_entry(entry: &SessionEntry) -> Option<Stub> {
+        let status = entry.status?;
+        let url = reqwest::Url::parse(entry.request.url.as_deref()?).ok()?;
+        Some(Stub {
+            method: entry.request.method.clone().unwrap_or_else(|| "GET".to_string()).to_uppercase(),
+            path: url.path().to_string(),
+            query: url.query().map(str::to_string),
+            status,
+            headers: entry.headers.clone(),
+            body: entry.body.clone().unwrap_or_default(),
+            elapsed: entry.elapsed_ms.map(Duration::from_millis),
+        })
+    }
+
+    fn response(&self) -> Response<Body> {
+        respond(self.status, &self.headers, self.body.clone())
+    }
+}
+
+/// Builds a response, leaving out headers that don't apply to the re-sent body.
+fn respond(status: u16, headers: &BTreeMap<String, String>, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+    for (name, value) in headers {
+        if HOP_BY_HOP.contains(&name.to_lowercase().as_str()) || name.eq_ignore_ascii_case("content-encoding") {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (name.parse::<hyper::header::HeaderName>(), value.parse()) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+}
+
+/// Prefix of the admin API paths, which are never matched against stubs.
+const ADMIN_PREFIX: &str = "/__admin/";
+
+/// How often the stubs files and the session are checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stub from a `--stubs` file: conditions on the request and a response template.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Rule {
+    /// Shown in the request log instead of the rule's position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Any method matches when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    /// Path with `{name}` parameters and `*` for any single segment, e.g. "/users/{id}".
+    path: String,
+    /// Query parameters and headers the request must have; values may contain `*`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    query: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    /// JSON the request body must contain: listed keys must match, other keys are ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+    /// Regular expression the raw request body must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_matches: Option<String>,
+    #[serde(skip)]
+    body_regex: Option<Regex>,
+    #[serde(default)]
+    response: Reply,
+}
+
+/// The response of a rule; strings in the headers and body may use
+/// `{{request.*}}` placeholders.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Reply {
+    #[serde(default = "default_status")]
+    status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Value>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl Default for Reply {
+    fn default() -> Self {
+        Reply {
+            status: default_status(),
+            headers: BTreeMap::new(),
+            body: None,
+        }
+    }
+}
+
+/// The parts of an incoming request that rules match on and templates read from.
+struct Incoming {
+    method: String,
+    path: String,
+    query: BTreeMap<String, String>,
+    /// Header names are lowercase.
+    headers: BTreeMap<String, String>,
+    text: String,
+    /// The body parsed as JSON, or the text when it is not JSON.
+    body: Value,
+}
+
+/// Matches VALUE against PATTERN, where `*` stands for any text.
+fn wildcard(pattern: &str, value: &str) -> bool {
+    if !pattern.contains('*') {
+        return pattern == value;
+    }
+    let source = pattern.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    Regex::new(&format!("^{}$", source)).is_ok_and(|re| re.is_match(value))
+}
+
+/// True when ACTUAL contains PATTERN: objects match on the listed keys, every item of an
+/// array pattern must be found in the array, and strings may contain `*`.
+fn contains(pattern: &Value, actual: &Value) -> bool {
+    match (pattern, actual) {
+        (Value::Object(pattern), Value::Object(actual)) => pattern
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|actual| contains(value, actual))),
+        (Value::Array(pattern), Value::Array(actual)) => {
+            pattern.iter().all(|value| actual.iter().any(|actual| contains(value, actual)))
+        }
+        (Value::String(pattern), Value::String(actual)) => wildcard(pattern, actual),
+        _ => pattern == actual,
+    }
+}
+
+impl Rule {
+    /// Returns the path parameters when the request meets every condition of the rule.
+    fn matches(&self, request: &Incoming) -> Option<Map<String, Value>> {
+        if self.method.as_ref().is_some_and(|method| !method.eq_ignore_ascii_case(&request.method)) {
+            return None;
+        }
+        let expected: Vec<&str> = self.path.trim_matches('/').split('/').collect();
+        let actual: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        if expected.len() != actual.len() {
+            return None;
+        }
+        let mut params = Map::new();
+        for (expected, actual) in expected.iter().zip(&actual) {
+            if let Some(name) = expected.strip_prefix('{').and_then(|e| e.strip_suffix('}')) {
+                params.insert(name.to_string(), Value::String(actual.to_string()));
+            } else if !wildcard(expected, actual) {
+                return None;
+            }
+        }
+        let present = |expected: &BTreeMap<String, String>, actual: &BTreeMap<String, String>| {
+            expected.iter().all(|(name, pattern)| actual.get(name).is_some_and(|value| wildcard(pattern, value)))
+        };
+        if !present(&self.query, &request.query) || !present(&self.headers, &request.headers) {
+            return None;
+        }
+        if self.body.as_ref().is_some_and(|body| !contains(body, &request.body)) {
+            return None;
+        }
+        if self.body_regex.as_ref().is_some_and(|re| !re.is_match(&request.text)) {
+            return None;
+        }
+        Some(params)
+    }
+
+    fn label(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("rule {}", index + 1))
+    }
+
+    fn response(&self, request: &Incoming, params: Map<String, Value>) -> Response<Body> {
+        let context = json!({
+            "request": {
+                "method": request.method,
+                "path": request.path,
+                "params": params,
+                "query": request.query,
+                "headers": request.headers,
+                "body": request.body,
+            }
+        });
+        let mut headers: BTreeMap<String, String> = self
+            .response
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), render_text(value, &context)))
+            .collect();
+        let body = match &self.response.body {
+            None => String::new(),
+            Some(Value::String(text)) => render_text(text, &context),
+            Some(body) => {
+                if !headers.keys().any(|name| name.eq_ignore_ascii_case("content-type")) {
+                    headers.insert("content-type".to_string(), "application/json".to_string());
+                }
+                format!("{}\n", render_value(body, &context))
+            }
+        };
+        respond(self.response.status, &headers, body)
+    }
+}
+
+/// Finds the next `{{request...}}` placeholder in TEXT at or after FROM and returns its
+/// range and the path expression inside it.
+fn next_placeholder(text: &str, from: usize) -> Option<(std::ops::Range<usize>, String)> {
+    let mut from = from;
+    loop {
+        let start = from + text[from..].find("{{")?;
+        let end = start + text[start..].find("}}")? + 2;
+        let expr = text[start + 2..end - 2].trim();
+        if expr == "request" || expr.starts_with("request.") {
+            return Some((start..end, format!(".{}", expr)));
+        }
+        from = start + 2;
+    }
+}
+
+fn lookup(expr: &str, context: &Value) -> Option<Value> {
+    Filter::parse(expr).ok().map(|filter| filter.apply(context))
+}
+
+/// Replaces the placeholders in TEXT; missing values become empty text.
+fn render_text(text: &str, context: &Value) -> String {
+    let mut text = text.to_string();
+    let mut from = 0;
+    while let Some((range, expr)) = next_placeholder(&text, from) {
+        let Some(value) = lookup(&expr, context) else {
+            from = range.end;
+            continue;
+        };
+        let value = match value {
+            Value::String(s) => s,
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        from = range.start + value.len();
+        text.replace_range(range, &value);
+    }
+    text
+}
+
+/// Renders a JSON body template. A string that is a single placeholder takes the
+/// value with its JSON type, so `id: "{{request.body.id}}"` stays a number.
+fn render_value(template: &Value, context: &Value) -> Value {
+    match template {
+        Value::String(text) => match next_placeholder(text, 0) {
+            Some((range, expr)) if range == (0..text.len()) => {
+                lookup(&expr, context).unwrap_or_else(|| template.clone())
+            }
+            _ => Value::String(render_text(text, context)),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, context)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render_value(value, context)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Checks a rule and compiles its body pattern; LABEL names it in errors.
+fn prepare(rule: &mut Rule, label: &str) -> Result<()> {
+    if !rule.path.starts_with('/') {
+        bail!("Stub '{}': path must start with '/': {}", label, rule.path);
+    }
+    if rule.path.starts_with(ADMIN_PREFIX) {
+        bail!("Stub '{}': paths under {} are reserved for the admin API", label, ADMIN_PREFIX);
+    }
+    if !(100..=599).contains(&rule.response.status) {
+        bail!("Stub '{}': invalid status {}", label, rule.response.status);
+    }
+    if let Some(ref pattern) = rule.body_matches {
+        let re = Regex::new(pattern).with_context(|| format!("Stub '{}': invalid body_matches pattern", label))?;
+        rule.body_regex = Some(re);
+    }
+    Ok(())
+}
+
+/// Loads the rules of a stubs file (a YAML or JSON list).
+fn load_rules(path: &Path) -> Result<Vec<Rule>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read stubs from {:?}", path))?;
+    let mut rules: Vec<Rule> =
+        serde_yaml::from_str(&content).with_context(|| format!("Failed to parse stubs in {:?}", path))?;
+    for (index, rule) in rules.iter_mut().enumerate() {
+        let label = rule.label(index);
+        prepare(rule, &label).with_context(|| format!("Invalid stub in {:?}", path))?;
+    }
+    Ok(rules)
+}
+
+fn load_session(path: &Path) -> Result<Vec<Stub>> {
+    Ok(session::load(path)?.iter().filter_map(Stub::from_entry).collect())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// A file stubs were loaded from, reloaded when its modification time changes.
+struct Source<T> {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    items: Vec<T>,
+}
+
+impl<T> Source<T> {
+    fn load(path: PathBuf, load: fn(&Path) -> Result<Vec<T>>) -> Result<Self> {
+        Ok(Source {
+            modified: modified(&path),
+            items: load(&path)?,
+            path,
+        })
+    }
+
+    /// Reloads the file if it changed on disk, or always with FORCE. A file that fails
+    /// to load keeps its previous stubs.
+    fn reload(&mut self, force: bool, load: fn(&Path) -> Result<Vec<T>>) -> Result<()> {
+        let current = modified(&self.path);
+        if !force && current == self.modified {
+            return Ok(());
+        }
+        // 読み込みに失敗しても同じエラーを繰り返し出さないよう、更新時刻は先に記録する
+        self.modified = current;
+        let items = load(&self.path)
+            .with_context(|| format!("Failed to reload {:?}; keeping the previous stubs", self.path))?;
+        println!("{}", i18n::tf("Reloaded {} stub(s) from {}", &[&items.len(), &format!("{:?}", self.path)]));
+        self.items = items;
+        Ok(())
+    }
+}
+
+/// The stubs being served.
+struct Stubs {
+    /// Stubs added through the admin API; checked first and kept across reloads.
+    added: Vec<Rule>,
+    files: Vec<Source<Rule>>,
+    session: Option<Source<Stub>>,
+}
+
+impl Stubs {
+    /// Every rule in matching order; admin API indexes refer to this order.
+    fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.added.iter().chain(self.files.iter().flat_map(|file| &file.items))
+    }
+
+    fn rule_mut(&mut self, index: usize) -> Option<&mut Rule> {
+        self.added
+            .iter_mut()
+            .chain(self.files.iter_mut().flat_map(|file| file.items.iter_mut()))
+            .nth(index)
+    }
+
+    fn remove(&mut self, index: usize) -> Option<Rule> {
+        if index < self.added.len() {
+            return Some(self.added.remove(index));
+        }
+        let mut index = index - self.added.len();
+        for file in &mut self.files {
+            if index < file.items.len() {
+                return Some(file.items.remove(index));
+            }
+            index -= file.items.len();
+        }
+        None
+    }
+
+    fn recorded(&self) -> &[Stub] {
+        self.session.as_ref().map_or(&[], |session| &session.items)
+    }
+
+    /// Finds the recorded stub for a request: same method, path and query first, then same
+    /// method and path. The latest recording wins when an endpoint was recorded several times.
+    fn find(&self, method: &str, path: &str, query: Option<&str>) -> Option<&Stub> {
+        let candidates = || self.recorded().iter().rev().filter(|s| s.method == method && s.path == path);
+        candidates().find(|s| s.query.as_deref() == query).or_else(|| candidates().next())
+    }
+
+    /// Reloads the files that changed on disk (all of them with FORCE) and returns the
+    /// errors of those that failed.
+    fn reload(&mut self, force: bool) -> Vec<String> {
+        let mut errors = Vec::new();
+        for file in &mut self.files {
+            if let Err(err) = file.reload(force, load_rules) {
+                errors.push(format!("{:#}", err));
+            }
+        }
+        if let Some(ref mut session) = self.session {
+            if let Err(err) = session.reload(force, load_session) {
+                errors.push(format!("{:#}", err));
+            }
+        }
+        for error in &errors {
+            println!("{}", error);
+        }
+        errors
+    }
+}
+
+/// Returned by the service to close the connection without a response.
+#[derive(Debug)]
+struct Dropped;
+
+impl fmt::Display for Dropped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("connection dropped by failure injection")
+    }
+}
+
+impl std::error::Error for Dropped {}
+
+struct Mock {
+    stubs: Mutex<Stubs>,
+    args: MockArgs,
+    client: reqwest::Client,
+    started: Instant,
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(format!("{}\n", body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert("content-type", "application/json".parse().expect("valid header value"));
+    response
+}
+
+/// Parses a rule sent to the admin API as JSON or YAML.
+fn parse_rule(body: &[u8]) -> Result<Rule> {
+    let mut rule: Rule = serde_yaml::from_slice(body).context("Invalid stub")?;
+    let label = rule.label(0);
+    prepare(&mut rule, &label)?;
+    Ok(rule)
+}
+
+fn bad_request(err: anyhow::Error) -> Response<Body> {
+    json_response(StatusCode::BAD_REQUEST, json!({ "error": format!("{:#}", err) }))
+}
+
+impl Mock {
+    fn stubs(&self) -> MutexGuard<'_, Stubs> {
+        self.stubs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serves the admin API: list, add, replace and delete stubs, and force a reload.
+    fn admin(&self, method: &str, path: &str, body: &[u8]) -> Response<Body> {
+        let mut stubs = self.stubs();
+        let index = path.strip_prefix("stubs/").map(|index| (index, index.parse::<usize>()));
+        match (method, path, index) {
+            ("GET", "stubs", _) => {
+                let rules: Vec<Value> = stubs
+                    .rules()
+                    .enumerate()
+                    .map(|(index, rule)| {
+                        let mut value = serde_json::to_value(rule).unwrap_or_default();
+                        if let Value::Object(ref mut fields) = value {
+                            fields.insert("index".to_string(), json!(index));
+                        }
+                        value
+                    })
+                    .collect();
+                let recorded: Vec<Value> = stubs
+                    .recorded()
+                    .iter()
+                    .map(|stub| json!({ "method": stub.method, "path": stub.path, "query": stub.query, "status": stub.status }))
+                    .collect();
+                json_response(StatusCode::OK, json!({ "stubs": rules, "recorded": recorded }))
+            }
+            ("POST", "stubs", _) => match parse_rule(body) {
+                Ok(rule) => {
+                    let value = serde_json::to_value(&rule).unwrap_or_default();
+                    // 追加したスタブが既存の定義より優先されるよう先頭に入れる
+                    stubs.added.insert(0, rule);
+                    println!("{}", i18n::t("Added stub 0 through the admin API"));
+                    json_response(StatusCode::CREATED, json!({ "index": 0, "stub": value }))
+                }
+                Err(err) => bad_request(err),
+            },
+            ("PUT" | "DELETE", _, Some((_, Ok(index)))) if index < stubs.rules().count() => {
+                if method == "DELETE" {
+                    let removed = stubs.remove(index).map(|rule| serde_json::to_value(rule).unwrap_or_default());
+                    println!("{}", i18n::tf("Deleted stub {} through the admin API", &[&index]));
+                    return json_response(StatusCode::OK, json!({ "index": index, "stub": removed }));
+                }
+                match parse_rule(body) {
+                    Ok(rule) => {
+                        let value = serde_json::to_value(&rule).unwrap_or_default();
+                        if let Some(slot) = stubs.rule_mut(index) {
+                            *slot = rule;
+                        }
+                        println!("{}", i18n::tf("Replaced stub {} through the admin API", &[&index]));
+                        json_response(StatusCode::OK, json!({ "index": index, "stub": value }))
+                    }
+                    Err(err) => bad_request(err),
+                }
+            }
+            ("PUT" | "DELETE", _, Some((index, _))) => json_response(
+                StatusCode::NOT_FOUND,
+                json!({ "error": format!("No stub with index {}", index) }),
+            ),
+            ("POST", "reload", _) => {
+                let errors = stubs.reload(true);
+                let status = if errors.is_empty() { StatusCode::OK } else { StatusCode::UNPROCESSABLE_ENTITY };
+                let counts = json!({ "stubs": stubs.rules().count(), "recorded": stubs.recorded().len(), "errors": errors });
+                json_response(status, counts)
+            }
+            _ => json_response(
+                StatusCode::NOT_FOUND,
+                json!({ "error": format!("Unknown admin endpoint {} {}{}", method, ADMIN_PREFIX, path) }),
+            ),
+        }
+    }
+
+    async fn handle(self: Arc<Self>, request: Request<Body>) -> Result<Response<Body>, Dropped> {
+        let method = request.method().as_str().to_uppercase();
+        let uri = request.uri().clone();
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.as_str().to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+            .collect();
+        let bytes = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+        if let Some(path) = uri.path().strip_prefix(ADMIN_PREFIX) {
+            return Ok(self.admin(&method, path.trim_end_matches('/'), &bytes));
+        }
+        if let Some((low, high)) = self.args.delay_jitter {
+            let delay = low + (high - low).mul_f64(fastrand::f64());
+            tokio::time::sleep(delay).await;
+        }
+        if self.args.fail_rate > 0.0 && fastrand::f64() < self.args.fail_rate {
+            if self.args.drop_connections {
+                println!("{}", i18n::tf("{} {} -> dropped (injected)", &[&method, &uri]));
+                return Err(Dropped);
+            }
+            println!("{}", i18n::tf("{} {} -> {} (injected)", &[&method, &uri, &self.args.fail_status]));
+            let status = StatusCode::from_u16(self.args.fail_status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+            return Ok(json_response(status, json!({ "error": "failure injected by ferrapi_tester mock" })));
+        }
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        let incoming = Incoming {
+            method: method.clone(),
+            path: uri.path().to_string(),
+            query: reqwest::Url::parse(&format!("http://mock{}", uri))
+                .map(|url| url.query_pairs().into_owned().collect())
+                .unwrap_or_default(),
+            headers,
+            body: match serde_json::from_str(&text) {
+                Ok(json) => json,
+                Err(_) if text.is_empty() => Value::Null,
+                Err(_) => Value::String(text.clone()),
+            },
+            text,
+        };
+        if let Some((response, delay)) = self.answer(&incoming, &uri) {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            return Ok(response);
+        }
+        if let Some(ref upstream) = self.args.record_through {
+            return Ok(self.record_through(upstream, &incoming, &uri, bytes).await);
+        }
+        println!("{}", i18n::tf("{} {} -> 404 (no stub)", &[&method, &uri]));
+        Ok(json_response(
+            StatusCode::NOT_FOUND,
+            json!({ "error": format!("No stub for {} {}", method, uri.path()) }),
+        ))
+    }
+
+    /// Answers from the stubs, or returns None when no stub matches. A recorded response
+    /// comes with the delay to wait before sending it when --replay-latency is set.
+    fn answer(&self, request: &Incoming, uri: &Uri) -> Option<(Response<Body>, Option<Duration>)> {
+        let stubs = self.stubs();
+        // ファイルに定義したスタブを記録より優先し、先に書かれたものから照合する
+        for (index, rule) in stubs.rules().enumerate() {
+            if let Some(params) = rule.matches(request) {
+                println!("{} {} -> {} ({})", request.method, uri, rule.response.status, rule.label(index));
+                return Some((rule.response(request, params), None));
+            }
+        }
+        let stub = stubs.find(&request.method, uri.path(), uri.query())?;
+        let delay = self.args.replay_latency.zip(stub.elapsed).map(|(scale, elapsed)| elapsed.mul_f64(scale));
+        match delay {
+            Some(delay) => println!(
+                "{}",
+                i18n::tf("{} {} -> {} (after {} ms)", &[&request.method, &uri, &stub.status, &delay.as_millis()])
+            ),
+            None => println!("{} {} -> {}", request.method, uri, stub.status),
+        }
+        Some((stub.response(), delay))
+    }
+
+    /// Forwards a request without a stub to the --record-through backend and appends the
+    /// exchange to the session, so the same request is answered from the recording next time.
+    async fn record_through(
+        &self,
+        upstream: &reqwest::Url,
+        request: &Incoming,
+        uri: &Uri,
+        body: Bytes,
+    ) -> Response<Body> {
+        let url = match upstream.join(uri.path_and_query().map_or("/", |p| p.as_str())) {
+            Ok(url) => url,
+            Err(err) => {
+                let error = format!("Invalid request URL: {}", err);
+                return json_response(StatusCode::BAD_REQUEST, json!({ "error": error }));
+            }
+        };
+        let method = reqwest::Method::from_bytes(request.method.as_bytes()).unwrap_or(reqwest::Method::GET);
+        let mut upstream_request = self.client.request(method, url.clone());
+        let mut headers = HashMap::new();
+        for (name, value) in &request.headers {
+            // 圧縮された応答はテキストとして記録できないので accept-encoding は転送しない
+            if HOP_BY_HOP.contains(&name.as_str()) || name == "host" || name == "accept-encoding" {
+                continue;
+            }
+            upstream_request = upstream_request.header(name, value);
+            headers.insert(name.clone(), value.clone());
+        }
+        let offset_ms = self.started.elapsed().as_millis() as u64;
+        let started = Instant::now();
+        let outcome = async {
+            let response = upstream_request.body(body).send().await?;
+            let status = response.status().as_u16();
+            let headers: BTreeMap<String, String> = response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+                .collect();
+            Ok::<_, reqwest::Error>((status, headers, response.text().await?))
+        }
+        .await;
+        let (status, response_headers, text) = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                let err = NetworkError::from(err);
+                println!("{}", i18n::tf("{} {} -> 502 (backend error: {})", &[&request.method, &uri, &err]));
+                let error = format!("Backend request failed: {}", err);
+                return json_response(StatusCode::BAD_GATEWAY, json!({ "error": error }));
+            }
+        };
+        let entry = SessionEntry {
+            offset_ms,
+            target: None,
+            request: RequestConfig {
+                url: Some(url.to_string()),
+                method: Some(request.method.clone()),
+                headers: Some(headers),
+                data: (!request.body.is_null()).then(|| request.body.clone()),
+                timeout: Some(30),
+                ..RequestConfig::default()
+            },
+            status: Some(status),
+            headers: response_headers.clone(),
+            body: Some(text.clone()),
+            elapsed_ms: Some(started.elapsed().as_millis() as u64),
+            error: None,
+        };
+        let mut stubs = self.stubs();
+        if let Some(ref mut session) = stubs.session {
+            match session::append(&session.path, &entry) {
+                Ok(()) => {
+                    // 自分で追記した変更を再読み込みの対象にしない
+                    session.modified = modified(&session.path);
+                    session.items.extend(Stub::from_entry(&entry));
+                    println!(
+                        "{}",
+                        i18n::tf("{} {} -> {} (recorded from {})", &[&request.method, &uri, &status, upstream])
+                    );
+                }
+                Err(err) => {
+                    let err = format!("{:#}", err);
+                    println!(
+                        "{}",
+                        i18n::tf(
+                            "{} {} -> {} (from {}; not recorded: {})",
+                            &[&request.method, &uri, &status, upstream, &err]
+                        )
+                    )
+                }
+            }
+        }
+        respond(status, &response_headers, text)
+    }
+}
+
+/// Serves the stubs files and the responses of a recorded session, optionally injecting
+/// failures and delays.
+pub async fn run(args: MockArgs) -> Result<()> {
+    if !(100..=599).contains(&args.fail_status) {
+        bail!("--fail-status must be an HTTP status code: {}", args.fail_status);
+    }
+    let mut files = Vec::new();
+    for path in &args.stubs {
+        let file = Source::load(path.clone(), load_rules)?;
+        println!("{}", i18n::tf("Loaded {} stub(s) from {}", &[&file.items.len(), &format!("{:?}", path)]));
+        files.push(file);
+    }
+    let mut session = None;
+    if let Some(ref name) = args.session {
+        let recorded = Source::load(session::find(name)?, load_session)?;
+        if recorded.items.is_empty() {
+            bail!("Session {:?} has no recorded responses", recorded.path);
+        }
+        println!(
+            "{}",
+            i18n::tf(
+                "Serving {} recorded response(s) from {}",
+                &[&recorded.items.len(), &format!("{:?}", recorded.path)]
+            )
+        );
+        session = Some(recorded);
+    }
+    if args.record_through.is_some() && session.is_none() {
+        let path = session::new_path("mock")?;
+        println!("{}", i18n::tf("Recording backend responses to {}", &[&format!("{:?}", path)]));
+        session = Some(Source {
+            path,
+            modified: None,
+            items: Vec::new(),
+        });
+    }
+    let address = SocketAddr::from(([127, 0, 0, 1], args.port));
+    let tls = tls_acceptor(&args)?;
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(60))
+        .build()?;
+    let stubs = Stubs {
+        added: Vec::new(),
+        files,
+        session,
+    };
+    let mock = Arc::new(Mock {
+        stubs: Mutex::new(stubs),
+        args,
+        client,
+        started: Instant::now(),
+    });
+    // ファイルの変更を定期的に確認し、再起動せずにスタブを読み直す
+    let watched = Arc::clone(&mock);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            watched.stubs().reload(false);
+        }
+    });
+    if let Some(acceptor) = tls {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(err) => bail!("Failed to listen on {}: {}", address, err),
+        };
+        println!(
+            "{}",
+            i18n::tf(
+                "Mock server listening on https://{} (admin API under {}, Ctrl-C to stop)",
+                &[&address, &ADMIN_PREFIX]
+            )
+        );
+        return serve_tls(listener, acceptor, mock).await;
+    }
+    let make_service = make_service_fn(move |_| {
+        let mock = Arc::clone(&mock);
+        async move {
+            Ok::<_, std::convert::Infallible>(service_fn(move |request| Arc::clone(&mock).handle(request)))
+        }
+    });
+    let server = match Server::try_bind(&address) {
+        Ok(builder) => builder.serve(make_service),
+        Err(err) => bail!("Failed to listen on {}: {}", address, err),
+    };
+    println!(
+        "{}",
+        i18n::tf("Mock server listening on http://{} (admin API under {}, Ctrl-C to stop)", &[&address, &ADMIN_PREFIX])
+    );
+    server.with_graceful_shutdown(cancel::cancelled()).await?;
+    Ok(())
+}
+
+/// Builds the TLS acceptor for --tls-cert/--tls-key, or for --tls a certificate for
+/// localhost issued by the local CA; None when serving plain HTTP.
+fn tls_acceptor(args: &MockArgs) -> Result<Option<TlsAcceptor>> {
+    if let (Some(cert_path), Some(key_path)) = (&args.tls_cert, &args.tls_key) {
+        let cert = fs::read(cert_path).with_context(|| format!("Failed to read {:?}", cert_path))?;
+        let key = fs::read(key_path).with_context(|| format!("Failed to read {:?}", key_path))?;
+        let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+            .with_context(|| format!("Failed to load {:?} and {:?} (the key must be PKCS#8 PEM)", cert_path, key_path))?;
+        println!("{}", i18n::tf("Serving HTTPS with the certificate at {}", &[&format!("{:?}", cert_path)]));
+        return Ok(Some(TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?)));
+    }
+    if !args.tls {
+        return Ok(None);
+    }
+    let (authority, cert_path) = Authority::load_or_create()?;
+    println!(
+        "{}",
+        i18n::tf("Serving HTTPS with a certificate signed by the CA at {}.", &[&format!("{:?}", cert_path)])
+    );
+    println!("{}", i18n::t("Add it to the trust store of the client (browser, OS, or e.g. NODE_EXTRA_CA_CERTS)."));
+    Ok(Some(authority.issue(&["localhost", "127.0.0.1", "::1"])?))
+}
+
+/// Accepts HTTPS connections on LISTENER until cancelled.
+async fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, mock: Arc<Mock>) -> Result<()> {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => accepted?.0,
+            _ = cancel::cancelled() => return Ok(()),
+        };
+        let acceptor = acceptor.clone();
+        let mock = Arc::clone(&mock);
+        tokio::spawn(async move {
+            // 証明書を信頼していないクライアントはハンドシェイクで失敗するので、理由を表示する
+            let tls = match acceptor.accept(stream).await {
+                Ok(tls) => tls,
+                Err(err) => {
+                    println!("{}", i18n::tf("TLS handshake failed: {}", &[&err]));
+                    return;
+                }
+            };
+            let service = service_fn(move |request| Arc::clone(&mock).handle(request));
+            let _ = Http::new().serve_connection(tls, service).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_match_and_render_request_values() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+method: POST
+path: /users/{id}/orders
+query: { dry: "*" }
+body: { items: [{ sku: "A-*" }] }
+response:
+  body: { user: "{{request.params.id}}", count: "{{request.body.count}}", note: "dry={{request.query.dry}}" }
+"#,
+        )
+        .unwrap();
+        let incoming = |path: &str, body: Value| Incoming {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query: BTreeMap::from([("dry".to_string(), "1".to_string())]),
+            headers: BTreeMap::new(),
+            text: body.to_string(),
+            body,
+        };
+        let body = json!({ "count": 2, "items": [{ "sku": "B-1" }, { "sku": "A-9" }] });
+        assert!(rule.matches(&incoming("/users/7", body.clone())).is_none());
+        assert!(rule.matches(&incoming("/users/7/orders", json!({ "items": [] }))).is_none());
+
+        let request = incoming("/users/7/orders", body);
+        let params = rule.matches(&request).unwrap();
+        let context = json!({ "request": { "params": params, "query": request.query, "body": request.body } });
+        let rendered = render_value(rule.response.body.as_ref().unwrap(), &context);
+        assert_eq!(rendered, json!({ "user": "7", "count": 2, "note": "dry=1" }));
+    }
+}
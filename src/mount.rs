@@ -0,0 +1,138 @@
+use anyhow::{bail, Result};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::hooks::HOOKS_FILE;
+use crate::i18n;
+use crate::namespace::{self, SavedConfig};
+use crate::output::{self, Table};
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::vars::VARS_FILE;
+use crate::{get_config_path, get_default_dir};
+
+/// The read-only configuration roots mounted in settings.json (`mounts`), in the
+/// order they are searched after the base directory.
+pub fn roots() -> Result<Vec<PathBuf>> {
+    Settings::load()?.mount_paths()
+}
+
+/// Path of the configuration file of TARGET and METHOD: the one in the base directory
+/// if it exists, otherwise the first mount that has it, otherwise the (missing) file
+/// in the base directory.
+pub fn config_path(target: &str, method: &str) -> Result<PathBuf> {
+    let path = get_config_path(&get_default_dir()?, target, method)?;
+    if path.exists() {
+        return Ok(path);
+    }
+    for root in roots()? {
+        let mounted = get_config_path(&root, target, method)?;
+        if mounted.exists() {
+            return Ok(mounted);
+        }
+    }
+    Ok(path)
+}
+
+/// Directories whose vars.json, settings.json and hooks file apply to TARGET, from the
+/// root down; at each level the mounts come before the base directory so the personal
+/// tree takes precedence.
+pub fn ancestry(target: &str) -> Result<Vec<PathBuf>> {
+    let roots = roots()?;
+    if roots.is_empty() {
+        return namespace::ancestry(&get_default_dir()?, target);
+    }
+    let mut trees = Vec::new();
+    for root in roots.iter().rev().chain([&get_default_dir()?]) {
+        trees.push(namespace::ancestry(root, target)?);
+    }
+    let depth = trees.first().map_or(0, Vec::len);
+    Ok((0..depth).flat_map(|level| trees.iter().map(move |tree| tree[level].clone())).collect())
+}
+
+/// Every saved configuration in the base directory and the mounts. A configuration in
+/// the base directory hides the mounted ones of the same target and method.
+pub fn list_saved() -> Result<Vec<SavedConfig>> {
+    let mut saved = namespace::list_saved(&get_default_dir()?)?;
+    let roots = roots()?;
+    if roots.is_empty() {
+        return Ok(saved);
+    }
+    let mut seen: HashSet<(String, String)> =
+        saved.iter().map(|config| (config.target.clone(), config.method.clone())).collect();
+    for root in roots {
+        for mut config in namespace::list_saved(&root)? {
+            if seen.insert((config.target.clone(), config.method.clone())) {
+                config.mount = Some(root.clone());
+                saved.push(config);
+            }
+        }
+    }
+    saved.sort_by(|a, b| (&a.target, &a.method).cmp(&(&b.target, &b.method)));
+    Ok(saved)
+}
+
+/// Fails when TARGET (its METHOD configuration, or the namespace without one) exists
+/// only in a mount, so deleting it would have to touch the read-only tree.
+pub fn check_deletable(target: &str, method: Option<&str>, what: &str) -> Result<()> {
+    let path = |root: &Path| match method {
+        Some(method) => get_config_path(root, target, method),
+        None => namespace::dir(root, target),
+    };
+    if path(&get_default_dir()?)?.exists() {
+        return Ok(());
+    }
+    for root in roots()? {
+        if path(&root)?.exists() {
+            bail!("{} {} is in the read-only mount {:?} and cannot be deleted", what, target, root);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the files that decide a request to TARGET with METHOD, highest precedence
+/// first: the configuration candidates and the vars.json, settings.json and hooks files.
+pub fn which(target: &str, method: &str) -> Result<()> {
+    let base_dir = get_default_dir()?;
+    let mut rows: Vec<(&str, PathBuf, &str)> = Vec::new();
+    let mut used = None;
+    for root in [base_dir.clone()].into_iter().chain(roots()?) {
+        let path = get_config_path(&root, target, &method.to_uppercase())?;
+        let status = match path.exists() {
+            true if used.is_none() => {
+                used = Some(path.clone());
+                "used"
+            }
+            true => "hidden",
+            false => "missing",
+        };
+        rows.push(("config", path, status));
+    }
+    // 近いディレクトリほど優先され、同じ階層ではベースディレクトリがマウントより優先される
+    let mut dirs = ancestry(target)?;
+    dirs.insert(0, base_dir);
+    dirs.reverse();
+    for (kind, file) in [("vars", VARS_FILE), ("settings", SETTINGS_FILE)] {
+        for path in dirs.iter().map(|dir| dir.join(file)).filter(|path| path.exists()) {
+            rows.push((kind, path, "applies"));
+        }
+    }
+    let mut hooks = dirs.iter().map(|dir| dir.join(HOOKS_FILE)).filter(|path| path.exists());
+    if let Some(path) = hooks.next() {
+        rows.push(("hooks", path, "used"));
+    }
+    for path in hooks {
+        rows.push(("hooks", path, "hidden"));
+    }
+    if used.is_none() {
+        let message = "No configuration is saved for {} {}; only flags would be sent.";
+        eprintln!("{}", i18n::tf(message, &[&method.to_uppercase(), &target]));
+    }
+    let mut table = Table::new(&[("#", "rank"), ("KIND", "kind"), ("FILE", "file"), ("STATUS", "status")]);
+    for (rank, (kind, path, status)) in rows.into_iter().enumerate() {
+        table.push(vec![json!(rank + 1), json!(kind), json!(path.display().to_string()), json!(status)]);
+    }
+    output::renderer()?.print(&table, "")
+}
@@ -0,0 +1,251 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::store;
+
+/// Metadata file kept in a namespace directory whose on-disk name is a slug,
+/// recording the name the user typed.
+pub const METADATA_FILE: &str = ".namespace.json";
+
+/// Device names Windows reserves in every directory, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Metadata {
+    name: String,
+}
+
+fn check_segment(target: &str, segment: &str) -> Result<()> {
+    if segment == ".." {
+        bail!("Namespace {:?} must not contain '..'", target);
+    }
+    if segment.starts_with('.') {
+        bail!("Namespace segment {:?} in {:?} must not start with '.' (reserved for internal directories)", segment, target);
+    }
+    if segment.chars().any(char::is_control) {
+        bail!("Namespace {:?} contains a control character", target);
+    }
+    Ok(())
+}
+
+/// Splits TARGET into validated segments. Both '/' and '\' separate segments;
+/// empty and "." segments are dropped. A segment in double quotes may itself
+/// contain separators, e.g. `SystemA/"v1/v2 API"`.
+pub fn segments(target: &str) -> Result<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let (mut in_quotes, mut quoted) = (false, false);
+    for c in target.chars() {
+        match c {
+            '"' if in_quotes => in_quotes = false,
+            '"' if !quoted && current.trim().is_empty() => {
+                current.clear();
+                in_quotes = true;
+                quoted = true;
+            }
+            '"' => bail!("Misplaced quote in namespace {:?}", target),
+            '/' | '\\' if !in_quotes => {
+                segments.push((std::mem::take(&mut current), quoted));
+                quoted = false;
+            }
+            _ if quoted && !in_quotes => {
+                if !c.is_whitespace() {
+                    bail!("Unexpected characters after a quoted segment in namespace {:?}", target);
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("Unterminated quote in namespace {:?}", target);
+    }
+    segments.push((current, quoted));
+
+    let segments: Vec<String> = segments
+        .into_iter()
+        .map(|(s, quoted)| if quoted { s } else { s.trim().to_string() })
+        .filter(|s| !s.is_empty() && s != ".")
+        .collect();
+    if segments.is_empty() {
+        bail!("Namespace {:?} is empty", target);
+    }
+    for segment in &segments {
+        check_segment(target, segment)?;
+    }
+    Ok(segments)
+}
+
+/// Formats segments as a TARGET string, quoting segments that contain separators.
+pub fn format_target(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|s| {
+            if s.contains(['/', '\\']) {
+                format!("\"{}\"", s)
+            } else {
+                s.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns TARGET in canonical form (segments joined with '/').
+pub fn normalize(target: &str) -> Result<String> {
+    Ok(format_target(&segments(target)?))
+}
+
+/// True when the segment can be used as a directory name on every platform as is.
+fn is_portable(segment: &str) -> bool {
+    let stem = segment.split('.').next().unwrap_or(segment);
+    segment
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !segment.ends_with('.')
+        && !RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+/// On-disk directory name for a segment. Portable names are kept unchanged;
+/// anything else (spaces, unicode, separators, reserved names) becomes an ASCII
+/// slug plus a short hash of the original name, so distinct names never collide.
+pub fn slug(segment: &str) -> String {
+    if is_portable(segment) {
+        return segment.to_string();
+    }
+    let mut slug = String::new();
+    for c in segment.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(40).collect();
+    let digest = format!("{:x}", Sha256::digest(segment.as_bytes()));
+    if slug.is_empty() {
+        digest[..8].to_string()
+    } else {
+        format!("{}-{}", slug, &digest[..8])
+    }
+}
+
+/// Maps TARGET to its directory under the base directory. This is the only place
+/// a namespace becomes a path, so no target can escape the configuration tree.
+pub fn dir(base_dir: &Path, target: &str) -> Result<PathBuf> {
+    let mut dir = base_dir.to_path_buf();
+    for segment in segments(target)? {
+        dir.push(slug(&segment));
+    }
+    Ok(dir)
+}
+
+/// Like `dir`, but creates the directories and records the display name of
+/// every slugged segment in its metadata file.
+pub fn create(base_dir: &Path, target: &str) -> Result<PathBuf> {
+    let mut dir = base_dir.to_path_buf();
+    for segment in segments(target)? {
+        let slug = slug(&segment);
+        dir.push(&slug);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create directory {:?}", dir))?;
+        let metadata = dir.join(METADATA_FILE);
+        if slug != segment && !metadata.exists() {
+            store::write_atomic(&metadata, serde_json::to_string_pretty(&Metadata { name: segment })?)?;
+        }
+    }
+    Ok(dir)
+}
+
+/// Directories from the first segment of TARGET down to TARGET itself, for
+/// files that are inherited along the namespace tree (vars.json, settings.json).
+pub fn ancestry(base_dir: &Path, target: &str) -> Result<Vec<PathBuf>> {
+    let mut dir = base_dir.to_path_buf();
+    let mut dirs = Vec::new();
+    for segment in segments(target)? {
+        dir.push(slug(&segment));
+        dirs.push(dir.clone());
+    }
+    Ok(dirs)
+}
+
+/// The name shown to the user for a namespace directory: the recorded display
+/// name if the directory is a slug, otherwise the directory name itself.
+pub fn display_name(dir: &Path) -> String {
+    fs::read_to_string(dir.join(METADATA_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Metadata>(&content).ok())
+        .map(|metadata| metadata.name)
+        .unwrap_or_else(|| dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default())
+}
+
+/// A saved request configuration found in the namespace tree.
+#[derive(Debug, Clone)]
+pub struct SavedConfig {
+    /// TARGET as the user types it (display names, '/' separated).
+    pub target: String,
+    /// HTTP method, from the file name (e.g. GET.json).
+    pub method: String,
+    pub path: PathBuf,
+    /// The read-only mount the configuration comes from (None for the base directory).
+    pub mount: Option<PathBuf>,
+}
+
+impl SavedConfig {
+    /// True if the configuration is TARGET itself or lies below it.
+    pub fn is_under(&self, target: &str) -> bool {
+        self.target == target || self.target.starts_with(&format!("{}/", target))
+    }
+}
+
+/// True for file names of saved configurations (METHOD.json with an upper-case method,
+/// which may be a custom verb such as VERSION-CONTROL.json).
+pub fn is_config_file(name: &str) -> bool {
+    name.strip_suffix(".json").is_some_and(|stem| {
+        stem.starts_with(|c: char| c.is_ascii_uppercase())
+            && stem.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    })
+}
+
+fn collect_saved(dir: &Path, names: &mut Vec<String>, found: &mut Vec<SavedConfig>) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        // .snapshots などの内部ディレクトリ・ファイルは対象外
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            names.push(display_name(&path));
+            collect_saved(&path, names, found)?;
+            names.pop();
+        } else if !names.is_empty() && is_config_file(&name) {
+            found.push(SavedConfig {
+                target: format_target(names),
+                method: name.trim_end_matches(".json").to_string(),
+                path,
+                mount: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Lists every saved configuration under the base directory, sorted by path.
+pub fn list_saved(base_dir: &Path) -> Result<Vec<SavedConfig>> {
+    let mut found = Vec::new();
+    if base_dir.is_dir() {
+        collect_saved(base_dir, &mut Vec::new(), &mut found)?;
+    }
+    Ok(found)
+}
@@ -0,0 +1,192 @@
+use std::{error::Error, fmt, io};
+
+/// What went wrong while talking to the server, as far as it can be told from the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkErrorKind {
+    /// The host name could not be resolved.
+    Dns,
+    /// The TCP connection was not established within the connect timeout.
+    ConnectTimeout,
+    /// The host actively refused the connection (nothing listening on the port).
+    ConnectionRefused,
+    /// Any other failure while connecting.
+    Connect,
+    /// The TLS handshake or certificate verification failed.
+    Tls,
+    /// Connected, but no response arrived within the timeout.
+    ResponseTimeout,
+    /// The response started but the body stalled past the timeout.
+    BodyTimeout,
+    /// The connection was closed or reset before the response was complete.
+    ConnectionClosed,
+    /// The request could not be built or the URL is invalid.
+    InvalidRequest,
+    /// Redirect loop or too many redirects.
+    Redirect,
+    Other,
+}
+
+impl NetworkErrorKind {
+    /// Stable identifier used in machine-readable error output.
+    pub fn code(self) -> &'static str {
+        match self {
+            NetworkErrorKind::Dns => "dns",
+            NetworkErrorKind::ConnectTimeout => "connect_timeout",
+            NetworkErrorKind::ConnectionRefused => "connection_refused",
+            NetworkErrorKind::Connect => "connect",
+            NetworkErrorKind::Tls => "tls",
+            NetworkErrorKind::ResponseTimeout => "response_timeout",
+            NetworkErrorKind::BodyTimeout => "body_timeout",
+            NetworkErrorKind::ConnectionClosed => "connection_closed",
+            NetworkErrorKind::InvalidRequest => "invalid_request",
+            NetworkErrorKind::Redirect => "redirect",
+            NetworkErrorKind::Other => "network",
+        }
+    }
+
+    /// The stage of the exchange the failure happened in.
+    pub fn phase(self) -> &'static str {
+        match self {
+            NetworkErrorKind::Dns => "dns",
+            NetworkErrorKind::ConnectTimeout | NetworkErrorKind::ConnectionRefused | NetworkErrorKind::Connect => {
+                "connect"
+            }
+            NetworkErrorKind::Tls => "tls",
+            NetworkErrorKind::InvalidRequest => "request",
+            NetworkErrorKind::BodyTimeout => "body",
+            NetworkErrorKind::ResponseTimeout
+            | NetworkErrorKind::ConnectionClosed
+            | NetworkErrorKind::Redirect
+            | NetworkErrorKind::Other => "response",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            NetworkErrorKind::Dns => "DNS lookup failed",
+            NetworkErrorKind::ConnectTimeout => "Timed out while connecting",
+            NetworkErrorKind::ConnectionRefused => "Connection refused",
+            NetworkErrorKind::Connect => "Could not connect",
+            NetworkErrorKind::Tls => "TLS handshake failed",
+            NetworkErrorKind::ResponseTimeout => "Timed out waiting for the response",
+            NetworkErrorKind::BodyTimeout => "Server stalled while sending the response body",
+            NetworkErrorKind::ConnectionClosed => "Connection closed before the response was complete",
+            NetworkErrorKind::InvalidRequest => "Invalid request",
+            NetworkErrorKind::Redirect => "Too many redirects",
+            NetworkErrorKind::Other => "Request failed",
+        }
+    }
+
+    fn hint(self) -> Option<&'static str> {
+        match self {
+            NetworkErrorKind::Dns => Some("Check the host name in the URL and your DNS or VPN settings."),
+            NetworkErrorKind::ConnectTimeout => Some(
+                "The host did not accept the connection in time. Check the host, port and firewall, or raise --connect-timeout.",
+            ),
+            NetworkErrorKind::ConnectionRefused => Some("Nothing is listening on that port. Is the server running?"),
+            NetworkErrorKind::Tls => Some(
+                "Check the certificate of the server. For self-signed certificates in test environments, pass --insecure.",
+            ),
+            NetworkErrorKind::ResponseTimeout => Some(
+                "The server accepted the connection but did not answer in time. Raise --timeout or check the server.",
+            ),
+            NetworkErrorKind::BodyTimeout => Some(
+                "The server stopped sending data mid-response. Raise --timeout or check the server logs.",
+            ),
+            NetworkErrorKind::ConnectionClosed => Some("The server or a proxy closed the connection; check the server logs."),
+            _ => None,
+        }
+    }
+}
+
+/// A failed request with its cause classified, so the message says concretely
+/// where it failed instead of just "error sending request".
+#[derive(Debug)]
+pub struct NetworkError {
+    pub kind: NetworkErrorKind,
+    pub url: Option<String>,
+    /// The innermost underlying error message.
+    pub cause: String,
+}
+
+impl NetworkError {
+    pub fn hint(&self) -> Option<&'static str> {
+        self.kind.hint()
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.describe())?;
+        if let Some(ref url) = self.url {
+            write!(f, " ({})", url)?;
+        }
+        write!(f, ": {}", self.cause)
+    }
+}
+
+impl Error for NetworkError {}
+
+/// Walks the source chain of the error, outermost first.
+fn chain(err: &reqwest::Error) -> impl Iterator<Item = &(dyn Error + 'static)> {
+    std::iter::successors(err.source(), |&cause| cause.source())
+}
+
+fn classify(err: &reqwest::Error) -> NetworkErrorKind {
+    let messages: Vec<String> = chain(err).map(|cause| cause.to_string().to_lowercase()).collect();
+    let mentions = |needles: &[&str]| messages.iter().any(|m| needles.iter().any(|n| m.contains(n)));
+    let io_kind = chain(err).find_map(|cause| cause.downcast_ref::<io::Error>().map(io::Error::kind));
+
+    if err.is_builder() {
+        NetworkErrorKind::InvalidRequest
+    } else if err.is_redirect() {
+        NetworkErrorKind::Redirect
+    } else if err.is_timeout() && err.is_connect() {
+        NetworkErrorKind::ConnectTimeout
+    } else if err.is_timeout() && (err.is_body() || err.is_decode()) {
+        NetworkErrorKind::BodyTimeout
+    } else if err.is_timeout() {
+        NetworkErrorKind::ResponseTimeout
+    } else if mentions(&["dns error", "failed to lookup address", "no such host"]) {
+        NetworkErrorKind::Dns
+    } else if io_kind == Some(io::ErrorKind::ConnectionRefused) {
+        NetworkErrorKind::ConnectionRefused
+    } else if mentions(&["certificate", "ssl", "tls", "handshake"]) {
+        NetworkErrorKind::Tls
+    } else if err.is_connect() {
+        NetworkErrorKind::Connect
+    } else if matches!(
+        io_kind,
+        Some(
+            io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+        )
+    ) || mentions(&["connection closed", "incomplete message"])
+    {
+        NetworkErrorKind::ConnectionClosed
+    } else {
+        NetworkErrorKind::Other
+    }
+}
+
+impl From<&reqwest::Error> for NetworkError {
+    fn from(err: &reqwest::Error) -> Self {
+        let cause = chain(err)
+            .last()
+            .map(|cause| cause.to_string())
+            .unwrap_or_else(|| err.to_string());
+        NetworkError {
+            kind: classify(err),
+            url: err.url().map(|url| url.to_string()),
+            cause,
+        }
+    }
+}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(err: reqwest::Error) -> Self {
+        NetworkError::from(&err)
+    }
+}
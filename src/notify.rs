@@ -0,0 +1,96 @@
+use anyhow::Result;
+use notify_rust::Notification;
+use std::{sync::Mutex, time::Duration};
+
+use crate::exit_code;
+use crate::i18n;
+use crate::request::{Exchange, RequestConfig};
+
+/// Requests sent by this command, summarized in the notification.
+#[derive(Default)]
+struct Sent {
+    count: usize,
+    /// Requests without a response or with a 4xx/5xx status.
+    failed: usize,
+    /// The last request and its result, e.g. "POST SystemA/import -> 201 Created".
+    last: Option<String>,
+}
+
+static SENT: Mutex<Sent> = Mutex::new(Sent { count: 0, failed: 0, last: None });
+
+/// Counts a finished request for the notification of `finished`.
+pub fn observe(config: &RequestConfig, target: Option<&str>, outcome: &Result<Exchange>) {
+    let result = match outcome {
+        Ok(exchange) => exchange.status.to_string(),
+        Err(err) => format!("failed ({})", exit_code::name(exit_code::of(err))),
+    };
+    let mut sent = SENT.lock().unwrap_or_else(|e| e.into_inner());
+    sent.count += 1;
+    if !outcome.as_ref().is_ok_and(|exchange| exchange.status.as_u16() < 400) {
+        sent.failed += 1;
+    }
+    sent.last = Some(format!(
+        "{} {} -> {}",
+        config.method.as_deref().unwrap_or("GET"),
+        target.unwrap_or_else(|| config.url.as_deref().unwrap_or("")),
+        result
+    ));
+}
+
+/// Title and body of the notification for COMMAND, which took ELAPSED and ended with RESULT.
+fn message(command: &str, elapsed: Duration, result: &Result<()>, sent: &Sent) -> (String, String) {
+    let status = match result {
+        Ok(()) => "succeeded".to_string(),
+        Err(err) => format!("failed ({})", exit_code::name(exit_code::of(err))),
+    };
+    let mut lines = Vec::new();
+    match (sent.count, &sent.last) {
+        (1, Some(last)) => lines.push(last.clone()),
+        (count, Some(last)) if count > 1 => {
+            lines.push(format!("{} requests, {} failed; last: {}", count, sent.failed, last))
+        }
+        _ => {}
+    }
+    if let Err(err) = result {
+        lines.push(err.to_string());
+    }
+    // 秒未満は通知では意味がないので切り捨てる
+    let took = humantime::format_duration(Duration::from_secs(elapsed.as_secs()));
+    lines.push(format!("Took {}", took));
+    (format!("ferrapi_tester {}: {}", command, status), lines.join("\n"))
+}
+
+/// Shows a desktop notification that COMMAND has finished with RESULT, when it took at
+/// least AFTER. A notification that cannot be shown (e.g. no desktop session) only
+/// prints a warning.
+pub fn finished(command: &str, elapsed: Duration, after: Duration, result: &Result<()>) {
+    if elapsed < after {
+        return;
+    }
+    let (title, body) = message(command, elapsed, result, &SENT.lock().unwrap_or_else(|e| e.into_inner()));
+    if let Err(err) = Notification::new().appname("ferrapi_tester").summary(&title).body(&body).show() {
+        eprintln!("{}", i18n::tf("Warning: Could not show the desktop notification: {}", &[&err]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn the_notification_tells_how_the_command_ended() {
+        let sent = Sent { count: 1, failed: 0, last: Some("POST SystemA/import -> 201 Created".to_string()) };
+        let (title, body) = message("send", Duration::from_millis(75_400), &Ok(()), &sent);
+        assert_eq!(title, "ferrapi_tester send: succeeded");
+        assert_eq!(body, "POST SystemA/import -> 201 Created\nTook 1m 15s");
+        let last = Some("GET SystemA/users -> 503 Service Unavailable".to_string());
+        let sent = Sent { count: 12, failed: 1, last };
+        let failed = exit_code::assertion(anyhow!("1 of 12 requests failed"));
+        let (title, body) = message("run-all", Duration::from_secs(30), &Err(failed), &sent);
+        assert_eq!(title, "ferrapi_tester run-all: failed (assertion_failed)");
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines[0], "12 requests, 1 failed; last: GET SystemA/users -> 503 Service Unavailable");
+        assert_eq!(lines[1..], ["1 of 12 requests failed", "Took 30s"]);
+    }
+}
@@ -0,0 +1,912 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use crate::curl;
+use crate::generate;
+use crate::i18n;
+use crate::namespace;
+use crate::request::RequestConfig;
+use crate::session;
+use crate::settings::Settings;
+use crate::stats;
+use crate::store;
+use crate::vars::{self, Vars};
+
+/// Requests sent while a coverage report is being collected: method, URL and status.
+type Observed = Vec<(String, String, Option<u16>)>;
+
+/// Set only while `run` or `run-all` collects coverage.
+static OBSERVED: OnceLock<Mutex<Observed>> = OnceLock::new();
+
+const METHODS: &[&str] = &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// One documented operation (method + path template) and its documented responses.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub method: String,
+    pub path: String,
+    /// Response keys as written in the spec: "200", "4XX", "default", ...
+    pub responses: Vec<String>,
+}
+
+impl Operation {
+    /// Number of literal (non-parameter) segments; more specific templates win.
+    fn specificity(&self) -> usize {
+        self.path.split('/').filter(|s| !s.is_empty() && !s.contains('{')).count()
+    }
+
+    /// Returns true if the spec documents STATUS for this operation.
+    pub fn documents(&self, status: u16) -> bool {
+        self.responses.iter().any(|response| response_matches(response, status))
+    }
+
+    /// Returns true if one of STATUSES exercises the documented RESPONSE key. `default`
+    /// counts only for statuses that no explicit key documents.
+    pub fn response_exercised(&self, response: &str, statuses: &BTreeMap<u16, usize>) -> bool {
+        if response.eq_ignore_ascii_case("default") {
+            return statuses.keys().any(|&status| {
+                !self
+                    .responses
+                    .iter()
+                    .any(|other| !other.eq_ignore_ascii_case("default") && response_matches(other, status))
+            });
+        }
+        statuses.keys().any(|&status| response_matches(response, status))
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+}
+
+/// Returns true if the response key ("200", "4XX" or "default") covers STATUS.
+fn response_matches(response: &str, status: u16) -> bool {
+    let response = response.to_uppercase();
+    let code = status.to_string();
+    response == code
+        || response == "DEFAULT"
+        || (response.len() == 3 && response.ends_with("XX") && code.starts_with(&response[..1]))
+}
+
+/// The operations of an OpenAPI 3 (or Swagger 2) document.
+#[derive(Debug)]
+pub struct Spec {
+    pub operations: Vec<Operation>,
+    /// Path prefixes taken from `servers` (or `basePath`) that precede every path.
+    base_paths: Vec<String>,
+}
+
+/// Matches one path segment against a template segment such as `{id}` or `v{major}.json`.
+fn segment_matches(template: &str, actual: &str) -> bool {
+    if !template.contains('{') {
+        return template == actual;
+    }
+    // パラメーター以外のリテラル部分が順番どおりに現れるかを確認する
+    let mut literals = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        literals.push(&rest[..start]);
+        rest = rest[start..].find('}').map(|end| &rest[start + end + 1..]).unwrap_or("");
+    }
+    let last = rest;
+    let Some(mut remaining) = actual.strip_prefix(literals[0]) else {
+        return false;
+    };
+    for literal in &literals[1..] {
+        match remaining.find(literal) {
+            Some(position) if !literal.is_empty() => remaining = &remaining[position + literal.len()..],
+            Some(_) => {}
+            None => return false,
+        }
+    }
+    remaining.ends_with(last) && remaining.len() > last.len()
+}
+
+fn path_matches(template: &str, path: &str) -> bool {
+    let template: Vec<&str> = template.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    template.len() == path.len() && template.iter().zip(&path).all(|(t, p)| segment_matches(t, p))
+}
+
+/// Reads an OpenAPI document from a JSON or YAML file.
+fn read_document(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read spec {:?}", path))?;
+    match serde_json::from_str(&content) {
+        Ok(document) => Ok(document),
+        Err(_) => serde_yaml::from_str(&content).with_context(|| format!("Failed to parse spec {:?} as JSON or YAML", path)),
+    }
+}
+
+impl Spec {
+    /// Loads a spec from a JSON or YAML file.
+    pub fn load(path: &Path) -> Result<Spec> {
+        let document = read_document(path)?;
+        let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+            bail!("{:?} is not an OpenAPI document (no \"paths\")", path);
+        };
+        let mut operations = Vec::new();
+        for (template, item) in paths {
+            for method in METHODS {
+                let Some(operation) = item.get(*method) else {
+                    continue;
+                };
+                let responses = operation
+                    .get("responses")
+                    .and_then(Value::as_object)
+                    .map(|r| r.keys().cloned().collect())
+                    .unwrap_or_default();
+                operations.push(Operation {
+                    method: method.to_uppercase(),
+                    path: template.clone(),
+                    responses,
+                });
+            }
+        }
+        let mut base_paths: Vec<String> = document
+            .get("servers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server.get("url").and_then(Value::as_str))
+            .map(|url| match reqwest::Url::parse(url) {
+                Ok(url) => url.path().to_string(),
+                // 相対 URL（例: /v1）はそのままパスとして扱う
+                Err(_) => url.to_string(),
+            })
+            .chain(document.get("basePath").and_then(Value::as_str).map(str::to_string))
+            .map(|base| base.trim_end_matches('/').to_string())
+            .filter(|base| !base.is_empty())
+            .collect();
+        base_paths.sort_by_key(|base| std::cmp::Reverse(base.len()));
+        base_paths.dedup();
+        Ok(Spec { operations, base_paths })
+    }
+
+    /// Finds the operation documenting METHOD and URL (an absolute URL or a path).
+    pub fn find(&self, method: &str, url: &str) -> Option<&Operation> {
+        let path = request_path(url);
+        let mut candidates = vec![path.as_str()];
+        candidates.extend(self.base_paths.iter().filter_map(|base| {
+            path.strip_prefix(base.as_str()).filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        }));
+        self.operations
+            .iter()
+            .filter(|op| op.method.eq_ignore_ascii_case(method))
+            .filter(|op| candidates.iter().any(|candidate| path_matches(&op.path, candidate)))
+            .max_by_key(|op| op.specificity())
+    }
+}
+
+/// The path of URL without its query string.
+fn request_path(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => url.split(['?', '#']).next().unwrap_or("").to_string(),
+    }
+}
+
+/// What was exercised for one documented operation.
+#[derive(Debug, Default)]
+pub struct Usage {
+    pub calls: usize,
+    pub statuses: BTreeMap<u16, usize>,
+}
+
+/// Observed traffic cross-checked against a spec.
+pub struct Coverage<'a> {
+    pub spec: &'a Spec,
+    /// Usage per operation, indexed like `spec.operations`.
+    pub usage: Vec<Usage>,
+    /// Requests matching no documented operation, keyed by "METHOD /path".
+    pub undocumented: BTreeMap<String, usize>,
+}
+
+impl<'a> Coverage<'a> {
+    pub fn new(spec: &'a Spec) -> Coverage<'a> {
+        Coverage {
+            spec,
+            usage: spec.operations.iter().map(|_| Usage::default()).collect(),
+            undocumented: BTreeMap::new(),
+        }
+    }
+
+    /// Records one observed request and its status (None if no response was received).
+    pub fn record(&mut self, method: &str, url: &str, status: Option<u16>) {
+        match self.spec.find(method, url) {
+            Some(operation) => {
+                let index = self
+                    .spec
+                    .operations
+                    .iter()
+                    .position(|op| std::ptr::eq(op, operation))
+                    .unwrap_or_default();
+                let usage = &mut self.usage[index];
+                usage.calls += 1;
+                if let Some(status) = status {
+                    *usage.statuses.entry(status).or_default() += 1;
+                }
+            }
+            None => {
+                *self
+                    .undocumented
+                    .entry(format!("{} {}", method.to_uppercase(), request_path(url)))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    /// Number of documented operations exercised at least once.
+    pub fn exercised(&self) -> usize {
+        self.usage.iter().filter(|usage| usage.calls > 0).count()
+    }
+
+    /// Observed statuses that the spec does not document, per operation.
+    pub fn undocumented_statuses(&self) -> Vec<(&Operation, u16, usize)> {
+        self.spec
+            .operations
+            .iter()
+            .zip(&self.usage)
+            .flat_map(|(operation, usage)| {
+                usage
+                    .statuses
+                    .iter()
+                    .filter(|(status, _)| !operation.documents(**status))
+                    .map(move |(status, count)| (operation, *status, *count))
+            })
+            .collect()
+    }
+}
+
+/// Subcommands of `import` for formats other than export bundles.
+#[derive(clap::Subcommand, Debug)]
+pub enum ImportCommand {
+    /// OpenAPI 3（または Swagger 2）仕様の各パス・メソッドから保存済み設定を生成します。
+    Openapi {
+        /// OpenAPI 仕様ファイル（JSON または YAML）
+        spec: PathBuf,
+
+        /// 生成先の名前空間（例: SystemA）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: String,
+
+        /// 仕様の servers の代わりに使うベース URL（例: https://staging.example.com/v1）
+        #[arg(long = "base")]
+        base: Option<String>,
+
+        /// 既存のファイルを上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// curl のコマンドラインを保存済み設定に変換します（省略時は標準入力から読み込みます）。
+    Curl {
+        /// curl コマンド全体を引用符で囲んだもの（例: "curl -X POST https://... -d '{...}'"）
+        command: Option<String>,
+
+        /// 設定を表示する代わりに TARGET として保存します。
+        #[arg(long = "save", value_name = "TARGET")]
+        save: Option<String>,
+
+        /// 既存の設定を上書きします。
+        #[arg(long = "force")]
+        force: bool,
+    },
+}
+
+/// Follows local `$ref`s ("#/components/...") from VALUE to the referenced object.
+fn dereference<'a>(document: &'a Value, mut value: &'a Value) -> &'a Value {
+    // 循環参照で止まらないよう、たどる回数を制限する
+    for _ in 0..16 {
+        let target = value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|pointer| document.pointer(pointer));
+        match target {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+/// Builds an example value for SCHEMA: its `example`, `default` or first `enum` value,
+/// or else a placeholder of the right type, recursing into objects and arrays.
+fn example_for(document: &Value, schema: &Value, depth: usize) -> Value {
+    let schema = dereference(document, schema);
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return example.clone();
+    }
+    if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()) {
+        return first.clone();
+    }
+    if depth > 8 {
+        return Value::Null;
+    }
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = serde_json::Map::new();
+        for part in parts {
+            if let Value::Object(object) = example_for(document, part, depth + 1) {
+                merged.extend(object);
+            }
+        }
+        return Value::Object(merged);
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|keyword| schema.get(*keyword).and_then(Value::as_array).and_then(|options| options.first()))
+    {
+        return example_for(document, first, depth + 1);
+    }
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        // OpenAPI 3.1 の ["string", "null"] のような指定は最初の型を使う
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).find(|k| *k != "null").unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "",
+    };
+    match kind {
+        "object" => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(name, property)| (name.clone(), example_for(document, property, depth + 1)))
+                .collect(),
+        ),
+        "array" => match schema.get("items") {
+            Some(items) => Value::Array(vec![example_for(document, items, depth + 1)]),
+            None => Value::Array(Vec::new()),
+        },
+        "integer" | "number" => json!(0),
+        "boolean" => json!(false),
+        "string" => json!(match schema.get("format").and_then(Value::as_str) {
+            Some("date-time") => "2024-01-01T00:00:00Z",
+            Some("date") => "2024-01-01",
+            Some("email") => "user@example.com",
+            Some("uuid") => "00000000-0000-0000-0000-000000000000",
+            Some("uri") | Some("url") => "https://example.com",
+            _ => "string",
+        }),
+        _ => Value::Null,
+    }
+}
+
+/// Turns NAME into a `{{name}}` variable name (letters, digits, '_', '-' and '.').
+fn variable_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+/// The base URL of the API: `servers[0]` with its variables at their defaults, or
+/// scheme, host and basePath of a Swagger 2 document. None if only a relative path is given.
+fn base_url(document: &Value) -> Option<String> {
+    if let Some(server) = document.get("servers").and_then(Value::as_array).and_then(|servers| servers.first()) {
+        let mut url = server.get("url").and_then(Value::as_str)?.to_string();
+        for (name, variable) in server.get("variables").and_then(Value::as_object).into_iter().flatten() {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+        return url.starts_with("http").then(|| url.trim_end_matches('/').to_string());
+    }
+    let host = document.get("host").and_then(Value::as_str)?;
+    let scheme = document
+        .get("schemes")
+        .and_then(Value::as_array)
+        .and_then(|schemes| schemes.first())
+        .and_then(Value::as_str)
+        .unwrap_or("https");
+    let base_path = document.get("basePath").and_then(Value::as_str).unwrap_or("");
+    Some(format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/')))
+}
+
+/// Adds the credentials the operation's security requirement asks for as headers or
+/// query parameters with `{{scheme}}` placeholders, left for the user to define.
+fn apply_security(document: &Value, operation: &Value, headers: &mut HashMap<String, String>, query: &mut Vec<String>) {
+    let requirement = operation
+        .get("security")
+        .or_else(|| document.get("security"))
+        .and_then(Value::as_array)
+        .and_then(|requirements| requirements.first())
+        .and_then(Value::as_object);
+    let schemes = document
+        .pointer("/components/securitySchemes")
+        .or_else(|| document.get("securityDefinitions"));
+    for name in requirement.into_iter().flat_map(|requirement| requirement.keys()) {
+        let Some(scheme) = schemes.and_then(|schemes| schemes.get(name)).map(|s| dereference(document, s)) else {
+            continue;
+        };
+        let placeholder = format!("{{{{{}}}}}", variable_name(name));
+        let kind = scheme.get("type").and_then(Value::as_str).unwrap_or("");
+        let http_scheme = scheme.get("scheme").and_then(Value::as_str).unwrap_or("bearer");
+        match kind {
+            "apiKey" => {
+                let key = scheme.get("name").and_then(Value::as_str).unwrap_or(name);
+                match scheme.get("in").and_then(Value::as_str) {
+                    Some("query") => query.push(format!("{}={}", key, placeholder)),
+                    Some("header") => {
+                        headers.insert(key.to_string(), placeholder);
+                    }
+                    _ => {}
+                }
+            }
+            "http" if http_scheme.eq_ignore_ascii_case("basic") => {
+                headers.insert("Authorization".to_string(), format!("Basic {}", placeholder));
+            }
+            "basic" | "http" | "oauth2" | "openIdConnect" => {
+                let prefix = if kind == "basic" { "Basic" } else { "Bearer" };
+                headers.insert("Authorization".to_string(), format!("{} {}", prefix, placeholder));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Picks the JSON media type of a request body (or the first one) and its example.
+fn request_body(document: &Value, operation: &Value, parameters: &[&Value]) -> Option<(String, Value)> {
+    // Swagger 2 ではボディは in: body のパラメーター
+    if let Some(body) = parameters.iter().find(|p| p.get("in").and_then(Value::as_str) == Some("body")) {
+        let schema = body.get("schema").unwrap_or(&Value::Null);
+        return Some(("application/json".to_string(), example_for(document, schema, 0)));
+    }
+    let content = dereference(document, operation.get("requestBody")?).get("content")?.as_object()?;
+    let (media_type, media) = content
+        .iter()
+        .find(|(media_type, _)| media_type.contains("json"))
+        .or_else(|| content.iter().next())?;
+    let example = media
+        .get("example")
+        .cloned()
+        .or_else(|| {
+            let examples = media.get("examples")?.as_object()?;
+            let first = dereference(document, examples.values().next()?);
+            first.get("value").cloned()
+        })
+        .unwrap_or_else(|| example_for(document, media.get("schema").unwrap_or(&Value::Null), 0));
+    Some((media_type.clone(), example))
+}
+
+/// Writes a saved configuration for every operation in SPEC under NAMESPACE, with
+/// `{{name}}` placeholders for path, required query and header parameters whose
+/// example values go into the namespace's vars.json.
+fn import_openapi(spec: &Path, namespace: &str, base: Option<String>, force: bool) -> Result<()> {
+    let document = read_document(spec)?;
+    let Some(paths) = document.get("paths").and_then(Value::as_object) else {
+        bail!("{:?} is not an OpenAPI document (no \"paths\")", spec);
+    };
+    let namespace = namespace::normalize(namespace)?;
+    let base = base.map(|base| base.trim_end_matches('/').to_string()).or_else(|| base_url(&document));
+    let mut vars = Vars::new();
+    if base.is_none() {
+        vars.insert("base_url".to_string(), "http://localhost:8080".to_string());
+    }
+    let base = base.unwrap_or_else(|| "{{base_url}}".to_string());
+    let mut scaffold = generate::Scaffold::default();
+    let mut targets = vec![namespace.clone()];
+    let mut count = 0;
+    for (template, item) in paths {
+        let item = dereference(&document, item);
+        let segments: Vec<&str> = template.split('/').filter(|s| !s.is_empty()).collect();
+        let target = if segments.is_empty() {
+            namespace.clone()
+        } else {
+            namespace::normalize(&format!("{}/{}", namespace, segments.join("/")))?
+        };
+        for method in METHODS {
+            let Some(operation) = item.get(*method) else {
+                continue;
+            };
+            let parameters: Vec<&Value> = item
+                .get("parameters")
+                .and_then(Value::as_array)
+                .into_iter()
+                .chain(operation.get("parameters").and_then(Value::as_array))
+                .flatten()
+                .map(|parameter| dereference(&document, parameter))
+                .collect();
+            let mut path = template.clone();
+            let mut query = Vec::new();
+            let mut headers = HashMap::new();
+            for parameter in &parameters {
+                let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                let location = parameter.get("in").and_then(Value::as_str).unwrap_or("");
+                let required = parameter.get("required") == Some(&Value::Bool(true));
+                if location == "body" || (location != "path" && !required) {
+                    continue;
+                }
+                let variable = variable_name(name);
+                let placeholder = format!("{{{{{}}}}}", variable);
+                match location {
+                    "path" => path = path.replace(&format!("{{{}}}", name), &placeholder),
+                    "query" => query.push(format!("{}={}", name, placeholder)),
+                    "header" => {
+                        headers.insert(name.to_string(), placeholder);
+                    }
+                    _ => continue,
+                }
+                // Swagger 2 のパラメーターは schema を使わず型を直接持つ
+                let example = parameter
+                    .get("example")
+                    .cloned()
+                    .unwrap_or_else(|| example_for(&document, parameter.get("schema").unwrap_or(parameter), 0));
+                let value = match example {
+                    Value::String(text) => text,
+                    Value::Null => "1".to_string(),
+                    other => other.to_string(),
+                };
+                vars.entry(variable).or_insert(value);
+            }
+            apply_security(&document, operation, &mut headers, &mut query);
+            let data = match request_body(&document, operation, &parameters) {
+                Some((media_type, example)) => {
+                    headers.insert("Content-Type".to_string(), media_type);
+                    Some(example)
+                }
+                None => None,
+            };
+            let mut url = format!("{}{}", base, path);
+            if !query.is_empty() {
+                url = format!("{}?{}", url, query.join("&"));
+            }
+            let config = RequestConfig {
+                url: Some(url),
+                method: Some(method.to_uppercase()),
+                headers: Some(headers),
+                data,
+                timeout: Some(30),
+                ..RequestConfig::default()
+            };
+            scaffold.request(&target, config)?;
+            if !targets.contains(&target) {
+                targets.push(target.clone());
+            }
+            count += 1;
+        }
+    }
+    if count == 0 {
+        bail!("{:?} documents no operations", spec);
+    }
+    let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+    scaffold.write(&targets, force)?;
+    // 再インポートでも `vars set` 済みの値は上書きしない
+    if !vars.is_empty() {
+        let path = vars::add_missing(Some(&namespace), vars)?;
+        println!("{}", i18n::tf("  updated  {}", &[&format!("{:?}", path)]));
+    }
+    println!(
+        "{}",
+        i18n::tf("Imported {} operation(s) from {} into {}.", &[&count, &format!("{:?}", spec), &namespace])
+    );
+    println!(
+        "{}",
+        i18n::tf(
+            "Run them all with `ferrapi_tester run {}`; credentials for secured operations are left as {{name}} placeholders to define with `vars set`.",
+            &[&namespace]
+        )
+    );
+    Ok(())
+}
+
+pub fn import(command: ImportCommand) -> Result<()> {
+    match command {
+        ImportCommand::Openapi { spec, namespace, base, force } => import_openapi(&spec, &namespace, base, force),
+        ImportCommand::Curl { command, save, force } => curl::import(command.as_deref(), save.as_deref(), force),
+    }
+}
+
+/// Options of `contract`.
+#[derive(clap::Args, Debug)]
+pub struct ContractArgs {
+    /// OpenAPI 仕様ファイル（JSON または YAML）
+    spec: PathBuf,
+
+    /// 照合する記録済みセッション（複数指定可。proxy --record で記録したもの）
+    #[arg(long = "session")]
+    sessions: Vec<String>,
+
+    /// 履歴（history.jsonl）を照合の対象に含めません。
+    #[arg(long = "no-history")]
+    no_history: bool,
+
+    /// 履歴のうち対象とする名前空間または URL の接頭辞
+    #[arg(long = "target")]
+    target: Option<String>,
+
+    /// 履歴の対象期間（例: 7d）
+    #[arg(long = "since", value_parser = humantime::parse_duration)]
+    since: Option<Duration>,
+
+    /// 仕様に無いエンドポイントやステータスコードが使われていた場合にエラーで終了します。
+    #[arg(long = "strict")]
+    strict: bool,
+}
+
+pub fn contract(args: ContractArgs) -> Result<()> {
+    let spec = Spec::load(&args.spec)?;
+    let mut coverage = Coverage::new(&spec);
+    let mut observed = 0;
+    if !args.no_history {
+        for entry in stats::select(args.target.as_deref(), args.since)? {
+            coverage.record(entry.method(), entry.url(), entry.status);
+            observed += 1;
+        }
+    }
+    for name in &args.sessions {
+        for entry in session::load(&session::find(name)?)? {
+            let url = entry.request.url.as_deref().unwrap_or("");
+            coverage.record(entry.request.method.as_deref().unwrap_or("GET"), url, entry.status);
+            observed += 1;
+        }
+    }
+    if observed == 0 {
+        bail!("No recorded requests to check; send some requests or pass --session");
+    }
+
+    let total = spec.operations.len();
+    let exercised_percent = if total == 0 { 0.0 } else { coverage.exercised() as f64 / total as f64 * 100.0 };
+    println!(
+        "{}",
+        i18n::tf(
+            "{} request(s) checked. Documented operations exercised: {}/{} ({}%)",
+            &[&observed, &coverage.exercised(), &total, &format!("{:.1}", exercised_percent)]
+        )
+    );
+
+    let mut exercised: Vec<String> = Vec::new();
+    let mut never: Vec<String> = Vec::new();
+    for (operation, usage) in spec.operations.iter().zip(&coverage.usage) {
+        if usage.calls == 0 {
+            never.push(operation.label());
+        } else {
+            let statuses: Vec<String> = usage.statuses.keys().map(u16::to_string).collect();
+            exercised.push(format!(
+                "{:<40} {:>5} call(s)  {}",
+                operation.label(),
+                usage.calls,
+                if statuses.is_empty() { "no response".to_string() } else { statuses.join(", ") }
+            ));
+        }
+    }
+    let sections: [(&str, Vec<String>); 2] = [(i18n::t("Exercised"), exercised), (i18n::t("Never exercised"), never)];
+    for (title, lines) in sections {
+        if !lines.is_empty() {
+            println!("\n{}:", title);
+            for line in lines {
+                println!("  {}", line);
+            }
+        }
+    }
+    if !coverage.undocumented.is_empty() {
+        println!("{}", i18n::t("\nUndocumented endpoints in use:"));
+        for (endpoint, count) in &coverage.undocumented {
+            println!("  {:<40} {:>5} call(s)", endpoint, count);
+        }
+    }
+    let statuses = coverage.undocumented_statuses();
+    if !statuses.is_empty() {
+        println!("{}", i18n::t("\nUndocumented status codes:"));
+        for (operation, status, count) in &statuses {
+            println!("  {:<40} {} ({}x)", operation.label(), status, count);
+        }
+    }
+    if args.strict && (!coverage.undocumented.is_empty() || !statuses.is_empty()) {
+        bail!(
+            "{} undocumented endpoint(s) and {} undocumented status code(s) found",
+            coverage.undocumented.len(),
+            statuses.len()
+        );
+    }
+    Ok(())
+}
+
+/// Records a sent request for the coverage report, if one is being collected.
+pub fn observe(method: &str, url: &str, status: Option<u16>) {
+    if let Some(observed) = OBSERVED.get() {
+        observed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((method.to_string(), url.to_string(), status));
+    }
+}
+
+/// Coverage options of `run` and `run-all`.
+#[derive(clap::Args, Debug, Clone)]
+pub struct CoverageArgs {
+    /// 送信したリクエストを照合してカバレッジを報告する OpenAPI 仕様（省略時は settings.json の openapi）
+    #[arg(long = "spec")]
+    spec: Option<PathBuf>,
+
+    /// カバレッジレポートを HTML で書き出すファイル
+    #[arg(long = "coverage-html", value_hint = clap::ValueHint::FilePath)]
+    html: Option<PathBuf>,
+}
+
+/// Runs a suite and, when a spec is given or linked in the settings for TARGET,
+/// reports which operations and status codes it exercised. The report is printed
+/// even if the suite fails.
+pub async fn with_coverage<T>(
+    args: &CoverageArgs,
+    target: Option<&str>,
+    suite: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let spec_path = match args.spec {
+        Some(ref path) => Some(path.clone()),
+        None => Settings::for_target(target)?.openapi_path()?,
+    };
+    let Some(spec_path) = spec_path else {
+        if args.html.is_some() {
+            bail!("--coverage-html requires --spec or \"openapi\" in settings.json");
+        }
+        return suite.await;
+    };
+    let spec = Spec::load(&spec_path)?;
+    let _ = OBSERVED.set(Mutex::new(Vec::new()));
+    let result = suite.await;
+
+    let observed = OBSERVED
+        .get()
+        .map(|o| std::mem::take(&mut *o.lock().unwrap_or_else(|e| e.into_inner())))
+        .unwrap_or_default();
+    let mut coverage = Coverage::new(&spec);
+    for (method, url, status) in &observed {
+        coverage.record(method, url, *status);
+    }
+    print_coverage(&coverage, &spec_path);
+    if let Some(ref html) = args.html {
+        store::write_atomic(html, render_html(&coverage, &spec_path))?;
+        println!("{}", i18n::tf("Coverage report written to {}", &[&format!("{:?}", html)]));
+    }
+    result
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+/// Documented response keys and how many of them were exercised.
+fn response_counts(coverage: &Coverage) -> (usize, usize) {
+    let mut exercised = 0;
+    let mut total = 0;
+    for (operation, usage) in coverage.spec.operations.iter().zip(&coverage.usage) {
+        for response in &operation.responses {
+            total += 1;
+            if operation.response_exercised(response, &usage.statuses) {
+                exercised += 1;
+            }
+        }
+    }
+    (exercised, total)
+}
+
+fn print_coverage(coverage: &Coverage, spec_path: &Path) {
+    let operations = coverage.spec.operations.len();
+    let (responses_hit, responses) = response_counts(coverage);
+    println!(
+        "{}",
+        i18n::tf(
+            "\nAPI coverage against {}: operations {}/{} ({}%), responses {}/{} ({}%)",
+            &[
+                &format!("{:?}", spec_path),
+                &coverage.exercised(),
+                &operations,
+                &format!("{:.1}", percent(coverage.exercised(), operations)),
+                &responses_hit,
+                &responses,
+                &format!("{:.1}", percent(responses_hit, responses)),
+            ]
+        )
+    );
+    let mut gaps = Vec::new();
+    for (operation, usage) in coverage.spec.operations.iter().zip(&coverage.usage) {
+        let missing: Vec<&str> = operation
+            .responses
+            .iter()
+            .filter(|response| !operation.response_exercised(response, &usage.statuses))
+            .map(String::as_str)
+            .collect();
+        if usage.calls == 0 {
+            gaps.push(i18n::tf("{} (never called)", &[&operation.label()]));
+        } else if !missing.is_empty() {
+            gaps.push(i18n::tf("{}: {} not exercised", &[&operation.label(), &missing.join(", ")]));
+        }
+    }
+    if !gaps.is_empty() {
+        println!("{}", i18n::t("Not covered:"));
+        for gap in gaps {
+            println!("  {}", gap);
+        }
+    }
+    if !coverage.undocumented.is_empty() {
+        println!("{}", i18n::t("Undocumented endpoints called:"));
+        for (endpoint, count) in &coverage.undocumented {
+            println!("  {} ({}x)", endpoint, count);
+        }
+    }
+}
+
+/// Escapes TEXT for HTML text and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn render_html(coverage: &Coverage, spec_path: &Path) -> String {
+    let operations = coverage.spec.operations.len();
+    let (responses_hit, responses) = response_counts(coverage);
+    let mut rows = String::new();
+    for (operation, usage) in coverage.spec.operations.iter().zip(&coverage.usage) {
+        let cells: Vec<String> = operation
+            .responses
+            .iter()
+            .map(|response| {
+                let class = if operation.response_exercised(response, &usage.statuses) { "hit" } else { "miss" };
+                format!("<span class=\"{}\">{}</span>", class, escape(response))
+            })
+            .collect();
+        let observed: Vec<String> = usage.statuses.iter().map(|(s, n)| format!("{} ({}x)", s, n)).collect();
+        rows.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            if usage.calls > 0 { "hit" } else { "miss" },
+            escape(&operation.method),
+            escape(&operation.path),
+            usage.calls,
+            cells.join(" "),
+            escape(&observed.join(", "))
+        ));
+    }
+    let mut undocumented = String::new();
+    if !coverage.undocumented.is_empty() {
+        undocumented.push_str("<h2>Undocumented endpoints called</h2>\n<ul>\n");
+        for (endpoint, count) in &coverage.undocumented {
+            undocumented.push_str(&format!("<li>{} ({}x)</li>\n", escape(endpoint), count));
+        }
+        undocumented.push_str("</ul>\n");
+    }
+    format!(
+        r#"<!doctype html>
+<html><head><meta charset="utf-8"><title>API coverage</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}
+tr.miss td:first-child, tr.miss td:nth-child(2) {{ color: #b00; }}
+span {{ display: inline-block; padding: 1px 6px; margin: 1px; border-radius: 3px; }}
+span.hit {{ background: #cfc; }}
+span.miss {{ background: #fcc; }}
+</style></head>
+<body>
+<h1>API coverage</h1>
+<p>Spec: {}</p>
+<p>Operations: {}/{} ({:.1}%) &middot; Responses: {}/{} ({:.1}%)</p>
+<table>
+<tr><th>Method</th><th>Path</th><th>Calls</th><th>Documented responses</th><th>Observed statuses</th></tr>
+{}</table>
+{}</body></html>
+"#,
+        escape(&spec_path.display().to_string()),
+        coverage.exercised(),
+        operations,
+        percent(coverage.exercised(), operations),
+        responses_hit,
+        responses,
+        percent(responses_hit, responses),
+        rows,
+        undocumented
+    )
+}
@@ -0,0 +1,582 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::{BTreeMap, BTreeSet}, fs, io, path::Path, sync::OnceLock};
+
+use crate::assertion;
+use crate::cancel::Interrupted;
+use crate::diff::Mismatch;
+use crate::exit_code;
+use crate::filter::Filter;
+use crate::i18n;
+use crate::net_error::NetworkError;
+use crate::request::{Exchange, RequestConfig};
+use crate::settings::Settings;
+use crate::theme;
+
+/// Output format of responses and of command results such as `run-all` or `stats`.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Status line followed by the raw body; results as aligned columns.
+    #[default]
+    Human,
+    /// Bordered tables of array-of-objects responses and of results.
+    Table,
+    /// The body only; results as tab-separated rows without a header.
+    Minimal,
+    /// Array-of-objects responses and results converted to CSV.
+    Csv,
+    /// One JSON document with status, latency, headers, body and the resolved request;
+    /// failures are reported as JSON too.
+    Json,
+    /// A user template such as '{{status}} {{.id}}', rendered per response or result row.
+    Template,
+}
+
+/// Output options given on the command line; unset ones fall back to settings.json.
+#[derive(Debug, Default)]
+struct Selection {
+    format: Option<OutputFormat>,
+    template: Option<String>,
+    columns: Vec<String>,
+    /// -i: show the response headers along with the body.
+    headers: bool,
+}
+
+static SELECTION: OnceLock<Selection> = OnceLock::new();
+
+/// Sets the output options given on the command line for the rest of the process.
+pub fn select(format: Option<OutputFormat>, template: Option<String>, columns: Vec<String>, headers: bool) {
+    let _ = SELECTION.set(Selection { format, template, columns, headers });
+}
+
+fn selection() -> &'static Selection {
+    SELECTION.get_or_init(Selection::default)
+}
+
+/// Returns the effective output format: --output-format, then settings.json, then human.
+pub fn format() -> OutputFormat {
+    selection()
+        .format
+        .or_else(|| Settings::load().ok().and_then(|settings| settings.output_format))
+        .unwrap_or_default()
+}
+
+/// Returns the renderer for the effective output format.
+pub fn renderer() -> Result<Box<dyn Renderer>> {
+    let selection = selection();
+    Ok(match format() {
+        OutputFormat::Human => Box::new(Human),
+        OutputFormat::Table => Box::new(Bordered { columns: selection.columns.clone() }),
+        OutputFormat::Minimal => Box::new(Minimal),
+        OutputFormat::Csv => Box::new(Csv { columns: selection.columns.clone() }),
+        OutputFormat::Json => Box::new(Json),
+        OutputFormat::Template => {
+            let template = match selection.template {
+                Some(ref template) => template.clone(),
+                None => Settings::load()?.output_template.context(
+                    "--output-format template requires --template or \"output_template\" in settings.json",
+                )?,
+            };
+            Box::new(Template { template })
+        }
+    })
+}
+
+/// Tabular command results. Cells are JSON values so machine-readable formats keep
+/// their types; null cells are shown as "-" to people.
+#[derive(Debug, Default)]
+pub struct Table {
+    /// (title, key) of each column: titles are shown to people, keys name JSON fields and CSV columns.
+    columns: Vec<(&'static str, &'static str)>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(columns: &[(&'static str, &'static str)]) -> Table {
+        Table {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Numeric columns are right-aligned.
+    fn numeric(&self, column: usize) -> bool {
+        self.rows.iter().any(|row| row[column].is_number())
+            && self.rows.iter().all(|row| matches!(row[column], Value::Number(_) | Value::Null))
+    }
+
+    fn titles(&self) -> Vec<String> {
+        self.columns.iter().map(|(title, _)| title.to_string()).collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.columns.iter().map(|(_, key)| key.to_string()).collect()
+    }
+
+    /// The rows as JSON objects keyed by column key.
+    fn objects(&self) -> Vec<Value> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let object: Map<String, Value> =
+                    self.columns.iter().zip(row).map(|((_, key), value)| (key.to_string(), value.clone())).collect();
+                Value::Object(object)
+            })
+            .collect()
+    }
+}
+
+/// Renders responses and command results in one output format.
+pub trait Renderer {
+    /// Renders the response of a single request.
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String>;
+
+    /// Renders tabular command results.
+    fn table(&self, table: &Table) -> Result<String>;
+
+    /// True for output meant for people. Other renderers keep stdout for the rendered
+    /// document, so summaries and progress go to stderr.
+    fn human(&self) -> bool {
+        false
+    }
+
+    /// Prints a summary or progress line next to the rendered output.
+    fn note(&self, text: &str) {
+        if self.human() {
+            println!("{}", text);
+        } else {
+            eprintln!("{}", text);
+        }
+    }
+
+    /// Prints TABLE to stdout, or EMPTY to people when there are no rows.
+    fn print(&self, table: &Table, empty: &str) -> Result<()> {
+        if table.is_empty() && self.human() {
+            println!("{}", empty);
+        } else {
+            print!("{}", self.table(table)?);
+        }
+        Ok(())
+    }
+}
+
+/// The response headers as `name: value` lines, in the order they were received; None
+/// unless -i was given.
+fn header_lines(exchange: &Exchange) -> Option<String> {
+    if !selection().headers {
+        return None;
+    }
+    let lines: String = exchange
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\n", name, String::from_utf8_lossy(value.as_bytes())))
+        .collect();
+    Some(lines)
+}
+
+/// Displays a cell for people.
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Status line and raw body; aligned columns without borders.
+struct Human;
+
+impl Renderer for Human {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        let status = theme::status(
+            exchange.status.as_u16(),
+            &i18n::tf("Response Status: {}", &[&exchange.status]),
+        );
+        let headers = match header_lines(exchange) {
+            Some(lines) => format!("{}\n{}", i18n::t("Response Headers:"), lines),
+            None => String::new(),
+        };
+        Ok(format!("{}\n{}{}\n{}\n", status, headers, i18n::t("Response Body:"), theme::json(text)))
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        let cells: Vec<Vec<String>> = table.rows.iter().map(|row| row.iter().map(display).collect()).collect();
+        let titles = table.titles();
+        let statuses: Vec<bool> = table.columns.iter().map(|(_, key)| key.ends_with("status")).collect();
+        let widths: Vec<usize> = (0..titles.len())
+            .map(|i| cells.iter().map(|row| row[i].chars().count()).chain([titles[i].chars().count()]).max().unwrap_or(0))
+            .collect();
+        let line = |row: &[String], values: Option<&Vec<Value>>| {
+            let padded: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let padded = match (table.numeric(i), i + 1 == row.len()) {
+                        (true, _) => format!("{:>width$}", cell, width = widths[i]),
+                        (false, true) => cell.clone(),
+                        (false, false) => format!("{:<width$}", cell, width = widths[i]),
+                    };
+                    // 幅を揃えた後で色を付ける（エスケープシーケンスを幅に数えない）
+                    match values.and_then(|values| values[i].as_u64()) {
+                        Some(status) if statuses[i] => theme::status(status as u16, &padded),
+                        _ => padded,
+                    }
+                })
+                .collect();
+            format!("{}\n", padded.join("  "))
+        };
+        let mut rendered = line(&titles, None);
+        for (row, values) in cells.iter().zip(&table.rows) {
+            rendered.push_str(&line(row, Some(values)));
+        }
+        Ok(rendered)
+    }
+
+    fn human(&self) -> bool {
+        true
+    }
+}
+
+/// Bordered tables; a response body must be a JSON array of objects or an object.
+struct Bordered {
+    columns: Vec<String>,
+}
+
+fn bordered(titles: &[String], cells: &[Vec<String>], right: impl Fn(usize) -> bool) -> String {
+    let widths: Vec<usize> = (0..titles.len())
+        .map(|i| cells.iter().map(|row| row[i].chars().count()).chain([titles[i].chars().count()]).max().unwrap_or(0))
+        .collect();
+    let rule = format!("+{}+\n", widths.iter().map(|w| "-".repeat(w + 2)).collect::<Vec<_>>().join("+"));
+    let line = |row: &[String], header: bool| {
+        let padded: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if right(i) && !header {
+                    format!(" {:>width$} ", cell, width = widths[i])
+                } else {
+                    format!(" {:<width$} ", cell, width = widths[i])
+                }
+            })
+            .collect();
+        format!("|{}|\n", padded.join("|"))
+    };
+    let mut rendered = rule.clone();
+    rendered.push_str(&line(titles, true));
+    rendered.push_str(&rule);
+    for row in cells {
+        rendered.push_str(&line(row, false));
+    }
+    rendered.push_str(&rule);
+    rendered
+}
+
+impl Renderer for Bordered {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        // 表を標準出力に出すため、ステータスは標準エラーに出力
+        eprintln!("{}", i18n::tf("Response Status: {}", &[&exchange.status]));
+        if let Some(lines) = header_lines(exchange) {
+            eprint!("{}", lines);
+        }
+        let body: Value =
+            serde_json::from_str(text).with_context(|| "Table output requires a JSON response body")?;
+        let (titles, cells) = match body {
+            Value::Array(_) => object_rows(&body, &self.columns)?,
+            Value::Object(ref map) => (
+                vec!["KEY".to_string(), "VALUE".to_string()],
+                map.iter().map(|(key, value)| vec![key.clone(), cell(Some(value))]).collect(),
+            ),
+            _ => bail!("Table output requires a JSON array or object response"),
+        };
+        Ok(bordered(&titles, &cells, |_| false))
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        let cells: Vec<Vec<String>> = table.rows.iter().map(|row| row.iter().map(display).collect()).collect();
+        Ok(bordered(&table.titles(), &cells, |i| table.numeric(i)))
+    }
+}
+
+/// Only the body, or tab-separated rows without a header, for piping into other tools.
+struct Minimal;
+
+impl Renderer for Minimal {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        let body = if text.ends_with('\n') { text.to_string() } else { format!("{}\n", text) };
+        // curl -i と同じく、ヘッダーと空行の後にボディを続ける
+        Ok(match header_lines(exchange) {
+            Some(lines) => format!("{}\n{}", lines, body),
+            None => body,
+        })
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        Ok(table
+            .rows
+            .iter()
+            .map(|row| format!("{}\n", row.iter().map(|value| cell(Some(value))).collect::<Vec<_>>().join("\t")))
+            .collect())
+    }
+}
+
+struct Csv {
+    columns: Vec<String>,
+}
+
+impl Renderer for Csv {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        // CSV は標準出力を汚さないよう、ステータスは標準エラーに出力
+        eprintln!("{}", i18n::tf("Response Status: {}", &[&exchange.status]));
+        let body: Value =
+            serde_json::from_str(text).with_context(|| "CSV output requires a JSON response body")?;
+        render_csv(&body, &self.columns)
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        render_csv(&Value::Array(table.objects()), &table.keys())
+    }
+}
+
+struct Json;
+
+impl Renderer for Json {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        render_json(exchange, text)
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        Ok(format!("{}\n", serde_json::to_string_pretty(&table.objects())?))
+    }
+}
+
+/// Fills `{{name}}` placeholders: `{{status}}`, `{{elapsed_ms}}` (or `{{latency_ms}}`), `{{body}}`,
+/// `{{header.NAME}}` and `{{.path}}` filters for responses, column keys for result rows.
+struct Template {
+    template: String,
+}
+
+impl Template {
+    fn fill(&self, lookup: impl Fn(&str) -> Result<Option<String>>) -> Result<String> {
+        let mut rendered = String::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .map(|offset| start + offset)
+                .context("Unterminated '{{' placeholder in the output template")?;
+            rendered.push_str(&rest[..start]);
+            let name = rest[start + 2..end].trim();
+            rendered.push_str(&lookup(name)?.unwrap_or_default());
+            rest = &rest[end + 2..];
+        }
+        rendered.push_str(rest);
+        if !rendered.ends_with('\n') {
+            rendered.push('\n');
+        }
+        Ok(rendered)
+    }
+}
+
+impl Renderer for Template {
+    fn response(&self, exchange: &Exchange, text: &str) -> Result<String> {
+        let body = serde_json::from_str::<Value>(text).ok();
+        self.fill(|name| {
+            Ok(match name {
+                "status" => Some(exchange.status.as_u16().to_string()),
+                "elapsed_ms" | "latency_ms" => Some(exchange.elapsed.as_millis().to_string()),
+                "body" => Some(text.to_string()),
+                _ if name.starts_with(['.', '$']) => {
+                    let body = body.as_ref().context("'{{.path}}' placeholders require a JSON response body")?;
+                    Some(cell(Some(&Filter::parse(name)?.apply(body))))
+                }
+                _ => name
+                    .strip_prefix("header.")
+                    .and_then(|header| exchange.headers.get(header))
+                    .map(|value| value.to_str().unwrap_or_default().to_string()),
+            })
+        })
+    }
+
+    fn table(&self, table: &Table) -> Result<String> {
+        let mut rendered = String::new();
+        for row in &table.rows {
+            rendered.push_str(&self.fill(|name| {
+                Ok(table
+                    .columns
+                    .iter()
+                    .position(|(_, key)| *key == name)
+                    .map(|i| cell(Some(&row[i]))))
+            })?);
+        }
+        Ok(rendered)
+    }
+}
+
+/// Looks up a dotted path (e.g. "user.name") inside a JSON object.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| current.get(segment))
+}
+
+/// Converts a JSON value into a single CSV cell.
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Splits an array-of-objects JSON value into column names and rows of cells.
+/// If `columns` is empty, the union of top-level keys is used in sorted order.
+fn object_rows(body: &Value, columns: &[String]) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let rows = match body {
+        Value::Array(rows) => rows,
+        _ => bail!("This output format requires a JSON array response"),
+    };
+    if rows.iter().any(|row| !row.is_object()) {
+        bail!("This output format requires every array element to be a JSON object");
+    }
+    let columns: Vec<String> = if columns.is_empty() {
+        // serde_json の Map はキーをソート順に保持するため、出現順ではなくソート順に揃える
+        let keys: BTreeSet<&String> = rows.iter().filter_map(Value::as_object).flat_map(|map| map.keys()).collect();
+        keys.into_iter().cloned().collect()
+    } else {
+        columns.to_vec()
+    };
+    let cells = rows
+        .iter()
+        .map(|row| columns.iter().map(|column| cell(lookup(row, column))).collect())
+        .collect();
+    Ok((columns, cells))
+}
+
+/// Renders an array-of-objects JSON value as CSV.
+pub fn render_csv(body: &Value, columns: &[String]) -> Result<String> {
+    let (columns, rows) = object_rows(body, columns)?;
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    let bytes = writer.into_inner().context("Failed to flush CSV output")?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// The resolved request of the JSON document: method, URL, headers and body.
+fn request_json(config: &RequestConfig) -> Value {
+    let headers: BTreeMap<&String, &String> = config.headers.iter().flatten().collect();
+    let body = match (&config.data, &config.form, &config.form_urlencoded) {
+        (Some(data), _, _) => data.clone(),
+        (None, Some(form), _) | (None, None, Some(form)) => json!(form),
+        (None, None, None) => Value::Null,
+    };
+    json!({
+        "method": config.method.as_deref().unwrap_or("GET"),
+        "url": config.url,
+        "headers": headers,
+        "body": body,
+    })
+}
+
+/// Renders the exchange as a JSON document. A JSON body is embedded as JSON,
+/// anything else as a string.
+pub fn render_json(exchange: &Exchange, text: &str) -> Result<String> {
+    let headers: Map<String, Value> = exchange
+        .headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), json!(value.to_str().unwrap_or_default())))
+        .collect();
+    let body = serde_json::from_str::<Value>(text).unwrap_or_else(|_| json!(text));
+    let elapsed_ms = exchange.elapsed.as_millis() as u64;
+    // elapsed_ms は以前からのスクリプトのために残している
+    let document = json!({
+        "status": exchange.status.as_u16(),
+        "latency_ms": elapsed_ms,
+        "elapsed_ms": elapsed_ms,
+        "headers": headers,
+        "body": body,
+        "request": exchange.request.as_ref().map(request_json),
+    });
+    Ok(format!("{}\n", serde_json::to_string_pretty(&document)?))
+}
+
+/// Describes a failure as JSON so automation can branch on `kind` and `phase`
+/// instead of parsing messages. `phase` is null when it cannot be determined.
+pub fn error_report(err: &anyhow::Error, exit_code: i32) -> Value {
+    let (kind, phase, hint) = if let Some(network) = err.downcast_ref::<NetworkError>() {
+        (network.kind.code(), Some(network.kind.phase()), network.hint())
+    } else if err.is::<Interrupted>() {
+        ("interrupted", None, None)
+    } else if err.is::<Mismatch>() {
+        ("body_mismatch", Some("verify"), None)
+    } else if err.is::<assertion::Failed>() {
+        ("assertion_failed", Some("verify"), None)
+    } else if let Some(classified) = err.chain().find_map(|cause| cause.downcast_ref::<exit_code::Classified>()) {
+        let phase = (classified.code == exit_code::ASSERTION).then_some("verify");
+        (exit_code::name(classified.code), phase, None)
+    } else if err.chain().any(|cause| cause.is::<serde_json::Error>()) {
+        ("invalid_json", None, None)
+    } else if err.chain().any(|cause| cause.is::<io::Error>()) {
+        ("io", None, None)
+    } else {
+        ("error", None, None)
+    };
+    json!({
+        "error": {
+            "kind": kind,
+            "phase": phase,
+            "message": err.to_string(),
+            "causes": err.chain().skip(1).map(|cause| cause.to_string()).collect::<Vec<_>>(),
+            "hint": hint,
+            "exit_code": exit_code,
+        }
+    })
+}
+
+/// Writes a response body to PATH byte for byte, creating its directory if needed.
+pub fn write_body(path: &Path, body: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    fs::write(path, body).with_context(|| format!("Failed to write the response body to {:?}", path))
+}
+
+/// Writes rendered output to the given file, or to stdout when no file is given.
+pub fn emit(rendered: &str, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)
+                .with_context(|| format!("Failed to write output to {:?}", path))?;
+            println!("{}", i18n::tf("Output written to {}", &[&format!("{:?}", path)]));
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_columns_default_to_the_sorted_union_of_keys() {
+        let body = json!([{"name": "Alice", "id": 1}, {"email": "bob@example.com", "id": 2}]);
+        let csv = render_csv(&body, &[]).unwrap();
+        assert_eq!(csv, "email,id,name\n,1,Alice\nbob@example.com,2,\n");
+        let columns = ["name".to_string(), "id".to_string()];
+        assert_eq!(render_csv(&body, &columns).unwrap(), "name,id\nAlice,1\n,2\n");
+    }
+}
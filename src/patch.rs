@@ -0,0 +1,143 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+use crate::filter::{self, Segment};
+
+/// A single `--set` or `--unset` edit of the request body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Patch {
+    Set(Vec<Segment>, Value),
+    Unset(Vec<Segment>),
+}
+
+/// Index of the first '=' outside a quoted key.
+fn assignment(expr: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in expr.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '=' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses `--set` expressions: `.path=text` assigns a string, `.path:=json` a JSON value.
+pub fn parse_set(expr: &str) -> Result<Patch> {
+    let eq = assignment(expr).with_context(|| format!("--set expects PATH=VALUE or PATH:=JSON: {}", expr))?;
+    let (path, value) = (&expr[..eq], &expr[eq + 1..]);
+    let (path, value) = match path.strip_suffix(':') {
+        Some(path) => (
+            path,
+            serde_json::from_str(value).with_context(|| format!("Invalid JSON value in --set {}", expr))?,
+        ),
+        None => (path, Value::String(value.to_string())),
+    };
+    Ok(Patch::Set(parse(path)?, value))
+}
+
+pub fn parse_unset(expr: &str) -> Result<Patch> {
+    Ok(Patch::Unset(parse(expr)?))
+}
+
+fn parse(path: &str) -> Result<Vec<Segment>> {
+    let segments = filter::parse_path(path)?;
+    if segments.is_empty() {
+        bail!("Path must name a field, e.g. .user.name: {}", path);
+    }
+    if segments.contains(&Segment::Iterate) {
+        bail!("'[]' is not supported in body patches: {}", path);
+    }
+    Ok(segments)
+}
+
+/// Resolves a possibly negative index against an array of `len` elements.
+fn position(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    usize::try_from(index).ok()
+}
+
+fn set(target: &mut Value, path: &[Segment], value: Value) -> Result<()> {
+    let Some((first, rest)) = path.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    // 途中のフィールドが無い場合はオブジェクトを作成する
+    if target.is_null() {
+        *target = match first {
+            Segment::Index(_) => Value::Array(Vec::new()),
+            _ => Value::Object(Map::new()),
+        };
+    }
+    match (first, target) {
+        (Segment::Key(key), Value::Object(map)) => set(map.entry(key.clone()).or_insert(Value::Null), rest, value),
+        (Segment::Index(index), Value::Array(items)) => {
+            let len = items.len();
+            match position(*index, len) {
+                Some(i) if i < len => set(&mut items[i], rest, value),
+                // 末尾の次の位置への代入は追加として扱う
+                Some(i) if i == len => {
+                    items.push(Value::Null);
+                    set(&mut items[i], rest, value)
+                }
+                _ => bail!("Index {} is out of range for an array of {} element(s)", index, len),
+            }
+        }
+        (Segment::Key(key), other) => bail!("Cannot set field '{}' on {}", key, kind(other)),
+        (_, other) => bail!("Cannot index into {}", kind(other)),
+    }
+}
+
+fn unset(target: &mut Value, path: &[Segment]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    match (first, target) {
+        (Segment::Key(key), Value::Object(map)) if rest.is_empty() => {
+            map.remove(key);
+        }
+        (Segment::Key(key), Value::Object(map)) => {
+            if let Some(next) = map.get_mut(key) {
+                unset(next, rest);
+            }
+        }
+        (Segment::Index(index), Value::Array(items)) => {
+            if let Some(i) = position(*index, items.len()).filter(|&i| i < items.len()) {
+                if rest.is_empty() {
+                    items.remove(i);
+                } else {
+                    unset(&mut items[i], rest);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Applies the patches to the body in order. A missing body starts as an empty object;
+/// unsetting a field that does not exist is not an error.
+pub fn apply(body: Option<Value>, patches: &[Patch]) -> Result<Option<Value>> {
+    if patches.is_empty() {
+        return Ok(body);
+    }
+    let mut body = body.unwrap_or(Value::Null);
+    for patch in patches {
+        match patch {
+            Patch::Set(path, value) => set(&mut body, path, value.clone())?,
+            Patch::Unset(path) => unset(&mut body, path),
+        }
+    }
+    Ok(Some(body))
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// Characters that only make sense in a regular expression; a pattern without them
+/// is read as a shell-style wildcard pattern.
+const REGEX_ONLY: &[char] = &['.', '^', '$', '(', ')', '[', ']', '{', '}', '+', '|', '\\'];
+
+/// Selects tests by name with `--filter` patterns. A name is selected when any
+/// pattern is found anywhere in it.
+#[derive(Debug, Default)]
+pub struct NameFilter {
+    patterns: Vec<Regex>,
+}
+
+/// Compiles one pattern: a regular expression such as `users.*create`, or a
+/// wildcard pattern such as `SystemA/*/POST` where `*` and `?` are the only specials.
+fn compile(pattern: &str) -> Result<Regex> {
+    let source = if pattern.contains(REGEX_ONLY) {
+        pattern.to_string()
+    } else {
+        pattern
+            .split('*')
+            .map(|part| part.split('?').map(regex::escape).collect::<Vec<_>>().join("."))
+            .collect::<Vec<_>>()
+            .join(".*")
+    };
+    Regex::new(&source).with_context(|| format!("Invalid --filter pattern '{}'", pattern))
+}
+
+impl NameFilter {
+    pub fn parse(patterns: &[String]) -> Result<NameFilter> {
+        Ok(NameFilter {
+            patterns: patterns.iter().map(|p| compile(p)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// True when NAME is selected; everything is selected without patterns.
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|pattern| pattern.is_match(name))
+    }
+}
+
+/// The hierarchical name of a saved configuration in reports and for `--filter`,
+/// e.g. "SystemA/users/create POST".
+pub fn config_name(target: &str, method: &str) -> String {
+    format!("{} {}", target, method)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selects(patterns: &[&str], name: &str) -> bool {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        NameFilter::parse(&patterns).unwrap().matches(name)
+    }
+
+    #[test]
+    fn patterns_are_wildcards_or_regexes() {
+        let name = config_name("SystemA/users/create", "POST");
+        assert!(selects(&[], &name));
+        assert!(selects(&["users.*create"], &name));
+        assert!(selects(&["SystemA/*/create"], &name));
+        assert!(selects(&["c?eate POST"], &name));
+        assert!(selects(&["orders", "^SystemA/"], &name));
+        assert!(!selects(&["SystemB/*"], &name));
+        assert!(!selects(&["users$"], &name));
+        assert!(NameFilter::parse(&["users(".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use anyhow::{bail, Context, Result};
+use hyper::{
+    body::{self, Body},
+    server::conn::Http,
+    service::{make_service_fn, service_fn},
+    Method, Request, Response, Server, StatusCode,
+};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa, KeyPair, KeyUsagePurpose};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsAcceptor};
+
+use crate::cancel;
+use crate::get_default_dir;
+use crate::history;
+use crate::i18n;
+use crate::namespace;
+use crate::net_error::NetworkError;
+use crate::request::{Exchange, RequestConfig};
+use crate::session::{self, SessionEntry};
+use crate::store;
+use crate::vars;
+
+/// Directory (under the base directory) holding the proxy's certificate authority.
+const CA_DIR: &str = ".proxy";
+
+const CA_NAME: &str = "ferrapi_tester proxy CA";
+
+/// Headers that only apply to a single connection and are never forwarded or saved.
+pub const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "proxy-authorization",
+    "proxy-authenticate",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// Options of `proxy`.
+#[derive(clap::Args, Debug)]
+pub struct ProxyArgs {
+    /// 待ち受けるポート
+    #[arg(long = "port", default_value = "8888")]
+    port: u16,
+
+    /// 通過したリクエストを設定・履歴・セッションとして保存する名前空間（例: SystemA）
+    #[arg(long = "record")]
+    record: Option<String>,
+
+    /// HTTPS 通信も復号して記録します（生成される CA 証明書をクライアントに信頼させる必要があります）
+    #[arg(long = "mitm")]
+    mitm: bool,
+
+    /// 転送先の TLS 証明書を検証しません。
+    #[arg(short = 'k', long = "insecure")]
+    insecure: bool,
+}
+
+/// The local certificate authority, used to issue a certificate per HTTPS host
+/// intercepted by the proxy and for the mock server's `--tls`.
+pub struct Authority {
+    cert: Certificate,
+    key: KeyPair,
+    /// PEM of the CA certificate as installed by the user, appended to each chain.
+    pem: String,
+    acceptors: Mutex<HashMap<String, TlsAcceptor>>,
+}
+
+fn ca_params() -> Result<CertificateParams> {
+    let mut params = CertificateParams::new(Vec::<String>::new())?;
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.distinguished_name.push(DnType::CommonName, CA_NAME);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign, KeyUsagePurpose::DigitalSignature];
+    Ok(params)
+}
+
+impl Authority {
+    /// Loads the CA from the base directory, creating it on first use.
+    pub fn load_or_create() -> Result<(Authority, PathBuf)> {
+        let dir = get_default_dir()?.join(CA_DIR);
+        let cert_path = dir.join("ca.pem");
+        let key_path = dir.join("ca.key");
+        let key = if key_path.exists() {
+            let pem = fs::read_to_string(&key_path).with_context(|| format!("Failed to read {:?}", key_path))?;
+            KeyPair::from_pem(&pem).with_context(|| format!("Failed to parse {:?}", key_path))?
+        } else {
+            let key = KeyPair::generate()?;
+            store::write_private(&key_path, key.serialize_pem())?;
+            let _ = fs::remove_file(&cert_path);
+            key
+        };
+        // 発行者として必要なのは名前と鍵だけなので、証明書は毎回同じ内容で作り直す
+        let cert = ca_params()?.self_signed(&key)?;
+        if !cert_path.exists() {
+            store::write_atomic(&cert_path, cert.pem())?;
+        }
+        let pem = fs::read_to_string(&cert_path).with_context(|| format!("Failed to read {:?}", cert_path))?;
+        let authority = Authority {
+            cert,
+            key,
+            pem,
+            acceptors: Mutex::new(HashMap::new()),
+        };
+        Ok((authority, cert_path))
+    }
+
+    /// Returns a TLS acceptor presenting a certificate for HOST signed by this CA.
+    fn acceptor(&self, host: &str) -> Result<TlsAcceptor> {
+        let mut acceptors = self.acceptors.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(acceptor) = acceptors.get(host) {
+            return Ok(acceptor.clone());
+        }
+        let acceptor = self.issue(&[host])?;
+        acceptors.insert(host.to_string(), acceptor.clone());
+        Ok(acceptor)
+    }
+
+    /// Returns a TLS acceptor presenting a new certificate for NAMES (host names or
+    /// IP addresses) signed by this CA; the first name is the subject.
+    pub fn issue(&self, names: &[&str]) -> Result<TlsAcceptor> {
+        let mut params = CertificateParams::new(names.iter().map(|name| name.to_string()).collect::<Vec<_>>())?;
+        params.distinguished_name.push(DnType::CommonName, names[0]);
+        let key = KeyPair::generate()?;
+        let cert = params.signed_by(&key, &self.cert, &self.key)?;
+        let chain = format!("{}{}", cert.pem(), self.pem);
+        let identity = native_tls::Identity::from_pkcs8(chain.as_bytes(), key.serialize_pem().as_bytes())
+            .context("Failed to load the generated certificate")?;
+        Ok(TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?))
+    }
+}
+
+struct Proxy {
+    client: reqwest::Client,
+    record: Option<String>,
+    session: Option<PathBuf>,
+    authority: Option<Authority>,
+    started: Instant,
+}
+
+fn text_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+/// Maps a recorded URL to a TARGET below the recording namespace: host, then path segments.
+fn record_target(record: &str, url: &reqwest::Url) -> Result<String> {
+    let mut segments = namespace::segments(record)?;
+    let host = match url.port() {
+        Some(port) => format!("{}_{}", url.host_str().unwrap_or("unknown"), port),
+        None => url.host_str().unwrap_or("unknown").to_string(),
+    };
+    segments.push(host);
+    for segment in url.path().split('/').filter(|s| !s.is_empty() && *s != ".") {
+        // '.' で始まる名前は内部用なので先頭に '_' を付ける
+        segments.push(if segment.starts_with('.') {
+            format!("_{}", segment)
+        } else {
+            segment.to_string()
+        });
+    }
+    Ok(namespace::format_target(&segments))
+}
+
+/// Drops the header values and the body of CONFIG that contain `{{`. Websites the browser
+/// visits can set cookies and bodies, and in a saved config such text would be resolved as
+/// placeholders (e.g. `{{cmd:...}}`) when the request is sent again.
+fn without_placeholders(config: &RequestConfig) -> RequestConfig {
+    let mut config = config.clone();
+    if let Some(ref mut headers) = config.headers {
+        headers.retain(|name, value| {
+            let keep = !vars::has_placeholder(value);
+            if !keep {
+                eprintln!("{}", i18n::tf("Header {} contains '{{' and is not recorded.", &[name]));
+            }
+            keep
+        });
+    }
+    if config.data.as_ref().is_some_and(|data| vars::has_placeholder(&data.to_string())) {
+        eprintln!("{}", i18n::t("The request body contains '{{' and is not recorded."));
+        config.data = None;
+    }
+    config
+}
+
+impl Proxy {
+    /// Forwards one request upstream and records the exchange.
+    async fn forward(&self, request: Request<Body>, https_authority: Option<&str>) -> Result<Response<Body>> {
+        let url = match https_authority {
+            Some(authority) => format!(
+                "https://{}{}",
+                authority,
+                request.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/")
+            ),
+            None if request.uri().scheme().is_some() => request.uri().to_string(),
+            None => {
+                return Ok(text_response(
+                    StatusCode::BAD_REQUEST,
+                    "This is a proxy; configure your client to send requests through it.\n".to_string(),
+                ))
+            }
+        };
+        let url = reqwest::Url::parse(&url).with_context(|| format!("Invalid request URL {}", url))?;
+        let method = request.method().clone();
+        let mut headers = BTreeMap::new();
+        let mut upstream = self.client.request(method.clone(), url.clone());
+        for (name, value) in request.headers() {
+            if HOP_BY_HOP.contains(&name.as_str()) {
+                continue;
+            }
+            upstream = upstream.header(name, value);
+            if name != "accept-encoding" {
+                headers.insert(name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned());
+            }
+        }
+        let request_body = body::to_bytes(request.into_body()).await?;
+        let offset_ms = self.started.elapsed().as_millis() as u64;
+        let started = Instant::now();
+        let outcome: Result<Exchange> = async {
+            let response = upstream.body(request_body.clone()).send().await.map_err(NetworkError::from)?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await.map_err(NetworkError::from)?.to_vec();
+            Ok(Exchange {
+                status,
+                headers,
+                body,
+                elapsed: started.elapsed(),
+                request: None,
+            })
+        }
+        .await;
+
+        let data = if request_body.is_empty() {
+            None
+        } else {
+            Some(serde_json::from_slice(&request_body).unwrap_or_else(|_| {
+                Value::String(String::from_utf8_lossy(&request_body).into_owned())
+            }))
+        };
+        let config = RequestConfig {
+            url: Some(url.to_string()),
+            method: Some(method.to_string()),
+            headers: Some(headers.into_iter().collect()),
+            data,
+            timeout: Some(30),
+            ..RequestConfig::default()
+        };
+        match outcome {
+            Ok(ref exchange) => println!(
+                "{} {} -> {} ({} ms)",
+                method,
+                url,
+                exchange.status.as_u16(),
+                exchange.elapsed.as_millis()
+            ),
+            Err(ref err) => println!("{}", i18n::tf("{} {} -> error: {}", &[&method, &url, err])),
+        }
+        if let Some(ref record) = self.record {
+            if let Err(err) = self.save(record, &url, &config, &outcome, offset_ms) {
+                eprintln!(
+                    "{}",
+                    i18n::tf("Warning: failed to record {} {}: {}", &[&method, &url, &format!("{:#}", err)])
+                );
+            }
+        }
+
+        let exchange = match outcome {
+            Ok(exchange) => exchange,
+            Err(err) => return Ok(text_response(StatusCode::BAD_GATEWAY, format!("{}\n", err))),
+        };
+        let mut response = Response::new(Body::from(exchange.body));
+        *response.status_mut() = exchange.status;
+        for (name, value) in &exchange.headers {
+            if !HOP_BY_HOP.contains(&name.as_str()) {
+                response.headers_mut().append(name, value.clone());
+            }
+        }
+        Ok(response)
+    }
+
+    /// Saves the exchange as a config under the recording namespace, in the history and in the session.
+    fn save(
+        &self,
+        record: &str,
+        url: &reqwest::Url,
+        config: &RequestConfig,
+        outcome: &Result<Exchange>,
+        offset_ms: u64,
+    ) -> Result<()> {
+        if vars::has_placeholder(url.as_str()) {
+            bail!("The URL contains '{{{{' and is not recorded");
+        }
+        let config = &without_placeholders(config);
+        let target = record_target(record, url)?;
+        let base_dir = get_default_dir()?;
+        let dir = namespace::create(&base_dir, &target)?;
+        let method = config.method.as_deref().unwrap_or("GET");
+        store::write_atomic(&dir.join(format!("{}.json", method)), serde_json::to_string_pretty(config)?)?;
+        history::record(Some(&target), config, config, outcome)?;
+        if let Some(ref path) = self.session {
+            let entry = SessionEntry {
+                offset_ms,
+                target: Some(target),
+                request: config.clone(),
+                status: outcome.as_ref().ok().map(|e| e.status.as_u16()),
+                headers: outcome
+                    .as_ref()
+                    .map(|e| {
+                        e.headers
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).into_owned()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                body: outcome.as_ref().ok().map(Exchange::text),
+                elapsed_ms: outcome.as_ref().ok().map(|e| e.elapsed.as_millis() as u64),
+                error: outcome.as_ref().err().map(|e| format!("{:#}", e)),
+            };
+            session::append(path, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Handles CONNECT: decrypts the tunnel with a generated certificate when MITM is
+    /// enabled, otherwise relays the bytes unrecorded.
+    fn connect(self: Arc<Self>, request: Request<Body>) -> Response<Body> {
+        let Some(authority) = request.uri().authority().map(|a| a.to_string()) else {
+            return text_response(StatusCode::BAD_REQUEST, "CONNECT requires host:port\n".to_string());
+        };
+        tokio::spawn(async move {
+            let result: Result<()> = async {
+                let upgraded = hyper::upgrade::on(request).await?;
+                match self.authority {
+                    Some(ref ca) => {
+                        let host = authority.rsplit_once(':').map(|(h, _)| h).unwrap_or(&authority);
+                        let tls = ca.acceptor(host)?.accept(upgraded).await?;
+                        let proxy = Arc::clone(&self);
+                        let authority = authority.clone();
+                        let service = service_fn(move |request| {
+                            let proxy = Arc::clone(&proxy);
+                            let authority = authority.clone();
+                            async move { Ok::<_, Infallible>(proxy.handle(request, Some(&authority)).await) }
+                        });
+                        Http::new().serve_connection(tls, service).await?;
+                    }
+                    None => {
+                        let mut upgraded = upgraded;
+                        let mut server = TcpStream::connect(&authority).await?;
+                        tokio::io::copy_bidirectional(&mut upgraded, &mut server).await?;
+                    }
+                }
+                Ok(())
+            }
+            .await;
+            if let Err(err) = result {
+                eprintln!("CONNECT {}: {:#}", authority, err);
+            }
+        });
+        Response::new(Body::empty())
+    }
+
+    async fn handle(self: Arc<Self>, request: Request<Body>, https_authority: Option<&str>) -> Response<Body> {
+        if request.method() == Method::CONNECT {
+            return self.connect(request);
+        }
+        match self.forward(request, https_authority).await {
+            Ok(response) => response,
+            Err(err) => text_response(StatusCode::BAD_GATEWAY, format!("{:#}\n", err)),
+        }
+    }
+}
+
+pub async fn run(args: ProxyArgs) -> Result<()> {
+    if let Some(ref record) = args.record {
+        namespace::normalize(record)?;
+    }
+    let authority = if args.mitm {
+        let (authority, cert_path) = Authority::load_or_create()?;
+        println!(
+            "{}",
+            i18n::tf("HTTPS traffic is decrypted with the CA certificate at {}.", &[&format!("{:?}", cert_path)])
+        );
+        println!("{}", i18n::t("Add it to the trust store of the client (browser, OS, or e.g. NODE_EXTRA_CA_CERTS)."));
+        Some(authority)
+    } else {
+        None
+    };
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(60))
+        .danger_accept_invalid_certs(args.insecure)
+        .build()?;
+    let session = args.record.as_deref().map(session::new_path).transpose()?;
+    let proxy = Arc::new(Proxy {
+        client,
+        record: args.record.clone(),
+        session: session.clone(),
+        authority,
+        started: Instant::now(),
+    });
+
+    let address = SocketAddr::from(([127, 0, 0, 1], args.port));
+    let make_service = make_service_fn(move |_| {
+        let proxy = Arc::clone(&proxy);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let proxy = Arc::clone(&proxy);
+                async move { Ok::<_, Infallible>(proxy.handle(request, None).await) }
+            }))
+        }
+    });
+    let server = match Server::try_bind(&address) {
+        Ok(builder) => builder.serve(make_service),
+        Err(err) => bail!("Failed to listen on {}: {}", address, err),
+    };
+    println!("{}", i18n::tf("Proxy listening on http://{} (Ctrl-C to stop)", &[&address]));
+    match (&args.record, &session) {
+        (Some(record), Some(path)) => {
+            println!("{}", i18n::tf("Recording into {} (session {})", &[record, &format!("{:?}", path)]))
+        }
+        _ => println!("{}", i18n::t("Not recording; pass --record NAMESPACE to save the traffic.")),
+    }
+    server.with_graceful_shutdown(cancel::cancelled()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn recorded_requests_keep_no_placeholders() {
+        let config = RequestConfig {
+            headers: Some(HashMap::from([
+                ("accept".to_string(), "*/*".to_string()),
+                ("cookie".to_string(), "theme={{cmd:touch pwned}}".to_string()),
+            ])),
+            data: Some(json!({"name": "{{cmd:touch pwned}}"})),
+            ..RequestConfig::default()
+        };
+        let recorded = without_placeholders(&config);
+        assert_eq!(recorded.headers, Some(HashMap::from([("accept".to_string(), "*/*".to_string())])));
+        assert_eq!(recorded.data, None);
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::cancel;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::output::{self, Table};
+use crate::request::{self, RequestArgs, RequestConfig};
+use crate::retry::{RetryArgs, RetryPolicy};
+use crate::store;
+
+/// Subcommands of `queue`.
+#[derive(Subcommand, Debug)]
+pub enum QueueCommand {
+    /// リクエストをキューに追加します（TARGET の保存済み設定と CLI オプションを解決して保存）
+    Add {
+        #[command(flatten)]
+        request: Box<RequestArgs>,
+
+        /// TARGET: 保存済み設定の名前空間パス（例: "SystemA/example"）
+        target: Option<String>,
+    },
+    /// キューに溜まっているリクエストを一覧表示します。
+    List,
+    /// キュー内のリクエストを送信します。送信に成功したものはキューから取り除かれます。
+    /// 送信中は他の flush や add はこの flush の完了を待ちます。
+    Flush {
+        /// 同時に送信するリクエスト数（1 の場合は追加順に逐次送信）
+        #[arg(long = "concurrency", default_value = "1")]
+        concurrency: usize,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+    },
+    /// キューを空にします。
+    Clear,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct QueueItem {
+    id: u64,
+    added_at: u64,
+    target: Option<String>,
+    config: RequestConfig,
+}
+
+/// Returns the queue file path (e.g., ~/.ferrapi_tester/queue.json).
+fn queue_path() -> Result<PathBuf> {
+    Ok(get_default_dir()?.join("queue.json"))
+}
+
+fn load() -> Result<Vec<QueueItem>> {
+    let path = queue_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read queue from {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse queue in {:?}", path))
+}
+
+fn save(items: &[QueueItem]) -> Result<()> {
+    let path = queue_path()?;
+    let serialized = serde_json::to_string_pretty(items)
+        .with_context(|| "Failed to serialize queue")?;
+    store::write_atomic(&path, serialized).with_context(|| format!("Failed to write queue to {:?}", path))
+}
+
+fn describe(config: &RequestConfig) -> String {
+    format!(
+        "{} {}",
+        config.method.as_deref().unwrap_or("GET"),
+        config.url.as_deref().unwrap_or("<no url>")
+    )
+}
+
+/// Outcome of dispatching one queued request.
+struct Dispatch {
+    id: u64,
+    summary: String,
+    delivered: bool,
+}
+
+async fn dispatch(item: QueueItem, retry: RetryPolicy) -> Dispatch {
+    let summary = describe(&item.config);
+    match request::execute(&item.config, item.target.as_deref(), &retry).await {
+        // 5xx はバックエンド側の一時的な障害とみなし、キューに残して再送できるようにする
+        Ok(exchange) if exchange.status.is_server_error() => Dispatch {
+            id: item.id,
+            summary: format!("{} -> {} (kept in queue)", summary, exchange.status),
+            delivered: false,
+        },
+        Ok(exchange) => Dispatch {
+            id: item.id,
+            summary: format!("{} -> {}", summary, exchange.status),
+            delivered: true,
+        },
+        Err(err) => Dispatch {
+            id: item.id,
+            summary: format!("{} -> error: {} (kept in queue)", summary, err),
+            delivered: false,
+        },
+    }
+}
+
+pub async fn run(command: QueueCommand) -> Result<()> {
+    match command {
+        QueueCommand::Add { request, target } => {
+            let config = request::resolve(&request, target.as_deref())?;
+            config.url.as_ref().context("URL is not specified")?;
+            let _lock = store::lock(&queue_path()?)?;
+            let mut items = load()?;
+            let id = items.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+            let added_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            println!("{}", i18n::tf("Queued #{}: {}", &[&id, &describe(&config)]));
+            items.push(QueueItem { id, added_at, target, config });
+            save(&items)?;
+        }
+        QueueCommand::List => {
+            let mut table = Table::new(&[("ID", "id"), ("REQUEST", "request"), ("TARGET", "target")]);
+            for item in load()? {
+                table.push(vec![json!(item.id), json!(describe(&item.config)), json!(item.target)]);
+            }
+            output::renderer()?.print(&table, i18n::t("The queue is empty."))?;
+        }
+        QueueCommand::Flush { concurrency, retry } => {
+            // 送信から書き戻しまでロックを保持し、同時の flush による二重送信や、その間の add の消失を防ぐ
+            let _lock = store::lock(&queue_path()?)?;
+            let items = load()?;
+            if items.is_empty() {
+                println!("{}", i18n::t("The queue is empty."));
+                return Ok(());
+            }
+            let retry = retry.policy();
+            let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+            let mut tasks = JoinSet::new();
+            for item in items.iter().cloned() {
+                let semaphore = Arc::clone(&semaphore);
+                tasks.spawn(async move {
+                    // Ctrl-C 後は未送信のリクエストを送らずキューに残す（送信中のものは完了を待つ）
+                    let _permit = tokio::select! {
+                        permit = semaphore.acquire_owned() => permit,
+                        _ = cancel::cancelled() => {
+                            return Dispatch {
+                                id: item.id,
+                                summary: "cancelled (kept in queue)".to_string(),
+                                delivered: false,
+                            };
+                        }
+                    };
+                    dispatch(item, retry).await
+                });
+            }
+            let mut results = Vec::new();
+            while let Some(result) = tasks.join_next().await {
+                results.push(result.context("Queue dispatch task panicked")?);
+            }
+            results.sort_by_key(|result| result.id);
+            let renderer = output::renderer()?;
+            let mut table = Table::new(&[("ID", "id"), ("RESULT", "result"), ("DELIVERED", "delivered")]);
+            for result in &results {
+                table.push(vec![json!(result.id), json!(result.summary), json!(result.delivered)]);
+            }
+            print!("{}", renderer.table(&table)?);
+            let delivered: Vec<u64> = results.iter().filter(|r| r.delivered).map(|r| r.id).collect();
+            let remaining: Vec<QueueItem> = items
+                .into_iter()
+                .filter(|item| !delivered.contains(&item.id))
+                .collect();
+            renderer.note(&i18n::tf(
+                "Delivered {} of {} request(s); {} remaining in queue.",
+                &[&delivered.len(), &results.len(), &remaining.len()],
+            ));
+            save(&remaining)?;
+        }
+        QueueCommand::Clear => {
+            let _lock = store::lock(&queue_path()?)?;
+            save(&[])?;
+            println!("{}", i18n::t("Queue cleared."));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+    task::JoinSet,
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+use crate::cancel;
+use crate::i18n;
+use crate::throttle;
+
+/// Options of `raw`.
+#[derive(clap::Args, Debug)]
+pub struct RawArgs {
+    /// 送信先の URL（http:// または https://）
+    url: String,
+
+    /// 不正なリクエストを送信することを承知したうえで指定します（必須）
+    #[arg(long = "unsafe-raw")]
+    unsafe_raw: bool,
+
+    /// HTTP メソッド
+    #[arg(short = 'X', long = "method", default_value = "GET")]
+    method: String,
+
+    /// そのまま送信するヘッダー（例: "X-Test: 1"、複数指定可）
+    #[arg(short = 'H', long = "header", value_name = "HEADER")]
+    headers: Vec<String>,
+
+    /// リクエストボディ
+    #[arg(short = 'd', long = "body")]
+    body: Option<String>,
+
+    /// 正しい Content-Length に加えて、この値の Content-Length ヘッダーをもう 1 つ送ります
+    #[arg(long = "duplicate-content-length", value_name = "N")]
+    duplicate_content_length: Option<String>,
+
+    /// ボディを chunked で送り、実際の長さの代わりにこのチャンクサイズを宣言します（例: ffffffff、zz）
+    #[arg(long = "bad-chunk-size", value_name = "SIZE")]
+    bad_chunk_size: Option<String>,
+
+    /// 指定したバイト数の値を持つヘッダーを追加します
+    #[arg(long = "oversized-header", value_name = "BYTES")]
+    oversized_header: Option<usize>,
+
+    /// ファイルの内容を一切変更せずにリクエストとして送信します（URL は接続先にのみ使用）
+    #[arg(
+        long = "request-file",
+        value_name = "FILE",
+        conflicts_with_all = ["headers", "body", "duplicate_content_length", "bad_chunk_size", "oversized_header"]
+    )]
+    request_file: Option<PathBuf>,
+
+    /// 応答を待つ秒数（この間データが届かなければ読み取りを終えます）
+    #[arg(long = "timeout", default_value = "10")]
+    timeout: u64,
+
+    /// TLS 証明書の検証を行いません。
+    #[arg(short = 'k', long = "insecure")]
+    insecure: bool,
+
+    /// ヘッダーを含むリクエスト全体をこの速度でゆっくり送信します（例: 10B/s、1kbps）
+    #[arg(long = "send-rate", value_name = "RATE", value_parser = throttle::parse_rate)]
+    send_rate: Option<u64>,
+
+    /// 指定したバイト数を送信した時点で --pause の間送信を止めます
+    #[arg(long = "pause-after-bytes", value_name = "N")]
+    pause_after_bytes: Option<usize>,
+
+    /// --pause-after-bytes で送信を止める時間（例: 30s）
+    #[arg(long = "pause", value_name = "DURATION", default_value = "30s", value_parser = humantime::parse_duration)]
+    pause: Duration,
+
+    /// 同時に開く接続数（それぞれで同じリクエストを送信します）
+    #[arg(long = "connections", value_name = "N", default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    connections: u32,
+}
+
+/// What happened on one connection.
+struct Outcome {
+    /// Bytes of the request written before the server stopped accepting them.
+    sent: usize,
+    response: Vec<u8>,
+    /// Time from connecting until the server closed the connection or went idle.
+    elapsed: Duration,
+    error: Option<String>,
+}
+
+/// Builds the request bytes for ARGS, applying the requested malformations.
+fn build(args: &RawArgs, url: &reqwest::Url) -> Vec<u8> {
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target = format!("{}?{}", target, query);
+    }
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", args.method, target, host);
+    for header in &args.headers {
+        head.push_str(header);
+        head.push_str("\r\n");
+    }
+    let has = |name: &str| {
+        args.headers
+            .iter()
+            .any(|h| h.split(':').next().is_some_and(|n| n.trim().eq_ignore_ascii_case(name)))
+    };
+    let body = args.body.clone().unwrap_or_default();
+    let body = match args.bad_chunk_size {
+        Some(ref size) => {
+            head.push_str("Transfer-Encoding: chunked\r\n");
+            format!("{}\r\n{}\r\n0\r\n\r\n", size, body)
+        }
+        None => body,
+    };
+    if !has("content-length") && (args.body.is_some() || args.duplicate_content_length.is_some()) {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    if let Some(ref length) = args.duplicate_content_length {
+        head.push_str(&format!("Content-Length: {}\r\n", length));
+    }
+    if let Some(size) = args.oversized_header {
+        head.push_str(&format!("X-Oversized: {}\r\n", "A".repeat(size)));
+    }
+    // 応答の終わりを接続の切断で判断できるよう、指定がなければ接続を閉じてもらう
+    if !has("connection") {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    let mut bytes = head.into_bytes();
+    bytes.extend_from_slice(body.as_bytes());
+    bytes
+}
+
+/// Writes REQUEST to STREAM, as slowly as ARGS ask for, and reads until the server
+/// closes the connection or nothing arrives for --timeout.
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, request: &[u8], args: &RawArgs) -> Outcome {
+    let started = Instant::now();
+    let mut sent = 0;
+    let mut error = None;
+    // 送信速度の指定がなければ、止める位置まで一度に書き込む
+    let chunk = args.send_rate.map(|rate| (rate / 10).max(1) as usize).unwrap_or(request.len().max(1));
+    let mut paused = false;
+    while sent < request.len() {
+        let mut end = (sent + chunk).min(request.len());
+        if let Some(limit) = args.pause_after_bytes.filter(|_| !paused) {
+            if sent == limit {
+                tokio::time::sleep(args.pause).await;
+                paused = true;
+                continue;
+            }
+            if sent < limit {
+                end = end.min(limit);
+            }
+        }
+        if let Err(err) = stream.write_all(&request[sent..end]).await.and(stream.flush().await) {
+            // サーバーがタイムアウトで接続を閉じた場合は、ここまでの結果として報告する
+            error = Some(format!("write failed: {}", err));
+            break;
+        }
+        sent = end;
+        if let Some(rate) = args.send_rate {
+            let due = Duration::from_secs_f64(sent as f64 / rate as f64) + if paused { args.pause } else { Duration::ZERO };
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+    }
+    let idle = Duration::from_secs(args.timeout);
+    let mut response = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        match tokio::time::timeout(idle, stream.read(&mut buffer)).await {
+            Ok(Ok(0)) => break,
+            Err(_) => {
+                error.get_or_insert(format!("still open, nothing received for {}s", args.timeout));
+                break;
+            }
+            Ok(Ok(read)) => response.extend_from_slice(&buffer[..read]),
+            // 不正なリクエストに対してはサーバーが接続をリセットすることが多いので、受信済みの分を返す
+            Ok(Err(err)) => {
+                error.get_or_insert(format!("read failed: {}", err));
+                break;
+            }
+        }
+    }
+    Outcome { sent, response, elapsed: started.elapsed(), error }
+}
+
+/// Opens one connection to HOST:PORT (with TLS if asked) and sends REQUEST over it.
+async fn connect_and_send(args: &RawArgs, host: &str, port: u16, tls: bool, request: &[u8]) -> Result<Outcome> {
+    let stream = TcpStream::connect((host, port))
+        .await
+        .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+    if !tls {
+        return Ok(exchange(stream, request, args).await);
+    }
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(args.insecure)
+        .danger_accept_invalid_hostnames(args.insecure)
+        .build()?;
+    let stream = TlsConnector::from(connector)
+        .connect(host, stream)
+        .await
+        .with_context(|| format!("TLS handshake with {} failed", host))?;
+    Ok(exchange(stream, request, args).await)
+}
+
+/// Describes how OUTCOME ended for a request of TOTAL bytes.
+fn summarize(outcome: &Outcome, total: usize) -> String {
+    let mut summary = i18n::tf(
+        "{} of {} bytes sent, {} bytes received, connection open for {}s",
+        &[&outcome.sent, &total, &outcome.response.len(), &format!("{:.1}", outcome.elapsed.as_secs_f64())],
+    );
+    if let Some(ref error) = outcome.error {
+        summary.push_str(&format!(" ({})", error));
+    }
+    summary
+}
+
+/// Sends one deliberately malformed or slowly sent request over plain TCP or TLS
+/// connections and prints exactly what was sent and received.
+pub async fn run(args: RawArgs) -> Result<()> {
+    if !args.unsafe_raw {
+        bail!(
+            "raw sends deliberately malformed requests that can disrupt proxies and servers; \
+             only use it against systems you are allowed to test, and pass --unsafe-raw to confirm"
+        );
+    }
+    let url = reqwest::Url::parse(&args.url).with_context(|| format!("Invalid URL: {}", args.url))?;
+    let Some(host) = url.host_str().map(str::to_string) else {
+        bail!("URL has no host: {}", args.url);
+    };
+    let tls = match url.scheme() {
+        "http" => false,
+        "https" => true,
+        other => bail!("Unsupported scheme '{}' (use http or https)", other),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    let request = match args.request_file {
+        Some(ref path) => fs::read(path).with_context(|| format!("Failed to read {:?}", path))?,
+        None => build(&args, &url),
+    };
+    let total = request.len();
+    let (args, host, request) = (Arc::new(args), Arc::new(host), Arc::new(request));
+    let mut tasks = JoinSet::new();
+    for index in 0..args.connections {
+        let (args, host, request) = (Arc::clone(&args), Arc::clone(&host), Arc::clone(&request));
+        tasks.spawn(async move { (index, connect_and_send(&args, &host, port, tls, &request).await) });
+    }
+    // Ctrl-C で中断した場合は JoinSet の破棄で残りの接続も閉じる
+    let mut outcomes = cancel::guard(async {
+        let mut outcomes = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            outcomes.push(joined?);
+        }
+        anyhow::Ok(outcomes)
+    })
+    .await?;
+    outcomes.sort_by_key(|(index, _)| *index);
+    println!("{}", i18n::tf("--- request ({} bytes) ---", &[&total]));
+    println!("{}", String::from_utf8_lossy(&request));
+    if outcomes.len() == 1 {
+        let outcome = outcomes.remove(0).1?;
+        println!("{}", i18n::tf("--- response ({} bytes) ---", &[&outcome.response.len()]));
+        println!("{}", String::from_utf8_lossy(&outcome.response));
+        println!("--- {} ---", summarize(&outcome, total));
+        return Ok(());
+    }
+    for (index, outcome) in outcomes {
+        match outcome {
+            Ok(outcome) => println!("#{}: {}", index + 1, summarize(&outcome, total)),
+            Err(err) => println!("#{}: {:#}", index + 1, err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        raw: RawArgs,
+    }
+
+    #[test]
+    fn build_applies_malformations() {
+        let cli = Cli::parse_from([
+            "raw",
+            "http://example.com:8080/a?b=1",
+            "-X",
+            "POST",
+            "-d",
+            "hello",
+            "--duplicate-content-length",
+            "0",
+            "--bad-chunk-size",
+            "zz",
+            "--oversized-header",
+            "3",
+        ]);
+        let url = reqwest::Url::parse(&cli.raw.url).unwrap();
+        let request = String::from_utf8(build(&cli.raw, &url)).unwrap();
+        assert_eq!(
+            request,
+            "POST /a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nTransfer-Encoding: chunked\r\nContent-Length: 16\r\n\
+             Content-Length: 0\r\nX-Oversized: AAA\r\nConnection: close\r\n\r\nzz\r\nhello\r\n0\r\n\r\n"
+        );
+    }
+}
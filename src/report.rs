@@ -0,0 +1,340 @@
+use anyhow::{bail, Context, Result};
+use reqwest::{header::HeaderMap, Url};
+use serde_json::{json, Map, Value};
+use std::{
+    env,
+    io::{Cursor, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::auth;
+use crate::cancel::Interrupted;
+use crate::dashboard::{self, ReportCommand};
+use crate::doctor::PROXY_VARS;
+use crate::i18n;
+use crate::request::{self, Exchange, RequestArgs, RequestConfig};
+use crate::retry::RetryPolicy;
+use crate::store;
+use crate::vars;
+
+/// Replacement for redacted values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Parts of header, field, parameter and variable names that mark their values as secret.
+const SENSITIVE: &[&str] = &[
+    "auth", "token", "secret", "password", "passwd", "apikey", "api-key", "api_key", "cookie", "session",
+    "credential", "signature", "private",
+];
+
+/// Options of `report`.
+#[derive(clap::Args, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub action: Option<ReportCommand>,
+
+    #[command(flatten)]
+    pub request: Box<RequestArgs>,
+
+    /// TARGET: 保存済み設定の名前空間パス（例: "SystemA/example"）または URL
+    #[arg(required = true)]
+    pub target: Option<String>,
+
+    /// 書き出す ZIP ファイルのパス
+    #[arg(long = "bundle", required = true, value_hint = clap::ValueHint::FilePath)]
+    pub bundle: Option<PathBuf>,
+}
+
+/// True if values of NAME (a header, field, parameter or variable) should be hidden.
+pub fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE.iter().any(|part| name.contains(part))
+}
+
+/// Removes secrets from bundle contents: values of sensitive names, and any
+/// occurrence of a secret sent with the request (e.g. a token echoed back in the response).
+struct Redactor {
+    secrets: Vec<String>,
+}
+
+/// Collects the values of sensitive fields in a JSON value.
+fn sensitive_values(value: &Value, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    Value::String(text) if is_sensitive(key) => found.push(text.clone()),
+                    Value::Number(_) | Value::Bool(_) if is_sensitive(key) => found.push(value.to_string()),
+                    _ => sensitive_values(value, found),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| sensitive_values(item, found)),
+        _ => {}
+    }
+}
+
+impl Redactor {
+    /// Learns the secrets of the request: sensitive variables of TARGET and the values of
+    /// sensitive headers, query parameters and body fields of CONFIG.
+    fn new(target: Option<&str>, config: &RequestConfig) -> Redactor {
+        let mut secrets: Vec<String> = vars::resolve(target)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(name, _)| is_sensitive(name))
+            .map(|(_, value)| value)
+            .collect();
+        for (name, value) in config.headers.iter().flatten() {
+            if is_sensitive(name) {
+                // "Bearer xxx" のようにスキームが付いていれば、値の部分も個別に伏せる
+                secrets.extend(value.split_once(' ').map(|(_, credential)| credential.to_string()));
+                secrets.push(value.clone());
+            }
+        }
+        if let Some(url) = config.url.as_deref().and_then(|url| Url::parse(url).ok()) {
+            secrets.extend(url.password().map(str::to_string));
+            secrets.extend(url.query_pairs().filter(|(key, _)| is_sensitive(key)).map(|(_, value)| value.into_owned()));
+        }
+        if let Some(data) = &config.data {
+            sensitive_values(data, &mut secrets);
+        }
+        // 短すぎる値は無関係な箇所まで置き換えてしまうので対象外にする
+        secrets.retain(|secret| secret.len() >= 4);
+        // 長い値から置き換えて、部分一致で一部だけ残らないようにする
+        secrets.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        secrets.dedup();
+        Redactor { secrets }
+    }
+
+    fn text(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED))
+    }
+
+    /// Keeps the scheme of Authorization-style values ("Bearer [REDACTED]").
+    fn header(&self, name: &str, value: &str) -> String {
+        if !is_sensitive(name) {
+            return self.text(value);
+        }
+        match value.split_once(' ') {
+            Some((scheme, _)) if name.to_lowercase().contains("authorization") => format!("{} {}", scheme, REDACTED),
+            _ => REDACTED.to_string(),
+        }
+    }
+
+    fn json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if is_sensitive(key) && !value.is_object() && !value.is_array() {
+                        *value = json!(REDACTED);
+                    } else {
+                        self.json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.json(item)),
+            Value::String(text) => *text = self.text(text),
+            _ => {}
+        }
+    }
+
+    fn url(&self, url: &str) -> String {
+        let Ok(mut parsed) = Url::parse(url) else {
+            return self.text(url);
+        };
+        if parsed.password().is_some() {
+            let _ = parsed.set_password(Some(REDACTED));
+        }
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(key, value)| {
+                let value = if is_sensitive(&key) { REDACTED.to_string() } else { value.into_owned() };
+                (key.into_owned(), value)
+            })
+            .collect();
+        if !pairs.is_empty() {
+            parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        self.text(parsed.as_str())
+    }
+
+    /// Redacts a body: structurally when it is JSON, as text otherwise.
+    fn body(&self, body: &[u8]) -> Vec<u8> {
+        if let Ok(mut value) = serde_json::from_slice::<Value>(body) {
+            self.json(&mut value);
+            return serde_json::to_vec_pretty(&value).unwrap_or_default();
+        }
+        match std::str::from_utf8(body) {
+            Ok(text) => self.text(text).into_bytes(),
+            Err(_) => body.to_vec(),
+        }
+    }
+
+    fn headers(&self, headers: impl IntoIterator<Item = (String, String)>) -> Map<String, Value> {
+        headers
+            .into_iter()
+            .map(|(name, value)| {
+                let value = self.header(&name, &value);
+                (name, json!(value))
+            })
+            .collect()
+    }
+}
+
+fn response_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect()
+}
+
+/// A shell command reproducing the (redacted) request.
+fn curl(method: &str, url: &str, headers: &Map<String, Value>, body: Option<&Value>) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', r"'\''"));
+    let mut command = format!("curl -X {} {}", method, quote(url));
+    for (name, value) in headers {
+        command.push_str(&format!(" \\\n  -H {}", quote(&format!("{}: {}", name, value.as_str().unwrap_or_default()))));
+    }
+    if let Some(body) = body {
+        command.push_str(" \\\n  -H 'Content-Type: application/json'");
+        command.push_str(&format!(" \\\n  --data {}", quote(&body.to_string())));
+    }
+    command.push('\n');
+    command
+}
+
+fn environment(redactor: &Redactor, target: &str) -> Value {
+    let proxies: Map<String, Value> = PROXY_VARS
+        .iter()
+        .filter_map(|name| env::var(name).ok().map(|value| (name.to_string(), json!(redactor.url(&value)))))
+        .collect();
+    json!({
+        "tool": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": env::consts::OS,
+        "arch": env::consts::ARCH,
+        "target": redactor.url(target),
+        "generated_at": humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        "proxies": proxies,
+    })
+}
+
+fn summary(redactor: &Redactor, config: &RequestConfig, url: &str, outcome: &Result<Exchange>, files: &[&str]) -> String {
+    let method = config.method.as_deref().unwrap_or("GET");
+    let result = match outcome {
+        Ok(exchange) => format!(
+            "Status:  {}\nElapsed: {} ms",
+            exchange.status,
+            exchange.elapsed.as_millis()
+        ),
+        Err(err) => format!("Error:   {}", redactor.text(&format!("{:#}", err))),
+    };
+    format!(
+        "{} {} bug report\n\nRequest: {} {}\n{}\n\nFiles:\n{}\n\nHeaders, body fields, query parameters and variables whose names look secret\n(token, password, cookie, ...) are replaced with {}.\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        method,
+        url,
+        result,
+        files.iter().map(|file| format!("  {}", file)).collect::<Vec<_>>().join("\n"),
+        REDACTED
+    )
+}
+
+pub async fn run(args: ReportArgs) -> Result<()> {
+    match args.action {
+        Some(ReportCommand::Html { target, out, since, title }) => {
+            dashboard::generate(target.as_deref(), &out, since, &title)
+        }
+        None => bug_report(args).await,
+    }
+}
+
+/// Sends the request and packages the request, response, timing and environment,
+/// with secrets redacted, into a ZIP archive for bug reports.
+async fn bug_report(args: ReportArgs) -> Result<()> {
+    let (Some(target_name), Some(bundle)) = (args.target, args.bundle) else {
+        bail!("Specify a TARGET and --bundle FILE, or `report html --out DIR`");
+    };
+    let target = Some(target_name.as_str());
+    let config = request::resolve(&args.request, target)?;
+    let policy = RetryPolicy {
+        retries: 0,
+        delay: Duration::ZERO,
+        allow_unsafe: false,
+    };
+    let outcome = request::execute(&config, target, &policy).await;
+    if let Err(ref err) = outcome {
+        // 割り込みはレポートにせずそのまま終了する
+        if err.is::<Interrupted>() {
+            return outcome.map(|_| ());
+        }
+    }
+
+    // 送信時と同じ値にするため変数だけ展開する（{{secret:...}} などはプレースホルダーのまま）
+    let resolved = vars::substitute(&config, target).unwrap_or_else(|_| config.clone());
+    // OAuth2 のトークンは送信時にキャッシュされているので、同じヘッダーになる
+    let resolved = auth::apply(resolved.clone()).await.unwrap_or(resolved);
+    let redactor = Redactor::new(target, &resolved);
+    let method = resolved.method.clone().unwrap_or_else(|| "GET".to_string());
+    let url = redactor.url(resolved.url.as_deref().unwrap_or_default());
+    let headers = redactor.headers(resolved.headers.clone().unwrap_or_default());
+    let body = resolved.data.clone().map(|mut data| {
+        redactor.json(&mut data);
+        data
+    });
+
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        (
+            "request.json".to_string(),
+            serde_json::to_vec_pretty(&json!({ "method": method, "url": url, "headers": headers, "body": body }))?,
+        ),
+        ("curl.sh".to_string(), curl(&method, &url, &headers, body.as_ref()).into_bytes()),
+    ];
+    match &outcome {
+        Ok(exchange) => {
+            let body_file = format!("response_body.{}", exchange.body_extension());
+            let response = json!({
+                "status": exchange.status.as_u16(),
+                "reason": exchange.status.canonical_reason(),
+                "headers": redactor.headers(response_headers(&exchange.headers)),
+                "elapsed_ms": exchange.elapsed.as_millis() as u64,
+                "body_bytes": exchange.body.len(),
+                "body_file": body_file,
+            });
+            files.push(("response.json".to_string(), serde_json::to_vec_pretty(&response)?));
+            files.push((body_file, redactor.body(&exchange.body)));
+        }
+        Err(err) => {
+            let error = json!({ "error": redactor.text(&format!("{:#}", err)) });
+            files.push(("error.json".to_string(), serde_json::to_vec_pretty(&error)?));
+        }
+    }
+    files.push(("environment.json".to_string(), serde_json::to_vec_pretty(&environment(&redactor, &target_name))?));
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    let readme = summary(&redactor, &resolved, &url, &outcome, &names);
+    files.insert(0, ("README.txt".to_string(), readme.into_bytes()));
+
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in &files {
+        archive.start_file(name.as_str(), options)?;
+        archive.write_all(content)?;
+    }
+    let archive = archive.finish().context("Failed to build the bug report archive")?;
+    store::write_atomic(&bundle, archive.into_inner())?;
+
+    match &outcome {
+        Ok(exchange) => println!("{}", i18n::tf("Response Status: {}", &[&exchange.status])),
+        Err(err) => eprintln!("{}", i18n::tf("Request failed: {}", &[&format!("{:#}", err)])),
+    }
+    println!(
+        "{}",
+        i18n::tf("Bug report written to {} ({} files, secrets redacted).", &[&format!("{:?}", bundle), &files.len()])
+    );
+    Ok(())
+}
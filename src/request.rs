@@ -0,0 +1,767 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueHint;
+use reqwest::{header::HeaderMap, Client, Method, StatusCode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+use crate::auth::{self, Auth};
+use crate::budget;
+use crate::cookies::{self, Jar};
+use crate::docs::ResponseExample;
+use crate::exit_code;
+use crate::form::{self, FormPart};
+use crate::history;
+use crate::i18n;
+use crate::mount;
+use crate::notify;
+use crate::openapi;
+use crate::patch;
+use crate::request_log;
+use crate::retry::RetryPolicy;
+use crate::schema;
+use crate::secret;
+use crate::throttle;
+use crate::tls;
+use crate::vars;
+
+/// Options describing a single HTTP request, shared by every command that builds one.
+#[derive(clap::Args, Debug, Clone)]
+pub struct RequestArgs {
+    /// HTTP メソッド (GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS, TRACE や PROPFIND などの独自メソッド) [default: GET]
+    #[arg(short = 'X', long = "request", default_value = "GET")]
+    pub method: String,
+
+    /// ヘッダーの指定（例: -H "Content-Type: application/json"）
+    #[arg(short = 'H', long = "header")]
+    pub headers: Vec<String>,
+
+    /// コマンドの出力をヘッダー値として使用します（例: --header-from-cmd "Authorization: op read op://vault/api/token"）。
+    /// コマンドはリクエストのたびに実行され、値は保存・記録されません。
+    #[arg(long = "header-from-cmd", value_name = "NAME: COMMAND")]
+    pub header_from_cmd: Vec<String>,
+
+    /// リクエストボディの文字列（-d または -v で指定）
+    #[arg(short = 'd', long = "data")]
+    pub data: Option<String>,
+
+    /// JSON 形式でのリクエストボディ。保存済み設定の data にディープマージします
+    /// （オブジェクトはキーごとにマージ、配列やその他の値は置き換え）
+    #[arg(short = 'v', long = "value", conflicts_with = "json")]
+    pub value: Option<String>,
+
+    /// -v の値でマージせず、保存済みの data 全体を置き換えます
+    #[arg(long = "replace-data", requires = "value")]
+    pub replace_data: bool,
+
+    /// JSON 形式でのリクエストボディ（-j を使う場合、-v と競合します）
+    #[arg(short = 'j', long = "json", conflicts_with = "value")]
+    pub json: Option<String>,
+
+    /// ボディのフィールドを上書きします（例: --set .user.name=Alice、JSON 値は --set .count:=5）
+    #[arg(long = "set", value_name = "PATH=VALUE")]
+    pub set: Vec<String>,
+
+    /// ボディからフィールドを削除します（例: --unset .debug。--set の後に適用）
+    #[arg(long = "unset", value_name = "PATH")]
+    pub unset: Vec<String>,
+
+    /// リクエスト先の URL。この URL は保存する際にも使用されます。
+    #[arg(short = 'u', long = "url")]
+    pub url: Option<String>,
+
+    /// タイムアウト秒数（デフォルトは 30 秒）
+    #[arg(long = "timeout", default_value = "30")]
+    pub timeout: u64,
+
+    /// 接続確立までのタイムアウト秒数（省略時は --timeout のみ適用）
+    #[arg(long = "connect-timeout")]
+    pub connect_timeout: Option<u64>,
+
+    /// TLS 証明書の検証を行いません（自己署名証明書のテスト環境向け）
+    #[arg(short = 'k', long = "insecure")]
+    pub insecure: bool,
+
+    /// 追加で信頼する CA 証明書の PEM ファイル（社内 CA や自己署名証明書向け。設定にも保存）
+    #[arg(long = "cacert", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub cacert: Option<String>,
+
+    /// 相互 TLS（mTLS）のクライアント証明書の PEM ファイル（秘密鍵を含めても可。設定にも保存）
+    #[arg(long = "cert", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub cert: Option<String>,
+
+    /// --cert の秘密鍵の PEM ファイル（PKCS#8。設定にも保存）
+    #[arg(long = "key", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub key: Option<String>,
+
+    /// 名前空間のクッキージャー（cookies.json）でクッキーを保存し、以降のリクエストで送信します（設定にも保存）
+    #[arg(long = "cookies")]
+    pub cookies: bool,
+
+    /// 設定に付けるタグ（複数指定可。run-all --tag での絞り込みに使用）
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// レスポンスに対するアサーション（複数指定可。例: --assert "status == 201" --assert ".count >= 10"）。
+    /// 保存済み設定のアサーションに追加されます
+    #[arg(long = "assert", value_name = "EXPR")]
+    pub assertions: Vec<String>,
+
+    /// ボディを正規化した JSON（キー順・数値表記を統一）で送信し、設定にも正規化して保存します。
+    #[arg(long = "canonical-json")]
+    pub canonical_json: bool,
+
+    /// Bearer トークンで認証します（Authorization: Bearer TOKEN として送信し、設定の auth に保存）
+    #[arg(long = "bearer", value_name = "TOKEN", conflicts_with_all = ["basic", "api_key"])]
+    pub bearer: Option<String>,
+
+    /// Basic 認証を行います（USER:PASSWORD を Base64 エンコードして送信し、設定の auth に保存）
+    #[arg(long = "basic", value_name = "USER:PASSWORD", conflicts_with = "api_key")]
+    pub basic: Option<String>,
+
+    /// API キーをヘッダーで送信します（例: --api-key "X-Api-Key=abc"。設定の auth に保存）
+    #[arg(long = "api-key", value_name = "NAME=VALUE")]
+    pub api_key: Option<String>,
+
+    /// multipart/form-data のフィールド（複数指定可。例: --form title=Report）
+    #[arg(long = "form", value_name = "NAME=VALUE", conflicts_with_all = ["data", "value", "json"])]
+    pub form: Vec<String>,
+
+    /// multipart/form-data でアップロードするファイル（複数指定可。例: --file avatar=@./me.png;type=image/png）
+    #[arg(long = "file", value_name = "NAME=@PATH", conflicts_with_all = ["data", "value", "json"])]
+    pub files: Vec<String>,
+
+    /// application/x-www-form-urlencoded のフィールド（複数指定可。例: --form-urlencoded grant_type=client_credentials）
+    #[arg(
+        long = "form-urlencoded",
+        value_name = "NAME=VALUE",
+        conflicts_with_all = ["data", "value", "json", "form", "files"]
+    )]
+    pub form_urlencoded: Vec<String>,
+
+    /// URL に追加するクエリパラメータ（複数指定可。例: --query "q=a&b"。値は送信時にエンコードされます）
+    #[arg(long = "query", value_name = "NAME=VALUE")]
+    pub query: Vec<String>,
+}
+
+/// A saved configuration: the request to send and the checks on its response.
+/// String values may contain `{{name}}` placeholders.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, JsonSchema)]
+pub struct RequestConfig {
+    pub url: Option<String>,
+    /// HTTP method, e.g. "GET" or a custom verb such as "PROPFIND".
+    pub method: Option<String>,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub headers: Option<HashMap<String, String>>,
+    /// Query parameters added to the URL when the request is sent, encoded as needed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<BTreeMap<String, String>>,
+    /// JSON body.
+    pub data: Option<Value>,
+    /// Timeout in seconds (30 when omitted).
+    pub timeout: Option<u64>,
+    /// Timeout in seconds for establishing the connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// Skips TLS certificate verification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+    /// PEM file of CA certificates trusted in addition to the system roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cacert: Option<String>,
+    /// PEM client certificate for mutual TLS; it may also hold the private key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert: Option<String>,
+    /// PEM (PKCS#8) private key of `cert`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Sends and stores cookies with the cookie jar of the namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookies: Option<bool>,
+    /// Labels used to select configurations in run-all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Checks on the response such as "status == 201" or ".items | length > 0".
+    #[serde(default, rename = "assert", skip_serializing_if = "Option::is_none")]
+    pub assertions: Option<Vec<String>>,
+    /// Times run-all runs this configuration again while it fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_retries: Option<u32>,
+    /// Sends (and saves) the body as canonical JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_json: Option<bool>,
+    /// Bearer, Basic or API key credentials, sent as a header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// Fields and files sent as a multipart/form-data body instead of `data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form: Option<Vec<FormPart>>,
+    /// Fields sent as an application/x-www-form-urlencoded body instead of `data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form_urlencoded: Option<Vec<FormPart>>,
+    /// Example responses shown by `docs generate`; they are never sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<ResponseExample>>,
+}
+
+/// Writes headers in name order so that saved configurations diff cleanly.
+fn serialize_sorted<S: serde::Serializer>(headers: &Option<HashMap<String, String>>, serializer: S) -> Result<S::Ok, S::Error> {
+    headers.as_ref().map(|headers| headers.iter().collect::<BTreeMap<_, _>>()).serialize(serializer)
+}
+
+impl RequestConfig {
+    /// Overlays every field that is set in `other` on top of `self`.
+    /// Headers and query parameters are merged key by key; other fields are replaced.
+    pub fn overlay(mut self, other: &RequestConfig) -> RequestConfig {
+        if other.url.is_some() {
+            self.url = other.url.clone();
+        }
+        if other.method.is_some() {
+            self.method = other.method.clone();
+        }
+        if let Some(ref query) = other.query {
+            self.query.get_or_insert_with(BTreeMap::new).extend(query.clone());
+        }
+        if let Some(ref headers) = other.headers {
+            self.headers.get_or_insert_with(HashMap::new).extend(headers.clone());
+        }
+        if other.data.is_some() {
+            self.data = other.data.clone();
+        }
+        if other.timeout.is_some() {
+            self.timeout = other.timeout;
+        }
+        if other.connect_timeout.is_some() {
+            self.connect_timeout = other.connect_timeout;
+        }
+        if other.insecure.is_some() {
+            self.insecure = other.insecure;
+        }
+        if other.cacert.is_some() {
+            self.cacert = other.cacert.clone();
+        }
+        if other.cert.is_some() {
+            self.cert = other.cert.clone();
+        }
+        if other.key.is_some() {
+            self.key = other.key.clone();
+        }
+        if other.cookies.is_some() {
+            self.cookies = other.cookies;
+        }
+        if other.tags.is_some() {
+            self.tags = other.tags.clone();
+        }
+        if other.assertions.is_some() {
+            self.assertions = other.assertions.clone();
+        }
+        if other.test_retries.is_some() {
+            self.test_retries = other.test_retries;
+        }
+        if other.canonical_json.is_some() {
+            self.canonical_json = other.canonical_json;
+        }
+        if other.auth.is_some() {
+            self.auth = other.auth.clone();
+        }
+        if other.form.is_some() {
+            self.form = other.form.clone();
+        }
+        if other.form_urlencoded.is_some() {
+            self.form_urlencoded = other.form_urlencoded.clone();
+        }
+        if other.examples.is_some() {
+            self.examples = other.examples.clone();
+        }
+        self
+    }
+
+    /// Returns the configuration with `query` merged into the URL: parameters already in
+    /// the URL are replaced by those of the same name, the rest are appended.
+    pub fn with_query(mut self) -> Result<RequestConfig> {
+        let (Some(query), Some(url)) = (self.query.take().filter(|query| !query.is_empty()), self.url.as_ref()) else {
+            return Ok(self);
+        };
+        let mut parsed = Url::parse(url).with_context(|| format!("Cannot add query parameters to URL {}", url))?;
+        let kept: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(name, _)| !query.contains_key(name.as_ref()))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(kept).extend_pairs(&query);
+        self.url = Some(parsed.to_string());
+        Ok(self)
+    }
+}
+
+/// Loads the saved configuration for TARGET and method, or an empty one if none is saved.
+pub fn load_saved(target: &str, method: &str) -> Result<RequestConfig> {
+    let config_path = mount::config_path(target, method)?;
+    if config_path.exists() {
+        let content = fs::read_to_string(&config_path)
+            .with_context(|| format!("Failed to read config from {:?}", config_path))?;
+        serde_json::from_str::<RequestConfig>(&content)
+            .with_context(|| "Failed to parse saved configuration")
+    } else {
+        Ok(RequestConfig::default())
+    }
+}
+
+/// Parses header strings in "Key: Value" format into a HashMap.
+pub fn parse_headers(headers: &[String]) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for header in headers {
+        let parts: Vec<&str> = header.splitn(2, ':').collect();
+        if parts.len() != 2 {
+            bail!("Invalid header format: {}", header);
+        }
+        map.insert(parts[0].trim().to_string(), parts[1].trim().to_string());
+    }
+    Ok(map)
+}
+
+/// Deep-merges `overlay` into `base`: objects are merged key by key (recursively),
+/// while arrays and all other values in `overlay` replace the ones in `base`.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Applies a `-v` value to the saved data. Input that is not valid JSON is sent as a string.
+fn apply_value(saved: Option<Value>, raw: &str, replace: bool) -> Value {
+    let value = serde_json::from_str::<Value>(raw).unwrap_or_else(|_| json!(raw));
+    match saved {
+        Some(mut saved) if !replace => {
+            merge(&mut saved, value);
+            saved
+        }
+        _ => value,
+    }
+}
+
+/// Returns VALUE in canonical form: numbers without a fractional part are written as
+/// integers (1.0 and 1e2 become 1 and 100). Object keys are always kept sorted, so
+/// serializing the result gives the same bytes for the same data.
+pub fn canonical_json(value: Value) -> Value {
+    match value {
+        Value::Number(number) => match number.as_f64() {
+            // 2^53 までなら整数として正確に表せる
+            Some(float) if number.is_f64() && float.fract() == 0.0 && float.abs() < 9_007_199_254_740_992.0 => {
+                json!(float as i64)
+            }
+            _ => Value::Number(number),
+        },
+        Value::Array(items) => Value::Array(items.into_iter().map(canonical_json).collect()),
+        Value::Object(object) => Value::Object(object.into_iter().map(|(key, value)| (key, canonical_json(value))).collect()),
+        other => other,
+    }
+}
+
+/// Parses an HTTP method: a standard one such as PATCH or HEAD, or a custom verb such as
+/// PROPFIND. Verbs are limited to letters, digits, '-' and '_' because they also name
+/// saved configuration files.
+pub fn parse_method(method: &str) -> Result<Method> {
+    if method.is_empty() || !method.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("Invalid HTTP method '{}' (use letters, digits, '-' and '_')", method);
+    }
+    Method::from_bytes(method.as_bytes()).with_context(|| format!("Invalid HTTP method '{}'", method))
+}
+
+/// Builds the effective request configuration: the saved configuration for TARGET
+/// (if any) overlaid with the options given on the command line.
+pub fn resolve(args: &RequestArgs, target: Option<&str>) -> Result<RequestConfig> {
+    overlay_args(args, target).map_err(exit_code::config)
+}
+
+fn overlay_args(args: &RequestArgs, target: Option<&str>) -> Result<RequestConfig> {
+    parse_method(&args.method)?;
+    // TARGET が指定されている場合は保存／読み込みモード、指定がない場合は --url のみで実行
+    let target_is_url = target.map(|t| t.starts_with("http")).unwrap_or(false);
+    let url_to_use = if let Some(ref url) = args.url {
+        url.clone()
+    } else if target_is_url {
+        target.unwrap_or_default().to_string()
+    } else {
+        String::new()
+    };
+
+    let mut config = match target {
+        Some(target) if !target_is_url => load_saved(target, &args.method)?,
+        _ => RequestConfig::default(),
+    };
+
+    config.method = Some(args.method.to_uppercase());
+    if !url_to_use.is_empty() {
+        config.url = Some(url_to_use);
+    }
+    let mut cli_headers = parse_headers(&args.headers)?;
+    for option in &args.header_from_cmd {
+        let (name, value) = secret::header_from_command(option).map_err(exit_code::auth)?;
+        cli_headers.insert(name, value);
+    }
+    if let Some(ref mut saved_headers) = config.headers {
+        saved_headers.extend(cli_headers);
+    } else {
+        config.headers = Some(cli_headers);
+    }
+    if let Some(ref val) = args.value {
+        config.data = Some(apply_value(config.data.take(), val, args.replace_data));
+    } else if let Some(ref j) = args.json {
+        match serde_json::from_str::<Value>(j) {
+            Ok(v) => config.data = Some(v),
+            Err(_) => config.data = Some(json!(j)),
+        }
+    } else if let Some(ref data) = args.data {
+        config.data = Some(json!(data));
+    }
+    let patches = args
+        .set
+        .iter()
+        .map(|expr| patch::parse_set(expr))
+        .chain(args.unset.iter().map(|expr| patch::parse_unset(expr)))
+        .collect::<Result<Vec<_>>>()?;
+    config.data = patch::apply(config.data.take(), &patches)?;
+    if args.canonical_json {
+        config.canonical_json = Some(true);
+    }
+    if config.canonical_json == Some(true) {
+        config.data = config.data.take().map(canonical_json);
+    }
+    config.timeout = Some(args.timeout);
+    if args.connect_timeout.is_some() {
+        config.connect_timeout = args.connect_timeout;
+    }
+    if args.insecure {
+        config.insecure = Some(true);
+    }
+    // 保存した設定を別のディレクトリや run-all から使えるよう、絶対パスにしておく
+    if let Some(ref path) = args.cacert {
+        config.cacert = Some(tls::absolute(path)?);
+    }
+    if let Some(ref path) = args.cert {
+        config.cert = Some(tls::absolute(path)?);
+    }
+    if let Some(ref path) = args.key {
+        config.key = Some(tls::absolute(path)?);
+    }
+    if args.cookies {
+        config.cookies = Some(true);
+    }
+    if let Some(ref token) = args.bearer {
+        config.auth = Some(Auth::Bearer { token: token.clone() });
+    } else if let Some(ref basic) = args.basic {
+        config.auth = Some(Auth::basic(basic)?);
+    } else if let Some(ref api_key) = args.api_key {
+        config.auth = Some(Auth::api_key(api_key)?);
+    }
+    let parts = args
+        .form
+        .iter()
+        .map(|option| FormPart::field(option))
+        .chain(args.files.iter().map(|option| FormPart::upload(option)))
+        .collect::<Result<Vec<_>>>()?;
+    if !parts.is_empty() {
+        // 同じ名前の保存済みフィールドは置き換え、それ以外は残す
+        let form = config.form.get_or_insert_with(Vec::new);
+        form.retain(|saved| !parts.iter().any(|part| part.name == saved.name));
+        form.extend(parts);
+        config.data = None;
+        config.form_urlencoded = None;
+    }
+    let fields = args.form_urlencoded.iter().map(|option| FormPart::field(option)).collect::<Result<Vec<_>>>()?;
+    if !fields.is_empty() {
+        let form = config.form_urlencoded.get_or_insert_with(Vec::new);
+        form.retain(|saved| !fields.iter().any(|field| field.name == saved.name));
+        form.extend(fields);
+        config.data = None;
+        config.form = None;
+    }
+    for option in &args.query {
+        let (name, value) = option
+            .split_once('=')
+            .filter(|(name, _)| !name.is_empty())
+            .with_context(|| format!("Invalid --query value (expected NAME=VALUE): {}", option))?;
+        config.query.get_or_insert_with(BTreeMap::new).insert(name.to_string(), value.to_string());
+    }
+    if !args.tags.is_empty() {
+        config.tags = Some(args.tags.clone());
+    }
+    if !args.assertions.is_empty() {
+        // 保存済みのアサーションに追加する（同じ式は重複させない）
+        let assertions = config.assertions.get_or_insert_with(Vec::new);
+        for expr in &args.assertions {
+            if !assertions.contains(expr) {
+                assertions.push(expr.clone());
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// A completed request: the response with its body fully read.
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub elapsed: Duration,
+    /// The request as sent by `execute`: variables and query resolved, secrets left as
+    /// placeholders (None for traffic that did not go through `execute`).
+    pub request: Option<RequestConfig>,
+}
+
+impl Exchange {
+    /// The body decoded as UTF-8 (invalid sequences are replaced).
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// File extension for the body, from its Content-Type.
+    pub fn body_extension(&self) -> &'static str {
+        let content_type = self
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let known =
+            [("html", "html"), ("xml", "xml"), ("png", "png"), ("jpeg", "jpg"), ("gif", "gif"), ("pdf", "pdf"), ("zip", "zip")];
+        if content_type.contains("json") || serde_json::from_slice::<Value>(&self.body).is_ok() {
+            "json"
+        } else if let Some((_, extension)) = known.iter().find(|(name, _)| content_type.contains(name)) {
+            extension
+        } else if std::str::from_utf8(&self.body).is_ok() {
+            "txt"
+        } else {
+            "bin"
+        }
+    }
+}
+
+/// Resolves `{{name}}` placeholders, sends the request and records it in the
+/// history store and the request log (if enabled for TARGET).
+/// Every command that talks to a server goes through this function.
+pub async fn execute(written: &RequestConfig, target: Option<&str>, retry: &RetryPolicy) -> Result<Exchange> {
+    let config = vars::substitute(written, target).and_then(RequestConfig::with_query).map_err(exit_code::config)?;
+    schema::check_request(&config, target).map_err(exit_code::config)?;
+    let jar = cookies::open(&config, target).map_err(exit_code::config)?;
+    // 秘密情報は送信用のコピーにだけ展開し、履歴やログには残さない。
+    // 変数の値に含まれるプレースホルダーを展開しないよう、書かれたままの設定から一度に解決する
+    let outcome = async {
+        let vars = vars::resolve(target).map_err(exit_code::config)?;
+        let substituted = secret::substitute(written, &config, &vars).await.map_err(exit_code::auth)?;
+        let resolved = auth::apply(substituted).await.map_err(exit_code::auth)?;
+        send(&resolved, retry, jar.clone()).await
+    }
+    .await
+    .map(|exchange| Exchange { request: Some(config.clone()), ..exchange });
+    openapi::observe(
+        config.method.as_deref().unwrap_or("GET"),
+        config.url.as_deref().unwrap_or(""),
+        outcome.as_ref().ok().map(|e| e.status.as_u16()),
+    );
+    notify::observe(&config, target, &outcome);
+    if let Ok(ref exchange) = outcome {
+        budget::observe(&config, target, exchange);
+    }
+    if let Some(ref jar) = jar {
+        jar.save()?;
+    }
+    // 送信は済んでいるので、記録に失敗してもリクエストの結果は変えない。
+    // 解決済みの設定を再び execute に渡すと変数の値の中のプレースホルダーが展開されるため、書かれたままの設定も残す
+    if let Err(err) = history::record(target, written, &config, &outcome) {
+        eprintln!("{}", i18n::tf("Warning: Failed to record the request in the history: {}", &[&format!("{:#}", err)]));
+    }
+    if let Err(err) = request_log::record(target, &config, &outcome) {
+        eprintln!("{}", i18n::tf("Warning: Failed to write the request log: {}", &[&format!("{:#}", err)]));
+    }
+    outcome
+}
+
+/// Sends the request described by `config`, retrying according to `retry`. Cookies are
+/// taken from and stored in `jar` when one is given.
+async fn send(config: &RequestConfig, retry: &RetryPolicy, jar: Option<Arc<Jar>>) -> Result<Exchange> {
+    let url = config.url.as_ref().context("URL is not specified").map_err(exit_code::config)?;
+    let mut client = Client::builder().timeout(Duration::from_secs(config.timeout.unwrap_or(30)));
+    if let Some(secs) = config.connect_timeout {
+        client = client.connect_timeout(Duration::from_secs(secs));
+    }
+    if config.insecure == Some(true) {
+        client = client.danger_accept_invalid_certs(true);
+    }
+    client = tls::configure(client, config).map_err(exit_code::config)?;
+    if let Some(jar) = jar {
+        client = client.cookie_provider(jar);
+    }
+    let client = client.build()?;
+    let method = config.method.as_deref().context("HTTP method is not specified").map_err(exit_code::config)?;
+    let mut request_builder = client.request(parse_method(method)?, url);
+    if let Some(ref headers) = config.headers {
+        for (key, value) in headers {
+            request_builder = request_builder.header(key, value);
+        }
+    }
+    let bodies = [config.data.is_some(), config.form.is_some(), config.form_urlencoded.is_some()];
+    if bodies.into_iter().filter(|&set| set).count() > 1 {
+        bail!("A request can have only one of data, form and form_urlencoded");
+    }
+    // ファイルは一度だけ読み、再試行のたびに同じ内容からフォームを組み立てる
+    let form = config.form.as_deref().map(form::load).transpose()?;
+    // .json() と同様に、Content-Type が指定されていなければボディの種類から決める
+    let has_content_type = config
+        .headers
+        .as_ref()
+        .is_some_and(|headers| headers.keys().any(|key| key.eq_ignore_ascii_case("content-type")));
+    let body = match (&config.data, &config.form_urlencoded) {
+        (Some(data), _) => {
+            if !has_content_type {
+                request_builder = request_builder.header(reqwest::header::CONTENT_TYPE, "application/json");
+            }
+            // 変数の展開で値が変わることがあるため、送信直前にも正規化する
+            match config.canonical_json {
+                Some(true) => Some(serde_json::to_vec(&canonical_json(data.clone()))?),
+                _ => Some(serde_json::to_vec(data)?),
+            }
+        }
+        (None, Some(fields)) => {
+            if !has_content_type {
+                request_builder =
+                    request_builder.header(reqwest::header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+            }
+            Some(form::urlencode(fields)?.into_bytes())
+        }
+        (None, None) => None,
+    };
+    let started = Instant::now();
+    throttle::delay().await;
+    // 帯域制限時のボディはストリームで複製できないため、試行ごとに組み立てる
+    let build = || {
+        let builder = request_builder.try_clone().context("Request cannot be cloned for retrying")?;
+        Ok(match (&body, &form) {
+            (Some(bytes), _) => throttle::attach_body(builder, bytes.clone()),
+            (None, Some(form)) => builder.multipart(form::build(form)?),
+            (None, None) => builder,
+        })
+    };
+    let response = retry.send(build, method).await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = throttle::read_body(response).await?;
+    Ok(Exchange {
+        status,
+        headers,
+        body,
+        elapsed: started.elapsed(),
+        request: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_method_accepts_standard_and_custom_verbs() {
+        assert_eq!(parse_method("PATCH").unwrap(), Method::PATCH);
+        assert_eq!(parse_method("VERSION-CONTROL").unwrap().as_str(), "VERSION-CONTROL");
+        assert!(parse_method("").is_err());
+        assert!(parse_method("BAD VERB").is_err());
+        assert!(parse_method("../GET").is_err());
+    }
+
+    #[test]
+    fn merge_combines_nested_objects() {
+        let mut base = json!({"user": {"name": "Bob", "age": 30}, "active": true});
+        merge(&mut base, json!({"user": {"name": "Alice"}, "role": "admin"}));
+        assert_eq!(
+            base,
+            json!({"user": {"name": "Alice", "age": 30}, "active": true, "role": "admin"})
+        );
+    }
+
+    #[test]
+    fn merge_replaces_arrays() {
+        let mut base = json!({"tags": ["a", "b", "c"]});
+        merge(&mut base, json!({"tags": ["d"]}));
+        assert_eq!(base, json!({"tags": ["d"]}));
+    }
+
+    #[test]
+    fn merge_replaces_values_of_a_different_type() {
+        let mut base = json!({"user": {"name": "Bob"}, "count": 1});
+        merge(&mut base, json!({"user": "anonymous", "count": {"value": 2}}));
+        assert_eq!(base, json!({"user": "anonymous", "count": {"value": 2}}));
+
+        let mut base = json!(["saved"]);
+        merge(&mut base, json!({"key": 1}));
+        assert_eq!(base, json!({"key": 1}));
+    }
+
+    #[test]
+    fn merge_keeps_explicit_null() {
+        let mut base = json!({"note": "x"});
+        merge(&mut base, json!({"note": null}));
+        assert_eq!(base, json!({"note": null}));
+    }
+
+    #[test]
+    fn value_merges_into_saved_data() {
+        let saved = Some(json!({"name": "morpheus", "job": "leader"}));
+        assert_eq!(
+            apply_value(saved, r#"{"job": "zion resident"}"#, false),
+            json!({"name": "morpheus", "job": "zion resident"})
+        );
+    }
+
+    #[test]
+    fn value_without_saved_data_is_used_as_is() {
+        assert_eq!(apply_value(None, r#"{"a": 1}"#, false), json!({"a": 1}));
+    }
+
+    #[test]
+    fn replace_data_overwrites_saved_data() {
+        let saved = Some(json!({"name": "morpheus", "job": "leader"}));
+        assert_eq!(apply_value(saved, r#"{"job": "x"}"#, true), json!({"job": "x"}));
+    }
+
+    #[test]
+    fn non_json_value_replaces_saved_data_as_string() {
+        let saved = Some(json!({"name": "morpheus"}));
+        assert_eq!(apply_value(saved, "plain text", false), json!("plain text"));
+    }
+
+    #[test]
+    fn canonical_json_normalizes_numbers_and_sorts_keys() {
+        let value: Value = serde_json::from_str(r#"{"b": [1.0, 1e2, -0.0, 2.5], "a": {"d": 3, "c": 1.50}}"#).unwrap();
+        assert_eq!(canonical_json(value).to_string(), r#"{"a":{"c":1.5,"d":3},"b":[1,100,0,2.5]}"#);
+    }
+
+    #[test]
+    fn query_replaces_parameters_of_the_same_name_and_encodes_values() {
+        let config = RequestConfig {
+            url: Some("https://example.com/items?page=1&q=old".to_string()),
+            query: Some(BTreeMap::from([("q".to_string(), "a&b c".to_string()), ("sort".to_string(), "-id".to_string())])),
+            ..RequestConfig::default()
+        };
+        let config = config.with_query().unwrap();
+        assert_eq!(config.url.as_deref(), Some("https://example.com/items?page=1&q=a%26b+c&sort=-id"));
+        assert_eq!(config.query, None);
+    }
+}
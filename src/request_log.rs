@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::request::{Exchange, RequestConfig};
+use crate::settings::Settings;
+use crate::store;
+
+/// Log file given with --log-requests; takes precedence over the settings files.
+static LOG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the log file given on the command line for the rest of the process.
+pub fn set_override(path: PathBuf) {
+    let _ = LOG_OVERRIDE.set(path);
+}
+
+fn log_path(target: Option<&str>) -> Result<Option<PathBuf>> {
+    if let Some(path) = LOG_OVERRIDE.get() {
+        return Ok(Some(path.clone()));
+    }
+    Settings::for_target(target)?.log_path()
+}
+
+/// Appends one JSON line describing the request and its outcome, if logging is enabled.
+pub fn record(target: Option<&str>, config: &RequestConfig, outcome: &Result<Exchange>) -> Result<()> {
+    let Some(path) = log_path(target)? else {
+        return Ok(());
+    };
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    let (response, error) = match outcome {
+        Ok(exchange) => {
+            let headers: serde_json::Map<String, Value> = exchange
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    (name.to_string(), json!(String::from_utf8_lossy(value.as_bytes())))
+                })
+                .collect();
+            let response = json!({
+                "status": exchange.status.as_u16(),
+                "headers": headers,
+                "body": exchange.text(),
+                "elapsed_ms": exchange.elapsed.as_millis() as u64,
+            });
+            (response, Value::Null)
+        }
+        Err(err) => (Value::Null, json!(err.to_string())),
+    };
+    let line = json!({
+        "timestamp_ms": timestamp,
+        "target": target,
+        "request": config,
+        "response": response,
+        "error": error,
+    });
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let _lock = store::lock(&path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open request log {:?}", path))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write request log {:?}", path))
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use reqwest::{RequestBuilder, Response};
+use std::{error::Error as _, io, time::Duration};
+
+use crate::i18n;
+use crate::net_error::NetworkError;
+
+/// Methods that can safely be sent more than once without changing the outcome.
+const IDEMPOTENT_METHODS: &[&str] = &["GET", "HEAD", "PUT", "DELETE", "OPTIONS", "TRACE"];
+
+/// Controls how transient network failures are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt.
+    pub retries: u32,
+    /// Delay before the first retry; doubled after every further attempt.
+    pub delay: Duration,
+    /// Also retry non-idempotent methods such as POST.
+    pub allow_unsafe: bool,
+}
+
+/// Command-line options controlling retries.
+#[derive(clap::Args, Debug, Clone)]
+pub struct RetryArgs {
+    /// タイムアウトや接続エラー時のリトライ回数（冪等なメソッドのみ対象、0 でリトライしない）
+    #[arg(long = "retry", default_value = "2")]
+    pub retry: u32,
+
+    /// 最初のリトライまでの待機ミリ秒（以降は倍々に増加）
+    #[arg(long = "retry-delay", default_value = "500")]
+    pub retry_delay: u64,
+
+    /// POST などの非冪等メソッドもリトライ対象にします（リソースの二重作成に注意）
+    #[arg(long = "retry-unsafe")]
+    pub retry_unsafe: bool,
+}
+
+impl RetryArgs {
+    pub fn policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.retry,
+            delay: Duration::from_millis(self.retry_delay),
+            allow_unsafe: self.retry_unsafe,
+        }
+    }
+}
+
+pub fn is_idempotent(method: &str) -> bool {
+    IDEMPOTENT_METHODS.contains(&method.to_uppercase().as_str())
+}
+
+/// Returns true for failures where the server most likely never processed the
+/// request: timeouts, failed connects and connections reset by the peer.
+pub fn is_retryable(err: &reqwest::Error) -> bool {
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+    let mut source = err.source();
+    while let Some(cause) = source {
+        if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+            if matches!(
+                io_err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        source = cause.source();
+    }
+    false
+}
+
+impl RetryPolicy {
+    /// Number of retries allowed for the given method.
+    pub fn retries_for(&self, method: &str) -> u32 {
+        if is_idempotent(method) || self.allow_unsafe {
+            self.retries
+        } else {
+            0
+        }
+    }
+
+    /// Sends the request made by BUILD (called for every attempt), retrying transient
+    /// network errors according to the policy.
+    pub async fn send(&self, build: impl Fn() -> Result<RequestBuilder>, method: &str) -> Result<Response> {
+        let retries = self.retries_for(method);
+        let mut delay = self.delay;
+        let mut attempt = 0;
+        loop {
+            match build()?.send().await {
+                Ok(response) => return Ok(response),
+                Err(err) if is_retryable(&err) && attempt < retries => {
+                    attempt += 1;
+                    eprintln!(
+                        "{}",
+                        i18n::tf(
+                            "Request failed ({}); retrying in {} ({}/{})",
+                            &[&NetworkError::from(&err), &format!("{:?}", delay), &attempt, &retries]
+                        )
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(err) => {
+                    if self.retries > 0 && retries == 0 && is_retryable(&err) {
+                        eprintln!(
+                            "{}",
+                            i18n::tf(
+                                "{} is not idempotent, so it was not retried. Pass --retry-unsafe to retry it anyway.",
+                                &[&method]
+                            )
+                        );
+                    }
+                    return Err(NetworkError::from(err).into());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        retry: RetryArgs,
+    }
+
+    fn policy(args: &[&str]) -> RetryPolicy {
+        Cli::parse_from(std::iter::once("ferrapi_tester").chain(args.iter().copied())).retry.policy()
+    }
+
+    #[test]
+    fn idempotent_methods_are_retried_by_default() {
+        let default = policy(&[]);
+        assert_eq!(default.retries_for("GET"), 2);
+        assert_eq!(default.retries_for("delete"), 2);
+        assert_eq!(default.retries_for("POST"), 0);
+        assert_eq!(default.retries_for("PATCH"), 0);
+        assert_eq!(policy(&["--retry-unsafe"]).retries_for("POST"), 2);
+        assert_eq!(policy(&["--retry", "0"]).retries_for("GET"), 0);
+    }
+}
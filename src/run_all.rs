@@ -0,0 +1,605 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, FromArgMatches, ValueHint};
+use serde_json::json;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::annotation;
+use crate::assertion::{self, Failure};
+use crate::budget;
+use crate::cancel;
+use crate::exit_code;
+use crate::hooks::Hooks;
+use crate::i18n;
+use crate::mount;
+use crate::namespace::{self, SavedConfig};
+use crate::openapi::{self, CoverageArgs};
+use crate::output::{self, Table};
+use crate::pattern::{self, NameFilter};
+use crate::request::{self, Exchange, RequestConfig};
+use crate::retry::{RetryArgs, RetryPolicy};
+use crate::schema;
+use crate::theme;
+use crate::get_default_dir;
+
+/// Options of `run-all`.
+#[derive(clap::Args, Debug)]
+pub struct RunAllArgs {
+    /// 実行する名前空間のサブツリー（例: SystemA/）。省略時はすべての保存済み設定
+    pub target: Option<String>,
+
+    /// 指定したメソッドの設定だけを実行します（例: GET）
+    #[arg(short = 'X', long = "method")]
+    pub method: Option<String>,
+
+    /// 指定したタグのいずれかを持つ設定だけを実行します（複数指定可）
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// 名前（例: "SystemA/users/create POST"）がパターンに一致する設定だけを実行します（複数指定可）。
+    /// ワイルドカード（SystemA/*/POST）または正規表現（users.*create）で指定します
+    #[arg(long = "filter", value_name = "PATTERN")]
+    pub filter: Vec<String>,
+
+    /// 同時に送信するリクエスト数
+    #[arg(long = "concurrency", default_value = "4")]
+    pub concurrency: usize,
+
+    /// 名前空間ごとに並列実行するワーカー数。同じ名前空間の設定は保存順に逐次実行します
+    /// （--concurrency の代わりに使用）
+    #[arg(short = 'j', long = "jobs", value_name = "N", conflicts_with = "concurrency")]
+    pub jobs: Option<usize>,
+
+    /// 失敗した設定を再実行する回数（設定ファイルの test_retries が優先）
+    #[arg(long = "test-retries", default_value = "0")]
+    pub test_retries: u32,
+
+    /// 既知の不安定なターゲットを列挙した隔離リスト（省略時は ~/.ferrapi_tester/quarantine.txt）。
+    /// 隔離中の設定の失敗は別に集計され、終了コードに影響しません
+    #[arg(long = "quarantine", value_name = "FILE")]
+    pub quarantine: Option<PathBuf>,
+
+    /// 設定をランダムな順序で実行し、保存済みリクエスト間の隠れた順序依存を洗い出します
+    #[arg(long = "shuffle")]
+    pub shuffle: bool,
+
+    /// --shuffle の乱数シード。前回の実行順序を再現する場合に指定します
+    #[arg(long = "seed", value_name = "N", requires = "shuffle")]
+    pub seed: Option<u64>,
+
+    /// 名前空間の _hooks.yaml に定義された前後処理（before/after）を実行しません
+    #[arg(long = "no-hooks")]
+    pub no_hooks: bool,
+
+    /// 名前空間の request_schema（settings.json）によるリクエストボディの事前検証を行いません
+    #[arg(long = "no-validate-request")]
+    pub no_validate_request: bool,
+
+    /// 失敗した設定が N 件に達したら、残りの設定を実行せずに中止します
+    #[arg(long = "abort-after-failures", value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub abort_after_failures: Option<u64>,
+
+    /// 5 件以上終わった時点で失敗率がこの値（例: 20%）以上になったら、残りの設定を実行せずに中止します
+    #[arg(long = "abort-on-error-rate", value_name = "PERCENT", value_parser = parse_percent)]
+    pub abort_on_error_rate: Option<f64>,
+
+    /// 結果の下に表示するレスポンス時間の SLO（例: 500ms）。超えたリクエストは赤で表示します。
+    /// 省略時は各設定の budget の max_latency（settings.json）
+    #[arg(long = "slo", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub slo: Option<Duration>,
+
+    /// 各レスポンスボディを DIR/<TARGET>/<METHOD>.<拡張子> にそのまま（バイナリも）書き込みます
+    #[arg(long = "output-dir", value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub output_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    #[command(flatten)]
+    pub coverage: CoverageArgs,
+}
+
+impl RunAllArgs {
+    /// The options of `run-all TARGET` with every other value at its default.
+    pub fn for_target(target: &str) -> Result<RunAllArgs> {
+        let command = RunAllArgs::augment_args(clap::Command::new("run-all"));
+        Ok(RunAllArgs::from_arg_matches(&command.try_get_matches_from(["run-all", target])?)?)
+    }
+}
+
+/// Parses a percentage such as "20%" or "20".
+fn parse_percent(input: &str) -> Result<f64> {
+    let number = input.trim().trim_end_matches('%').trim();
+    let percent: f64 = number.parse().with_context(|| format!("Invalid percentage: {}", input))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        bail!("Percentage must be above 0% and at most 100%: {}", input);
+    }
+    Ok(percent)
+}
+
+/// Number of finished configurations before --abort-on-error-rate applies, so that a
+/// single early failure does not stop the run.
+const MIN_REQUESTS_FOR_ERROR_RATE: usize = 5;
+
+/// Stops a run early once too many configurations failed (--abort-after-failures and
+/// --abort-on-error-rate), instead of hammering a struggling service with the rest.
+#[derive(Default)]
+struct Breaker {
+    max_failures: Option<u64>,
+    max_error_rate: Option<f64>,
+    finished: AtomicUsize,
+    failed: AtomicUsize,
+    /// Why the run was aborted; set once.
+    tripped: OnceLock<String>,
+}
+
+impl Breaker {
+    /// Counts a finished configuration and trips the breaker when a threshold is reached.
+    fn record(&self, failed: bool) {
+        let finished = self.finished.fetch_add(1, Ordering::SeqCst) + 1;
+        let failures = self.failed.fetch_add(usize::from(failed), Ordering::SeqCst) + usize::from(failed);
+        if let Some(max) = self.max_failures.filter(|max| failures as u64 >= *max) {
+            let _ = self.tripped.set(format!("{} failure(s) (--abort-after-failures {})", failures, max));
+        }
+        let rate = failures as f64 * 100.0 / finished as f64;
+        if let Some(max) = self.max_error_rate.filter(|max| finished >= MIN_REQUESTS_FOR_ERROR_RATE && rate >= *max) {
+            let _ = self.tripped.set(format!(
+                "error rate {:.0}% after {} request(s) (--abort-on-error-rate {}%)",
+                rate, finished, max
+            ));
+        }
+    }
+
+    fn reason(&self) -> Option<&str> {
+        self.tripped.get().map(String::as_str)
+    }
+}
+
+/// Bars of a sparkline, lowest first.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One bar per value, scaled between the smallest and the largest.
+fn sparkline(values: &[u64]) -> Vec<char> {
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let range = (max - min).max(1);
+    values.iter().map(|value| SPARKS[((value - min) * (SPARKS.len() as u64 - 1) / range) as usize]).collect()
+}
+
+/// The result of running one saved configuration.
+struct Outcome {
+    index: usize,
+    status: Option<u16>,
+    elapsed: Option<Duration>,
+    error: Option<String>,
+    /// Failed `warn:` assertions; reported without failing the run.
+    warnings: Vec<String>,
+    /// An assertion on the status replaces the default "status below 400" rule.
+    status_asserted: bool,
+    /// Number of times the configuration was run (more than 1 after test retries).
+    attempts: u32,
+    /// The failed assertions of the last attempt, for CI annotations.
+    failures: Vec<Failure>,
+    /// Exit code of the failure (see `exit_code`); assertion failure for a bad response.
+    exit_code: i32,
+    /// Not run because the run was aborted.
+    skipped: bool,
+}
+
+impl Outcome {
+    fn failed(index: usize, error: String, exit_code: i32) -> Outcome {
+        Outcome {
+            index,
+            status: None,
+            elapsed: None,
+            error: Some(error),
+            warnings: Vec::new(),
+            status_asserted: false,
+            attempts: 1,
+            failures: Vec::new(),
+            exit_code,
+            skipped: false,
+        }
+    }
+
+    fn skipped(index: usize) -> Outcome {
+        Outcome { skipped: true, ..Outcome::failed(index, "skipped (run aborted)".to_string(), exit_code::SUCCESS) }
+    }
+
+    fn passed(&self) -> bool {
+        self.error.is_none() && (self.status_asserted || self.status.is_some_and(|s| s < 400))
+    }
+}
+
+/// Known-flaky targets whose failures are reported separately and don't fail the run.
+/// One entry per line: `TARGET [METHOD]`, where TARGET also covers everything below it;
+/// `#` starts a comment.
+#[derive(Debug, Default)]
+struct Quarantine {
+    entries: Vec<(String, Option<String>)>,
+}
+
+impl Quarantine {
+    fn default_path() -> Result<PathBuf> {
+        Ok(get_default_dir()?.join("quarantine.txt"))
+    }
+
+    /// Loads the list; a missing default file means nothing is quarantined.
+    fn load(path: Option<&Path>) -> Result<Quarantine> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (Self::default_path()?, false),
+        };
+        if !required && !path.exists() {
+            return Ok(Quarantine::default());
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read quarantine list {:?}", path))?;
+        let mut entries = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let mut words = line.split_whitespace();
+            let Some(target) = words.next() else {
+                continue;
+            };
+            let target = namespace::normalize(target)
+                .with_context(|| format!("Invalid target on line {} of {:?}", number + 1, path))?;
+            entries.push((target, words.next().map(str::to_uppercase)));
+        }
+        Ok(Quarantine { entries })
+    }
+
+    fn contains(&self, saved: &SavedConfig) -> bool {
+        self.entries
+            .iter()
+            .any(|(target, method)| saved.is_under(target) && method.as_ref().is_none_or(|m| *m == saved.method))
+    }
+}
+
+fn load(saved: &SavedConfig) -> Result<RequestConfig> {
+    let content = fs::read_to_string(&saved.path)
+        .with_context(|| format!("Failed to read config from {:?}", saved.path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", saved.path))
+}
+
+/// Where `--output-dir` puts the response body of SAVED.
+fn body_path(dir: &Path, saved: &SavedConfig, exchange: &Exchange) -> Result<PathBuf> {
+    Ok(namespace::dir(dir, &saved.target)?.join(format!("{}.{}", saved.method, exchange.body_extension())))
+}
+
+async fn attempt(
+    index: usize,
+    saved: &SavedConfig,
+    config: &RequestConfig,
+    retry: RetryPolicy,
+    output_dir: Option<&Path>,
+) -> Outcome {
+    let result = async {
+        let assertions = assertion::parse_all(config.assertions.as_deref().unwrap_or_default())?;
+        let exchange = request::execute(config, Some(&saved.target), &retry).await?;
+        if let Some(dir) = output_dir {
+            output::write_body(&body_path(dir, saved, &exchange)?, &exchange.body)?;
+        }
+        anyhow::Ok((exchange, assertions))
+    }
+    .await;
+    match result {
+        Ok((exchange, assertions)) => {
+            let failures = assertion::check_all(&assertions, &exchange);
+            let (errors, warnings) = assertion::partition(failures.clone());
+            Outcome {
+                index,
+                status: Some(exchange.status.as_u16()),
+                elapsed: Some(exchange.elapsed),
+                error: (!errors.is_empty()).then(|| errors.join("; ")),
+                warnings,
+                status_asserted: assertion::checks_status(&assertions),
+                attempts: 1,
+                failures,
+                exit_code: exit_code::ASSERTION,
+                skipped: false,
+            }
+        }
+        Err(err) => Outcome::failed(index, err.to_string(), exit_code::of(&err)),
+    }
+}
+
+/// Runs a configuration, running it again up to its test retry count while it fails.
+async fn execute(
+    index: usize,
+    saved: SavedConfig,
+    retry: RetryPolicy,
+    test_retries: u32,
+    output_dir: Option<&Path>,
+) -> Outcome {
+    let mut config = match load(&saved) {
+        Ok(config) => config,
+        Err(err) => return Outcome::failed(index, format!("{:#}", err), exit_code::CONFIG),
+    };
+    config.method = Some(saved.method.clone());
+    let retries = config.test_retries.unwrap_or(test_retries);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let mut outcome = attempt(index, &saved, &config, retry, output_dir).await;
+        outcome.attempts = attempts;
+        if outcome.passed() || attempts > retries || cancel::is_cancelled() {
+            return outcome;
+        }
+    }
+}
+
+/// Groups configurations by the namespace one level below PREFIX (the top-level
+/// namespace without a prefix), keeping their original order within each group.
+fn group_by_namespace(selected: &[SavedConfig], prefix: Option<&str>) -> Vec<Vec<(usize, SavedConfig)>> {
+    let depth = prefix.map(|p| p.split('/').count()).unwrap_or(0) + 1;
+    let mut groups: Vec<(String, Vec<(usize, SavedConfig)>)> = Vec::new();
+    for (index, saved) in selected.iter().enumerate() {
+        let key = saved.target.split('/').take(depth).collect::<Vec<_>>().join("/");
+        match groups.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, group)) => group.push((index, saved.clone())),
+            None => groups.push((key, vec![(index, saved.clone())])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Saved configurations under the subtree that match the method and tag filters.
+fn select(args: &RunAllArgs) -> Result<Vec<SavedConfig>> {
+    let prefix = args.target.as_deref().map(namespace::normalize).transpose()?;
+    let method = args.method.as_ref().map(|m| m.to_uppercase());
+    let names = NameFilter::parse(&args.filter)?;
+    let saved = mount::list_saved()?;
+    let mut selected = Vec::new();
+    for config in saved {
+        if prefix.as_deref().is_some_and(|p| !config.is_under(p)) {
+            continue;
+        }
+        if method.as_deref().is_some_and(|m| m != config.method) {
+            continue;
+        }
+        if !names.matches(&pattern::config_name(&config.target, &config.method)) {
+            continue;
+        }
+        if !args.tags.is_empty() {
+            let tags = load(&config)?.tags.unwrap_or_default();
+            if !args.tags.iter().any(|tag| tags.contains(tag)) {
+                continue;
+            }
+        }
+        selected.push(config);
+    }
+    Ok(selected)
+}
+
+pub async fn run(args: RunAllArgs) -> Result<()> {
+    // テストとして実行する run-all では、既定でボディを送信前に検証する
+    schema::set_validation(!args.no_validate_request);
+    openapi::with_coverage(&args.coverage, args.target.as_deref(), run_suite(&args)).await
+}
+
+/// Runs the selected configurations, wrapped in the setup and teardown hooks of the suite.
+async fn run_suite(args: &RunAllArgs) -> Result<()> {
+    let mut selected = select(args)?;
+    if selected.is_empty() {
+        return Err(exit_code::config(anyhow!("No saved configurations match")));
+    }
+    if args.shuffle {
+        // シードを表示しておけば、同じ順序を --seed で再現できる
+        let seed = args.seed.unwrap_or_else(|| fastrand::u64(..));
+        fastrand::Rng::with_seed(seed).shuffle(&mut selected);
+        output::renderer()?.note(&i18n::tf("Shuffled the execution order with seed {} (reproduce with --shuffle --seed {}).", &[&seed, &seed]));
+    }
+    let hooks = if args.no_hooks { None } else { Hooks::find(args.target.as_deref())? };
+    match hooks {
+        Some(hooks) => {
+            let renderer = output::renderer()?;
+            hooks.wrap(&args.retry.policy(), renderer.as_ref(), run_selected(args, selected)).await
+        }
+        None => run_selected(args, selected).await,
+    }
+}
+
+/// A sparkline of the response times in run order with their min, median and max. Bars
+/// within the SLO (--slo, otherwise the `max_latency` budget of each configuration) are
+/// green and bars over it red, so that a slowdown stands out.
+fn latency_summary(
+    outcomes: &[Outcome],
+    selected: &[SavedConfig],
+    slo: Option<Duration>,
+    stderr: bool,
+) -> Option<String> {
+    let timed: Vec<(&Outcome, Duration)> = outcomes.iter().filter_map(|o| o.elapsed.map(|e| (o, e))).collect();
+    if timed.is_empty() {
+        return None;
+    }
+    let millis: Vec<u64> = timed.iter().map(|(_, elapsed)| elapsed.as_millis() as u64).collect();
+    let mut over = 0;
+    let mut bars = String::new();
+    for ((outcome, elapsed), bar) in timed.iter().zip(sparkline(&millis)) {
+        let target = &selected[outcome.index].target;
+        match slo.or_else(|| budget::max_latency(Some(target))) {
+            Some(slo) => {
+                over += usize::from(*elapsed > slo);
+                bars.push_str(&theme::latency(*elapsed <= slo, &bar.to_string(), stderr));
+            }
+            None => bars.push(bar),
+        }
+    }
+    let mut sorted = millis.clone();
+    sorted.sort_unstable();
+    let (min, median, max) = (sorted[0], sorted[sorted.len() / 2], sorted[sorted.len() - 1]);
+    let mut line = i18n::tf("Latency: {} (min {}ms, median {}ms, max {}ms)", &[&bars, &min, &median, &max]);
+    if over > 0 {
+        line.push_str(&i18n::tf("; {} of {} over the SLO", &[&over, &timed.len()]));
+    }
+    Some(line)
+}
+
+async fn run_selected(args: &RunAllArgs, selected: Vec<SavedConfig>) -> Result<()> {
+    let retry = args.retry.policy();
+    let quarantine = Arc::new(Quarantine::load(args.quarantine.as_deref())?);
+    let breaker = Arc::new(Breaker {
+        max_failures: args.abort_after_failures,
+        max_error_rate: args.abort_on_error_rate,
+        ..Breaker::default()
+    });
+    let test_retries = args.test_retries;
+    let groups = match args.jobs {
+        Some(_) => {
+            let prefix = args.target.as_deref().map(namespace::normalize).transpose()?;
+            group_by_namespace(&selected, prefix.as_deref())
+        }
+        None => selected.iter().cloned().enumerate().map(|entry| vec![entry]).collect(),
+    };
+    let semaphore = Arc::new(Semaphore::new(args.jobs.unwrap_or(args.concurrency).max(1)));
+    let mut tasks = JoinSet::new();
+    for group in groups {
+        let semaphore = Arc::clone(&semaphore);
+        let quarantine = Arc::clone(&quarantine);
+        let breaker = Arc::clone(&breaker);
+        let output_dir = args.output_dir.clone();
+        tasks.spawn(async move {
+            let skipped = |index| Outcome::failed(index, "skipped (interrupted)".to_string(), exit_code::INTERRUPTED);
+            // Ctrl-C 後は未実行の設定を実行しない
+            let _permit = tokio::select! {
+                permit = semaphore.acquire_owned() => permit,
+                _ = cancel::cancelled() => {
+                    return group.iter().map(|(index, _)| skipped(*index)).collect::<Vec<_>>();
+                }
+            };
+            // 同じ名前空間の設定は保存順に 1 つずつ実行する
+            let mut outcomes = Vec::new();
+            for (index, saved) in group {
+                if cancel::is_cancelled() {
+                    outcomes.push(skipped(index));
+                    continue;
+                }
+                // 中止した後は残りの設定を送信しない
+                if breaker.reason().is_some() {
+                    outcomes.push(Outcome::skipped(index));
+                    continue;
+                }
+                let outcome = execute(index, saved.clone(), retry, test_retries, output_dir.as_deref()).await;
+                breaker.record(!outcome.passed() && !quarantine.contains(&saved));
+                outcomes.push(outcome);
+            }
+            outcomes
+        });
+    }
+    let mut outcomes = Vec::new();
+    while let Some(group) = tasks.join_next().await {
+        outcomes.extend(group.context("run-all task panicked")?);
+    }
+    outcomes.sort_by_key(|o| o.index);
+
+    let renderer = output::renderer()?;
+    let mut table = Table::new(&[
+        ("TARGET", "target"),
+        ("METHOD", "method"),
+        ("STATUS", "status"),
+        ("TIME(ms)", "elapsed_ms"),
+        ("RESULT", "result"),
+    ]);
+    for outcome in &outcomes {
+        let saved = &selected[outcome.index];
+        let mut result = match outcome.error {
+            _ if outcome.skipped => "SKIPPED".to_string(),
+            Some(ref error) => format!("FAIL {}", error),
+            None if outcome.passed() && !outcome.warnings.is_empty() => format!("ok ({})", outcome.warnings.join("; ")),
+            None if outcome.passed() => "ok".to_string(),
+            None => "FAIL".to_string(),
+        };
+        if outcome.attempts > 1 {
+            result = format!("{} [{} attempts]", result, outcome.attempts);
+        }
+        if !outcome.passed() && !outcome.skipped && quarantine.contains(saved) {
+            result = format!("QUARANTINED {}", result);
+        } else {
+            let endpoint = format!("{} {}", saved.method, saved.target);
+            annotation::report(&endpoint, std::slice::from_ref(&saved.path), &outcome.failures);
+        }
+        table.push(vec![
+            json!(saved.target),
+            json!(saved.method),
+            json!(outcome.status),
+            json!(outcome.elapsed.map(|e| e.as_millis() as u64)),
+            json!(result),
+        ]);
+    }
+    print!("{}", renderer.table(&table)?);
+    let passed = outcomes.iter().filter(|o| o.passed()).count();
+    // 隔離リストに載っている設定の失敗は集計を分け、全体の失敗には数えない
+    let quarantined = outcomes
+        .iter()
+        .filter(|o| !o.passed() && !o.skipped && quarantine.contains(&selected[o.index]))
+        .count();
+    let skipped = outcomes.iter().filter(|o| o.skipped).count();
+    let failed = outcomes.len() - passed - quarantined - skipped;
+    // 失敗の種類がすべて同じならその終了コード、混在していればアサーション失敗とする
+    let mut codes = outcomes
+        .iter()
+        .filter(|o| !o.passed() && !o.skipped && !quarantine.contains(&selected[o.index]))
+        .map(|o| o.exit_code);
+    let first = codes.next().unwrap_or(exit_code::ASSERTION);
+    let code = if codes.all(|code| code == first) { first } else { exit_code::ASSERTION };
+    if quarantined > 0 {
+        renderer.note(&i18n::tf(
+            "\n{} passed, {} failed, {} quarantined, {} total.",
+            &[&passed, &failed, &quarantined, &outcomes.len()],
+        ));
+    } else {
+        renderer.note(&i18n::tf("\n{} passed, {} failed, {} total.", &[&passed, &failed, &outcomes.len()]));
+    }
+    if let Some(line) = latency_summary(&outcomes, &selected, args.slo, !renderer.human()) {
+        renderer.note(&line);
+    }
+    if let Some(reason) = breaker.reason() {
+        renderer.note(&i18n::tf("Aborted the run after {}; {} configuration(s) skipped.", &[&reason, &skipped]));
+    }
+    if failed > 0 {
+        return Err(exit_code::classify(code, anyhow!("{} of {} request(s) failed", failed, outcomes.len())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_breaker_trips_on_failures_and_error_rate() {
+        let breaker = Breaker { max_failures: Some(2), ..Breaker::default() };
+        breaker.record(true);
+        breaker.record(false);
+        assert!(breaker.reason().is_none());
+        breaker.record(true);
+        assert_eq!(breaker.reason(), Some("2 failure(s) (--abort-after-failures 2)"));
+
+        let breaker = Breaker { max_error_rate: Some(parse_percent("50%").unwrap()), ..Breaker::default() };
+        // 最初の失敗だけでは中止しない
+        for failed in [true, true, false, false] {
+            breaker.record(failed);
+        }
+        assert!(breaker.reason().is_none());
+        breaker.record(true);
+        assert_eq!(breaker.reason(), Some("error rate 60% after 5 request(s) (--abort-on-error-rate 50%)"));
+        assert!(parse_percent("0%").is_err());
+        assert!(parse_percent("120").is_err());
+    }
+
+    #[test]
+    fn sparklines_scale_between_the_fastest_and_slowest() {
+        assert_eq!(sparkline(&[10, 20, 80, 45]).into_iter().collect::<String>(), "▁▂█▄");
+        assert_eq!(sparkline(&[30, 30]).into_iter().collect::<String>(), "▁▁");
+        assert!(sparkline(&[]).is_empty());
+    }
+}
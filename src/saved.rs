@@ -0,0 +1,86 @@
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::fs;
+
+use crate::get_config_path;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::mount;
+use crate::namespace;
+use crate::output::{self, Table};
+use crate::request::{self, RequestConfig};
+use crate::store;
+
+/// Writes CONFIG as the saved configuration of TARGET for METHOD.
+pub fn save(target: &str, method: &str, config: &RequestConfig) -> Result<()> {
+    let base_dir = get_default_dir()?;
+    namespace::create(&base_dir, target)?;
+    let config_path = get_config_path(&base_dir, target, method)?;
+    let shared = mount::config_path(target, method)?;
+    if shared != config_path {
+        // 共有ツリーはそのままにして、ベースディレクトリに個人用のコピーを作る
+        eprintln!("{}", i18n::tf("Note: this hides the read-only configuration at {}", &[&format!("{:?}", shared)]));
+    }
+    let serialized = serde_json::to_string_pretty(config).with_context(|| "Failed to serialize configuration")?;
+    store::write_atomic(&config_path, serialized)
+        .with_context(|| format!("Failed to write configuration to {:?}", config_path))?;
+    println!("{}", i18n::tf("Configuration saved to {}", &[&format!("{:?}", config_path)]));
+    Ok(())
+}
+
+/// Lists the saved configurations under PREFIX (all of them without one).
+pub fn list(prefix: Option<&str>) -> Result<()> {
+    let prefix = prefix.map(namespace::normalize).transpose()?;
+    let mounted = !mount::roots()?.is_empty();
+    let mut table = if mounted {
+        Table::new(&[("TARGET", "target"), ("METHOD", "method"), ("URL", "url"), ("MOUNT", "mount")])
+    } else {
+        Table::new(&[("TARGET", "target"), ("METHOD", "method"), ("URL", "url")])
+    };
+    for saved in mount::list_saved()? {
+        if prefix.as_deref().is_some_and(|p| !saved.is_under(p)) {
+            continue;
+        }
+        // 壊れた設定があっても一覧は表示し、URL 欄で知らせる
+        let url = match request::load_saved(&saved.target, &saved.method) {
+            Ok(config) => config.url.unwrap_or_default(),
+            Err(err) => format!("(invalid: {:#})", err),
+        };
+        let mut row = vec![json!(saved.target), json!(saved.method), json!(url)];
+        if mounted {
+            row.push(json!(saved.mount.map(|root| root.display().to_string())));
+        }
+        table.push(row);
+    }
+    output::renderer()?.print(&table, i18n::t("No saved configurations found."))
+}
+
+/// Deletes the configuration saved for METHOD in TARGET.
+pub fn delete(target: &str, method: &str) -> Result<()> {
+    mount::check_deletable(target, Some(method), "Configuration")?;
+    let config_path = get_config_path(&get_default_dir()?, target, method)?;
+    if config_path.exists() {
+        fs::remove_file(&config_path).with_context(|| format!("Failed to delete configuration at {:?}", config_path))?;
+        println!("{}", i18n::tf("Configuration at {} deleted.", &[&format!("{:?}", config_path)]));
+    } else {
+        println!("{}", i18n::tf("No configuration found at {}", &[&format!("{:?}", config_path)]));
+    }
+    Ok(())
+}
+
+/// Deletes the namespace directory of TARGET with everything in it.
+pub fn delete_namespace(target: &str) -> Result<()> {
+    mount::check_deletable(target, None, "Namespace")?;
+    let namespace_dir = namespace::dir(&get_default_dir()?, target)?;
+    if namespace_dir == get_default_dir()? {
+        bail!("Refusing to delete the whole configuration directory");
+    }
+    if namespace_dir.exists() {
+        fs::remove_dir_all(&namespace_dir)
+            .with_context(|| format!("Failed to delete namespace directory {:?}", namespace_dir))?;
+        println!("{}", i18n::tf("Namespace directory {} deleted.", &[&format!("{:?}", namespace_dir)]));
+    } else {
+        println!("{}", i18n::tf("No namespace directory found at {}", &[&format!("{:?}", namespace_dir)]));
+    }
+    Ok(())
+}
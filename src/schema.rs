@@ -0,0 +1,351 @@
+use anyhow::{bail, Context, Result};
+use clap::{Subcommand, ValueEnum};
+use regex::Regex;
+use schemars::schema::RootSchema;
+use serde_json::Value;
+use std::{
+    fs,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::chain;
+use crate::request::RequestConfig;
+use crate::settings::Settings;
+use crate::transaction;
+use crate::vars::Vars;
+
+/// Subcommands of `schema`.
+#[derive(Subcommand, Debug)]
+pub enum SchemaCommand {
+    /// 手で編集するファイルの JSON Schema を出力します（エディタでの検証と補完用）。
+    Print {
+        /// ファイルの種類
+        #[arg(value_enum)]
+        kind: SchemaKind,
+    },
+}
+
+/// Files the tool reads that have a published JSON Schema.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// A saved configuration, e.g. SystemA/users/GET.json.
+    Config,
+    /// An environment file (envs/NAME.json) or a vars.json file.
+    Env,
+    /// A transaction file run with `run FILE`.
+    Transaction,
+    /// A chain file run with `chain run FILE`.
+    Chain,
+}
+
+/// JSON Schema of the files of KIND.
+pub fn json_schema(kind: SchemaKind) -> RootSchema {
+    let (mut schema, title) = match kind {
+        SchemaKind::Config => (schemars::schema_for!(RequestConfig), "ferrapi_tester request configuration"),
+        SchemaKind::Env => (schemars::schema_for!(Vars), "ferrapi_tester environment variables"),
+        SchemaKind::Transaction => (transaction::file_schema(), "ferrapi_tester transaction file"),
+        SchemaKind::Chain => (chain::file_schema(), "ferrapi_tester chain file"),
+    };
+    schema.schema.metadata().title = Some(title.to_string());
+    schema
+}
+
+pub fn run(command: SchemaCommand) -> Result<()> {
+    match command {
+        SchemaCommand::Print { kind } => {
+            println!("{}", serde_json::to_string_pretty(&json_schema(kind))?);
+            Ok(())
+        }
+    }
+}
+
+/// Whether outgoing bodies are checked against the namespace's request schema
+/// (--validate-request, on by default in run-all).
+static VALIDATE: AtomicBool = AtomicBool::new(false);
+
+/// Turns request body validation on or off for the rest of the process.
+pub fn set_validation(enabled: bool) {
+    VALIDATE.store(enabled, Ordering::SeqCst);
+}
+
+/// Checks the body of CONFIG against the `request_schema` linked to TARGET in the
+/// settings, if validation is on; fails with one line per violation.
+pub fn check_request(config: &RequestConfig, target: Option<&str>) -> Result<()> {
+    if !VALIDATE.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let Some(ref body) = config.data else {
+        return Ok(());
+    };
+    let Some(path) = Settings::for_target(target)?.request_schema_path()? else {
+        return Ok(());
+    };
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read request schema {:?}", path))?;
+    let schema: Value =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse request schema {:?}", path))?;
+    let errors = validate(&schema, body);
+    if !errors.is_empty() {
+        bail!(
+            "Request body does not match the schema {:?}; it was not sent:\n  {}",
+            path,
+            errors.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Validates INSTANCE against SCHEMA (the commonly used JSON Schema keywords, with
+/// local `$ref`s) and returns the violations as "PATH: message", e.g. ".items[0].sku: ...".
+pub fn validate(schema: &Value, instance: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, schema, instance, ".", &mut errors);
+    errors
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected
+        || (expected == "number" && actual == "integer")
+        || (expected == "integer" && value.as_f64().is_some_and(|n| n.fract() == 0.0))
+}
+
+fn child(path: &str, key: &str) -> String {
+    if path == "." {
+        format!(".{}", key)
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn check(root: &Value, schema: &Value, instance: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // true / false のスキーマ
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{}: not allowed here", path));
+        }
+        return;
+    };
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer)) {
+            Some(target) => check(root, target, instance, path, errors),
+            None => errors.push(format!("{}: cannot resolve $ref {}", path, reference)),
+        }
+        return;
+    }
+    // OpenAPI 3.0 の nullable も受け付ける
+    if instance.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+        return;
+    }
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(list) => list.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(instance, t)) {
+            errors.push(format!("{}: expected {}, got {}", path, types.join(" or "), type_name(instance)));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(instance) {
+            errors.push(format!("{}: {} is not one of {}", path, instance, Value::Array(allowed.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != instance {
+            errors.push(format!("{}: expected {}, got {}", path, expected, instance));
+        }
+    }
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        let Some(options) = schema.get(keyword).and_then(Value::as_array) else {
+            continue;
+        };
+        let results: Vec<Vec<String>> = options
+            .iter()
+            .map(|option| {
+                let mut found = Vec::new();
+                check(root, option, instance, path, &mut found);
+                found
+            })
+            .collect();
+        let matched = results.iter().filter(|found| found.is_empty()).count();
+        match keyword {
+            "allOf" => errors.extend(results.into_iter().flatten()),
+            "anyOf" if matched == 0 => errors.push(format!("{}: matches none of the anyOf schemas", path)),
+            "oneOf" if matched != 1 => {
+                errors.push(format!("{}: matches {} of the oneOf schemas instead of exactly one", path, matched))
+            }
+            _ => {}
+        }
+    }
+    if let Some(not) = schema.get("not") {
+        let mut found = Vec::new();
+        check(root, not, instance, path, &mut found);
+        if found.is_empty() {
+            errors.push(format!("{}: must not match the \"not\" schema", path));
+        }
+    }
+    match instance {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required property \"{}\"", path, name));
+                }
+            }
+            for (key, value) in object {
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property) => check(root, property, value, &child(path, key), errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: unknown property \"{}\"", path, key));
+                        }
+                        Some(additional) => check(root, additional, value, &child(path, key), errors),
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{}: expected at least {} items, got {}", path, min, items.len()));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if items.len() as u64 > max {
+                    errors.push(format!("{}: expected at most {} items, got {}", path, max, items.len()));
+                }
+            }
+            if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+                let duplicate = items.iter().enumerate().any(|(index, item)| items[..index].contains(item));
+                if duplicate {
+                    errors.push(format!("{}: items must be unique", path));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(root, item_schema, item, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(format!("{}: expected at least {} characters, got {}", path, min, length));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(format!("{}: expected at most {} characters, got {}", path, max, length));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match Regex::new(pattern) {
+                    Ok(regex) if !regex.is_match(text) => {
+                        errors.push(format!("{}: \"{}\" does not match /{}/", path, text, pattern))
+                    }
+                    Ok(_) => {}
+                    Err(_) => errors.push(format!("{}: invalid pattern /{}/ in the schema", path, pattern)),
+                }
+            }
+        }
+        Value::Number(number) => {
+            let value = number.as_f64().unwrap_or_default();
+            let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+            if let Some(min) = bound("minimum").filter(|min| value < *min) {
+                errors.push(format!("{}: {} is less than the minimum {}", path, number, min));
+            }
+            if let Some(max) = bound("maximum").filter(|max| value > *max) {
+                errors.push(format!("{}: {} is greater than the maximum {}", path, number, max));
+            }
+            if let Some(min) = bound("exclusiveMinimum").filter(|min| value <= *min) {
+                errors.push(format!("{}: {} must be greater than {}", path, number, min));
+            }
+            if let Some(max) = bound("exclusiveMaximum").filter(|max| value >= *max) {
+                errors.push(format!("{}: {} must be less than {}", path, number, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validate_reports_violations_by_path() {
+        let schema = json!({
+            "type": "object",
+            "required": ["name", "items"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "count": { "type": "integer", "minimum": 1 },
+                "items": { "type": "array", "items": { "$ref": "#/$defs/item" } }
+            },
+            "$defs": {
+                "item": { "type": "object", "required": ["sku"], "properties": { "sku": { "type": "string", "pattern": "^A-" } } }
+            }
+        });
+        let valid = json!({ "name": "x", "count": 2, "items": [{ "sku": "A-1" }] });
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({ "nmae": "x", "count": 0, "items": [{ "sku": "B-1" }, { "sku": 3 }] });
+        assert_eq!(
+            validate(&schema, &invalid),
+            vec![
+                ".: missing required property \"name\"",
+                ".count: 0 is less than the minimum 1",
+                ".items[0].sku: \"B-1\" does not match /^A-/",
+                ".items[1].sku: expected string, got integer",
+                ".: unknown property \"nmae\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn published_schemas_are_up_to_date() {
+        for kind in SchemaKind::value_variants() {
+            let name = kind.to_possible_value().unwrap().get_name().to_string();
+            let path = format!("{}/schemas/{}.schema.json", env!("CARGO_MANIFEST_DIR"), name);
+            let published = fs::read_to_string(&path).unwrap();
+            let generated = serde_json::to_string_pretty(&json_schema(*kind)).unwrap() + "\n";
+            assert!(published == generated, "{} is outdated; regenerate it with `schema print {}`", path, name);
+        }
+    }
+
+    #[test]
+    fn chain_schema_reports_unknown_fields() {
+        let schema = serde_json::to_value(json_schema(SchemaKind::Chain)).unwrap();
+        let chain = json!({
+            "vars": { "base_url": "http://localhost" },
+            "steps": [
+                { "name": "login", "method": "POST", "url": "{{base_url}}/login", "extract": { "token": "$.token" } },
+                { "target": "SystemA/me", "headers": { "Authorization": "Bearer {{token}}" }, "expect_status": 200 }
+            ]
+        });
+        assert!(validate(&schema, &chain).is_empty());
+        let typo = json!({ "step": [] });
+        assert_eq!(
+            validate(&schema, &typo),
+            vec![".: missing required property \"steps\"", ".: unknown property \"step\""]
+        );
+    }
+}
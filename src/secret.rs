@@ -0,0 +1,163 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::HashMap,
+    env,
+    process::Command,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use crate::credential;
+use crate::request::RequestConfig;
+use crate::secret_provider::{self, Secret};
+use crate::vars::{self, Vars};
+
+/// A fetched value and the end of its lease (None: valid for the whole run).
+type Cached = (String, Option<Instant>);
+
+/// Values fetched during this run, keyed by placeholder. Secrets are only ever kept in memory.
+static CACHE: OnceLock<Mutex<HashMap<String, Cached>>> = OnceLock::new();
+
+/// Splits `provider:argument` placeholder names. The provider must be a plain word,
+/// so `{{name}}` variables and `{{.path}}` response placeholders are never matched.
+fn split(name: &str) -> Option<(&str, &str)> {
+    let (provider, argument) = name.split_once(':')?;
+    if provider.is_empty() || !provider.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((provider, argument.trim()))
+}
+
+/// Runs a shell command and returns its trimmed standard output.
+pub fn run_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }
+    .with_context(|| format!("Failed to run command: {}", command))?;
+    if !output.status.success() {
+        bail!(
+            "Command `{}` failed ({}): {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("Command `{}` printed non-UTF-8 output", command))?;
+    Ok(stdout.trim().to_string())
+}
+
+async fn fetch(provider: &str, argument: &str) -> Result<String> {
+    let key = format!("{}:{}", provider, argument);
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some((value, expires)) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        if expires.is_none_or(|at| Instant::now() < at) {
+            return Ok(value.clone());
+        }
+    }
+    let secret = match provider {
+        "cmd" => Secret {
+            value: run_command(argument)?,
+            lease: None,
+        },
+        "secret" => Secret {
+            value: credential::lookup(argument)?,
+            lease: None,
+        },
+        "env" => Secret {
+            value: env::var(argument).with_context(|| format!("Environment variable {} is not set", argument))?,
+            lease: None,
+        },
+        "vault" => secret_provider::vault(argument).await?,
+        "aws" => secret_provider::aws(argument).await?,
+        "gcp" => secret_provider::gcp(argument).await?,
+        other => bail!("Unknown placeholder provider '{}' in {{{{{}}}}}", other, key),
+    };
+    let expires = secret.lease.map(|lease| Instant::now() + lease);
+    cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key, (secret.value.clone(), expires));
+    Ok(secret.value)
+}
+
+/// Names of the `{{provider:argument}}` placeholders in INPUT.
+fn placeholders(input: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + len].trim();
+        if split(name).is_some() {
+            names.push(name);
+        }
+        rest = &rest[start + len + 2..];
+    }
+    names
+}
+
+/// Replaces `{{name}}` variables with values from VARS and `{{provider:argument}}`
+/// placeholders such as `{{cmd:op read op://vault/api/token}}` or
+/// `{{vault:secret/data/api#token}}` in one pass. Placeholders inside variable values are
+/// not expanded: a value captured from a response must never run a command.
+pub async fn render(input: &str, vars: &Vars) -> Result<String> {
+    let mut fetched = HashMap::new();
+    for name in placeholders(input) {
+        if let Some((provider, argument)) = split(name) {
+            fetched.insert(name, fetch(provider, argument).await?);
+        }
+    }
+    vars::render_with(input, vars, &|name| fetched.get(name).cloned())
+}
+
+/// Resolves the header and auth values of ORIGINAL, the request as written, with `render`;
+/// the rest of the request is taken from CONFIG, in which variables are already resolved.
+/// Only the copy that is sent contains the values; history, logs and saved configs keep the
+/// placeholders.
+pub async fn substitute(original: &RequestConfig, config: &RequestConfig, vars: &Vars) -> Result<RequestConfig> {
+    let mut config = config.clone();
+    if let (Some(headers), Some(written)) = (config.headers.as_mut(), original.headers.as_ref()) {
+        for (name, value) in headers.iter_mut() {
+            if let Some(written) = written.get(name) {
+                *value = render(written, vars).await?;
+            }
+        }
+    }
+    let mut written = original.auth.clone();
+    if let (Some(auth), Some(written)) = (config.auth.as_mut(), written.as_mut()) {
+        for (value, written) in auth.values_mut().into_iter().zip(written.values_mut()) {
+            *value = render(written, vars).await?;
+        }
+    }
+    Ok(config)
+}
+
+/// Turns a `--header-from-cmd "Name: command"` option into a header whose value
+/// is a `{{cmd:...}}` placeholder.
+pub fn header_from_command(option: &str) -> Result<(String, String)> {
+    let (name, command) = option
+        .split_once(':')
+        .with_context(|| format!("Invalid --header-from-cmd format (expected \"Name: command\"): {}", option))?;
+    let command = command.trim();
+    if command.is_empty() || command.contains("}}") {
+        bail!("Invalid command in --header-from-cmd: {}", option);
+    }
+    Ok((name.trim().to_string(), format!("{{{{cmd:{}}}}}", command)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn placeholders_in_variable_values_are_not_expanded() {
+        // レスポンスから保存された値がコマンドとして実行されてはならない
+        let vars = Vars::from([("token".to_string(), "{{cmd:echo injected}}".to_string())]);
+        let rendered = render("Bearer {{token}} {{cmd:echo written}}", &vars).await.unwrap();
+        assert_eq!(rendered, "Bearer {{cmd:echo injected}} written");
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Response};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    time::{Duration, SystemTime},
+};
+
+use crate::secret;
+
+/// A fetched secret and how long it may be reused within this run.
+pub struct Secret {
+    pub value: String,
+    pub lease: Option<Duration>,
+}
+
+impl Secret {
+    fn new(value: String) -> Secret {
+        Secret { value, lease: None }
+    }
+}
+
+fn client() -> Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Splits `path#field` into the secret path and the optional field name.
+fn split_field(argument: &str) -> (&str, Option<&str>) {
+    match argument.rsplit_once('#') {
+        Some((path, field)) => (path, Some(field)),
+        None => (argument, None),
+    }
+}
+
+/// Picks `field` from a JSON object. Without a field, an object with a single
+/// entry yields that entry; any other value is returned as is.
+fn pick(value: &Value, field: Option<&str>, source: &str) -> Result<String> {
+    let value = match (field, value) {
+        (Some(field), Value::Object(map)) => map
+            .get(field)
+            .with_context(|| format!("Secret {} has no field '{}'", source, field))?,
+        (Some(field), _) => bail!("Secret {} is not a JSON object, so '#{}' cannot be used", source, field),
+        (None, Value::Object(map)) if map.len() == 1 => map.values().next().unwrap_or(value),
+        (None, Value::Object(map)) => bail!(
+            "Secret {} has several fields ({}); select one with '#field'",
+            source,
+            map.keys().cloned().collect::<Vec<_>>().join(", ")
+        ),
+        (None, value) => value,
+    };
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
+
+async fn check(response: Response, provider: &str, source: &str) -> Result<Value> {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        bail!("{} returned {} for {}: {}", provider, status, source, text.trim());
+    }
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse the {} response for {}", provider, source))
+}
+
+/// `{{vault:secret/data/api#token}}`: reads a HashiCorp Vault secret (KV v1 or v2)
+/// using VAULT_ADDR, VAULT_TOKEN (or ~/.vault-token) and VAULT_NAMESPACE.
+pub async fn vault(argument: &str) -> Result<Secret> {
+    let (path, field) = split_field(argument);
+    let address = env::var("VAULT_ADDR").context("VAULT_ADDR is not set")?;
+    let token = match env::var("VAULT_TOKEN") {
+        Ok(token) => token,
+        Err(_) => {
+            let path = directories::BaseDirs::new()
+                .context("Failed to determine the home directory")?
+                .home_dir()
+                .join(".vault-token");
+            fs::read_to_string(&path)
+                .with_context(|| format!("VAULT_TOKEN is not set and {:?} could not be read", path))?
+                .trim()
+                .to_string()
+        }
+    };
+    let url = format!("{}/v1/{}", address.trim_end_matches('/'), path.trim_start_matches('/'));
+    let mut request = client()?.get(&url).header("X-Vault-Token", token);
+    if let Ok(namespace) = env::var("VAULT_NAMESPACE") {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+    let response = request.send().await.with_context(|| format!("Failed to reach Vault at {}", url))?;
+    let body = check(response, "Vault", path).await?;
+    // KV v2 は data.data に、KV v1 や他のシークレットエンジンは data に値を持つ
+    let data = match body.pointer("/data/data") {
+        Some(inner @ Value::Object(_)) => inner,
+        _ => body.get("data").with_context(|| format!("Vault response for {} has no data", path))?,
+    };
+    let lease = body
+        .get("lease_duration")
+        .and_then(Value::as_u64)
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs);
+    Ok(Secret {
+        value: pick(data, field, path)?,
+        lease,
+    })
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `{{aws:secret-id#key}}`: reads an AWS Secrets Manager secret with the credentials in
+/// AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY / AWS_SESSION_TOKEN. The region comes from
+/// the secret ARN or AWS_REGION / AWS_DEFAULT_REGION.
+pub async fn aws(argument: &str) -> Result<Secret> {
+    let (secret_id, field) = split_field(argument);
+    let region = match secret_id.strip_prefix("arn:") {
+        Some(arn) => arn.split(':').nth(2).map(str::to_string),
+        None => None,
+    }
+    .or_else(|| env::var("AWS_REGION").ok())
+    .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+    .context("AWS region is unknown; set AWS_REGION or use the secret ARN")?;
+    let access_key = env::var("AWS_ACCESS_KEY_ID").context("AWS_ACCESS_KEY_ID is not set")?;
+    let secret_key = env::var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY is not set")?;
+    let session_token = env::var("AWS_SESSION_TOKEN").ok();
+
+    let endpoint = env::var("AWS_ENDPOINT_URL_SECRETS_MANAGER")
+        .or_else(|_| env::var("AWS_ENDPOINT_URL"))
+        .unwrap_or_else(|_| format!("https://secretsmanager.{}.amazonaws.com", region));
+    let url = reqwest::Url::parse(&endpoint).with_context(|| format!("Invalid AWS endpoint {}", endpoint))?;
+    // 署名する host は実際に送られる Host ヘッダー（既定以外のポートを含む）と一致させる
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => bail!("AWS endpoint {} has no host", endpoint),
+    };
+    let body = json!({ "SecretId": secret_id }).to_string();
+
+    // Signature Version 4
+    let timestamp = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    let amz_date: String = timestamp.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let day = &amz_date[..8];
+    let mut headers = vec![
+        ("content-type", "application/x-amz-json-1.1".to_string()),
+        ("host", host),
+        ("x-amz-date", amz_date.clone()),
+        ("x-amz-target", "secretsmanager.GetSecretValue".to_string()),
+    ];
+    if let Some(ref token) = session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v)).collect();
+    let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers,
+        signed_headers,
+        hex(&Sha256::digest(body.as_bytes()))
+    );
+    let scope = format!("{}/{}/secretsmanager/aws4_request", day, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = hmac(format!("AWS4{}", secret_key).as_bytes(), day);
+    for part in [region.as_str(), "secretsmanager", "aws4_request"] {
+        key = hmac(&key, part);
+    }
+    let signature = hex(&hmac(&key, &string_to_sign));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key, scope, signed_headers, signature
+    );
+
+    let mut request = client()?.post(&endpoint).header("Authorization", authorization).body(body);
+    for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+        request = request.header(*name, value);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach AWS Secrets Manager at {}", endpoint))?;
+    let body = check(response, "AWS Secrets Manager", secret_id).await?;
+    let text = body
+        .get("SecretString")
+        .and_then(Value::as_str)
+        .with_context(|| format!("Secret {} has no SecretString (binary secrets are not supported)", secret_id))?;
+    let value = match field {
+        Some(_) => pick(&serde_json::from_str(text).unwrap_or(Value::Null), field, secret_id)?,
+        None => text.to_string(),
+    };
+    Ok(Secret::new(value))
+}
+
+/// `{{gcp:PROJECT/SECRET[/VERSION]#key}}` or a full `projects/.../versions/...` name:
+/// reads a GCP Secret Manager secret with GOOGLE_OAUTH_ACCESS_TOKEN or the gcloud CLI token.
+pub async fn gcp(argument: &str) -> Result<Secret> {
+    let (name, field) = split_field(argument);
+    let resource = if name.starts_with("projects/") {
+        name.to_string()
+    } else {
+        match name.split('/').collect::<Vec<_>>().as_slice() {
+            [project, secret] => format!("projects/{}/secrets/{}/versions/latest", project, secret),
+            [project, secret, version] => format!("projects/{}/secrets/{}/versions/{}", project, secret, version),
+            _ => bail!("GCP secret must be PROJECT/SECRET[/VERSION] or a full resource name: {}", name),
+        }
+    };
+    let token = match env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+        Ok(token) => token,
+        Err(_) => secret::run_command("gcloud auth print-access-token")
+            .context("Set GOOGLE_OAUTH_ACCESS_TOKEN or log in with gcloud")?,
+    };
+    let url = format!("https://secretmanager.googleapis.com/v1/{}:access", resource);
+    let response = client()?
+        .get(&url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .with_context(|| format!("Failed to reach GCP Secret Manager at {}", url))?;
+    let body = check(response, "GCP Secret Manager", &resource).await?;
+    let encoded = body
+        .pointer("/payload/data")
+        .and_then(Value::as_str)
+        .with_context(|| format!("GCP response for {} has no payload", resource))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .with_context(|| format!("Invalid payload encoding for {}", resource))?;
+    let text = String::from_utf8(bytes).with_context(|| format!("Secret {} is not UTF-8 text", resource))?;
+    let value = match field {
+        Some(_) => pick(&serde_json::from_str(&text).unwrap_or(Value::Null), field, &resource)?,
+        None => text,
+    };
+    Ok(Secret::new(value))
+}
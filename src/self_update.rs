@@ -0,0 +1,173 @@
+use anyhow::{bail, Context, Result};
+use dialoguer::Confirm;
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::i18n;
+use crate::net_error::NetworkError;
+
+/// Repository whose GitHub releases carry the prebuilt binaries.
+const REPOSITORY: &str = "hnkNkm/ferrapi_tester";
+
+/// Release asset listing the SHA-256 of every binary, in `sha256sum` format.
+const CHECKSUM_ASSET: &str = "SHA256SUMS";
+
+#[derive(Deserialize, Debug)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Options of `self-update`.
+#[derive(clap::Args, Debug)]
+pub struct SelfUpdateArgs {
+    /// 更新の有無を確認するだけで、置き換えは行いません。
+    #[arg(long = "check")]
+    check: bool,
+
+    /// 確認プロンプトを表示せずに更新します。
+    #[arg(short = 'y', long = "yes")]
+    yes: bool,
+}
+
+/// Name of the release asset built for this platform, e.g. `ferrapi_tester-x86_64-linux`.
+fn asset_name() -> String {
+    format!(
+        "ferrapi_tester-{}-{}{}",
+        env::consts::ARCH,
+        env::consts::OS,
+        env::consts::EXE_SUFFIX
+    )
+}
+
+/// Parses "v1.2.3" / "1.2.3" into comparable numeric parts.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+fn client() -> Result<Client> {
+    // GitHub API は User-Agent が必須
+    Ok(Client::builder()
+        .user_agent(concat!("ferrapi_tester/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(NetworkError::from)
+        .with_context(|| format!("Failed to download {}", url))?;
+    if !response.status().is_success() {
+        bail!("Failed to download {}: {}", url, response.status());
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Finds the expected digest of `name` in a `sha256sum`-style listing.
+fn expected_digest(listing: &str, name: &str) -> Option<String> {
+    listing.lines().find_map(|line| {
+        let (digest, file) = line.split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then(|| digest.to_lowercase())
+    })
+}
+
+/// Replaces the running executable with `bytes`. The new binary is written next to
+/// the current one and renamed over it; on Windows the running file is moved aside first.
+fn replace_executable(current: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = current.with_extension("new");
+    fs::write(&staged, bytes).with_context(|| format!("Failed to write {:?}", staged))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {:?} executable", staged))?;
+    }
+    if cfg!(windows) {
+        let old: PathBuf = current.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(current, &old).with_context(|| format!("Failed to move {:?} aside", current))?;
+    }
+    fs::rename(&staged, current).with_context(|| format!("Failed to replace {:?}", current))
+}
+
+pub async fn run(args: SelfUpdateArgs) -> Result<()> {
+    let client = client()?;
+    let url = format!("https://api.github.com/repos/{}/releases/latest", REPOSITORY);
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(NetworkError::from)
+        .with_context(|| "Failed to query the latest release")?;
+    if !response.status().is_success() {
+        bail!("Failed to query the latest release: {}", response.status());
+    }
+    let release: Release = response.json().await.with_context(|| "Failed to parse release information")?;
+
+    let current_version = env!("CARGO_PKG_VERSION");
+    if parse_version(&release.tag_name) <= parse_version(current_version) {
+        println!(
+            "{}",
+            i18n::tf("ferrapi_tester {} is up to date (latest release: {}).", &[&current_version, &release.tag_name])
+        );
+        return Ok(());
+    }
+    println!("{}", i18n::tf("A new version is available: {} -> {}", &[&current_version, &release.tag_name]));
+    if args.check {
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .with_context(|| format!("Release {} has no binary for this platform ({})", release.tag_name, name))?;
+    let checksums = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == CHECKSUM_ASSET)
+        .with_context(|| format!("Release {} has no {} file; refusing to update unverified", release.tag_name, CHECKSUM_ASSET))?;
+
+    if !args.yes
+        && !Confirm::new()
+            .with_prompt(i18n::tf("Update to {}?", &[&release.tag_name]))
+            .default(true)
+            .interact()?
+    {
+        println!("{}", i18n::t("Update cancelled."));
+        return Ok(());
+    }
+
+    let listing = String::from_utf8(download(&client, &checksums.browser_download_url).await?)
+        .with_context(|| format!("{} is not valid text", CHECKSUM_ASSET))?;
+    let expected = expected_digest(&listing, &name)
+        .with_context(|| format!("{} has no entry for {}", CHECKSUM_ASSET, name))?;
+    let bytes = download(&client, &asset.browser_download_url).await?;
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if actual != expected {
+        bail!("Checksum mismatch for {}: expected {}, got {}", name, expected, actual);
+    }
+
+    let current = env::current_exe().with_context(|| "Failed to locate the running executable")?;
+    replace_executable(&current, &bytes)?;
+    println!("{}", i18n::tf("Updated {} to {}.", &[&format!("{:?}", current), &release.tag_name]));
+    Ok(())
+}
@@ -0,0 +1,438 @@
+use anyhow::{Context, Result};
+use clap::ValueHint;
+use dialoguer::Input;
+use serde_json::Value;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::annotation;
+use crate::assertion;
+use crate::autocomplete;
+use crate::cancel;
+use crate::diff;
+use crate::docs;
+use crate::exit_code;
+use crate::filter::{self, Filter};
+use crate::header_editor;
+use crate::history;
+use crate::i18n;
+use crate::interactive_select_namespace;
+use crate::mount;
+use crate::namespace;
+use crate::output::{self, OutputFormat};
+use crate::request::{self, Exchange, RequestArgs};
+use crate::retry::RetryArgs;
+use crate::saved;
+use crate::schema;
+use crate::suggest;
+use crate::theme;
+use crate::triage;
+use crate::vars::{self, Vars};
+
+/// Options of a single request: the top-level flags and `send`.
+#[derive(clap::Args, Debug)]
+pub struct SendArgs {
+    #[command(flatten)]
+    pub request: RequestArgs,
+
+    /// 現在のリクエスト設定を保存するフラグ
+    #[arg(short = 's', long = "save")]
+    pub save: bool,
+
+    /// ステータスとボディに加えてレスポンスヘッダーを表示します。
+    #[arg(short = 'i', long = "include")]
+    pub include: bool,
+
+    /// レスポンスヘッダーの値を変数に保存し、後続のリクエストで {{VAR}} として使えるようにします（例: "X-Request-Id=reqid"）。
+    /// --env 指定時はその環境ファイル、それ以外は TARGET の vars.json に書き込みます
+    #[arg(long = "capture-header", value_name = "HEADER=VAR")]
+    pub capture_header: Vec<String>,
+
+    /// 対話モードで名前空間候補を表示して選択します。
+    #[arg(long = "comp")]
+    pub comp: bool,
+
+    /// レスポンスの出力形式（--output-format と同じ）。json ではステータス・ヘッダー・ボディ・所要時間と解決済みのリクエストを 1 つの JSON で出力します
+    #[arg(long = "format", value_enum, value_name = "FORMAT", conflicts_with = "output_format")]
+    pub format: Option<OutputFormat>,
+
+    /// CSV 出力で使用する列（カンマ区切り、"user.name" のようなドット区切りパスも可）。
+    /// 省略時は全オブジェクトのキーをソート順に使用します。
+    #[arg(long = "columns", value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// レスポンスボディを標準出力ではなく指定したファイルにそのまま（バイナリも）書き込みます。
+    /// csv / table / json / template 形式では整形した出力を書き込みます
+    #[arg(short = 'o', long = "output", value_hint = ValueHint::FilePath)]
+    pub output: Option<PathBuf>,
+
+    /// レスポンスに適用する jq 風フィルタ、または JSONPath（例: '.items[] | select(.active)'、'$.data.items[0].id'）
+    #[arg(long = "filter")]
+    pub filter: Option<String>,
+
+    /// --filter の結果だけを jq -r のように表示します（文字列は引用符なし、反復した結果は 1 行に 1 件）。
+    #[arg(short = 'r', long = "raw-output", requires = "filter")]
+    pub raw_output: bool,
+
+    /// 配列レスポンスを指定したパスの値でソートします（例: .created_at）
+    #[arg(long = "sort-by")]
+    pub sort_by: Option<String>,
+
+    /// --sort-by を降順で適用します。
+    #[arg(long = "sort-desc", requires = "sort_by")]
+    pub sort_desc: bool,
+
+    /// レスポンスボディを比較する期待値 JSON ファイル（--filter / --sort-by 適用後の本文と比較）
+    #[arg(long = "expect-body-file", value_hint = ValueHint::FilePath)]
+    pub expect_body_file: Option<PathBuf>,
+
+    /// --expect-body-file の比較で無視するパス（カンマ区切り、例: .timestamp,.items[].id）
+    #[arg(long = "ignore-paths", value_delimiter = ',', requires = "expect_body_file")]
+    pub ignore_paths: Vec<String>,
+
+    #[command(flatten)]
+    pub retry: RetryArgs,
+
+    /// レスポンス中の ID・トークン・名前などのパスと値を上位 N 件表示します（既定 10 件）。
+    /// 表示されるパスは --filter でそのまま使えます。
+    #[arg(long = "suggest-paths", num_args = 0..=1, default_missing_value = "10")]
+    pub suggest_paths: Option<usize>,
+
+    /// 送信（と --save）の前にヘッダーを対話的に追加・編集・削除します。
+    #[arg(long = "edit-headers")]
+    pub edit_headers: bool,
+
+    /// 送信前にボディを名前空間の request_schema（settings.json）で検証し、違反があれば送信しません。
+    #[arg(long = "validate-request")]
+    pub validate_request: bool,
+
+    /// JSON のレスポンスを正規化した形（キー順・数値表記を統一した 1 行）で表示します。
+    #[arg(long = "canonical-response")]
+    pub canonical_response: bool,
+
+    /// 同じリクエストの前回の記録と JSON レスポンスの構造と型だけを比較し、追加・削除・型が変わったフィールドを表示します。
+    #[arg(long = "type-diff")]
+    pub type_diff: bool,
+
+    /// レスポンスを保存済み設定の例（examples）として記録します（docs generate で表示）。説明を付けることもできます。
+    /// 同じステータスの例は置き換えます
+    #[arg(long = "save-example", value_name = "DESCRIPTION", num_args = 0..=1)]
+    pub save_example: Option<Option<String>>,
+}
+
+/// Returns the TARGET to use: the one picked interactively with COMP, otherwise TARGET
+/// in canonical form. URLs are passed through unchanged.
+pub fn select_target(comp: bool, target: Option<String>) -> Result<Option<String>> {
+    // --comp オプションが指定された場合、対話モードで名前空間を選択
+    let target = if comp {
+        let selected = interactive_select_namespace()?;
+        println!("{}", i18n::tf("Selected namespace: {}", &[&selected]));
+        Some(selected)
+    } else {
+        target
+    };
+    // 区切り文字を正規化し、ベースディレクトリの外を指す名前空間などは早めに拒否する
+    match target {
+        Some(target) if !target.starts_with("http") => {
+            Ok(Some(namespace::normalize(&target).map_err(exit_code::config)?))
+        }
+        other => Ok(other),
+    }
+}
+
+/// Sends one request built from ARGS and the configuration saved for TARGET, saving it
+/// first with --save, and checks the response against assertions and --expect-body-file.
+pub async fn run(args: SendArgs, target: Option<String>) -> Result<()> {
+    let target = select_target(args.comp, target)?;
+    schema::set_validation(args.validate_request);
+
+    // 通常の API 呼び出しモード
+    let mut config = request::resolve(&args.request, target.as_deref())?;
+    // 対話モードでは履歴と保存済み設定から URL・ヘッダーを補完候補として提示する
+    if args.comp || args.edit_headers {
+        let sources = autocomplete::Sources::load();
+        if config.url.is_none() {
+            let urls = autocomplete::Suggestions(sources.urls());
+            let url: String = Input::new()
+                .with_prompt(i18n::t("URL (Tab to complete)"))
+                .with_initial_text(urls.0.first().cloned().unwrap_or_default())
+                .completion_with(&urls)
+                .interact_text()?;
+            config.url = Some(url.trim().to_string());
+        }
+        if args.edit_headers {
+            header_editor::edit(config.headers.get_or_insert_with(Default::default), &sources)?;
+        }
+    }
+
+    // アサーションは保存・送信の前に構文チェックしておく
+    let assertions = assertion::parse_all(config.assertions.as_deref().unwrap_or_default())?;
+
+    if args.save {
+        match target {
+            Some(ref target) => saved::save(target, &args.request.method, &config)?,
+            None => println!("{}", i18n::t("--save is ignored because TARGET is not specified.")),
+        }
+    }
+
+    let captures = args.capture_header.iter().map(|option| parse_capture(option).map_err(exit_code::config));
+    let captures = captures.collect::<Result<Vec<_>>>()?;
+    // フィルタはリクエスト送信前に構文チェックしておく
+    let filter = args.filter.as_deref().map(Filter::parse).transpose().map_err(exit_code::config)?;
+    // 期待値ファイルも送信前に読み込み、壊れていれば早めに失敗させる
+    let expected_body = match args.expect_body_file {
+        Some(ref path) => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read expected body from {:?}", path))
+                .map_err(exit_code::config)?;
+            let value: Value = serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse expected body in {:?}", path))
+                .map_err(exit_code::config)?;
+            Some(value)
+        }
+        None => None,
+    };
+
+    // 前回の記録は、このリクエストが履歴に追加される前に探しておく
+    let previous = match args.type_diff {
+        true => {
+            let method = config.method.as_deref().unwrap_or("GET");
+            let endpoint = history::endpoint(target.as_deref(), method, config.url.as_deref().unwrap_or_default());
+            history::previous_json(&endpoint)?.map(|(entry, body)| (entry.timestamp_ms, body))
+        }
+        false => None,
+    };
+
+    let exchange = cancel::guard(request::execute(&config, target.as_deref(), &args.retry.policy())).await?;
+    let text = exchange.text();
+    let transformed = filter.is_some() || args.sort_by.is_some() || args.canonical_response;
+    // クライアント側でのフィルタ・ソートは描画前に適用する
+    // -r では --filter の結果だけを、ステータスなどを付けずにそのまま表示する
+    let mut raw = None;
+    let text = if filter.is_some() || args.sort_by.is_some() {
+        let mut body: Value = serde_json::from_str(&text)
+            .with_context(|| "--filter/--sort-by require a JSON response body")?;
+        if let Some(ref filter) = filter {
+            body = filter.apply(&body);
+        }
+        if let Some(ref path) = args.sort_by {
+            filter::sort_by(&mut body, path, args.sort_desc)?;
+        }
+        if args.raw_output {
+            raw = filter.as_ref().map(|filter| format!("{}\n", filter.raw(&body)));
+        }
+        serde_json::to_string_pretty(&body)?
+    } else {
+        text
+    };
+    let text = if args.canonical_response {
+        // JSON でないレスポンスはそのまま表示する
+        match serde_json::from_str::<Value>(&text) {
+            Ok(body) => request::canonical_json(body).to_string(),
+            Err(_) => text,
+        }
+    } else {
+        text
+    };
+    let renderer = output::renderer()?;
+    // 5xx の場合は、原因調査に必要な部分をボディより先に要約して表示する
+    match triage::summarize(&exchange, target.as_deref()) {
+        Ok(Some(block)) => renderer.note(&block),
+        Ok(None) => {}
+        // 要約に失敗してもレスポンス自体は表示する
+        Err(err) => {
+            eprintln!(
+                "{}",
+                i18n::tf("Warning: {}; skipping the server error summary.", &[&format!("{:#}", err)])
+            );
+        }
+    }
+    match args.output {
+        _ if raw.is_some() => output::emit(raw.as_deref().unwrap_or_default(), args.output.as_deref())?,
+        // 本文だけを書き出す形式では、加工していなければ受信したバイト列をそのまま保存する
+        Some(ref path) if matches!(output::format(), OutputFormat::Human | OutputFormat::Minimal) => {
+            let body = if transformed { text.as_bytes() } else { exchange.body.as_slice() };
+            output::write_body(path, body)?;
+            renderer.note(&theme::status(exchange.status.as_u16(), &i18n::tf("Response Status: {}", &[&exchange.status])));
+            renderer.note(&i18n::tf("Response body ({} bytes) written to {}", &[&body.len(), &format!("{:?}", path)]));
+        }
+        _ => output::emit(&renderer.response(&exchange, &text)?, args.output.as_deref())?,
+    }
+
+    if !captures.is_empty() {
+        capture_headers(&exchange, &captures, target.as_deref())?;
+    }
+
+    if let Some(ref description) = args.save_example {
+        match target {
+            Some(ref target) => docs::save_example(target, &args.request.method, &exchange, description.as_deref())?,
+            None => println!("{}", i18n::t("--save-example is ignored because TARGET is not specified.")),
+        }
+    }
+
+    if let Some(limit) = args.suggest_paths {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(body) => {
+                let suggestions = suggest::suggest_paths(&body, limit);
+                // human 以外の出力形式では標準出力を汚さないよう標準エラーへ
+                let lines: Vec<String> = suggestions
+                    .iter()
+                    .map(|s| format!("  {} = {}", s.path, s.value))
+                    .collect();
+                let block = if lines.is_empty() {
+                    i18n::t("No suggested paths found.").to_string()
+                } else {
+                    format!("{}\n{}", i18n::t("Suggested paths:"), lines.join("\n"))
+                };
+                renderer.note(&block);
+            }
+            Err(_) => eprintln!("{}", i18n::t("--suggest-paths requires a JSON response body")),
+        }
+    }
+
+    let mut assertion_failures = 0;
+    if !assertions.is_empty() {
+        let failures = assertion::check_all(&assertions, &exchange);
+        if annotation::enabled() {
+            let method = config.method.as_deref().unwrap_or("GET");
+            let files = match target {
+                Some(ref target) if !target.starts_with("http") => vec![mount::config_path(target, method)?],
+                _ => Vec::new(),
+            };
+            let endpoint = history::endpoint(target.as_deref(), method, config.url.as_deref().unwrap_or_default());
+            annotation::report(&endpoint, &files, &failures);
+        }
+        let (errors, warnings) = assertion::partition(failures);
+        if errors.is_empty() && warnings.is_empty() {
+            eprintln!("{}", i18n::tf("All {} assertion(s) passed.", &[&assertions.len()]));
+        }
+        // warn: 付きのアサーションは報告だけして終了コードには影響させない
+        if !warnings.is_empty() {
+            eprintln!("{}", i18n::tf("{} assertion warning(s):", &[&warnings.len()]));
+            for warning in &warnings {
+                eprintln!("  {}", warning);
+            }
+        }
+        if !errors.is_empty() {
+            eprintln!("{}", i18n::tf("{} of {} assertion(s) failed:", &[&errors.len(), &assertions.len()]));
+            for error in &errors {
+                eprintln!("{}", theme::diff_line(&format!("- {}", error), true));
+            }
+            assertion_failures = errors.len();
+        }
+    }
+
+    if args.type_diff {
+        report_type_diff(previous, &exchange.text());
+    }
+
+    if let Some(expected) = expected_body {
+        let actual: Value = serde_json::from_str(&text)
+            .with_context(|| "--expect-body-file requires a JSON response body")?;
+        let changes = diff::without_ignored(diff::diff(&expected, &actual), &args.ignore_paths);
+        if !changes.is_empty() {
+            let file = format!("{:?}", args.expect_body_file.unwrap_or_default());
+            eprintln!("{}", i18n::tf("Response body differs from {}:", &[&file]));
+            for change in &changes {
+                for line in change.to_string().lines() {
+                    eprintln!("{}", theme::diff_line(line, true));
+                }
+            }
+            return Err(diff::Mismatch { differences: changes.len() }.into());
+        }
+        eprintln!("{}", i18n::t("Response body matches the expected fixture."));
+    }
+
+    if assertion_failures > 0 {
+        return Err(assertion::Failed { failures: assertion_failures }.into());
+    }
+    Ok(())
+}
+
+/// Parses `--capture-header HEADER=VAR`.
+fn parse_capture(option: &str) -> Result<(String, String)> {
+    option
+        .split_once('=')
+        .map(|(header, var)| (header.trim().to_string(), var.trim().to_string()))
+        .filter(|(header, var)| !header.is_empty() && !var.is_empty())
+        .with_context(|| format!("Invalid --capture-header value (expected HEADER=VAR): {}", option))
+}
+
+/// Values of the response headers named in CAPTURES by variable name. A missing header
+/// or a value containing a placeholder is only reported, so that the response is still shown.
+fn captured_values(exchange: &Exchange, captures: &[(String, String)]) -> Vars {
+    let mut values = Vars::new();
+    for (header, var) in captures {
+        match exchange.headers.get(header.as_str()).and_then(|value| value.to_str().ok()) {
+            Some(value) if vars::has_placeholder(value) => {
+                eprintln!("{}", i18n::tf("Header {} contains '{{' and is not captured into {}.", &[header, var]))
+            }
+            Some(value) => {
+                values.insert(var.clone(), value.to_string());
+            }
+            None => eprintln!("{}", i18n::tf("Header {} is not in the response; {} is not captured.", &[header, var])),
+        }
+    }
+    values
+}
+
+/// Stores the response headers named in CAPTURES as variables.
+fn capture_headers(exchange: &Exchange, captures: &[(String, String)], target: Option<&str>) -> Result<()> {
+    let values = captured_values(exchange, captures);
+    if values.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = values.keys().map(String::as_str).collect();
+    let names = names.join(", ");
+    let path = vars::capture(target, values)?;
+    eprintln!("{}", i18n::tf("Captured {} into {}", &[&names, &format!("{:?}", path)]));
+    Ok(())
+}
+
+/// Prints how the shape of the JSON response BODY differs from the PREVIOUS recorded run.
+fn report_type_diff(previous: Option<(u64, Value)>, body: &str) {
+    let Some((timestamp_ms, previous)) = previous else {
+        eprintln!("{}", i18n::t("No earlier JSON response of this request in the history to compare types with."));
+        return;
+    };
+    let Ok(current) = serde_json::from_str::<Value>(body) else {
+        eprintln!("{}", i18n::t("--type-diff requires a JSON response body"));
+        return;
+    };
+    let time = humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_millis(timestamp_ms)).to_string();
+    let changes = diff::type_diff(&previous, &current);
+    if changes.is_empty() {
+        eprintln!("{}", i18n::tf("Response types match the previous run ({}).", &[&time]));
+        return;
+    }
+    eprintln!("{}", i18n::tf("Response types changed since the previous run ({}):", &[&time]));
+    for change in &changes {
+        for line in change.to_string().lines() {
+            eprintln!("{}", theme::diff_line(line, true));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{header::HeaderMap, StatusCode};
+
+    #[test]
+    fn header_values_with_placeholders_are_not_captured() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "abc-123".parse().unwrap());
+        headers.insert("x-token", "{{cmd:touch /tmp/pwned}}".parse().unwrap());
+        let exchange =
+            Exchange { status: StatusCode::OK, headers, body: Vec::new(), elapsed: Duration::ZERO, request: None };
+        let captures = [
+            ("X-Request-Id".to_string(), "request_id".to_string()),
+            ("X-Token".to_string(), "token".to_string()),
+        ];
+        let values = captured_values(&exchange, &captures);
+        assert_eq!(values, Vars::from([("request_id".to_string(), "abc-123".to_string())]));
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{task::JoinSet, time::Instant};
+
+use crate::cancel;
+use crate::diff::{self, Change};
+use crate::get_default_dir;
+use crate::i18n;
+use crate::namespace;
+use crate::output::{self, Table};
+use crate::request::{self, Exchange, RequestConfig};
+use crate::retry::{RetryArgs, RetryPolicy};
+use crate::store;
+use crate::theme;
+
+/// Directory (under the base directory) holding recorded proxy sessions.
+pub const SESSION_DIR: &str = ".sessions";
+
+/// One request seen by the recording proxy, with the response it received.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionEntry {
+    /// Milliseconds since the proxy started, used to reproduce the original timing.
+    pub offset_ms: u64,
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(with = "crate::compress")]
+    pub request: RequestConfig,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, with = "crate::compress")]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub elapsed_ms: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Returns a new session file path for a recording into NAMESPACE.
+pub fn new_path(namespace: &str) -> Result<PathBuf> {
+    let name = namespace::segments(namespace)?
+        .iter()
+        .map(|segment| namespace::slug(segment))
+        .collect::<Vec<_>>()
+        .join("-");
+    let started = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(get_default_dir()?
+        .join(SESSION_DIR)
+        .join(format!("{}-{}.jsonl", name, started)))
+}
+
+/// Appends an entry to the session file.
+pub fn append(path: &Path, entry: &SessionEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let _lock = store::lock(path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open session {:?}", path))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?).with_context(|| format!("Failed to write session {:?}", path))
+}
+
+/// Options of `replay`.
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// セッションファイル（パス、または ~/.ferrapi_tester/.sessions 内の名前）。省略時は一覧を表示します
+    session: Option<String>,
+
+    /// 記録時と異なる送信先（例: https://staging.example.com）。スキーム・ホスト・ポートを置き換え、パスは先頭に付けます
+    #[arg(long = "base-url")]
+    base_url: Option<String>,
+
+    /// 再生速度の倍率（1 は記録時と同じ間隔、2 は 2 倍速、0 は待たずに順次送信）
+    #[arg(long = "speed", default_value = "1")]
+    speed: f64,
+
+    /// ボディの比較で無視するパス（カンマ区切り、例: .timestamp,.items[].id）
+    #[arg(long = "ignore-paths", value_delimiter = ',')]
+    ignore_paths: Vec<String>,
+
+    /// 差異のあったリクエストについてボディの差分を表示します。
+    #[arg(long = "show-diff")]
+    show_diff: bool,
+
+    /// TLS 証明書の検証を行いません。
+    #[arg(short = 'k', long = "insecure")]
+    insecure: bool,
+
+    #[command(flatten)]
+    retry: RetryArgs,
+}
+
+fn session_dir() -> Result<PathBuf> {
+    Ok(get_default_dir()?.join(SESSION_DIR))
+}
+
+/// Resolves a session argument: a file path, or a name inside the session directory.
+pub fn find(session: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(session);
+    if path.is_file() {
+        return Ok(path);
+    }
+    let dir = session_dir()?;
+    for candidate in [dir.join(session), dir.join(format!("{}.jsonl", session))] {
+        if candidate.is_file() {
+            return Ok(candidate);
+        }
+    }
+    bail!("No session {:?} found (run `replay` without arguments to list sessions)", session)
+}
+
+pub fn load(path: &Path) -> Result<Vec<SessionEntry>> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open session {:?}", path))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            serde_json::from_str(&line?).with_context(|| format!("Failed to parse line {} of {:?}", i + 1, path))
+        })
+        .collect()
+}
+
+fn list() -> Result<()> {
+    let dir = session_dir()?;
+    let mut sessions: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    sessions.sort();
+    let mut table = Table::new(&[("SESSION", "session"), ("REQUESTS", "requests")]);
+    for path in sessions {
+        let count = load(&path).map(|entries| entries.len()).unwrap_or(0);
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        table.push(vec![json!(name), json!(count)]);
+    }
+    output::renderer()?.print(&table, i18n::t("No recorded sessions. Record one with `proxy --record NAMESPACE`."))
+}
+
+/// Replaces the scheme, host and port of URL with those of BASE, prefixing BASE's path.
+fn rebase(url: &str, base: &str) -> Result<String> {
+    let url = reqwest::Url::parse(url).with_context(|| format!("Invalid recorded URL {}", url))?;
+    let base = reqwest::Url::parse(base).with_context(|| format!("Invalid --base-url {}", base))?;
+    let mut rebased = format!("{}{}", base.as_str().trim_end_matches('/'), url.path());
+    if let Some(query) = url.query() {
+        rebased.push('?');
+        rebased.push_str(query);
+    }
+    Ok(rebased)
+}
+
+/// How a replayed response compares with the recorded one.
+enum Verdict {
+    Match,
+    Differs { status: bool, changes: Vec<Change>, text: bool },
+    Failed(String),
+}
+
+fn compare(entry: &SessionEntry, outcome: &Result<Exchange>, ignore: &[String]) -> Verdict {
+    let exchange = match outcome {
+        Ok(exchange) => exchange,
+        // 記録時にも失敗していたリクエストは、再生でも失敗すれば一致とみなす
+        Err(_) if entry.error.is_some() => return Verdict::Match,
+        Err(err) => return Verdict::Failed(err.to_string()),
+    };
+    let status = entry.status != Some(exchange.status.as_u16());
+    let recorded = entry.body.clone().unwrap_or_default();
+    let replayed = exchange.text();
+    let (changes, text) = match (
+        serde_json::from_str::<Value>(&recorded),
+        serde_json::from_str::<Value>(&replayed),
+    ) {
+        (Ok(expected), Ok(actual)) => (diff::without_ignored(diff::diff(&expected, &actual), ignore), false),
+        _ => (Vec::new(), recorded != replayed),
+    };
+    if !status && changes.is_empty() && !text {
+        Verdict::Match
+    } else {
+        Verdict::Differs { status, changes, text }
+    }
+}
+
+pub async fn replay(args: ReplayArgs) -> Result<()> {
+    let Some(ref session) = args.session else {
+        return list();
+    };
+    if !(args.speed >= 0.0 && args.speed.is_finite()) {
+        bail!("--speed must be 0 or a positive number");
+    }
+    let path = find(session)?;
+    let entries = load(&path)?;
+    if entries.is_empty() {
+        bail!("Session {:?} has no requests", path);
+    }
+    let retry: RetryPolicy = args.retry.policy();
+
+    let started = Instant::now();
+    let mut tasks = JoinSet::new();
+    for (index, entry) in entries.iter().cloned().enumerate() {
+        let mut config = entry.request.clone();
+        if let (Some(base), Some(url)) = (&args.base_url, &config.url) {
+            config.url = Some(rebase(url, base)?);
+        }
+        if args.insecure {
+            config.insecure = Some(true);
+        }
+        if args.speed > 0.0 {
+            // 記録時のタイミングを再現する（重なっていたリクエストは並行して送る）
+            let due = started + Duration::from_secs_f64(entry.offset_ms as f64 / 1000.0 / args.speed);
+            tokio::select! {
+                _ = tokio::time::sleep_until(due) => {}
+                _ = cancel::cancelled() => break,
+            }
+            tasks.spawn(async move {
+                let outcome = request::execute(&config, None, &retry).await;
+                (index, config, outcome)
+            });
+        } else {
+            if cancel::is_cancelled() {
+                break;
+            }
+            let outcome = request::execute(&config, None, &retry).await;
+            tasks.spawn(async move { (index, config, outcome) });
+        }
+    }
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        results.push(result.context("replay task panicked")?);
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+
+    let renderer = output::renderer()?;
+    let mut table = Table::new(&[
+        ("#", "index"),
+        ("METHOD", "method"),
+        ("URL", "url"),
+        ("RECORDED", "recorded_status"),
+        ("REPLAYED", "replayed_status"),
+        ("REC(ms)", "recorded_ms"),
+        ("NOW(ms)", "replayed_ms"),
+        ("RESULT", "result"),
+    ]);
+    let mut diffs = Vec::new();
+    let mut mismatches = 0;
+    for (index, config, outcome) in &results {
+        let entry = &entries[*index];
+        let verdict = compare(entry, outcome, &args.ignore_paths);
+        let result = match verdict {
+            Verdict::Match => "match".to_string(),
+            Verdict::Failed(ref error) => format!("FAIL {}", error),
+            Verdict::Differs { status, ref changes, text } => {
+                let mut parts = Vec::new();
+                if status {
+                    parts.push("status differs".to_string());
+                }
+                if !changes.is_empty() {
+                    parts.push(format!("{} body difference(s)", changes.len()));
+                }
+                if text {
+                    parts.push("body differs".to_string());
+                }
+                format!("DIFF {}", parts.join(", "))
+            }
+        };
+        table.push(vec![
+            json!(index + 1),
+            json!(config.method.as_deref().unwrap_or("GET")),
+            json!(config.url.as_deref().unwrap_or("")),
+            json!(entry.status),
+            json!(outcome.as_ref().ok().map(|e| e.status.as_u16())),
+            json!(entry.elapsed_ms),
+            json!(outcome.as_ref().ok().map(|e| e.elapsed.as_millis() as u64)),
+            json!(result),
+        ]);
+        if let Verdict::Differs { ref changes, .. } = verdict {
+            if args.show_diff && !changes.is_empty() {
+                diffs.push(format!("\n#{} {}:", index + 1, config.url.as_deref().unwrap_or("")));
+                for change in changes {
+                    for line in change.to_string().lines() {
+                        diffs.push(format!("  {}", theme::diff_line(line, !renderer.human())));
+                    }
+                }
+            }
+        }
+        if !matches!(verdict, Verdict::Match) {
+            mismatches += 1;
+        }
+    }
+    print!("{}", renderer.table(&table)?);
+    for line in &diffs {
+        renderer.note(line);
+    }
+    if results.len() < entries.len() {
+        renderer.note(&i18n::tf(
+            "\nInterrupted; {} of {} request(s) were not replayed.",
+            &[&(entries.len() - results.len()), &entries.len()],
+        ));
+    }
+    renderer.note(&i18n::tf(
+        "\n{} matched, {} differed or failed, {} replayed.",
+        &[&(results.len() - mismatches), &mismatches, &results.len()],
+    ));
+    if mismatches > 0 {
+        bail!("{} of {} replayed request(s) did not match the recording", mismatches, results.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::budget::BudgetSettings;
+use crate::credential::Backend;
+use crate::get_default_dir;
+use crate::mount;
+use crate::output::OutputFormat;
+use crate::theme::ThemeSetting;
+use crate::triage::TriageSettings;
+
+/// File name of the settings file, both at the base directory (global settings)
+/// and inside namespace directories (per-namespace overrides).
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// User settings (e.g. ~/.ferrapi_tester/settings.json).
+/// A namespace directory may contain its own settings.json whose values take
+/// precedence over the global ones for requests under that namespace.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Settings {
+    /// Append every request/response as a JSON line to this file.
+    /// Relative paths are resolved against the base directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_requests: Option<PathBuf>,
+
+    /// OpenAPI spec linked to the requests under this namespace, used for coverage
+    /// reports of `run` and `run-all`. Relative paths are resolved against the base directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openapi: Option<PathBuf>,
+
+    /// JSON Schema that request bodies under this namespace must match, checked before
+    /// sending with --validate-request and in run-all. Relative paths are resolved
+    /// against the base directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_schema: Option<PathBuf>,
+
+    /// Response size and time thresholds that print a warning when exceeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetSettings>,
+
+    /// Rules for the summary shown above 5xx response bodies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triage: Option<TriageSettings>,
+
+    /// Default for --output-format (global settings only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<OutputFormat>,
+
+    /// Default for --template with the template output format (global settings only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_template: Option<String>,
+
+    /// Color theme: a built-in name ("default", "solarized", "dracula", "mono") or
+    /// `{"base": NAME, STYLE: VALUE, ...}` overrides (global settings only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeSetting>,
+
+    /// Where `{{secret:NAME}}` values are kept: "keyring", "file" or "env" (global settings only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_store: Option<Backend>,
+
+    /// Set to false to store large history entries and recorded response bodies as
+    /// plain JSON instead of zstd-compressed (global settings only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compress_recordings: Option<bool>,
+
+    /// Storage and retention policy of the request history (global settings only).
+    #[serde(default)]
+    pub history: HistorySettings,
+
+    /// Additional configuration trees, e.g. a team repository checked out elsewhere, whose
+    /// saved requests are found like the local ones but never saved to or deleted (global
+    /// settings only). Relative paths are resolved against the base directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<PathBuf>,
+}
+
+fn default_max_entries() -> Option<usize> {
+    Some(10_000)
+}
+
+fn default_response_body_limit() -> String {
+    "256KB".to_string()
+}
+
+/// Limits enforced on the history store after every recorded request.
+/// Set a limit to null to disable it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistorySettings {
+    /// Keep at most this many entries (default 10000).
+    #[serde(default = "default_max_entries")]
+    pub max_entries: Option<usize>,
+    /// Drop entries older than this, e.g. "30d" or "12h".
+    #[serde(default)]
+    pub max_age: Option<String>,
+    /// Keep the file below this size, e.g. "50MB".
+    #[serde(default)]
+    pub max_size: Option<String>,
+    /// Response bodies are recorded up to this size for `history search`, e.g. "256KB";
+    /// "0" records none.
+    #[serde(default = "default_response_body_limit")]
+    pub response_body_limit: String,
+    /// Where entries are stored: "jsonl" (history.jsonl, the default) or "sqlite"
+    /// (history.sqlite3, faster to query with many entries).
+    #[serde(default)]
+    pub backend: HistoryBackend,
+}
+
+/// Storage of the request history.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    #[default]
+    Jsonl,
+    Sqlite,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        HistorySettings {
+            max_entries: default_max_entries(),
+            max_age: None,
+            max_size: None,
+            response_body_limit: default_response_body_limit(),
+            backend: HistoryBackend::default(),
+        }
+    }
+}
+
+/// Parses sizes such as "512", "64KB", "50MB" or "1GB" into bytes.
+pub fn parse_size(input: &str) -> Result<u64> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid size: {}", input))?;
+    let multiplier = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        other => bail!("Unknown size unit '{}' in {}", other, input),
+    };
+    Ok(number * multiplier)
+}
+
+impl Settings {
+    fn read(path: &Path) -> Result<Option<Settings>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings from {:?}", path))?;
+        let settings = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse settings in {:?}", path))?;
+        Ok(Some(settings))
+    }
+
+    /// Overlays every value set in `other` on top of `self`.
+    fn overlay(mut self, other: Settings) -> Settings {
+        if other.log_requests.is_some() {
+            self.log_requests = other.log_requests;
+        }
+        if other.openapi.is_some() {
+            self.openapi = other.openapi;
+        }
+        if other.request_schema.is_some() {
+            self.request_schema = other.request_schema;
+        }
+        if other.budget.is_some() {
+            self.budget = other.budget;
+        }
+        if other.triage.is_some() {
+            self.triage = other.triage;
+        }
+        self
+    }
+
+    /// Loads the global settings.
+    pub fn load() -> Result<Settings> {
+        let base_dir = get_default_dir()?;
+        Ok(Self::read(&base_dir.join(SETTINGS_FILE))?.unwrap_or_default())
+    }
+
+    /// Loads the settings effective for TARGET: the global settings overlaid with
+    /// the settings.json of each namespace directory from the root down to TARGET.
+    pub fn for_target(target: Option<&str>) -> Result<Settings> {
+        let mut settings = Self::load()?;
+        let Some(target) = target.filter(|t| !t.starts_with("http")) else {
+            return Ok(settings);
+        };
+        for dir in mount::ancestry(target)? {
+            if let Some(namespace) = Self::read(&dir.join(SETTINGS_FILE))? {
+                settings = settings.overlay(namespace);
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Returns the request log path, resolved against the base directory.
+    pub fn log_path(&self) -> Result<Option<PathBuf>> {
+        resolve_path(self.log_requests.as_ref())
+    }
+
+    /// Returns the linked OpenAPI spec path, resolved against the base directory.
+    pub fn openapi_path(&self) -> Result<Option<PathBuf>> {
+        resolve_path(self.openapi.as_ref())
+    }
+
+    /// Returns the linked request body schema path, resolved against the base directory.
+    pub fn request_schema_path(&self) -> Result<Option<PathBuf>> {
+        resolve_path(self.request_schema.as_ref())
+    }
+
+    /// Returns the mounted configuration roots, resolved against the base directory.
+    pub fn mount_paths(&self) -> Result<Vec<PathBuf>> {
+        self.mounts.iter().map(|path| Ok(resolve_path(Some(path))?.unwrap_or_default())).collect()
+    }
+}
+
+fn resolve_path(path: Option<&PathBuf>) -> Result<Option<PathBuf>> {
+    match path {
+        Some(path) if path.is_relative() => Ok(Some(get_default_dir()?.join(path))),
+        Some(path) => Ok(Some(path.clone())),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::get_default_dir;
+use crate::i18n;
+use crate::namespace;
+use crate::store;
+
+/// Directory (under the base directory) holding snapshot objects and manifests.
+/// Dot-directories are never treated as namespaces.
+pub const SNAPSHOT_DIR: &str = ".snapshots";
+
+/// Subcommands of `config`.
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// 名前空間のスナップショットを作成・一覧・復元します。
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommand,
+    },
+}
+
+/// Subcommands of `config snapshot`.
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommand {
+    /// 名前空間の現在の状態をスナップショットとして保存します。
+    Create {
+        /// 対象の名前空間（例: "SystemA"）
+        namespace: String,
+        /// スナップショットの説明
+        #[arg(short = 'm', long = "message")]
+        message: Option<String>,
+    },
+    /// スナップショットを新しい順に一覧表示します。
+    List {
+        /// 対象の名前空間（省略時はすべて）
+        namespace: Option<String>,
+    },
+    /// スナップショットの状態に名前空間を戻します（復元前の状態も自動で保存されます）。
+    Restore {
+        /// 対象の名前空間
+        namespace: String,
+        /// スナップショット ID（先頭の数文字でも可）
+        id: String,
+    },
+}
+
+/// Records which content object each file of a namespace pointed to at a point in time.
+#[derive(Serialize, Deserialize, Debug)]
+struct Manifest {
+    id: String,
+    namespace: String,
+    created_at: u64,
+    message: Option<String>,
+    /// Relative path ('/' separated) -> SHA-256 of the content.
+    files: BTreeMap<String, String>,
+}
+
+fn snapshot_root() -> Result<PathBuf> {
+    Ok(get_default_dir()?.join(SNAPSHOT_DIR))
+}
+
+fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<String, Vec<u8>>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            let bytes = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            files.insert(relative, bytes);
+        }
+    }
+    Ok(())
+}
+
+/// Stores the namespace content as objects and writes a manifest; returns its id.
+fn create(namespace: &str, message: Option<String>) -> Result<Manifest> {
+    let dir = namespace::dir(&get_default_dir()?, namespace)?;
+    if !dir.is_dir() {
+        bail!("No namespace directory found at {:?}", dir);
+    }
+    let root = snapshot_root()?;
+    let objects = root.join("objects");
+    fs::create_dir_all(&objects).with_context(|| format!("Failed to create directory {:?}", objects))?;
+
+    let mut contents = BTreeMap::new();
+    walk(&dir, &dir, &mut contents)?;
+    let mut files = BTreeMap::new();
+    for (relative, bytes) in contents {
+        let digest = hash(&bytes);
+        let object = objects.join(&digest);
+        // 同じ内容のオブジェクトは一度だけ保存する（コンテンツアドレス方式）
+        if !object.exists() {
+            store::write_atomic(&object, &bytes)?;
+        }
+        files.insert(relative, digest);
+    }
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let identity = serde_json::to_vec(&(namespace, created_at, &message, &files))?;
+    let manifest = Manifest {
+        id: hash(&identity)[..12].to_string(),
+        namespace: namespace.to_string(),
+        created_at,
+        message,
+        files,
+    };
+    let manifests = root.join("manifests");
+    fs::create_dir_all(&manifests).with_context(|| format!("Failed to create directory {:?}", manifests))?;
+    let path = manifests.join(format!("{}.json", manifest.id));
+    store::write_atomic(&path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+fn load_manifests() -> Result<Vec<Manifest>> {
+    let dir = snapshot_root()?.join("manifests");
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut manifests = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let path = entry?.path();
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        manifests.push(
+            serde_json::from_str::<Manifest>(&content).with_context(|| format!("Failed to parse {:?}", path))?,
+        );
+    }
+    manifests.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(manifests)
+}
+
+fn format_time(secs: u64) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(secs)).to_string()
+}
+
+fn restore(namespace: &str, id: &str) -> Result<()> {
+    let manifests = load_manifests()?;
+    let matches: Vec<&Manifest> = manifests
+        .iter()
+        .filter(|m| m.namespace == namespace && m.id.starts_with(id))
+        .collect();
+    let manifest = match matches.as_slice() {
+        [manifest] => *manifest,
+        [] => bail!("No snapshot '{}' found for namespace {}", id, namespace),
+        _ => bail!("Snapshot id '{}' is ambiguous; use more characters", id),
+    };
+    let objects = snapshot_root()?.join("objects");
+    // 先にすべてのオブジェクトが読めることを確認してから既存の内容を消す
+    let mut contents = Vec::new();
+    for (relative, digest) in &manifest.files {
+        if Path::new(relative).components().any(|c| !matches!(c, Component::Normal(_))) {
+            bail!("Snapshot contains unsafe path {:?}", relative);
+        }
+        let bytes = fs::read(objects.join(digest))
+            .with_context(|| format!("Snapshot object {} for {} is missing", digest, relative))?;
+        contents.push((relative, bytes));
+    }
+
+    let dir = namespace::dir(&get_default_dir()?, namespace)?;
+    if dir.is_dir() {
+        let backup = create(namespace, Some(format!("automatic backup before restoring {}", manifest.id)))?;
+        println!("{}", i18n::tf("Current state saved as snapshot {}", &[&backup.id]));
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed to clear {:?}", dir))?;
+    }
+    for (relative, bytes) in contents {
+        let path = dir.join(relative);
+        store::write_atomic(&path, bytes)?;
+    }
+    namespace::create(&get_default_dir()?, namespace)?;
+    println!(
+        "{}",
+        i18n::tf("Restored {} to snapshot {} ({} file(s)).", &[&namespace, &manifest.id, &manifest.files.len()])
+    );
+    Ok(())
+}
+
+pub fn run(command: ConfigCommand) -> Result<()> {
+    let ConfigCommand::Snapshot { action } = command;
+    match action {
+        SnapshotCommand::Create { namespace, message } => {
+            let manifest = create(&namespace, message)?;
+            println!(
+                "{}",
+                i18n::tf("Created snapshot {} of {} ({} file(s)).", &[&manifest.id, &namespace, &manifest.files.len()])
+            );
+        }
+        SnapshotCommand::List { namespace } => {
+            let manifests: Vec<Manifest> = load_manifests()?
+                .into_iter()
+                .filter(|m| namespace.as_ref().is_none_or(|ns| &m.namespace == ns))
+                .collect();
+            if manifests.is_empty() {
+                println!("{}", i18n::t("No snapshots found."));
+            }
+            for m in manifests {
+                let message = m.message.map(|msg| format!("  {}", msg)).unwrap_or_default();
+                println!(
+                    "{}",
+                    i18n::tf(
+                        "{}  {}  {}  {} file(s){}",
+                        &[&m.id, &format_time(m.created_at), &m.namespace, &m.files.len(), &message]
+                    )
+                );
+            }
+        }
+        SnapshotCommand::Restore { namespace, id } => restore(&namespace, &id)?,
+    }
+    Ok(())
+}
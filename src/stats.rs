@@ -0,0 +1,250 @@
+use anyhow::Result;
+use clap::Subcommand;
+use dialoguer::console::measure_text_width;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::history::{self, HistoryEntry};
+use crate::i18n;
+use crate::output::{self, Table};
+
+/// Subcommands of `stats`.
+#[derive(Subcommand, Debug)]
+pub enum StatsCommand {
+    /// 履歴のレスポンス時間をヒストグラムとパーセンタイルで表示します。
+    Latency {
+        /// 集計対象の名前空間または URL の接頭辞
+        target: String,
+
+        /// 集計期間（例: 7d, 12h, 30m）
+        #[arg(long = "since", value_parser = humantime::parse_duration)]
+        since: Option<Duration>,
+
+        /// ヒストグラムの区間数
+        #[arg(long = "buckets", default_value = "10")]
+        buckets: usize,
+    },
+}
+
+const PERCENTILES: &[(&str, f64)] = &[("p50", 0.5), ("p75", 0.75), ("p90", 0.9), ("p95", 0.95), ("p99", 0.99)];
+
+/// Longest histogram bar, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// Returns true if the entry belongs to TARGET (a namespace prefix or a URL prefix).
+pub fn matches_target(entry: &HistoryEntry, target: &str) -> bool {
+    if target.starts_with("http") {
+        return entry.url().starts_with(target);
+    }
+    let target = target.trim_end_matches('/');
+    entry
+        .target
+        .as_deref()
+        .is_some_and(|t| t == target || t.starts_with(&format!("{}/", target)))
+}
+
+/// Loads history entries for TARGET recorded within the `since` window.
+pub fn select(target: Option<&str>, since: Option<Duration>) -> Result<Vec<HistoryEntry>> {
+    let cutoff = match since {
+        Some(window) => SystemTime::now()
+            .checked_sub(window)
+            .unwrap_or(UNIX_EPOCH)
+            .duration_since(UNIX_EPOCH)?
+            .as_millis() as u64,
+        None => 0,
+    };
+    Ok(history::load_since(cutoff)?
+        .into_iter()
+        .filter(|entry| target.is_none_or(|t| matches_target(entry, t)))
+        .collect())
+}
+
+/// Returns the p-th percentile (0.0..=1.0) of sorted values.
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+/// Compares the mean latency of the newer half of the samples with the older half.
+pub fn trend(latencies: &[u64]) -> Option<String> {
+    if latencies.len() < 4 {
+        return None;
+    }
+    let (older, newer) = latencies.split_at(latencies.len() / 2);
+    let mean = |xs: &[u64]| xs.iter().sum::<u64>() as f64 / xs.len() as f64;
+    let (older, newer) = (mean(older), mean(newer));
+    if older == 0.0 {
+        return None;
+    }
+    Some(format!("{:+.0}%", (newer - older) / older * 100.0))
+}
+
+/// Describes why a request failed, for grouping the most frequent errors.
+pub fn failure_reason(entry: &HistoryEntry) -> Option<String> {
+    match (&entry.error, entry.status) {
+        (Some(error), _) => Some(error.chars().take(100).collect()),
+        (None, Some(status)) if status >= 400 => Some(format!("HTTP {}", status)),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct EndpointStats {
+    total: usize,
+    succeeded: usize,
+    latencies: Vec<u64>,
+    errors: BTreeMap<String, usize>,
+}
+
+fn top_error(errors: &BTreeMap<String, usize>) -> Option<(&String, &usize)> {
+    errors.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+}
+
+pub fn run(target: Option<&str>, since: Option<Duration>) -> Result<()> {
+    let entries = select(target, since)?;
+    let renderer = output::renderer()?;
+    if entries.is_empty() {
+        return renderer.print(&Table::default(), i18n::t("No history entries match."));
+    }
+
+    let mut endpoints: BTreeMap<String, EndpointStats> = BTreeMap::new();
+    let mut all_errors: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in &entries {
+        let stats = endpoints.entry(entry.endpoint()).or_default();
+        stats.total += 1;
+        if entry.succeeded() {
+            stats.succeeded += 1;
+        }
+        if let Some(ms) = entry.elapsed_ms {
+            stats.latencies.push(ms);
+        }
+        if let Some(reason) = failure_reason(entry) {
+            *stats.errors.entry(reason.clone()).or_default() += 1;
+            *all_errors.entry(reason).or_default() += 1;
+        }
+    }
+
+    let mut table = Table::new(&[
+        ("ENDPOINT", "endpoint"),
+        ("RUNS", "runs"),
+        ("SUCCESS(%)", "success_pct"),
+        ("AVG(ms)", "avg_ms"),
+        ("P95(ms)", "p95_ms"),
+        ("TREND", "trend"),
+    ]);
+    for (endpoint, stats) in &endpoints {
+        let mut sorted = stats.latencies.clone();
+        sorted.sort_unstable();
+        let (avg, p95) = if sorted.is_empty() {
+            (None, None)
+        } else {
+            (Some(sorted.iter().sum::<u64>() / sorted.len() as u64), Some(percentile(&sorted, 0.95)))
+        };
+        let success = stats.succeeded as f64 / stats.total as f64 * 100.0;
+        table.push(vec![
+            json!(endpoint),
+            json!(stats.total),
+            json!((success * 10.0).round() / 10.0),
+            json!(avg),
+            json!(p95),
+            json!(trend(&stats.latencies)),
+        ]);
+    }
+    print!("{}", renderer.table(&table)?);
+
+    let mut flaky: Vec<(&String, &EndpointStats)> =
+        endpoints.iter().filter(|(_, s)| s.succeeded < s.total).collect();
+    flaky.sort_by(|a, b| {
+        let rate = |s: &EndpointStats| s.succeeded as f64 / s.total as f64;
+        rate(a.1).partial_cmp(&rate(b.1)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    if !flaky.is_empty() {
+        renderer.note(i18n::t("\nLeast reliable endpoints:"));
+        for (endpoint, stats) in flaky.iter().take(5) {
+            let reason = top_error(&stats.errors).map(|(e, _)| e.as_str()).unwrap_or("-");
+            let failed = stats.total - stats.succeeded;
+            renderer.note(&i18n::tf("  {} ({}/{} failed): {}", &[endpoint, &failed, &stats.total, &reason]));
+        }
+    }
+
+    let mut errors: Vec<(&String, &usize)> = all_errors.iter().collect();
+    errors.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    if !errors.is_empty() {
+        renderer.note(i18n::t("\nMost frequent errors:"));
+        for (error, count) in errors.iter().take(5) {
+            renderer.note(&format!("  {:>4}x {}", count, error));
+        }
+    }
+    Ok(())
+}
+
+/// Rounds a bucket width up to 1, 2 or 5 times a power of ten, so bucket bounds are easy to read.
+fn nice_width(raw: u64) -> u64 {
+    let mut magnitude = 1;
+    loop {
+        for step in [1, 2, 5] {
+            if step * magnitude >= raw {
+                return step * magnitude;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+/// Counts SORTED latencies in up to BUCKETS equal-width ranges; returns (from, to, count).
+fn histogram(sorted: &[u64], buckets: usize) -> Vec<(u64, u64, usize)> {
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let width = nice_width((max - min + 1).div_ceil(buckets.max(1) as u64));
+    let start = min / width * width;
+    let mut counts = Vec::new();
+    let mut from = start;
+    while from <= max {
+        let to = from + width;
+        let count = sorted.iter().filter(|&&ms| ms >= from && ms < to).count();
+        counts.push((from, to, count));
+        from = to;
+    }
+    counts
+}
+
+fn latency(target: &str, since: Option<Duration>, buckets: usize) -> Result<()> {
+    let mut latencies: Vec<u64> = select(Some(target), since)?.iter().filter_map(|entry| entry.elapsed_ms).collect();
+    let renderer = output::renderer()?;
+    if latencies.is_empty() {
+        return renderer.print(&Table::default(), i18n::t("No history entries match."));
+    }
+    latencies.sort_unstable();
+
+    let rows = histogram(&latencies, buckets);
+    let ranges: Vec<String> = rows.iter().map(|(from, to, _)| format!("{} - {} ms", from, to)).collect();
+    let label_width = ranges.iter().map(|r| measure_text_width(r)).max().unwrap_or(0);
+    let peak = rows.iter().map(|(_, _, count)| *count).max().unwrap_or(1).max(1);
+    let mut lines = vec![i18n::tf("Latency of {} ({} request(s)):", &[&target, &latencies.len()])];
+    for (range, (_, _, count)) in ranges.iter().zip(&rows) {
+        // 件数が 1 件以上ある区間は最低 1 文字分のバーを描く
+        let bar = (count * BAR_WIDTH).div_ceil(peak);
+        lines.push(format!("  {:>width$} | {} {}", range, "█".repeat(bar), count, width = label_width));
+    }
+    renderer.note(&format!("{}\n", lines.join("\n")));
+
+    let mut table = Table::new(&[("PERCENTILE", "percentile"), ("LATENCY(ms)", "latency_ms")]);
+    table.push(vec![json!("min"), json!(latencies[0])]);
+    for (name, p) in PERCENTILES {
+        table.push(vec![json!(name), json!(percentile(&latencies, *p))]);
+    }
+    table.push(vec![json!("max"), json!(latencies[latencies.len() - 1])]);
+    print!("{}", renderer.table(&table)?);
+    Ok(())
+}
+
+pub fn run_command(action: StatsCommand) -> Result<()> {
+    match action {
+        StatsCommand::Latency { target, since, buckets } => latency(&target, since, buckets),
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    process,
+};
+
+use crate::get_default_dir;
+
+/// Directory (under the base directory) holding lock files.
+/// Keeping them apart avoids polluting namespace directories that get exported or snapshotted.
+pub const LOCK_DIR: &str = ".locks";
+
+/// An exclusive advisory lock guarding one file; released on drop.
+pub struct FileLock {
+    file: File,
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// Blocks until this process holds the exclusive lock for `path`.
+/// Every read-modify-write of a shared file should happen while holding it, so
+/// simultaneous invocations (e.g. from a parallel test runner) don't lose updates.
+pub fn lock(path: &Path) -> Result<FileLock> {
+    let dir = get_default_dir()?.join(LOCK_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create directory {:?}", dir))?;
+    let digest = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+    let lock_path = dir.join(format!("{}.lock", &digest[..16]));
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {:?}", lock_path))?;
+    file.lock_exclusive()
+        .with_context(|| format!("Failed to lock {:?}", path))?;
+    Ok(FileLock { file })
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into place,
+/// so readers never observe a half-written file.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_with_mode(path, contents.as_ref(), None)
+}
+
+/// Like `write_atomic`, for files holding secrets such as tokens, cookies and keys: the
+/// file is readable by the current user only (0600 on Unix) from the moment it is created.
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_with_mode(path, contents.as_ref(), Some(0o600))
+}
+
+fn write_with_mode(path: &Path, contents: &[u8], mode: Option<u32>) -> Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {:?}", parent))?;
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let temp = parent.join(format!(".{}.{}.tmp", name, process::id()));
+    let result = (|| {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(mode);
+        }
+        let mut file = options.open(&temp)?;
+        // 前回の書き込みで残った一時ファイルには mode が適用されないため、開いた後にも設定する
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(mode))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.with_context(|| format!("Failed to write {:?}", path))
+}
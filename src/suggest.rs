@@ -0,0 +1,75 @@
+use serde_json::Value;
+
+/// Key fragments that usually mark values worth capturing, with their weight.
+const RELEVANT_KEYS: &[(&str, i32)] = &[
+    ("token", 100),
+    ("secret", 90),
+    ("session", 85),
+    ("uuid", 80),
+    ("id", 80),
+    ("key", 70),
+    ("name", 60),
+    ("email", 55),
+    ("code", 50),
+    ("status", 45),
+    ("url", 40),
+    ("href", 40),
+    ("location", 35),
+];
+
+/// A JSON path suggested for capture/query expressions, with its value.
+pub struct Suggestion {
+    pub path: String,
+    pub value: Value,
+    score: i32,
+}
+
+fn key_score(key: &str) -> i32 {
+    let key = key.to_lowercase();
+    RELEVANT_KEYS
+        .iter()
+        .filter(|(fragment, _)| {
+            key == *fragment || key.ends_with(&format!("_{}", fragment)) || key.contains(fragment)
+        })
+        .map(|(fragment, weight)| if key == *fragment { weight + 10 } else { *weight })
+        .max()
+        .unwrap_or(0)
+}
+
+fn walk(value: &Value, path: &str, key: Option<&str>, depth: i32, out: &mut Vec<Suggestion>) {
+    match value {
+        Value::Object(map) => {
+            for (child_key, child) in map {
+                let child_path = format!("{}.{}", path.trim_end_matches('.'), child_key);
+                walk(child, &child_path, Some(child_key), depth + 1, out);
+            }
+        }
+        // 配列は先頭要素のみを候補にして、巨大なレスポンスで候補が埋もれないようにする
+        Value::Array(items) => {
+            if let Some(first) = items.first() {
+                let child_path = format!("{}[0]", if path == "." { "." } else { path });
+                walk(first, &child_path, key, depth + 1, out);
+            }
+        }
+        scalar => {
+            let base = key.map(key_score).unwrap_or(0);
+            if base > 0 {
+                out.push(Suggestion {
+                    path: path.to_string(),
+                    value: scalar.clone(),
+                    score: base - depth * 5,
+                });
+            }
+        }
+    }
+}
+
+/// Returns up to `limit` paths in the response that look like ids, tokens or names,
+/// most relevant first. Paths use the same syntax as --filter.
+pub fn suggest_paths(body: &Value, limit: usize) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    walk(body, ".", None, 0, &mut suggestions);
+    suggestions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    suggestions.truncate(limit);
+    suggestions
+}
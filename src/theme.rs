@@ -0,0 +1,230 @@
+use anyhow::{bail, Context, Result};
+use dialoguer::console::{self, Style};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, env, sync::OnceLock};
+
+use crate::i18n;
+use crate::settings::Settings;
+
+/// Built-in themes selectable by name in settings.json.
+pub const BUILT_IN: &[&str] = &["default", "solarized", "dracula", "mono"];
+
+/// Styles used across the output, written as dotted style strings such as
+/// "green", "red.bold" or "208" (a 256-color index). An empty string means no style.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub status_success: String,
+    pub status_redirect: String,
+    pub status_client_error: String,
+    pub status_server_error: String,
+    pub json_key: String,
+    pub json_string: String,
+    pub json_number: String,
+    /// true, false and null.
+    pub json_literal: String,
+    pub diff_added: String,
+    pub diff_removed: String,
+}
+
+/// The `theme` value of settings.json: a built-in name, or a built-in base with overrides.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ThemeSetting {
+    Named(String),
+    Custom {
+        #[serde(default)]
+        base: Option<String>,
+        #[serde(flatten)]
+        styles: BTreeMap<String, String>,
+    },
+}
+
+fn theme(styles: [&str; 10]) -> Theme {
+    let [status_success, status_redirect, status_client_error, status_server_error, json_key, json_string, json_number, json_literal, diff_added, diff_removed] =
+        styles.map(str::to_string);
+    Theme {
+        status_success,
+        status_redirect,
+        status_client_error,
+        status_server_error,
+        json_key,
+        json_string,
+        json_number,
+        json_literal,
+        diff_added,
+        diff_removed,
+    }
+}
+
+impl Theme {
+    /// Returns the built-in theme NAME.
+    pub fn built_in(name: &str) -> Result<Theme> {
+        Ok(match name {
+            "default" => theme(["green", "cyan", "yellow", "red.bold", "cyan", "green", "yellow", "magenta", "green", "red"]),
+            // Solarized のアクセントカラーは明るい背景・暗い背景のどちらでも読める
+            "solarized" => theme(["64", "37", "166", "160.bold", "33", "37", "125", "61", "64", "160"]),
+            "dracula" => theme(["84", "117", "215", "203.bold", "117", "228", "141", "212", "84", "203"]),
+            "mono" => theme(["bold", "", "underlined", "bold.underlined", "bold", "", "", "dim", "bold", "dim"]),
+            other => bail!("Unknown theme '{}' (built-in themes: {})", other, BUILT_IN.join(", ")),
+        })
+    }
+
+    /// Resolves the settings value into a theme, validating every style string.
+    pub fn from_setting(setting: &ThemeSetting) -> Result<Theme> {
+        let theme = match setting {
+            ThemeSetting::Named(name) => Theme::built_in(name)?,
+            ThemeSetting::Custom { base, styles } => {
+                let mut merged = serde_json::to_value(Theme::built_in(base.as_deref().unwrap_or("default"))?)?;
+                for (key, style) in styles {
+                    merged[key] = Value::String(style.clone());
+                }
+                serde_json::from_value(merged).context("Invalid theme in settings.json")?
+            }
+        };
+        for style in serde_json::to_value(&theme)?.as_object().into_iter().flat_map(|o| o.values()) {
+            validate(style.as_str().unwrap_or_default())?;
+        }
+        Ok(theme)
+    }
+
+    fn status_style(&self, status: u16) -> &str {
+        match status {
+            200..=299 => &self.status_success,
+            300..=399 => &self.status_redirect,
+            400..=499 => &self.status_client_error,
+            500..=599 => &self.status_server_error,
+            _ => "",
+        }
+    }
+}
+
+/// Rejects style strings with parts the terminal library would silently ignore.
+fn validate(style: &str) -> Result<()> {
+    const PARTS: &[&str] = &[
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white", "bright", "on_black", "on_red",
+        "on_green", "on_yellow", "on_blue", "on_magenta", "on_cyan", "on_white", "on_bright", "bold", "dim",
+        "underlined", "blink", "reverse", "strikethrough",
+    ];
+    for part in style.split('.').filter(|part| !part.is_empty()) {
+        let numeric = part.strip_prefix("on_").unwrap_or(part).parse::<u8>().is_ok();
+        if !numeric && !PARTS.contains(&part) {
+            bail!("Unknown style '{}' in theme (use names such as \"green.bold\" or 256-color numbers)", part);
+        }
+    }
+    Ok(())
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Returns the theme from settings.json, or the default theme. An invalid theme is
+/// reported once and replaced by the default so output still works.
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(|| {
+        let setting = Settings::load().ok().and_then(|settings| settings.theme);
+        match setting.as_ref().map(Theme::from_setting).transpose() {
+            Ok(theme) => theme.unwrap_or_else(|| Theme::built_in("default").expect("default theme exists")),
+            Err(err) => {
+                eprintln!("{}", i18n::tf("Warning: {}; using the default theme.", &[&format!("{:#}", err)]));
+                Theme::built_in("default").expect("default theme exists")
+            }
+        }
+    })
+}
+
+/// Turns colors off for NO_COLOR (https://no-color.org) and when output goes to a file.
+pub fn configure(writing_to_file: bool) {
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    if writing_to_file {
+        console::set_colors_enabled(false);
+    }
+}
+
+fn paint(style: &str, text: &str, stderr: bool) -> String {
+    if style.is_empty() {
+        return text.to_string();
+    }
+    let style = Style::from_dotted_str(style);
+    let style = if stderr { style.for_stderr() } else { style.for_stdout() };
+    style.apply_to(text).to_string()
+}
+
+/// Colors TEXT (written to stdout) by the class of STATUS.
+pub fn status(status: u16, text: &str) -> String {
+    paint(current().status_style(status), text, false)
+}
+
+/// Colors TEXT green when a response time is within its SLO and red when it is over.
+pub fn latency(within_slo: bool, text: &str, stderr: bool) -> String {
+    let theme = current();
+    let style = if within_slo { &theme.status_success } else { &theme.status_server_error };
+    paint(style, text, stderr)
+}
+
+/// Colors one line of a diff ("+ ..." or "- ...").
+pub fn diff_line(line: &str, stderr: bool) -> String {
+    let theme = current();
+    if line.starts_with('+') {
+        paint(&theme.diff_added, line, stderr)
+    } else if line.starts_with('-') {
+        paint(&theme.diff_removed, line, stderr)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Highlights a JSON document written to stdout, keeping its formatting.
+/// Anything that is not valid JSON is returned unchanged.
+pub fn json(text: &str) -> String {
+    if !console::colors_enabled() || serde_json::from_str::<Value>(text).is_err() {
+        return text.to_string();
+    }
+    let theme = current();
+    let chars: Vec<char> = text.chars().collect();
+    let mut highlighted = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let style = match chars[i] {
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                // 直後（空白を除く）が ':' ならオブジェクトのキー
+                match chars[i..].iter().find(|c| !c.is_whitespace()) {
+                    Some(':') => &theme.json_key,
+                    _ => &theme.json_string,
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || "+-.eE".contains(chars[i])) {
+                    i += 1;
+                }
+                &theme.json_number
+            }
+            c if c.is_ascii_alphabetic() => {
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                &theme.json_literal
+            }
+            c => {
+                highlighted.push(c);
+                i += 1;
+                continue;
+            }
+        };
+        let token: String = chars[start..i].iter().collect();
+        highlighted.push_str(&paint(style, &token, false));
+    }
+    highlighted
+}
@@ -0,0 +1,106 @@
+use anyhow::{bail, Context, Result};
+use reqwest::{header::CONTENT_LENGTH, Body, RequestBuilder, Response};
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::net_error::NetworkError;
+
+/// Simulated slow network applied to every request (--throttle / --latency).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Throttle {
+    /// Bandwidth in bytes per second for the request and response bodies.
+    pub bytes_per_sec: Option<u64>,
+    /// Extra delay before each request is sent.
+    pub latency: Option<Duration>,
+}
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+/// Sets the throttle for the rest of the process.
+pub fn set(throttle: Throttle) {
+    let _ = THROTTLE.set(throttle);
+}
+
+fn current() -> Throttle {
+    THROTTLE.get().copied().unwrap_or_default()
+}
+
+/// Parses a bandwidth such as "256kbps", "1.5mbps" (bits per second) or "64KB/s"
+/// (bytes per second) into bytes per second.
+pub fn parse_rate(input: &str) -> Result<u64> {
+    let trimmed = input.trim();
+    let split = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number.parse().with_context(|| format!("Invalid bandwidth: {}", input))?;
+    let unit = unit.trim().to_lowercase();
+    let (prefix, bits) = if let Some(prefix) = unit.strip_suffix("bps") {
+        (prefix, true)
+    } else if let Some(prefix) = unit.strip_suffix("b/s") {
+        (prefix, false)
+    } else {
+        bail!("Unknown bandwidth unit in '{}' (use e.g. 256kbps or 64KB/s)", input);
+    };
+    let multiplier = match prefix {
+        "" => 1.0,
+        "k" => 1e3,
+        "m" => 1e6,
+        "g" => 1e9,
+        other => bail!("Unknown bandwidth prefix '{}' in {}", other, input),
+    };
+    let bytes = number * multiplier / if bits { 8.0 } else { 1.0 };
+    if bytes < 1.0 {
+        bail!("Bandwidth must be at least 1 byte per second: {}", input);
+    }
+    Ok(bytes as u64)
+}
+
+/// Sleeps until SENT bytes would have taken at RATE bytes per second since STARTED.
+async fn pace(started: Instant, sent: usize, rate: u64) {
+    let due = Duration::from_secs_f64(sent as f64 / rate as f64);
+    if let Some(wait) = due.checked_sub(started.elapsed()) {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Waits for the simulated latency, if any.
+pub async fn delay() {
+    if let Some(latency) = current().latency {
+        tokio::time::sleep(latency).await;
+    }
+}
+
+/// Attaches the request body, streamed at the throttled rate when bandwidth is limited.
+pub fn attach_body(builder: RequestBuilder, bytes: Vec<u8>) -> RequestBuilder {
+    let Some(rate) = current().bytes_per_sec else {
+        return builder.body(bytes);
+    };
+    // Content-Length を付けて、チャンク転送ではなく通常のボディとして送る
+    let length = bytes.len();
+    // 10 分の 1 秒ぶんずつ送ることで、帯域を平滑に絞る
+    let chunk = (rate / 10).max(1) as usize;
+    let chunks: Vec<Vec<u8>> = bytes.chunks(chunk).map(<[u8]>::to_vec).collect();
+    let started = Instant::now();
+    let stream = futures_util::stream::unfold((chunks.into_iter(), 0), move |(mut chunks, sent)| async move {
+        let chunk = chunks.next()?;
+        pace(started, sent, rate).await;
+        let sent = sent + chunk.len();
+        Some((Ok::<_, std::io::Error>(chunk), (chunks, sent)))
+    });
+    builder.header(CONTENT_LENGTH, length).body(Body::wrap_stream(stream))
+}
+
+/// Reads the response body, no faster than the throttled rate when bandwidth is limited.
+pub async fn read_body(mut response: Response) -> Result<Vec<u8>> {
+    let Some(rate) = current().bytes_per_sec else {
+        return Ok(response.bytes().await.map_err(NetworkError::from)?.to_vec());
+    };
+    let started = Instant::now();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(NetworkError::from)? {
+        body.extend_from_slice(&chunk);
+        pace(started, body.len(), rate).await;
+    }
+    Ok(body)
+}
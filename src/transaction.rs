@@ -0,0 +1,292 @@
+use anyhow::{anyhow, Context, Result};
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::annotation;
+use crate::assertion::{self, Assertion};
+use crate::cancel;
+use crate::exit_code;
+use crate::filter::Filter;
+use crate::i18n;
+use crate::mount;
+use crate::output::{self, Renderer, Table};
+use crate::pattern::NameFilter;
+use crate::request::{self, Exchange, RequestConfig};
+use crate::retry::RetryPolicy;
+
+/// A request in a transaction file: a saved TARGET and/or inline request fields.
+#[derive(Deserialize, Debug, Clone, Default, JsonSchema)]
+pub struct Call {
+    /// Saved configuration to start from (e.g. "SystemA/users").
+    target: Option<String>,
+    /// Inline fields overlaid on the saved configuration.
+    #[serde(flatten)]
+    request: RequestConfig,
+    /// Expected status code; any 2xx is accepted when omitted.
+    expect_status: Option<u16>,
+}
+
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+struct Step {
+    name: Option<String>,
+    #[serde(flatten)]
+    call: Call,
+    /// Request that undoes this step, run in reverse order on rollback.
+    /// `{{.path}}` placeholders are filled from this step's response body.
+    compensate: Option<Call>,
+}
+
+/// A file run with `run FILE`.
+#[derive(Deserialize, Debug, JsonSchema)]
+struct TransactionFile {
+    steps: Vec<Step>,
+}
+
+/// A step that completed successfully, kept for rollback.
+struct Completed<'a> {
+    label: String,
+    step: &'a Step,
+    body: Value,
+}
+
+impl Call {
+    pub fn resolve(&self) -> Result<RequestConfig> {
+        let method = self.request.method.as_deref().unwrap_or("GET").to_uppercase();
+        let saved = match self.target {
+            Some(ref target) => request::load_saved(target, &method)?,
+            None => RequestConfig::default(),
+        };
+        let mut config = saved.overlay(&self.request);
+        config.method = Some(method);
+        Ok(config)
+    }
+
+    /// Sends the call and returns the parsed response body, failing on unexpected status
+    /// or failed assertions.
+    /// FILE is the transaction file, where failed assertions are looked up for annotations.
+    pub async fn execute(&self, config: &RequestConfig, retry: &RetryPolicy, file: &Path) -> Result<(String, Value)> {
+        let assertions = assertion::parse_all(config.assertions.as_deref().unwrap_or_default())?;
+        let exchange = request::execute(config, self.target.as_deref(), retry).await?;
+        let text = exchange.text();
+        self.check(config, &exchange, &assertions, file)?;
+        let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        Ok((exchange.status.to_string(), body))
+    }
+
+    /// Fails on an unexpected status or failed assertions; `warn:` assertions are only printed.
+    fn check(&self, config: &RequestConfig, exchange: &Exchange, assertions: &[Assertion], file: &Path) -> Result<()> {
+        let status = exchange.status;
+        let ok = match self.expect_status {
+            Some(expected) => status.as_u16() == expected,
+            None => assertion::checks_status(assertions) || status.is_success(),
+        };
+        if !ok {
+            return Err(exit_code::assertion(anyhow!("unexpected status {}: {}", status, exchange.text())));
+        }
+        let failures = assertion::check_all(assertions, exchange);
+        if annotation::enabled() {
+            // アサーションはファイル自体か、元にした保存済み設定に書かれている
+            let method = config.method.as_deref().unwrap_or("GET");
+            let mut files = vec![file.to_path_buf()];
+            if let Some(ref target) = self.target {
+                files.push(mount::config_path(target, method)?);
+            }
+            annotation::report(&describe(config), &files, &failures);
+        }
+        let (errors, warnings) = assertion::partition(failures);
+        for warning in &warnings {
+            eprintln!("{}", i18n::tf("Warning: assertion failed: {}", &[warning]));
+        }
+        if !errors.is_empty() {
+            return Err(exit_code::assertion(anyhow!("assertion failed: {}", errors.join("; "))));
+        }
+        Ok(())
+    }
+}
+
+/// Sends the one request described in FILE (YAML or JSON, read from stdin for "-") and
+/// prints the response. The request has the fields of a transaction step, so it may start
+/// from a saved TARGET but needs nothing in the configuration directory.
+pub async fn run_one(file: &Path, retry: &RetryPolicy) -> Result<()> {
+    let content = if file == Path::new("-") {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content).context("Failed to read the request from stdin")?;
+        content
+    } else {
+        fs::read_to_string(file).with_context(|| format!("Failed to read request file {:?}", file))?
+    };
+    // JSON も YAML として読める
+    let call: Call = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse request file {:?}", file))
+        .map_err(exit_code::config)?;
+    let config = call.resolve().map_err(exit_code::config)?;
+    if config.url.is_none() {
+        return Err(exit_code::config(anyhow!("The request in {:?} has no url", file)));
+    }
+    let assertions = assertion::parse_all(config.assertions.as_deref().unwrap_or_default())?;
+    let exchange = cancel::guard(request::execute(&config, call.target.as_deref(), retry)).await?;
+    let renderer = output::renderer()?;
+    output::emit(&renderer.response(&exchange, &exchange.text())?, None)?;
+    call.check(&config, &exchange, &assertions, file)
+}
+
+pub fn describe(config: &RequestConfig) -> String {
+    format!(
+        "{} {}",
+        config.method.as_deref().unwrap_or("GET"),
+        config.url.as_deref().unwrap_or("<no url>")
+    )
+}
+
+/// Replaces `{{.path}}` placeholders in the serialized config with values from `body`.
+pub fn fill_placeholders(config: &RequestConfig, body: &Value) -> Result<RequestConfig> {
+    let mut text = serde_json::to_string(config)?;
+    while let Some(start) = text.find("{{.") {
+        let end = text[start..]
+            .find("}}")
+            .map(|offset| start + offset)
+            .context("Unterminated '{{' placeholder in compensation")?;
+        let expr = &text[start + 2..end];
+        let value = match Filter::parse(expr)?.apply(body) {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+        // JSON 文字列内に埋め込むため、前後の引用符を除いてエスケープする
+        let escaped = serde_json::to_string(&value)?;
+        text.replace_range(start..end + 2, &escaped[1..escaped.len() - 1]);
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// JSON Schema of transaction files.
+pub fn file_schema() -> RootSchema {
+    schemars::schema_for!(TransactionFile)
+}
+
+/// Runs every step of the transaction file in order. When a step fails and
+/// `rollback` is set, the compensations of completed steps run in reverse order.
+/// Steps are named "FILE/STEP" (e.g. "checkout/create order"); steps not selected by
+/// STEPS are skipped.
+pub async fn run(file: &Path, rollback: bool, steps: &NameFilter, retry: &RetryPolicy) -> Result<()> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read transaction file {:?}", file))
+        .map_err(exit_code::config)?;
+    let transaction: TransactionFile = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse transaction file {:?}", file))
+        .map_err(exit_code::config)?;
+
+    let renderer = output::renderer()?;
+    let mut table = Table::new(&[("STEP", "step"), ("REQUEST", "request"), ("STATUS", "status"), ("RESULT", "result")]);
+    let result = execute_steps(&transaction, file, rollback, steps, retry, renderer.as_ref(), &mut table).await;
+    // human 形式では進捗行がそのまま結果になるので、表はそれ以外の形式でだけ出力する
+    if !renderer.human() {
+        print!("{}", renderer.table(&table)?);
+    }
+    result
+}
+
+async fn execute_steps(
+    transaction: &TransactionFile,
+    file: &Path,
+    rollback: bool,
+    selection: &NameFilter,
+    retry: &RetryPolicy,
+    renderer: &dyn Renderer,
+    table: &mut Table,
+) -> Result<()> {
+    let suite = file.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let total = transaction.steps.len();
+    let mut completed: Vec<Completed> = Vec::new();
+    let mut failure = None;
+    for (index, step) in transaction.steps.iter().enumerate() {
+        let label = format!("{}/{}", suite, step.name.clone().unwrap_or_else(|| format!("step {}", index + 1)));
+        if !selection.matches(&label) {
+            renderer.note(&i18n::tf("[{}/{}] {}: skipped (--filter)", &[&(index + 1), &total, &label]));
+            table.push(vec![json!(label), Value::Null, Value::Null, json!("skipped")]);
+            continue;
+        }
+        let config = step.call.resolve()?;
+        // Ctrl-C は失敗と同じ扱いにして、完了済みステップのロールバックへ進む
+        match cancel::guard(step.call.execute(&config, retry, file)).await {
+            Ok((status, body)) => {
+                renderer.note(&format!("[{}/{}] {}: {} -> {}", index + 1, total, label, describe(&config), status));
+                table.push(vec![json!(label), json!(describe(&config)), json!(status), json!("ok")]);
+                completed.push(Completed { label, step, body });
+            }
+            Err(err) => {
+                renderer.note(&i18n::tf(
+                    "[{}/{}] {}: {} -> failed: {}",
+                    &[&(index + 1), &total, &label, &describe(&config), &err],
+                ));
+                table.push(vec![json!(label), json!(describe(&config)), Value::Null, json!(format!("FAIL {}", err))]);
+                failure = Some((label, exit_code::of(&err)));
+                break;
+            }
+        }
+    }
+
+    let Some((failed_step, code)) = failure else {
+        renderer.note(&i18n::tf("Transaction completed: {} step(s) succeeded.", &[&completed.len()]));
+        return Ok(());
+    };
+    // トランザクション全体の終了コードは、失敗したステップの失敗の種類に合わせる
+    if !rollback {
+        return Err(exit_code::classify(
+            code,
+            anyhow!(
+                "Transaction failed at '{}'; {} completed step(s) were not rolled back (use --rollback-on-failure).",
+                failed_step,
+                completed.len()
+            ),
+        ));
+    }
+
+    renderer.note(&i18n::tf("Rolling back {} completed step(s)...", &[&completed.len()]));
+    let mut rollback_errors = 0;
+    for done in completed.iter().rev() {
+        let Some(ref compensate) = done.step.compensate else {
+            renderer.note(&i18n::tf("  {}: no compensation defined, skipped", &[&done.label]));
+            continue;
+        };
+        let result = async {
+            let config = fill_placeholders(&compensate.resolve()?, &done.body)?;
+            let (status, _) = compensate.execute(&config, retry, file).await?;
+            Ok::<_, anyhow::Error>((describe(&config), status))
+        }
+        .await;
+        match result {
+            Ok((request, status)) => {
+                renderer.note(&format!("  {}: {} -> {}", done.label, request, status));
+                table.push(vec![json!(format!("{} (compensation)", done.label)), json!(request), json!(status), json!("rolled back")]);
+            }
+            Err(err) => {
+                rollback_errors += 1;
+                renderer.note(&i18n::tf("  {}: compensation failed: {}", &[&done.label, &err]));
+                table.push(vec![
+                    json!(format!("{} (compensation)", done.label)),
+                    Value::Null,
+                    Value::Null,
+                    json!(format!("FAIL {}", err)),
+                ]);
+            }
+        }
+    }
+    if rollback_errors > 0 {
+        return Err(exit_code::classify(
+            code,
+            anyhow!(
+                "Transaction failed at '{}' and {} compensation(s) failed; manual cleanup may be needed.",
+                failed_step,
+                rollback_errors
+            ),
+        ));
+    }
+    let err = anyhow!("Transaction failed at '{}'; completed steps were rolled back.", failed_step);
+    Err(exit_code::classify(code, err))
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use dialoguer::console::measure_text_width;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::filter::Filter;
+use crate::i18n;
+use crate::request::Exchange;
+use crate::settings::Settings;
+use crate::theme;
+
+const CODE_FIELDS: &[&str] = &[".code", ".error_code", ".errorCode", ".error.code", ".errors[0].code", ".status_code"];
+const MESSAGE_FIELDS: &[&str] = &[
+    ".message", ".error.message", ".errors[0].message", ".detail", ".error_description", ".title", ".error",
+];
+const STACK_FIELDS: &[&str] = &[
+    ".stack", ".stackTrace", ".stack_trace", ".trace", ".exception", ".error.stack", ".error.stackTrace",
+    ".error.trace",
+];
+const CORRELATION_FIELDS: &[&str] = &[
+    ".requestId", ".request_id", ".correlationId", ".correlation_id", ".traceId", ".trace_id", ".error.requestId",
+];
+const CORRELATION_HEADERS: &[&str] = &[
+    "x-request-id", "x-correlation-id", "request-id", "x-amzn-requestid", "x-amz-request-id", "x-amzn-trace-id",
+    "traceparent", "x-b3-traceid", "x-cloud-trace-context", "cf-ray",
+];
+
+/// Lines that start a stack trace in plain-text bodies (Java, .NET, Node.js, Python, Go, Rust).
+const STACK_MARKERS: &[&str] = &[
+    "Traceback (most recent call last)", "Exception in thread", "Stack trace:", "goroutine ", "panicked at",
+];
+
+const DEFAULT_STACK_LINES: usize = 8;
+
+/// Extraction rules for the summary shown above 5xx responses (`triage` in settings.json).
+/// Each list replaces the built-in defaults; paths use the --filter syntax (e.g. ".error.code").
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TriageSettings {
+    /// Set to false to turn the summary off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_headers: Option<Vec<String>>,
+    /// Maximum number of stack trace lines in the summary (default 8).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_stack_lines: Option<usize>,
+}
+
+/// Returns the configured list, or the built-in defaults.
+fn rules(configured: &Option<Vec<String>>, defaults: &[&str]) -> Vec<String> {
+    match configured {
+        Some(list) => list.clone(),
+        None => defaults.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+/// Returns the first non-empty value found at one of PATHS.
+fn first_field(body: &Value, paths: &[String]) -> Result<Option<Value>> {
+    for path in paths {
+        let found = Filter::parse(path)
+            .with_context(|| format!("Invalid path '{}' in the triage settings", path))?
+            .apply(body);
+        let empty = match &found {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            Value::Array(items) => items.iter().all(Value::is_null),
+            _ => false,
+        };
+        if !empty {
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+fn one_line(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let line = text.lines().next().unwrap_or_default().trim();
+    if line.chars().count() > 200 {
+        format!("{}…", line.chars().take(200).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Stack trace lines from a field value: a multi-line string or an array of frames.
+fn stack_lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => s.lines().map(str::to_string).collect(),
+        Value::Array(frames) => frames.iter().map(one_line).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+fn is_frame(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("at ") || trimmed.starts_with("File \"") || (line.starts_with('\t') && trimmed.contains(".go:"))
+}
+
+/// Finds a stack trace in a plain-text body, starting at a marker line or the first frame.
+fn text_stack(text: &str) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines
+        .iter()
+        .position(|line| STACK_MARKERS.iter().any(|marker| line.contains(marker)))
+        .or_else(|| {
+            // フレーム行の直前の行が例外メッセージであることが多いので、1 行さかのぼる
+            lines.iter().position(|line| is_frame(line)).map(|i| i.saturating_sub(1))
+        });
+    match start {
+        Some(start) => lines[start..]
+            .iter()
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| line.to_string())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Builds the summary block printed above the body of a 5xx response, or None for
+/// other statuses, when nothing relevant is found, or when turned off for TARGET.
+pub fn summarize(exchange: &Exchange, target: Option<&str>) -> Result<Option<String>> {
+    if !exchange.status.is_server_error() {
+        return Ok(None);
+    }
+    let settings = Settings::for_target(target)?.triage.unwrap_or_default();
+    if settings.enabled == Some(false) {
+        return Ok(None);
+    }
+    let text = exchange.text();
+    let body = serde_json::from_str::<Value>(&text).ok();
+
+    let mut entries: Vec<(&'static str, String)> = Vec::new();
+    let mut stack = Vec::new();
+    if let Some(ref body) = body {
+        if let Some(code) = first_field(body, &rules(&settings.code_fields, CODE_FIELDS))? {
+            entries.push(("Error code", one_line(&code)));
+        }
+        if let Some(message) = first_field(body, &rules(&settings.message_fields, MESSAGE_FIELDS))? {
+            // {"error": {...}} のようなオブジェクトは 1 行にまとめて表示する
+            entries.push(("Message", one_line(&message)));
+        }
+        if let Some(id) = first_field(body, &rules(&settings.correlation_fields, CORRELATION_FIELDS))? {
+            entries.push(("Correlation id", one_line(&id)));
+        }
+        if let Some(trace) = first_field(body, &rules(&settings.stack_fields, STACK_FIELDS))? {
+            stack = stack_lines(&trace);
+        }
+    } else {
+        stack = text_stack(&text);
+        if stack.is_empty() {
+            if let Some(line) = text.lines().map(str::trim).find(|line| !line.is_empty()) {
+                entries.push(("Message", one_line(&Value::String(line.to_string()))));
+            }
+        }
+    }
+    for name in rules(&settings.correlation_headers, CORRELATION_HEADERS) {
+        if let Some(value) = exchange.headers.get(name.as_str()).and_then(|v| v.to_str().ok()) {
+            entries.push(("Correlation id", format!("{} ({})", value, name.to_lowercase())));
+        }
+    }
+    if entries.is_empty() && stack.is_empty() {
+        return Ok(None);
+    }
+
+    let heading = i18n::tf("Server error summary ({})", &[&exchange.status]);
+    let mut block = format!("{}\n", theme::status(exchange.status.as_u16(), &heading));
+    let labels: Vec<String> = entries.iter().map(|(label, _)| format!("{}:", i18n::t(label))).collect();
+    // 日本語のラベルは全角幅なので、文字数ではなく表示幅で揃える
+    let width = labels.iter().map(|label| measure_text_width(label)).max().unwrap_or(0);
+    for (label, (_, value)) in labels.iter().zip(&entries) {
+        let padding = " ".repeat(width - measure_text_width(label));
+        block.push_str(&format!("  {}{} {}\n", label, padding, value));
+    }
+    if !stack.is_empty() {
+        let limit = settings.max_stack_lines.unwrap_or(DEFAULT_STACK_LINES);
+        block.push_str(&format!("  {}\n", i18n::t("Stack trace:")));
+        for line in stack.iter().take(limit) {
+            block.push_str(&format!("    {}\n", line.trim_end().replace('\t', "    ")));
+        }
+        if stack.len() > limit {
+            block.push_str(&format!("    {}\n", i18n::tf("... {} more line(s)", &[&(stack.len() - limit)])));
+        }
+    }
+    Ok(Some(block))
+}
@@ -0,0 +1,605 @@
+use anyhow::{bail, Context, Result};
+use clap::{Subcommand, ValueHint};
+use dialoguer::{Input, Select};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use crate::curl;
+use crate::get_default_dir;
+use crate::i18n;
+use crate::mount;
+use crate::namespace;
+use crate::output::{self, Table};
+use crate::request::RequestConfig;
+use crate::store;
+
+/// File holding variables, both at the base directory (defaults) and inside namespace directories.
+pub const VARS_FILE: &str = "vars.json";
+
+/// Prefix of process environment variables exposed as `{{name}}` placeholders.
+pub const ENV_PREFIX: &str = "FERRAPI_VAR_";
+
+pub type Vars = BTreeMap<String, String>;
+
+/// Directory under the base directory holding environment files such as `dev.json`.
+pub const ENVS_DIR: &str = "envs";
+
+/// Variables given with --var; the highest-precedence layer.
+static CLI_VARS: OnceLock<Vars> = OnceLock::new();
+
+/// Name and variables of the environment selected with --env.
+static ENV_VARS: OnceLock<(String, Vars)> = OnceLock::new();
+
+/// Variables set while the process runs, such as values extracted by earlier steps of a
+/// chain; only --var takes precedence over them.
+static RUNTIME_VARS: Mutex<Vars> = Mutex::new(Vars::new());
+
+/// Subcommands of `vars`.
+#[derive(Subcommand, Debug)]
+pub enum VarsCommand {
+    /// 変数を設定します。
+    Set {
+        key: String,
+        value: String,
+        /// 対象の名前空間（省略時は ~/.ferrapi_tester/vars.json のデフォルト値）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+    },
+    /// 変数の値を表示します。
+    Get {
+        key: String,
+        /// 対象の名前空間（省略時はデフォルト値）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+        /// 名前空間ファイルだけでなく、全レイヤーを解決した実際の値を表示します。
+        #[arg(long = "resolved")]
+        resolved: bool,
+    },
+    /// 変数を削除します。
+    Unset {
+        key: String,
+        /// 対象の名前空間（省略時はデフォルト値）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+    },
+    /// 変数を一覧表示します。
+    List {
+        /// 対象の名前空間（省略時はデフォルト値）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+    },
+    /// 対話モードで変数を追加・編集・削除します。
+    Edit {
+        /// 対象の名前空間（省略時はデフォルト値）
+        #[arg(short = 'n', long = "namespace")]
+        namespace: Option<String>,
+    },
+}
+
+/// Subcommands of `env`.
+#[derive(Subcommand, Debug)]
+pub enum EnvCommand {
+    /// 環境の一覧を表示します。
+    List,
+    /// 環境の変数を表示します。
+    Show { name: String },
+    /// 環境に変数を設定します（ファイルがなければ作成します）。
+    Set { name: String, key: String, value: String },
+    /// 環境から変数を削除します。
+    Unset { name: String, key: String },
+    /// シェルの環境変数または .envrc / .env ファイルの変数を環境に取り込みます。
+    Import {
+        /// 取り込み先の環境（例: dev）
+        name: String,
+
+        /// この接頭辞で始まる、現在のプロセスの環境変数を取り込みます（例: MYAPP_）
+        #[arg(long = "from-shell", value_name = "PREFIX", required_unless_present = "from_file")]
+        from_shell: Option<String>,
+
+        /// KEY=VALUE（export 付きも可）の行を読み込むファイル（例: .envrc）
+        #[arg(long = "from-file", value_name = "FILE", conflicts_with = "from_shell", value_hint = ValueHint::FilePath)]
+        from_file: Option<PathBuf>,
+
+        /// 変数名から接頭辞を取り除きます（例: MYAPP_BASE_URL → BASE_URL）
+        #[arg(long = "strip-prefix", requires = "from_shell")]
+        strip_prefix: bool,
+    },
+}
+
+/// Parses "key=value" strings given with --var.
+pub fn parse_cli_vars(vars: &[String]) -> Result<Vars> {
+    vars.iter()
+        .map(|var| {
+            let (key, value) = var
+                .split_once('=')
+                .with_context(|| format!("Invalid variable format (expected key=value): {}", var))?;
+            Ok((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+pub fn set_cli_vars(vars: Vars) {
+    let _ = CLI_VARS.set(vars);
+}
+
+/// Makes VALUES visible to the following requests of this process.
+pub fn set_runtime_vars(values: Vars) {
+    RUNTIME_VARS.lock().unwrap_or_else(|e| e.into_inner()).extend(values);
+}
+
+/// Names of the environments in ~/.ferrapi_tester/envs.
+pub fn list_envs() -> Result<Vec<String>> {
+    let dir = get_default_dir()?.join(ENVS_DIR);
+    let mut names: Vec<String> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_string_lossy().strip_suffix(".json").map(str::to_string))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    Ok(names)
+}
+
+/// Path of the environment file for NAME.
+fn env_path(name: &str) -> Result<PathBuf> {
+    let valid = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+    if name.is_empty() || name.starts_with('.') || !name.chars().all(valid) {
+        bail!("Invalid environment name '{}'", name);
+    }
+    Ok(get_default_dir()?.join(ENVS_DIR).join(format!("{}.json", name)))
+}
+
+/// Reads an environment file; values that are not strings keep their JSON type.
+fn read_env(path: &Path) -> Result<BTreeMap<String, Value>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read environment from {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse environment in {:?}", path))
+}
+
+/// Sets VALUES in the environment NAME, creating its file if needed; returns the path.
+fn set_env_vars(name: &str, values: Vars) -> Result<PathBuf> {
+    let path = env_path(name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let _lock = store::lock(&path)?;
+    let mut vars = if path.exists() { read_env(&path)? } else { BTreeMap::new() };
+    vars.extend(values.into_iter().map(|(key, value)| (key, Value::String(value))));
+    let serialized = serde_json::to_string_pretty(&vars)?;
+    store::write_atomic(&path, serialized).with_context(|| format!("Failed to write environment to {:?}", path))?;
+    Ok(path)
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Reads the `KEY=VALUE` and `export KEY=VALUE` lines of a .envrc or .env file.
+/// Other lines, such as direnv commands, and values that refer to other variables
+/// or run commands are skipped and returned by name or line.
+fn read_env_file(path: &Path) -> Result<(Vars, Vec<String>)> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let mut vars = Vars::new();
+    let mut skipped = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+        let words = curl::split(line).with_context(|| format!("Invalid line in {:?}: {}", path, line))?;
+        let assignment = match words.as_slice() {
+            [word] => word.split_once('='),
+            // 行末のコメント
+            [word, comment, ..] if comment.starts_with('#') => word.split_once('='),
+            _ => None,
+        };
+        let Some((key, value)) = assignment.filter(|(key, _)| is_env_name(key)) else {
+            skipped.push(line.to_string());
+            continue;
+        };
+        // 単一引用符で囲んだ値を除き、$ や ` はシェルが展開するので値を決められない
+        let raw = line.split_once('=').map_or("", |(_, raw)| raw);
+        if !raw.starts_with('\'') && raw.contains(['$', '`']) {
+            skipped.push(key.to_string());
+            continue;
+        }
+        vars.insert(key.to_string(), value.to_string());
+    }
+    Ok((vars, skipped))
+}
+
+/// Loads ~/.ferrapi_tester/envs/NAME.json as the variables of the environment
+/// selected with --env. Values that are not strings are used in their JSON form.
+pub fn select_env(name: &str) -> Result<()> {
+    let path = env_path(name)?;
+    if !path.exists() {
+        let available = list_envs()?;
+        if available.is_empty() {
+            bail!("Environment '{}' not found: {:?} does not exist", name, path);
+        }
+        bail!("Environment '{}' not found: {:?} does not exist (available: {})", name, path, available.join(", "));
+    }
+    let vars = read_env(&path)?
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(s) => (key, s),
+            other => (key, other.to_string()),
+        })
+        .collect();
+    let _ = ENV_VARS.set((name.to_string(), vars));
+    Ok(())
+}
+
+fn vars_path(namespace: Option<&str>) -> Result<PathBuf> {
+    let base_dir = get_default_dir()?;
+    Ok(match namespace {
+        Some(namespace) => namespace::dir(&base_dir, namespace)?.join(VARS_FILE),
+        None => base_dir.join(VARS_FILE),
+    })
+}
+
+/// Like `vars_path`, but creates the namespace directory (and its metadata) first.
+fn vars_path_for_write(namespace: Option<&str>) -> Result<PathBuf> {
+    if let Some(target) = namespace {
+        namespace::create(&get_default_dir()?, target)?;
+    }
+    vars_path(namespace)
+}
+
+fn read(path: &Path) -> Result<Vars> {
+    if !path.exists() {
+        return Ok(Vars::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read variables from {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse variables in {:?}", path))
+}
+
+fn write(path: &Path, vars: &Vars) -> Result<()> {
+    let serialized = serde_json::to_string_pretty(vars).with_context(|| "Failed to serialize variables")?;
+    store::write_atomic(path, serialized).with_context(|| format!("Failed to write variables to {:?}", path))
+}
+
+/// Files that can define the variables of TARGET, lowest precedence first: the defaults,
+/// the vars.json files of its namespaces (mounts included) and every environment file.
+/// Files that do not exist are left out.
+pub fn definition_files(target: Option<&str>) -> Result<Vec<PathBuf>> {
+    let mut files = vec![get_default_dir()?.join(VARS_FILE)];
+    if let Some(target) = target.filter(|t| !t.starts_with("http")) {
+        files.extend(mount::ancestry(target)?.into_iter().map(|dir| dir.join(VARS_FILE)));
+    }
+    files.extend(list_envs()?.iter().filter_map(|name| env_path(name).ok()));
+    files.retain(|file| file.exists());
+    Ok(files)
+}
+
+/// Collects the variables visible to TARGET. Precedence (highest first):
+/// --var > variables set at run time (chain extractions) > FERRAPI_VAR_* environment
+/// variables > the --env file > namespace vars.json (nearest directory wins) > defaults
+/// in ~/.ferrapi_tester/vars.json.
+pub fn resolve(target: Option<&str>) -> Result<Vars> {
+    let base_dir = get_default_dir()?;
+    let mut vars = read(&base_dir.join(VARS_FILE))?;
+    if let Some(target) = target.filter(|t| !t.starts_with("http")) {
+        for dir in mount::ancestry(target)? {
+            vars.extend(read(&dir.join(VARS_FILE))?);
+        }
+    }
+    if let Some((_, env)) = ENV_VARS.get() {
+        vars.extend(env.clone());
+    }
+    vars.extend(
+        env::vars().filter_map(|(key, value)| key.strip_prefix(ENV_PREFIX).map(|name| (name.to_string(), value))),
+    );
+    vars.extend(RUNTIME_VARS.lock().unwrap_or_else(|e| e.into_inner()).clone());
+    if let Some(cli) = CLI_VARS.get() {
+        vars.extend(cli.clone());
+    }
+    Ok(vars)
+}
+
+/// Returns true for placeholder names handled by this module. Names starting with
+/// '.' are response paths and names containing ':' belong to other resolvers.
+pub fn is_variable(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Returns true when VALUE contains placeholder syntax. Values taken from a response are
+/// not stored as variables then, so that a server cannot put `{{...}}` into later requests.
+pub fn has_placeholder(value: &str) -> bool {
+    value.contains("{{")
+}
+
+/// Replaces `{{name}}` placeholders in `input` with values from `vars`.
+pub fn render(input: &str, vars: &Vars) -> Result<String> {
+    render_with(input, vars, &|_| None)
+}
+
+/// Like `render`, and also replaces other placeholders with the value `other` returns for
+/// their name (they are kept as written when it returns None). Inserted values are never
+/// scanned again, so a value containing `{{...}}` comes out literally.
+pub fn render_with(input: &str, vars: &Vars, other: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + len].trim();
+        output.push_str(&rest[..start]);
+        if is_variable(name) {
+            match vars.get(name) {
+                Some(value) => output.push_str(value),
+                None => bail!("Undefined variable {{{{{}}}}}", name),
+            }
+        } else if let Some(value) = other(name) {
+            output.push_str(&value);
+        } else {
+            output.push_str(&rest[start..start + len + 2]);
+        }
+        rest = &rest[start + len + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn render_value(value: &mut Value, vars: &Vars) -> Result<()> {
+    match value {
+        Value::String(s) => *s = render(s, vars)?,
+        Value::Array(items) => {
+            for item in items {
+                render_value(item, vars)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                render_value(item, vars)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Resolves placeholders in the URL, header values, body and TLS file paths of a request.
+pub fn substitute(config: &RequestConfig, target: Option<&str>) -> Result<RequestConfig> {
+    let vars = resolve(target)?;
+    let mut config = config.clone();
+    if let Some(ref mut url) = config.url {
+        *url = render(url, &vars)?;
+    }
+    if let Some(ref mut headers) = config.headers {
+        for value in headers.values_mut() {
+            *value = render(value, &vars)?;
+        }
+    }
+    for value in config.query.iter_mut().flat_map(|query| query.values_mut()) {
+        *value = render(value, &vars)?;
+    }
+    if let Some(ref mut data) = config.data {
+        render_value(data, &vars)?;
+    }
+    for part in config.form.iter_mut().chain(config.form_urlencoded.iter_mut()).flatten() {
+        for value in part.values_mut() {
+            *value = render(value, &vars)?;
+        }
+    }
+    if let Some(ref mut auth) = config.auth {
+        for value in auth.values_mut() {
+            *value = render(value, &vars)?;
+        }
+    }
+    for path in [&mut config.cacert, &mut config.cert, &mut config.key].into_iter().flatten() {
+        *path = render(path, &vars)?;
+    }
+    Ok(config)
+}
+
+/// Sets several variables in the namespace's vars.json at once; returns the file path.
+pub fn set_all(namespace: Option<&str>, values: Vars) -> Result<PathBuf> {
+    let path = vars_path_for_write(namespace)?;
+    let _lock = store::lock(&path)?;
+    let mut vars = read(&path)?;
+    vars.extend(values);
+    write(&path, &vars)?;
+    Ok(path)
+}
+
+/// Stores VALUES taken from a response where the next requests will find them: in the
+/// environment selected with --env, otherwise in the vars.json of TARGET (or the defaults
+/// without a namespace); returns the file path.
+pub fn capture(target: Option<&str>, values: Vars) -> Result<PathBuf> {
+    match ENV_VARS.get() {
+        Some((name, _)) => set_env_vars(name, values),
+        None => set_all(target.filter(|target| !target.starts_with("http")), values),
+    }
+}
+
+/// Adds the variables of VALUES that the namespace's vars.json does not define yet,
+/// keeping existing values; returns the file path.
+pub fn add_missing(namespace: Option<&str>, values: Vars) -> Result<PathBuf> {
+    let path = vars_path_for_write(namespace)?;
+    let _lock = store::lock(&path)?;
+    let mut vars = read(&path)?;
+    for (name, value) in values {
+        vars.entry(name).or_insert(value);
+    }
+    write(&path, &vars)?;
+    Ok(path)
+}
+
+/// Interactive loop for adding, editing and deleting variables.
+fn edit_interactively(path: &Path) -> Result<()> {
+    let mut vars = read(path)?;
+    loop {
+        let mut items: Vec<String> = vars.iter().map(|(k, v)| format!("{} = {}", k, v)).collect();
+        items.push(i18n::t("+ Add a variable").to_string());
+        items.push(i18n::t("Save and exit").to_string());
+        let selection = Select::new()
+            .with_prompt(i18n::tf("Variables in {}", &[&path.display()]))
+            .items(&items)
+            .default(0)
+            .interact()?;
+        if selection == items.len() - 1 {
+            break;
+        }
+        if selection == items.len() - 2 {
+            let key: String = Input::new().with_prompt(i18n::t("Name")).interact_text()?;
+            let value: String = Input::new().with_prompt(i18n::t("Value")).interact_text()?;
+            vars.insert(key, value);
+            continue;
+        }
+        let key = vars.keys().nth(selection).cloned().unwrap_or_default();
+        let action = Select::new()
+            .with_prompt(&key)
+            .items(&[i18n::t("Edit value"), i18n::t("Delete"), i18n::t("Back")])
+            .default(0)
+            .interact()?;
+        match action {
+            0 => {
+                let value: String = Input::new()
+                    .with_prompt(i18n::t("Value"))
+                    .with_initial_text(vars.get(&key).cloned().unwrap_or_default())
+                    .interact_text()?;
+                vars.insert(key, value);
+            }
+            1 => {
+                vars.remove(&key);
+            }
+            _ => {}
+        }
+    }
+    write(path, &vars)?;
+    println!("{}", i18n::tf("Variables saved to {}", &[&format!("{:?}", path)]));
+    Ok(())
+}
+
+pub fn run(command: VarsCommand) -> Result<()> {
+    match command {
+        VarsCommand::Set { key, value, namespace } => {
+            let path = vars_path_for_write(namespace.as_deref())?;
+            let _lock = store::lock(&path)?;
+            let mut vars = read(&path)?;
+            vars.insert(key.clone(), value);
+            write(&path, &vars)?;
+            println!("{}", i18n::tf("Set {} in {}", &[&key, &format!("{:?}", path)]));
+        }
+        VarsCommand::Get { key, namespace, resolved } => {
+            let vars = if resolved {
+                resolve(namespace.as_deref())?
+            } else {
+                read(&vars_path(namespace.as_deref())?)?
+            };
+            match vars.get(&key) {
+                Some(value) => println!("{}", value),
+                None => bail!("Variable '{}' is not defined", key),
+            }
+        }
+        VarsCommand::Unset { key, namespace } => {
+            let path = vars_path(namespace.as_deref())?;
+            let _lock = store::lock(&path)?;
+            let mut vars = read(&path)?;
+            if vars.remove(&key).is_some() {
+                write(&path, &vars)?;
+                println!("{}", i18n::tf("Unset {} in {}", &[&key, &format!("{:?}", path)]));
+            } else {
+                println!("{}", i18n::tf("Variable '{}' is not defined in {}", &[&key, &format!("{:?}", path)]));
+            }
+        }
+        VarsCommand::List { namespace } => {
+            let path = vars_path(namespace.as_deref())?;
+            let mut table = Table::new(&[("NAME", "name"), ("VALUE", "value")]);
+            for (key, value) in read(&path)? {
+                table.push(vec![json!(key), json!(value)]);
+            }
+            output::renderer()?.print(&table, &i18n::tf("No variables defined in {}", &[&format!("{:?}", path)]))?;
+        }
+        VarsCommand::Edit { namespace } => edit_interactively(&vars_path_for_write(namespace.as_deref())?)?,
+    }
+    Ok(())
+}
+
+pub fn run_env(command: EnvCommand) -> Result<()> {
+    match command {
+        EnvCommand::List => {
+            let mut table = Table::new(&[("ENV", "env"), ("VARIABLES", "variables")]);
+            for name in list_envs()? {
+                let count = read_env(&env_path(&name)?).map(|vars| vars.len().to_string());
+                table.push(vec![json!(name), json!(count.unwrap_or_else(|err| format!("(invalid: {:#})", err)))]);
+            }
+            let dir = get_default_dir()?.join(ENVS_DIR);
+            output::renderer()?.print(&table, &i18n::tf("No environments defined in {}", &[&format!("{:?}", dir)]))?;
+        }
+        EnvCommand::Show { name } => {
+            let path = env_path(&name)?;
+            if !path.exists() {
+                bail!("Environment '{}' not found: {:?} does not exist", name, path);
+            }
+            let mut table = Table::new(&[("NAME", "name"), ("VALUE", "value")]);
+            for (key, value) in read_env(&path)? {
+                table.push(vec![json!(key), value]);
+            }
+            output::renderer()?.print(&table, &i18n::tf("No variables defined in {}", &[&format!("{:?}", path)]))?;
+        }
+        EnvCommand::Set { name, key, value } => {
+            let path = set_env_vars(&name, Vars::from([(key.clone(), value)]))?;
+            println!("{}", i18n::tf("Set {} in {}", &[&key, &format!("{:?}", path)]));
+        }
+        EnvCommand::Unset { name, key } => {
+            let path = env_path(&name)?;
+            if !path.exists() {
+                bail!("Environment '{}' not found: {:?} does not exist", name, path);
+            }
+            let _lock = store::lock(&path)?;
+            let mut vars = read_env(&path)?;
+            if vars.remove(&key).is_some() {
+                let serialized = serde_json::to_string_pretty(&vars)?;
+                store::write_atomic(&path, serialized)
+                    .with_context(|| format!("Failed to write environment to {:?}", path))?;
+                println!("{}", i18n::tf("Unset {} in {}", &[&key, &format!("{:?}", path)]));
+            } else {
+                println!("{}", i18n::tf("Variable '{}' is not defined in {}", &[&key, &format!("{:?}", path)]));
+            }
+        }
+        EnvCommand::Import { name, from_shell, from_file, strip_prefix } => {
+            let vars = match (from_shell, from_file) {
+                (Some(prefix), _) => {
+                    if prefix.is_empty() {
+                        bail!("Specify a non-empty PREFIX so that unrelated environment variables are not imported");
+                    }
+                    env::vars()
+                        .filter_map(|(key, value)| {
+                            let name = key.strip_prefix(&prefix)?;
+                            let name = if strip_prefix { name } else { &key };
+                            (!name.is_empty()).then(|| (name.to_string(), value))
+                        })
+                        .collect::<Vars>()
+                }
+                (None, Some(file)) => {
+                    let (vars, skipped) = read_env_file(&file)?;
+                    for item in skipped {
+                        eprintln!("{}", i18n::tf("Skipped (not a plain KEY=VALUE): {}", &[&item]));
+                    }
+                    vars
+                }
+                (None, None) => bail!("Specify --from-shell PREFIX or --from-file FILE"),
+            };
+            if vars.is_empty() {
+                bail!("No variables to import");
+            }
+            let count = vars.len();
+            let path = set_env_vars(&name, vars)?;
+            println!("{}", i18n::tf("Imported {} variable(s) into {}", &[&count, &format!("{:?}", path)]));
+        }
+    }
+    Ok(())
+}